@@ -1,10 +1,19 @@
-#![feature(test)]
-
+#[macro_use]
+extern crate criterion;
 extern crate chappie;
-extern crate test;
+extern crate rand;
+extern crate rand_chacha;
 
-use chappie::search::SearchSpace;
-use test::{Bencher, black_box};
+use chappie::contraction::ContractionHierarchy;
+use chappie::domains::cube::{CornerPdb, Cube, PocketCube};
+use chappie::domains::sliding::{AdditivePdb, SlidingPuzzle};
+use chappie::domains::words::{hamming, WordLadder};
+use chappie::generators;
+use chappie::search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+use chappie::visited::NoVisited;
+use criterion::{Criterion, black_box};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use std::vec::IntoIter;
 
 enum Dir { Left, Right}
@@ -30,8 +39,66 @@ impl SearchSpace for BinaryTree {
     }
 }
 
-#[bench]
-fn dfs(b: &mut Bencher) {
+fn binary_tree(c: &mut Criterion) {
     let tree = BinaryTree;
-    b.iter(|| { black_box(tree.dfs(0, 2)) });
+    c.bench_function("dfs binary tree", |b| b.iter(|| black_box(tree.dfs(0, 2))));
+    let mut buffers = SearchBuffers::with_visited(NoVisited);
+    c.bench_function("dfs binary tree without visited set", |b| {
+        b.iter(|| black_box(tree.dfs_with(&mut buffers, 0, 2)))
+    });
+}
+
+fn random_graph(c: &mut Criterion) {
+    let graph = generators::random_graph(10_000, 4, 1, &mut ChaCha8Rng::seed_from_u64(42));
+    c.bench_function("dfs random graph", |b| b.iter(|| black_box(graph.dfs_iter(0).count())));
+}
+
+fn contraction_hierarchy(c: &mut Criterion) {
+    let graph = generators::random_graph(10_000, 4, 100, &mut ChaCha8Rng::seed_from_u64(42));
+    let hierarchy = ContractionHierarchy::new(&graph);
+    c.bench_function("contraction hierarchy query random graph", |b| {
+        b.iter(|| black_box(hierarchy.distance(0, 9_999)))
+    });
+}
+
+fn grid_map(c: &mut Criterion) {
+    let grid = generators::random_grid(128, 128, 0.25, &mut ChaCha8Rng::seed_from_u64(7));
+    c.bench_function("dfs grid map", |b| b.iter(|| black_box(grid.dfs((0, 0), (127, 127)))));
+}
+
+fn fifteen_puzzle(c: &mut Criterion) {
+    let puzzle = SlidingPuzzle::new(4, 4);
+    let start = puzzle.goal();
+    c.bench_function("dfs 15-puzzle 10k states", |b| {
+        b.iter(|| black_box(puzzle.dfs_iter(start).take(10_000).count()))
+    });
+}
+
+fn eight_puzzle(c: &mut Criterion) {
+    let puzzle = SlidingPuzzle::new(3, 3);
+    let pdb = AdditivePdb::new(puzzle, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+    let start = puzzle.board(&[8, 6, 7, 2, 5, 4, 3, 0, 1]);
+    c.bench_function("astar 8-puzzle manhattan", |b| {
+        b.iter(|| black_box(puzzle.astar(start, puzzle.goal(), |board: &_| puzzle.manhattan(*board))))
+    });
+    c.bench_function("astar 8-puzzle additive pdb", |b| b.iter(|| black_box(puzzle.astar(start, puzzle.goal(), &pdb))));
+}
+
+fn word_ladder(c: &mut Criterion) {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let words = (0..20_000).map(|_| (0..5).map(|_| rng.gen_range(b'a'..=b'h') as char).collect::<String>());
+    let ladder = WordLadder::new(words.chain(vec!["aaaaa".to_string(), "hhhhh".to_string()]));
+    let goal = "hhhhh".to_string();
+    c.bench_function("astar word ladder", |b| {
+        b.iter(|| black_box(ladder.astar("aaaaa".to_string(), goal.clone(), |word: &String| hamming(word, &goal))))
+    });
+}
+
+fn pocket_cube(c: &mut Criterion) {
+    let pdb = CornerPdb::new();
+    let start = Cube::scramble(30, &mut ChaCha8Rng::seed_from_u64(42));
+    c.bench_function("astar 2x2 cube corner pdb", |b| b.iter(|| black_box(PocketCube.astar(start, Cube::solved(), &pdb))));
 }
+
+criterion_group!(benches, binary_tree, random_graph, contraction_hierarchy, grid_map, fifteen_puzzle, eight_puzzle, word_ladder, pocket_cube);
+criterion_main!(benches);
@@ -0,0 +1,59 @@
+//! Times shortest path queries between random node pairs of a 9th DIMACS
+//! Challenge road network, such as `USA-road-d.NY.gr`.
+//!
+//!     cargo run --release --example dimacs -- USA-road-d.NY.gr [queries]
+
+extern crate chappie;
+extern crate rand;
+extern crate rand_chacha;
+
+use std::env;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use chappie::astar::AStarBuffers;
+use chappie::formats::dimacs::parse_dimacs;
+use chappie::search::WeightedSearchSpace;
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("usage: dimacs <graph.gr> [queries]".to_owned());
+    }
+    let queries: usize = match args.get(1) {
+        Some(queries) => queries.parse().map_err(|err| format!("bad query count {:?}: {}", queries, err))?,
+        None => 100,
+    };
+    let text = fs::read_to_string(&args[0]).map_err(|err| format!("{}: {}", args[0], err))?;
+    let loading = Instant::now();
+    let graph = parse_dimacs(&text).map_err(|err| format!("{}: {}", args[0], err))?;
+    println!("{} nodes, {} arcs, loaded in {:?}", graph.node_count(), graph.edge_count(), loading.elapsed());
+    if graph.node_count() == 0 {
+        return Ok(());
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(0);
+    let mut buffers = AStarBuffers::new();
+    let (mut elapsed, mut expanded, mut found) = (Duration::default(), 0, 0);
+    for _ in 0..queries {
+        let (from, to) = (rng.gen_range(0..graph.node_count()), rng.gen_range(0..graph.node_count()));
+        found += graph.astar_with(&mut buffers, from, to, |_: &usize| 0).is_some() as usize;
+        elapsed += buffers.stats().elapsed;
+        expanded += buffers.stats().expanded;
+    }
+    let per_query = queries.max(1);
+    println!("dijkstra: {} queries, {} connected, {:?} and {} expansions per query",
+             queries, found, elapsed / per_query as u32, expanded / per_query as u64);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
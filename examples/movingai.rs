@@ -0,0 +1,66 @@
+//! Runs every query of a MovingAI scenario file with octile A* and reports
+//! suboptimality and time.
+//!
+//! Maps are looked up in `map-dir`, by default the scenario file's
+//! directory.
+//!
+//!     cargo run --release --example movingai -- arena.map.scen [map-dir]
+
+extern crate chappie;
+
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::time::Duration;
+
+use chappie::astar::AStarBuffers;
+use chappie::formats::movingai::{parse_map, parse_scenarios, run_scenario_with};
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.is_empty() || args.len() > 2 {
+        return Err("usage: movingai <file.scen> [map-dir]".to_owned());
+    }
+    let read = |path: &Path| fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err));
+    let scenario_path = Path::new(&args[0]);
+    let scenarios = parse_scenarios(&read(scenario_path)?).map_err(|err| format!("{}: {}", args[0], err))?;
+    let map_dir = args.get(1).map(Path::new).or_else(|| scenario_path.parent()).unwrap_or(Path::new("."));
+
+    let mut maps = HashMap::new();
+    for scenario in &scenarios {
+        if !maps.contains_key(&scenario.map) {
+            let path = map_dir.join(&scenario.map);
+            let map = parse_map(&read(&path)?).map_err(|err| format!("{}: {}", path.display(), err))?;
+            maps.insert(scenario.map.clone(), map);
+        }
+    }
+
+    let mut buffers = AStarBuffers::new();
+    let (mut solved, mut total, mut worst) = (0, 0.0, 1.0f64);
+    let (mut elapsed, mut expanded) = (Duration::default(), 0);
+    for scenario in &scenarios {
+        let run = run_scenario_with(&mut buffers, &maps[&scenario.map], scenario);
+        elapsed += run.elapsed;
+        expanded += run.expanded;
+        if let Some(suboptimality) = run.suboptimality() {
+            solved += 1;
+            total += suboptimality;
+            worst = worst.max(suboptimality);
+        }
+    }
+    println!("{} scenarios on {} maps, {} solved", scenarios.len(), maps.len(), solved);
+    if solved > 0 {
+        println!("suboptimality: mean {:.6}, max {:.6}", total / solved as f64, worst);
+    }
+    println!("time: {:?} total, {} expansions", elapsed, expanded);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,89 @@
+//! Runs a search between two nodes of a graph file and prints the path,
+//! its cost and the search statistics.
+//!
+//! Files ending in `.dot` are read as Graphviz DOT. Anything else is an edge
+//! list: one `from to [cost]` triple per line, with the cost defaulting to 1
+//! and `#` starting a comment.
+//!
+//!     cargo run --example search -- examples/roads.txt depot harbour astar
+
+extern crate chappie;
+
+use std::env;
+use std::fs;
+use std::process;
+
+use chappie::astar::AStarBuffers;
+use chappie::formats::dot::parse_dot;
+use chappie::graph::NamedGraph;
+use chappie::search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+use chappie::stats::SearchStats;
+
+fn parse_edge_list(text: &str) -> Result<NamedGraph, String> {
+    let mut named = NamedGraph::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let cost = match fields.len() {
+            2 => 1,
+            3 => fields[2].parse().map_err(|err| format!("line {}: bad cost {:?}: {}", number + 1, fields[2], err))?,
+            _ => return Err(format!("line {}: expected `from to [cost]`", number + 1)),
+        };
+        named.add_edge(fields[0], fields[1], cost);
+    }
+    Ok(named)
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.len() != 4 {
+        return Err("usage: search <graph-file> <from> <to> <dfs|astar>".to_owned());
+    }
+    let text = fs::read_to_string(&args[0]).map_err(|err| format!("{}: {}", args[0], err))?;
+    let named = if args[0].ends_with(".dot") {
+        parse_dot(&text).map_err(|err| format!("{}: {}", args[0], err))?
+    } else {
+        parse_edge_list(&text)?
+    };
+    let lookup = |name: &str| named.node(name).ok_or_else(|| format!("no node named {:?}", name));
+    let (from, to) = (lookup(&args[1])?, lookup(&args[2])?);
+    let graph = named.graph();
+
+    let (found, stats): (Option<Vec<usize>>, SearchStats) = match args[3].as_str() {
+        "dfs" => {
+            let mut buffers = SearchBuffers::new();
+            let found = graph.dfs_with(&mut buffers, from, to);
+            (found, buffers.stats().clone())
+        }
+        "astar" => {
+            let mut buffers = AStarBuffers::new();
+            let found = graph.astar_with(&mut buffers, from, to, |_: &usize| 0).map(|(actions, _)| actions);
+            (found, buffers.stats().clone())
+        }
+        other => return Err(format!("unknown algorithm {:?}", other)),
+    };
+
+    match found {
+        Some(actions) => {
+            let nodes = graph.walk(from, &actions);
+            let cost: u64 = nodes.iter().zip(&actions).map(|(&node, action)| graph.cost(&node, action)).sum();
+            let path: Vec<&str> = nodes.iter().map(|&node| named.name(node)).collect();
+            println!("path: {}", path.join(" -> "));
+            println!("cost: {}", cost);
+        }
+        None => println!("no path"),
+    }
+    println!("expanded: {}, generated: {}, duplicates: {}, max open: {}, max depth: {}, elapsed: {:?}",
+             stats.expanded, stats.generated, stats.duplicates, stats.max_open, stats.max_depth, stats.elapsed);
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(err) = run(&args) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
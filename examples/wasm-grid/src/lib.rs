@@ -0,0 +1,99 @@
+//! Grid pathfinding exported to JavaScript as plain wasm functions, with no
+//! bindings generator needed. See `index.html` for the page driving it.
+//!
+//!     cargo build --release --target wasm32-unknown-unknown
+//!     cp target/wasm32-unknown-unknown/release/chappie_wasm_grid.wasm .
+//!     python3 -m http.server
+
+extern crate chappie;
+
+use std::cell::RefCell;
+
+use chappie::astar::AStarBuffers;
+use chappie::grid::{manhattan, Direction, Grid};
+use chappie::search::WeightedSearchSpace;
+
+struct Demo {
+    grid: Grid,
+    buffers: AStarBuffers<Grid>,
+    // Cells of the last path found, flattened as x, y pairs.
+    path: Vec<u32>,
+    expanded: u64,
+}
+
+thread_local! {
+    static DEMO: RefCell<Demo> = RefCell::new(Demo {
+        grid: Grid::new(0, 0),
+        buffers: AStarBuffers::new(),
+        path: vec![],
+        expanded: 0,
+    });
+}
+
+/// Starts over with an empty `width` x `height` grid.
+#[no_mangle]
+pub extern "C" fn reset(width: u32, height: u32) {
+    DEMO.with(|demo| {
+        let mut demo = demo.borrow_mut();
+        demo.grid = Grid::new(width as usize, height as usize);
+        demo.path.clear();
+    });
+}
+
+/// Flips a cell between open and blocked, returning whether it is now
+/// blocked.
+#[no_mangle]
+pub extern "C" fn toggle(x: u32, y: u32) -> bool {
+    DEMO.with(|demo| {
+        let grid = &mut demo.borrow_mut().grid;
+        let cell = (x as usize, y as usize);
+        if !grid.contains(cell) {
+            return false;
+        }
+        let blocked = !grid.is_blocked(cell);
+        grid.set_blocked(cell, blocked);
+        blocked
+    })
+}
+
+/// Runs A* between two cells and returns the number of cells on the path,
+/// or -1 if the goal cannot be reached. The cells are then available
+/// through `path_ptr`.
+#[no_mangle]
+pub extern "C" fn find_path(start_x: u32, start_y: u32, goal_x: u32, goal_y: u32) -> i32 {
+    DEMO.with(|demo| {
+        let demo = &mut *demo.borrow_mut();
+        let (start, goal) = ((start_x as usize, start_y as usize), (goal_x as usize, goal_y as usize));
+        demo.path.clear();
+        let found = demo.grid.astar_with(&mut demo.buffers, start, goal, |cell: &_| manhattan(*cell, goal));
+        demo.expanded = demo.buffers.stats().expanded;
+        match found {
+            Some((actions, _)) => {
+                let mut cell = start;
+                demo.path.extend_from_slice(&[cell.0 as u32, cell.1 as u32]);
+                for action in actions {
+                    cell = step(cell, action);
+                    demo.path.extend_from_slice(&[cell.0 as u32, cell.1 as u32]);
+                }
+                (demo.path.len() / 2) as i32
+            }
+            None => -1,
+        }
+    })
+}
+
+/// Address of the last path as `x, y` pairs of `u32`s in wasm memory.
+#[no_mangle]
+pub extern "C" fn path_ptr() -> *const u32 {
+    DEMO.with(|demo| demo.borrow().path.as_ptr())
+}
+
+/// States expanded by the last search.
+#[no_mangle]
+pub extern "C" fn expanded() -> u32 {
+    DEMO.with(|demo| demo.borrow().expanded as u32)
+}
+
+fn step(cell: (usize, usize), direction: Direction) -> (usize, usize) {
+    direction.step(cell).expect("path stays on the grid")
+}
@@ -0,0 +1,45 @@
+/* C interface to chappie, built with the `ffi` feature. */
+
+#ifndef CHAPPIE_H
+#define CHAPPIE_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct chappie_graph chappie_graph;
+typedef struct chappie_path chappie_path;
+
+/* Estimate of the remaining cost from `node`, given the caller's data. */
+typedef uint64_t (*chappie_heuristic)(size_t node, void *user_data);
+
+/* Directed graph with `nodes` nodes numbered from zero and no edges. */
+chappie_graph *chappie_graph_new(size_t nodes);
+void chappie_graph_free(chappie_graph *graph);
+/* Adds a node and returns its number. */
+size_t chappie_graph_add_node(chappie_graph *graph);
+/* Returns 0 on success and -1 if a node is out of range. */
+int32_t chappie_graph_add_edge(chappie_graph *graph, size_t from, size_t to, uint64_t cost);
+
+/* Searches return NULL when `goal` is unreachable. */
+chappie_path *chappie_bfs(const chappie_graph *graph, size_t start, size_t goal);
+chappie_path *chappie_dijkstra(const chappie_graph *graph, size_t start, size_t goal);
+/* A NULL heuristic makes this Dijkstra's algorithm. */
+chappie_path *chappie_astar(const chappie_graph *graph, size_t start, size_t goal,
+                            chappie_heuristic heuristic, void *user_data);
+
+/* Number of nodes on the path, both ends included. */
+size_t chappie_path_len(const chappie_path *path);
+/* The path's nodes, valid until the path is freed. */
+const size_t *chappie_path_nodes(const chappie_path *path);
+uint64_t chappie_path_cost(const chappie_path *path);
+void chappie_path_free(chappie_path *path);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
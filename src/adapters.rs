@@ -0,0 +1,568 @@
+//! Search space adapters changing how states and actions look, which moves
+//! are allowed, or combining two spaces into one, for composing
+//! cross-cutting concerns instead of writing new implementations.
+
+use alloc::vec::{IntoIter, Vec};
+use core::hash::Hash;
+
+use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+
+/// Search space whose states are those of the inner space converted by
+/// `into` and back by `from`, which must be inverses of each other; see
+/// `SearchSpace::map_states`.
+pub struct MapStates<S, F, G> {
+    search_space: S,
+    into: F,
+    from: G,
+}
+
+impl<S, F, G> MapStates<S, F, G> {
+    pub fn new(search_space: S, into: F, from: G) -> MapStates<S, F, G> {
+        MapStates { search_space, into, from }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+impl<S, F, G, T> MapStates<S, F, G>
+where S: SearchSpace, F: Fn(S::State) -> T, G: Fn(&T) -> S::State {
+    fn map(&self, successors: S::Iterator) -> IntoIter<(S::Action, T)> {
+        successors.map(|(action, state)| (action, (self.into)(state))).collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl<S, F, G, T> SearchSpace for MapStates<S, F, G>
+where S: SearchSpace, F: Fn(S::State) -> T, G: Fn(&T) -> S::State, T: Hash + Clone + Eq {
+    type State = T;
+    type Action = S::Action;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &T) -> Self::Iterator {
+        self.map(self.search_space.expand(&(self.from)(state)))
+    }
+
+    fn expand_at(&self, state: &T, context: ExpandContext) -> Self::Iterator {
+        self.map(self.search_space.expand_at(&(self.from)(state), context))
+    }
+
+    fn canonicalize(&self, state: &T) -> Option<T> {
+        self.search_space.canonicalize(&(self.from)(state)).map(&self.into)
+    }
+
+    fn is_dead_end(&self, state: &T) -> bool {
+        self.search_space.is_dead_end(&(self.from)(state))
+    }
+}
+
+impl<S, F, G, T> WeightedSearchSpace for MapStates<S, F, G>
+where S: WeightedSearchSpace, F: Fn(S::State) -> T, G: Fn(&T) -> S::State, T: Hash + Clone + Eq {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &T, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(&(self.from)(state), action)
+    }
+
+    fn arrival_cost(&self, state: &T) -> Self::Cost {
+        self.search_space.arrival_cost(&(self.from)(state))
+    }
+
+    fn step_cost(&self, state: &T, action: &Self::Action, successor: &T) -> Self::Cost {
+        self.search_space.step_cost(&(self.from)(state), action, &(self.from)(successor))
+    }
+
+    fn dominates(&self, a: &T, b: &T) -> bool {
+        self.search_space.dominates(&(self.from)(a), &(self.from)(b))
+    }
+
+    fn dominance_key(&self, state: &T) -> u64 {
+        self.search_space.dominance_key(&(self.from)(state))
+    }
+
+    fn expand_weighted(&self, state: &T, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.map(self.search_space.expand_weighted(&(self.from)(state), context))
+    }
+}
+
+/// Search space whose actions are those of the inner space converted by
+/// `into` and back by `from`; see `SearchSpace::map_actions`.
+pub struct MapActions<S, F, G> {
+    search_space: S,
+    into: F,
+    from: G,
+}
+
+impl<S, F, G> MapActions<S, F, G> {
+    pub fn new(search_space: S, into: F, from: G) -> MapActions<S, F, G> {
+        MapActions { search_space, into, from }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+impl<S, F, G, B> MapActions<S, F, G>
+where S: SearchSpace, F: Fn(S::Action) -> B, G: Fn(&B) -> S::Action {
+    fn map(&self, successors: S::Iterator) -> IntoIter<(B, S::State)> {
+        successors.map(|(action, state)| ((self.into)(action), state)).collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl<S, F, G, B> SearchSpace for MapActions<S, F, G>
+where S: SearchSpace, F: Fn(S::Action) -> B, G: Fn(&B) -> S::Action {
+    type State = S::State;
+    type Action = B;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.map(self.search_space.expand(state))
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.map(self.search_space.expand_at(state, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S, F, G, B> WeightedSearchSpace for MapActions<S, F, G>
+where S: WeightedSearchSpace, F: Fn(S::Action) -> B, G: Fn(&B) -> S::Action {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &B) -> Self::Cost {
+        self.search_space.cost(state, &(self.from)(action))
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &B, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, &(self.from)(action), successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.map(self.search_space.expand_weighted(state, context))
+    }
+}
+
+/// Search space keeping only the moves of the inner space that `allow`
+/// accepts as `(state, action, successor)`; see
+/// `SearchSpace::filter_expansions`.
+pub struct FilterExpansions<S, P> {
+    search_space: S,
+    allow: P,
+}
+
+impl<S, P> FilterExpansions<S, P> {
+    pub fn new(search_space: S, allow: P) -> FilterExpansions<S, P> {
+        FilterExpansions { search_space, allow }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+impl<S, P> FilterExpansions<S, P>
+where S: SearchSpace, P: Fn(&S::State, &S::Action, &S::State) -> bool {
+    fn filter(&self, state: &S::State, successors: S::Iterator) -> IntoIter<(S::Action, S::State)> {
+        successors.filter(|(action, successor)| (self.allow)(state, action, successor)).collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl<S, P> SearchSpace for FilterExpansions<S, P>
+where S: SearchSpace, P: Fn(&S::State, &S::Action, &S::State) -> bool {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.filter(state, self.search_space.expand(state))
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.filter(state, self.search_space.expand_at(state, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S, P> WeightedSearchSpace for FilterExpansions<S, P>
+where S: WeightedSearchSpace, P: Fn(&S::State, &S::Action, &S::State) -> bool {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, action, successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.filter(state, self.search_space.expand_weighted(state, context))
+    }
+}
+
+type Pairs<S, T> = IntoIter<(
+    (<S as SearchSpace>::Action, <T as SearchSpace>::Action),
+    (<S as SearchSpace>::State, <T as SearchSpace>::State),
+)>;
+
+// Every combination of a successor from `first` with one from `second`.
+fn pairs<S, T>(first: S::Iterator, second: T::Iterator) -> Pairs<S, T>
+where S: SearchSpace, T: SearchSpace, S::Action: Clone, T::Action: Clone {
+    let first: Vec<_> = first.collect();
+    let mut pairs = Vec::new();
+    for (second_action, second_state) in second {
+        for (first_action, first_state) in &first {
+            pairs.push(((first_action.clone(), second_action.clone()), (first_state.clone(), second_state.clone())));
+        }
+    }
+    pairs.into_iter()
+}
+
+/// Search space over pairs of states of two spaces where every action moves
+/// both at once, as agents acting in lockstep; see `SearchSpace::product`.
+/// A pair where either state has no moves left has no successors. A joint
+/// action costs what its two halves cost together.
+pub struct Product<S, T> {
+    first: S,
+    second: T,
+}
+
+impl<S, T> Product<S, T> {
+    pub fn new(first: S, second: T) -> Product<S, T> {
+        Product { first, second }
+    }
+
+    pub fn first(&self) -> &S {
+        &self.first
+    }
+
+    pub fn second(&self) -> &T {
+        &self.second
+    }
+
+    pub fn into_inner(self) -> (S, T) {
+        (self.first, self.second)
+    }
+}
+
+impl<S, T> SearchSpace for Product<S, T>
+where S: SearchSpace, T: SearchSpace, S::Action: Clone, T::Action: Clone {
+    type State = (S::State, T::State);
+    type Action = (S::Action, T::Action);
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, (first, second): &Self::State) -> Self::Iterator {
+        pairs::<S, T>(self.first.expand(first), self.second.expand(second))
+    }
+
+    fn expand_at(&self, (first, second): &Self::State, context: ExpandContext) -> Self::Iterator {
+        pairs::<S, T>(self.first.expand_at(first, context), self.second.expand_at(second, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        canonicalize_pair(&self.first, &self.second, state)
+    }
+
+    fn is_dead_end(&self, (first, second): &Self::State) -> bool {
+        self.first.is_dead_end(first) || self.second.is_dead_end(second)
+    }
+}
+
+impl<S, T> WeightedSearchSpace for Product<S, T>
+where S: WeightedSearchSpace, T: WeightedSearchSpace<Cost=S::Cost>, S::Action: Clone, T::Action: Clone {
+    type Cost = S::Cost;
+
+    fn cost(&self, (first, second): &Self::State, (first_action, second_action): &Self::Action) -> Self::Cost {
+        self.first.cost(first, first_action) + self.second.cost(second, second_action)
+    }
+
+    fn arrival_cost(&self, (first, second): &Self::State) -> Self::Cost {
+        self.first.arrival_cost(first) + self.second.arrival_cost(second)
+    }
+
+    fn step_cost(&self, (first, second): &Self::State, (first_action, second_action): &Self::Action,
+                 (first_next, second_next): &Self::State) -> Self::Cost {
+        self.first.step_cost(first, first_action, first_next) + self.second.step_cost(second, second_action, second_next)
+    }
+
+    fn dominates(&self, (a_first, a_second): &Self::State, (b_first, b_second): &Self::State) -> bool {
+        dominates_pair(&self.first, &self.second, (a_first, a_second), (b_first, b_second))
+    }
+
+    fn dominance_key(&self, (first, second): &Self::State) -> u64 {
+        dominance_key_pair(&self.first, &self.second, first, second)
+    }
+}
+
+/// Action of an `Interleaved` space, moving one of its two spaces.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Either<A, B> {
+    First(A),
+    Second(B),
+}
+
+/// Search space over pairs of states of two spaces where every action moves
+/// one of them while the other stays put, as agents taking turns or a
+/// position paired with a mode that can be switched; see
+/// `SearchSpace::interleave`. Successors moving the first state come first.
+pub struct Interleaved<S, T> {
+    first: S,
+    second: T,
+}
+
+impl<S, T> Interleaved<S, T> {
+    pub fn new(first: S, second: T) -> Interleaved<S, T> {
+        Interleaved { first, second }
+    }
+
+    pub fn first(&self) -> &S {
+        &self.first
+    }
+
+    pub fn second(&self) -> &T {
+        &self.second
+    }
+
+    pub fn into_inner(self) -> (S, T) {
+        (self.first, self.second)
+    }
+}
+
+type Turns<S, T> = IntoIter<(
+    Either<<S as SearchSpace>::Action, <T as SearchSpace>::Action>,
+    (<S as SearchSpace>::State, <T as SearchSpace>::State),
+)>;
+
+// Successors of `state` moving its first half by `first` or its second by
+// `second`.
+fn turns<S, T>(state: &(S::State, T::State), first: S::Iterator, second: T::Iterator) -> Turns<S, T>
+where S: SearchSpace, T: SearchSpace {
+    first.map(|(action, next)| (Either::First(action), (next, state.1.clone())))
+        .chain(second.map(|(action, next)| (Either::Second(action), (state.0.clone(), next))))
+        .collect::<Vec<_>>()
+        .into_iter()
+}
+
+impl<S, T> SearchSpace for Interleaved<S, T> where S: SearchSpace, T: SearchSpace {
+    type State = (S::State, T::State);
+    type Action = Either<S::Action, T::Action>;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        turns::<S, T>(state, self.first.expand(&state.0), self.second.expand(&state.1))
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        turns::<S, T>(state, self.first.expand_at(&state.0, context), self.second.expand_at(&state.1, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        canonicalize_pair(&self.first, &self.second, state)
+    }
+
+    fn is_dead_end(&self, (first, second): &Self::State) -> bool {
+        self.first.is_dead_end(first) || self.second.is_dead_end(second)
+    }
+}
+
+impl<S, T> WeightedSearchSpace for Interleaved<S, T>
+where S: WeightedSearchSpace, T: WeightedSearchSpace<Cost=S::Cost> {
+    type Cost = S::Cost;
+
+    fn cost(&self, (first, second): &Self::State, action: &Self::Action) -> Self::Cost {
+        match *action {
+            Either::First(ref action) => self.first.cost(first, action),
+            Either::Second(ref action) => self.second.cost(second, action),
+        }
+    }
+
+    /// Entering a pair pays the arrival cost of whichever half moved, which
+    /// only `step_cost` knows; this is the sum for both.
+    fn arrival_cost(&self, (first, second): &Self::State) -> Self::Cost {
+        self.first.arrival_cost(first) + self.second.arrival_cost(second)
+    }
+
+    fn step_cost(&self, (first, second): &Self::State, action: &Self::Action, (first_next, second_next): &Self::State)
+        -> Self::Cost {
+        match *action {
+            Either::First(ref action) => self.first.step_cost(first, action, first_next),
+            Either::Second(ref action) => self.second.step_cost(second, action, second_next),
+        }
+    }
+
+    fn dominates(&self, (a_first, a_second): &Self::State, (b_first, b_second): &Self::State) -> bool {
+        dominates_pair(&self.first, &self.second, (a_first, a_second), (b_first, b_second))
+    }
+
+    fn dominance_key(&self, (first, second): &Self::State) -> u64 {
+        dominance_key_pair(&self.first, &self.second, first, second)
+    }
+}
+
+fn canonicalize_pair<S, T>(first: &S, second: &T, (a, b): &(S::State, T::State)) -> Option<(S::State, T::State)>
+where S: SearchSpace, T: SearchSpace {
+    match (first.canonicalize(a), second.canonicalize(b)) {
+        (None, None) => None,
+        (a_canonical, b_canonical) => Some((a_canonical.unwrap_or_else(|| a.clone()),
+                                            b_canonical.unwrap_or_else(|| b.clone()))),
+    }
+}
+
+// Whether `a` dominates `b` in each half it differs from it in.
+fn dominates_pair<S, T>(first: &S, second: &T, a: (&S::State, &T::State), b: (&S::State, &T::State)) -> bool
+where S: WeightedSearchSpace, T: WeightedSearchSpace {
+    a != b && (a.0 == b.0 || first.dominates(a.0, b.0)) && (a.1 == b.1 || second.dominates(a.1, b.1))
+}
+
+// Key mixing the keys of both halves, which are equal between states that
+// dominate each other in each half or are the same in it.
+fn dominance_key_pair<S, T>(first: &S, second: &T, a: &S::State, b: &T::State) -> u64
+where S: WeightedSearchSpace, T: WeightedSearchSpace {
+    first.dominance_key(a).wrapping_mul(0x9e37_79b9_7f4a_7c15) ^ second.dominance_key(b)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Cell, Direction};
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::Either;
+
+    // Positions 0 to 9 on a line, stepping left or right at a cost of 2 to
+    // the right and 1 to the left.
+    struct Line;
+
+    impl SearchSpace for Line {
+        type State = u8;
+        type Action = i8;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &state: &Self::State) -> Self::Iterator {
+            let mut moves = vec![];
+            if state < 9 {
+                moves.push((1, state + 1));
+            }
+            if state > 0 {
+                moves.push((-1, state - 1));
+            }
+            moves.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Line {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, &action: &Self::Action) -> Self::Cost {
+            if action > 0 { 2 } else { 1 }
+        }
+    }
+
+    #[test]
+    pub fn test_product() {
+        let product = Line.product(Line);
+        // Moving both at once keeps the parity of their distance.
+        assert_eq!(product.bfs_iter((0, 0)).count(), 50);
+        assert_eq!(product.expand(&(0, 5)).collect::<Vec<_>>(), vec![((1, 1), (1, 6)), ((1, -1), (1, 4))]);
+        // Both have to move every time, so the first walks back and forth.
+        assert_eq!(product.astar((0, 0), (1, 3), |_: &(u8, u8)| 0).map(|found| found.1), Some(11));
+        assert_eq!(product.bfs_iter((0, 0)).find(|&(_, state)| state == (0, 1)), None);
+    }
+
+    #[test]
+    pub fn test_interleave() {
+        let interleaved = Line.interleave(Line);
+        assert_eq!(interleaved.bfs_iter((0, 0)).count(), 100);
+        let (actions, cost) = interleaved.astar((3, 0), (1, 2), |_: &(u8, u8)| 0).unwrap();
+        assert_eq!(cost, 6);
+        assert_eq!(actions.iter().filter(|action| matches!(action, Either::First(-1))).count(), 2);
+        assert_eq!(actions.iter().filter(|action| matches!(action, Either::Second(1))).count(), 2);
+    }
+
+    #[test]
+    pub fn test_map_states() {
+        let grid = random_grid(10, 10, 0.2, &mut StdRng::seed_from_u64(7));
+        let to_goal = |cell: &Cell| manhattan(*cell, (9, 9));
+        let found = grid.astar((0, 0), (9, 9), to_goal);
+        let indexed = grid.clone().map_states(|(x, y): Cell| y * 10 + x, |&index: &usize| (index % 10, index / 10));
+        assert_eq!(indexed.astar(0, 99, |&index: &usize| to_goal(&(index % 10, index / 10))), found);
+        assert_eq!(indexed.bfs_iter(0).count(), grid.bfs_iter((0, 0)).count());
+    }
+
+    #[test]
+    pub fn test_map_actions() {
+        let grid = random_grid(10, 10, 0.2, &mut StdRng::seed_from_u64(7));
+        let to_goal = |cell: &Cell| manhattan(*cell, (9, 9));
+        let (actions, cost) = grid.astar((0, 0), (9, 9), to_goal).unwrap();
+        let index = |direction: Direction| Direction::ALL.iter().position(|&known| known == direction).unwrap();
+        let indexed = grid.clone().map_actions(index, |&index: &usize| Direction::ALL[index]);
+        let indices: Vec<_> = actions.into_iter().map(index).collect();
+        assert_eq!(indexed.astar((0, 0), (9, 9), to_goal), Some((indices, cost)));
+    }
+
+    #[test]
+    pub fn test_filter_expansions() {
+        let grid = random_grid(10, 10, 0.0, &mut StdRng::seed_from_u64(7));
+        let to_goal = |cell: &Cell| manhattan(*cell, (9, 9));
+        // A wall along column 5 with a gap in the last row.
+        let walled = grid.clone().filter_expansions(|_: &Cell, _: &Direction, next: &Cell| next.0 != 5 || next.1 == 9);
+        let (_, cost) = walled.astar((0, 0), (9, 9), to_goal).unwrap();
+        assert_eq!(cost, 18);
+        assert_eq!(walled.astar((0, 0), (9, 0), |cell: &Cell| manhattan(*cell, (9, 0))).unwrap().1, 27);
+        assert!(walled.bfs_iter((0, 0)).all(|(_, cell)| cell.0 != 5 || cell.1 == 9));
+    }
+}
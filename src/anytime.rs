@@ -0,0 +1,533 @@
+//! Anytime search: iterators yielding ever cheaper solutions, each with a
+//! lower bound on the cheapest cost, until the last is proven optimal or the
+//! caller stops asking.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use hashbrown::HashMap;
+
+use cost::Scale;
+use heuristic::Heuristic;
+use search::{ExpandContext, SearchGoal, WeightedSearchSpace};
+use stats::SearchStats;
+use visited::RevisitPolicy;
+
+/// Solution found by an anytime search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchResult<A, C> {
+    pub actions: Vec<A>,
+    pub cost: C,
+    /// Cost no path can beat, as far as the search has proven so far.
+    pub lower_bound: C,
+}
+
+impl<A, C: PartialEq> SearchResult<A, C> {
+    /// Whether the solution is proven to be a cheapest one.
+    pub fn is_optimal(&self) -> bool {
+        self.cost == self.lower_bound
+    }
+}
+
+struct Node<S: WeightedSearchSpace + ?Sized> {
+    state: S::State,
+    parent: Option<(usize, S::Action)>,
+    g: S::Cost,
+    h: S::Cost,
+    depth: usize,
+}
+
+/// Anytime weighted A*: best-first on `g + weight * h`, carrying on past
+/// each goal with the path cost as a cut-off. The first solution comes as
+/// quickly as weighted A* finds it; later ones only improve on it, and the
+/// search ends once no open node could lead to a cheaper one.
+///
+/// When the search ends by proof rather than by finding a new solution, the
+/// last solution is yielded once more with its bound raised to its cost.
+pub struct AnytimeAStar<'a, S: WeightedSearchSpace + ?Sized + 'a, G, H> {
+    search_space: &'a S,
+    goal: G,
+    heuristic: H,
+    weight: f64,
+    open: BinaryHeap<Reverse<(S::Cost, usize)>>,
+    best: HashMap<S::State, usize>,
+    nodes: Vec<Node<S>>,
+    incumbent: Option<SearchResult<S::Action, S::Cost>>,
+    finished: bool,
+    stats: SearchStats,
+    revisit: RevisitPolicy,
+    cost_bound: Option<S::Cost>,
+    bound_reached: bool,
+}
+
+impl<'a, S, G, H> AnytimeAStar<'a, S, G, H>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, S::Cost: Scale, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    pub fn new(search_space: &'a S, start: S::State, goal: G, heuristic: H, weight: f64)
+        -> AnytimeAStar<'a, S, G, H> {
+        let h = heuristic.estimate(&start);
+        let mut search = AnytimeAStar {
+            search_space,
+            goal,
+            heuristic,
+            weight,
+            open: BinaryHeap::new(),
+            best: HashMap::new(),
+            nodes: Vec::new(),
+            incumbent: None,
+            finished: false,
+            stats: SearchStats::new(),
+            revisit: RevisitPolicy::default(),
+            cost_bound: None,
+            bound_reached: false,
+        };
+        search.open.push(Reverse((h.scale(weight), 0)));
+        search.best.insert(search_space.canonicalize(&start).unwrap_or_else(|| start.clone()), 0);
+        search.nodes.push(Node { state: start, parent: None, g: S::Cost::default(), h, depth: 0 });
+        search.stats.record_open(1);
+        search
+    }
+
+    /// Sets what the search does on reaching a state again.
+    pub fn with_revisit(mut self, revisit: RevisitPolicy) -> Self {
+        self.revisit = revisit;
+        self
+    }
+
+    /// Only looks for paths costing at most `bound`.
+    pub fn with_cost_bound(mut self, bound: S::Cost) -> Self {
+        self.cost_bound = Some(bound);
+        self
+    }
+
+    /// Whether nodes were dropped for exceeding the cost bound, so that
+    /// finding no path does not prove there is none.
+    pub fn bound_reached(&self) -> bool {
+        self.bound_reached
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Cheapest solution found so far.
+    pub fn incumbent(&self) -> Option<&SearchResult<S::Action, S::Cost>> {
+        self.incumbent.as_ref()
+    }
+
+    fn is_current(&self, index: usize) -> bool {
+        let state = &self.nodes[index].state;
+        let key = self.search_space.canonicalize(state);
+        self.revisit == RevisitPolicy::AlwaysRevisit || self.best[key.as_ref().unwrap_or(state)] == index
+    }
+
+    fn is_cut(&mut self, g: S::Cost, h: S::Cost) -> bool {
+        if self.cost_bound.is_some_and(|bound| g + h > bound) {
+            self.bound_reached = true;
+            return true;
+        }
+        self.incumbent.as_ref().is_some_and(|incumbent| g + h >= incumbent.cost)
+    }
+
+    fn path_to(&self, mut index: usize) -> Vec<S::Action> {
+        let mut actions = Vec::new();
+        while let Some((parent, ref action)) = self.nodes[index].parent {
+            actions.push(action.clone());
+            index = parent;
+        }
+        actions.reverse();
+        actions
+    }
+
+    // Lowest g + h over the open nodes that could still improve on `cost`.
+    fn lower_bound(&self, cost: S::Cost) -> S::Cost {
+        self.open.iter()
+            .map(|&Reverse((_, index))| index)
+            .filter(|&index| self.is_current(index))
+            .map(|index| self.nodes[index].g + self.nodes[index].h)
+            .fold(cost, |lowest, f| lowest.min(f))
+    }
+}
+
+impl<'a, S, G, H> Iterator for AnytimeAStar<'a, S, G, H>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, S::Cost: Scale, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    type Item = SearchResult<S::Action, S::Cost>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        while let Some(Reverse((_, index))) = self.open.pop() {
+            let Node { g, h, depth, .. } = self.nodes[index];
+            if !self.is_current(index) || self.is_cut(g, h) {
+                continue;
+            }
+            if self.goal.is_goal(&self.nodes[index].state) {
+                let result = SearchResult { actions: self.path_to(index), cost: g, lower_bound: self.lower_bound(g) };
+                self.incumbent = Some(result.clone());
+                return Some(result);
+            }
+            self.stats.record_expanded(depth);
+            for (action, state) in self.search_space.expand_weighted(&self.nodes[index].state, ExpandContext { depth, cost: g }) {
+                self.stats.record_generated(depth + 1);
+                let child_g = g + self.search_space.step_cost(&self.nodes[index].state, &action, &state);
+                let key = self.search_space.canonicalize(&state);
+                if let Some(&known) = self.best.get(key.as_ref().unwrap_or(&state)) {
+                    if !self.revisit.revisits(self.nodes[known].g, child_g) {
+                        self.stats.record_duplicate(depth + 1);
+                        continue;
+                    }
+                }
+                if self.search_space.is_dead_end(&state) {
+                    self.stats.record_dead_end();
+                    continue;
+                }
+                let child_h = self.heuristic.estimate(&state);
+                if self.is_cut(child_g, child_h) {
+                    continue;
+                }
+                self.best.insert(key.unwrap_or_else(|| state.clone()), self.nodes.len());
+                self.open.push(Reverse((child_g + child_h.scale(self.weight), self.nodes.len())));
+                self.nodes.push(Node { state, parent: Some((index, action)), g: child_g, h: child_h, depth: depth + 1 });
+                self.stats.record_depth(depth + 1);
+            }
+            self.stats.record_open(self.open.len());
+        }
+        self.finished = true;
+        match self.incumbent {
+            Some(ref mut incumbent) if !incumbent.is_optimal() => {
+                incumbent.lower_bound = incumbent.cost;
+                Some(incumbent.clone())
+            }
+            _ => None,
+        }
+    }
+}
+
+struct AraNode<S: WeightedSearchSpace + ?Sized> {
+    state: S::State,
+    parent: Option<(usize, S::Action)>,
+    g: S::Cost,
+    h: S::Cost,
+    depth: usize,
+    key: S::Cost,
+    open: bool,
+    closed: bool,
+    inconsistent: bool,
+}
+
+/// Anytime repairing A* (ARA*): weighted A* run again and again with the
+/// weight ε lowered by `decrement` each time, down to 1. Rather than
+/// starting over, each run goes on from the last one's open list, plus the
+/// states whose cost dropped after they were expanded - the INCONS list -
+/// so it only repairs what the lower weight changes.
+///
+/// Each solution costs at most ε times the cheapest one, and comes with the
+/// lowest `g + h` over the open and INCONS states as its lower bound. The
+/// heuristic must be consistent. Solutions are only yielded when cheaper
+/// than the last one; the search ends after the run with ε of 1, yielding
+/// the last solution once more with its bound raised to its cost if that
+/// was not yet proven.
+pub struct AraStar<'a, S: WeightedSearchSpace + ?Sized + 'a, G, H> {
+    search_space: &'a S,
+    goal: G,
+    heuristic: H,
+    epsilon: f64,
+    decrement: f64,
+    open: BinaryHeap<Reverse<(S::Cost, usize)>>,
+    index: HashMap<S::State, usize>,
+    nodes: Vec<AraNode<S>>,
+    inconsistent: Vec<usize>,
+    reached: Option<usize>,
+    incumbent: Option<SearchResult<S::Action, S::Cost>>,
+    finished: bool,
+    stats: SearchStats,
+}
+
+impl<'a, S, G, H> AraStar<'a, S, G, H>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, S::Cost: Scale, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    pub fn new(search_space: &'a S, start: S::State, goal: G, heuristic: H, epsilon: f64, decrement: f64)
+        -> AraStar<'a, S, G, H> {
+        assert!(epsilon >= 1.0, "ARA* weight below 1");
+        assert!(decrement > 0.0, "ARA* weight decrement not positive");
+        let mut search = AraStar {
+            search_space,
+            goal,
+            heuristic,
+            epsilon,
+            decrement,
+            open: BinaryHeap::new(),
+            index: HashMap::new(),
+            nodes: Vec::new(),
+            inconsistent: Vec::new(),
+            reached: None,
+            incumbent: None,
+            finished: false,
+            stats: SearchStats::new(),
+        };
+        search.reach(start, None, S::Cost::default(), 0);
+        search
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Cheapest solution found so far.
+    pub fn incumbent(&self) -> Option<&SearchResult<S::Action, S::Cost>> {
+        self.incumbent.as_ref()
+    }
+
+    /// Weight of the next run, or of the last one once the search ended.
+    pub fn epsilon(&self) -> f64 {
+        self.epsilon
+    }
+
+    // Records a path of cost `g` to `state`, opening it unless it is known
+    // at no more, or putting it on the INCONS list if already expanded in
+    // this run.
+    fn reach(&mut self, state: S::State, parent: Option<(usize, S::Action)>, g: S::Cost, depth: usize) {
+        let key = self.search_space.canonicalize(&state).unwrap_or_else(|| state.clone());
+        let index = match self.index.get(&key) {
+            Some(&index) if self.nodes[index].g <= g => {
+                self.stats.record_duplicate(depth);
+                return;
+            }
+            Some(&index) => {
+                let node = &mut self.nodes[index];
+                node.parent = parent;
+                node.g = g;
+                node.depth = depth;
+                index
+            }
+            None => {
+                let h = self.heuristic.estimate(&state);
+                self.index.insert(key, self.nodes.len());
+                self.nodes.push(AraNode {
+                    state, parent, g, h, depth,
+                    key: g, open: false, closed: false, inconsistent: false,
+                });
+                self.stats.record_depth(depth);
+                self.nodes.len() - 1
+            }
+        };
+        if self.goal.is_goal(&self.nodes[index].state)
+            && self.reached.is_none_or(|reached| g < self.nodes[reached].g) {
+            self.reached = Some(index);
+        }
+        if !self.nodes[index].closed {
+            self.open(index);
+        } else if !self.nodes[index].inconsistent {
+            self.nodes[index].inconsistent = true;
+            self.inconsistent.push(index);
+        }
+    }
+
+    fn open(&mut self, index: usize) {
+        let node = &mut self.nodes[index];
+        node.open = true;
+        node.key = node.g + node.h.scale(self.epsilon);
+        self.open.push(Reverse((node.key, index)));
+    }
+
+    // Lowest key among the open nodes, dropping stale heap entries.
+    fn peek(&mut self) -> Option<S::Cost> {
+        while let Some(&Reverse((key, index))) = self.open.peek() {
+            if self.nodes[index].open && self.nodes[index].key == key {
+                return Some(key);
+            }
+            self.open.pop();
+        }
+        None
+    }
+
+    // Expands nodes until none open could lead to a goal cheaper, under the
+    // current weight, than the one reached.
+    fn improve_path(&mut self) {
+        while let Some(key) = self.peek() {
+            if self.reached.is_some_and(|reached| self.nodes[reached].g <= key) {
+                break;
+            }
+            let Reverse((_, index)) = self.open.pop().unwrap();
+            let node = &mut self.nodes[index];
+            node.open = false;
+            node.closed = true;
+            let (g, depth) = (node.g, node.depth);
+            self.stats.record_expanded(depth);
+            let state = self.nodes[index].state.clone();
+            for (action, next) in self.search_space.expand_weighted(&state, ExpandContext { depth, cost: g }) {
+                self.stats.record_generated(depth + 1);
+                if self.search_space.is_dead_end(&next) {
+                    self.stats.record_dead_end();
+                    continue;
+                }
+                let next_g = g + self.search_space.step_cost(&state, &action, &next);
+                self.reach(next, Some((index, action)), next_g, depth + 1);
+            }
+            self.stats.record_open(self.open.len());
+        }
+    }
+
+    // Lowest g + h over the open and INCONS nodes, which no path beats.
+    fn lower_bound(&self, cost: S::Cost) -> S::Cost {
+        self.nodes.iter()
+            .filter(|node| node.open || node.inconsistent)
+            .map(|node| node.g + node.h)
+            .fold(cost, |lowest, f| lowest.min(f))
+    }
+
+    // Lowers the weight and sets up the next run: the INCONS list joins the
+    // open list, every key is recomputed, and nothing counts as expanded.
+    fn next_run(&mut self) {
+        self.epsilon = (self.epsilon - self.decrement).max(1.0);
+        for index in self.inconsistent.drain(..) {
+            self.nodes[index].inconsistent = false;
+            self.nodes[index].open = true;
+        }
+        self.open.clear();
+        for index in 0..self.nodes.len() {
+            self.nodes[index].closed = false;
+            if self.nodes[index].open {
+                self.open(index);
+            }
+        }
+    }
+
+    // Path to the node at `index` by its parents, and its cost. Parents
+    // move to cheaper paths as found, before the costs of the nodes below
+    // them catch up, so this may cost less than the node's `g`.
+    fn path_to(&self, mut index: usize) -> (Vec<S::Action>, S::Cost) {
+        let mut actions = Vec::new();
+        let mut cost = S::Cost::default();
+        while let Some((parent, ref action)) = self.nodes[index].parent {
+            cost = cost + self.search_space.step_cost(&self.nodes[parent].state, action, &self.nodes[index].state);
+            actions.push(action.clone());
+            index = parent;
+        }
+        actions.reverse();
+        (actions, cost)
+    }
+}
+
+impl<'a, S, G, H> Iterator for AraStar<'a, S, G, H>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, S::Cost: Scale, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    type Item = SearchResult<S::Action, S::Cost>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            self.improve_path();
+            let reached = match self.reached {
+                Some(reached) => reached,
+                None => {
+                    self.finished = true;
+                    return None;
+                }
+            };
+            let (actions, cost) = self.path_to(reached);
+            self.finished = self.epsilon <= 1.0;
+            let lower_bound = if self.finished { cost } else { self.lower_bound(cost) };
+            if !self.finished {
+                self.next_run();
+            }
+            match self.incumbent {
+                Some(ref mut incumbent) if incumbent.cost <= cost => {
+                    if lower_bound > incumbent.lower_bound {
+                        incumbent.lower_bound = lower_bound;
+                        if self.finished {
+                            return Some(incumbent.clone());
+                        }
+                    }
+                }
+                _ => {
+                    let result = SearchResult { actions, cost, lower_bound };
+                    self.incumbent = Some(result.clone());
+                    return Some(result);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::manhattan;
+    use search::WeightedSearchSpace;
+
+    #[test]
+    pub fn test_anytime_astar() {
+        let mut grid = random_grid(40, 40, 0.25, &mut StdRng::seed_from_u64(4));
+        let (start, goal) = ((0, 0), (39, 39));
+        grid.set_blocked(start, false);
+        grid.set_blocked(goal, false);
+        let to_goal = |cell: &_| manhattan(*cell, goal);
+        let optimal = grid.astar(start, goal, to_goal).unwrap().1;
+
+        let results: Vec<_> = grid.anytime(start, goal, to_goal, 3.0).collect();
+        assert!(!results.is_empty());
+        for pair in results.windows(2) {
+            assert!(pair[1].cost <= pair[0].cost && pair[1].lower_bound >= pair[0].lower_bound);
+        }
+        for result in &results {
+            assert_eq!(result.actions.len() as u64, result.cost);
+            assert!(result.lower_bound <= optimal && result.cost >= optimal);
+        }
+        let last = results.last().unwrap();
+        assert!(last.is_optimal());
+        assert_eq!(last.cost, optimal);
+
+        // Stopping early still leaves a usable solution.
+        let mut search = grid.anytime(start, goal, to_goal, 3.0);
+        let first = search.next().unwrap();
+        assert_eq!(search.incumbent(), Some(&first));
+        assert_eq!(grid.anytime((0, 0), (0, 0), to_goal, 2.0).collect::<Vec<_>>().len(), 1);
+    }
+
+    #[test]
+    pub fn test_ara_star() {
+        let mut grid = random_grid(40, 40, 0.25, &mut StdRng::seed_from_u64(4));
+        let (start, goal) = ((0, 0), (39, 39));
+        grid.set_blocked(start, false);
+        grid.set_blocked(goal, false);
+        let to_goal = |cell: &_| manhattan(*cell, goal);
+        let optimal = grid.astar(start, goal, to_goal).unwrap().1;
+
+        let mut search = grid.ara_star(start, goal, to_goal, 3.0, 0.5);
+        let first = search.next().unwrap();
+        assert_eq!(search.incumbent(), Some(&first));
+        assert!(first.cost as f64 <= 3.0 * optimal as f64);
+        let results: Vec<_> = Some(first).into_iter().chain(search.by_ref()).collect();
+        assert_eq!(search.epsilon(), 1.0);
+        for pair in results.windows(2) {
+            assert!(pair[1].cost <= pair[0].cost && pair[1].lower_bound >= pair[0].lower_bound);
+        }
+        for result in &results {
+            assert_eq!(result.actions.len() as u64, result.cost);
+            assert!(result.lower_bound <= optimal && result.cost >= optimal);
+        }
+        let last = results.last().unwrap();
+        assert!(last.is_optimal());
+        assert_eq!(last.cost, optimal);
+
+        // Reusing each run's work expands fewer states than starting over.
+        let repeated: u64 = [3.0, 2.5, 2.0, 1.5, 1.0].iter().map(|&epsilon| {
+            let mut search = grid.ara_star(start, goal, to_goal, epsilon, 10.0);
+            search.by_ref().count();
+            search.stats().expanded
+        }).sum();
+        assert!(search.stats().expanded < repeated);
+
+        assert_eq!(grid.ara_star((0, 0), (0, 0), to_goal, 2.0, 1.0).count(), 1);
+        grid.set_blocked((38, 39), true);
+        grid.set_blocked((39, 38), true);
+        assert_eq!(grid.ara_star(start, goal, to_goal, 2.0, 1.0).count(), 0);
+    }
+}
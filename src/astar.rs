@@ -0,0 +1,1360 @@
+use alloc::boxed::Box;
+use alloc::collections::{BTreeSet, BinaryHeap};
+use alloc::vec::Vec;
+use core::cmp::{Ordering, Reverse};
+use core::hash::Hash;
+use core::mem;
+use core::ops::Bound;
+use core::time::Duration;
+
+use hashbrown::HashMap;
+
+
+use cost::Scale;
+use heuristic::Heuristic;
+use observer::{NodeCost, Observer};
+use search::{ExpandContext, SearchGoal, SearchSpace, WeightedSearchSpace};
+use session::{Cutoff, SearchSession, Step};
+use stats::{hash_entry_bytes, LayerProfile, SearchStats};
+use stopwatch::Stopwatch;
+#[cfg(feature = "tracing")]
+use trace;
+use visited::{RankableState, RevisitPolicy};
+#[cfg(all(feature = "json", feature = "std"))]
+use checkpoint::{self, CheckpointError};
+#[cfg(all(feature = "json", feature = "std"))]
+use serde::Serialize;
+#[cfg(all(feature = "json", feature = "std"))]
+use serde::de::DeserializeOwned;
+
+struct Node<S: WeightedSearchSpace + ?Sized> {
+    state: S::State,
+    parent: Option<usize>,
+    // Action from the parent, taken once the path through it is returned.
+    action: Option<S::Action>,
+    g: S::Cost,
+    depth: usize,
+    expanded: bool,
+}
+
+// Node of a search saved by a checkpoint, holding references when written
+// and owned values when read back.
+#[cfg(all(feature = "json", feature = "std"))]
+#[derive(Serialize, Deserialize)]
+struct SavedNode<T, A, C> {
+    state: T,
+    parent: Option<usize>,
+    action: Option<A>,
+    g: C,
+    depth: usize,
+    expanded: bool,
+}
+
+// Search in progress saved by a checkpoint, with the open list as f and node
+// index pairs.
+#[cfg(all(feature = "json", feature = "std"))]
+#[derive(Serialize, Deserialize)]
+struct SavedSearch<T, A, C> {
+    nodes: Vec<SavedNode<T, A, C>>,
+    open: Vec<(C, usize)>,
+    closest: Option<(C, usize)>,
+    bound_reached: bool,
+    stats: SearchStats,
+}
+
+/// State reached by a best-first search, as kept by `AStarBuffers` with
+/// `with_closed_list`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ClosedNode<S, C> {
+    pub state: S,
+    /// Cost of the path the search reached the state by.
+    pub g: C,
+    /// Index of the node the state was reached from; `None` for the start.
+    pub parent: Option<usize>,
+    pub depth: usize,
+    /// Whether the successors of the state were generated; nodes left on
+    /// the open list were not.
+    pub expanded: bool,
+}
+
+type Solution<S> = Option<(Vec<<S as SearchSpace>::Action>, <S as WeightedSearchSpace>::Cost)>;
+
+// Numbers the profile layer of an f-value.
+type FLayers<C> = Box<dyn Fn(&C) -> usize>;
+
+/// Which of the open nodes with the lowest f a best-first search expands
+/// first. Ties are common on grids, where the choice decides how many nodes
+/// of equal f get expanded before the goal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TieBreak {
+    /// Oldest node first.
+    Fifo,
+    /// Newest node first.
+    #[default]
+    Lifo,
+    /// Node with the highest g first, which is the one closest to the goal
+    /// by the heuristic; newest first among those.
+    HigherG,
+    /// Order shuffled by the seed, the same on every run.
+    Random(u64),
+}
+
+impl TieBreak {
+    // Key ordering the entry among ties, highest first.
+    fn key<C: Default>(self, g: C, index: usize) -> (C, u64) {
+        match self {
+            TieBreak::Fifo => (C::default(), !(index as u64)),
+            TieBreak::Lifo => (C::default(), index as u64),
+            TieBreak::HigherG => (g, index as u64),
+            TieBreak::Random(seed) => (C::default(), mix(seed ^ index as u64)),
+        }
+    }
+}
+
+// SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+struct OpenEntry<C> {
+    f: C,
+    tie: (C, u64),
+    index: usize,
+}
+
+impl<C: Ord> Ord for OpenEntry<C> {
+    fn cmp(&self, other: &OpenEntry<C>) -> Ordering {
+        other.f.cmp(&self.f).then_with(|| self.tie.cmp(&other.tie)).then(self.index.cmp(&other.index))
+    }
+}
+
+impl<C: Ord> PartialOrd for OpenEntry<C> {
+    fn partial_cmp(&self, other: &OpenEntry<C>) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<C: Ord> PartialEq for OpenEntry<C> {
+    fn eq(&self, other: &OpenEntry<C>) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<C: Ord> Eq for OpenEntry<C> {}
+
+/// Map from the states reached by a best-first search to the node holding
+/// the cheapest path found to each, and so its g-value.
+pub trait NodeIndex<T> {
+    fn get(&self, state: &T) -> Option<usize>;
+
+    fn insert(&mut self, state: T, index: usize);
+
+    fn clear(&mut self);
+
+    /// Hints that about `additional` more states are going to be inserted.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Approximate heap bytes taken per state stored, for memory statistics.
+    fn approx_entry_bytes(&self) -> usize;
+}
+
+impl<T: Hash + Eq> NodeIndex<T> for HashMap<T, usize> {
+    fn get(&self, state: &T) -> Option<usize> {
+        HashMap::get(self, state).copied()
+    }
+
+    fn insert(&mut self, state: T, index: usize) {
+        HashMap::insert(self, state, index);
+    }
+
+    fn clear(&mut self) {
+        HashMap::clear(self)
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        HashMap::reserve(self, additional)
+    }
+
+    fn approx_entry_bytes(&self) -> usize {
+        hash_entry_bytes::<(T, usize)>()
+    }
+}
+
+/// Node index over rankable states backed by an array indexed by rank, so
+/// that looking up the g-value of a state takes no hashing.
+pub struct RankedNodes {
+    nodes: Vec<usize>,
+}
+
+impl RankedNodes {
+    /// Node index for states ranked below `count`.
+    pub fn new(count: usize) -> RankedNodes {
+        RankedNodes { nodes: vec![usize::MAX; count] }
+    }
+}
+
+impl<T: RankableState> NodeIndex<T> for RankedNodes {
+    fn get(&self, state: &T) -> Option<usize> {
+        let rank = state.rank();
+        assert!(rank < self.nodes.len(), "state rank {} out of range 0..{}", rank, self.nodes.len());
+        Some(self.nodes[rank]).filter(|&index| index != usize::MAX)
+    }
+
+    fn insert(&mut self, state: T, index: usize) {
+        let rank = state.rank();
+        assert!(rank < self.nodes.len(), "state rank {} out of range 0..{}", rank, self.nodes.len());
+        self.nodes[rank] = index;
+    }
+
+    fn clear(&mut self) {
+        for index in &mut self.nodes {
+            *index = usize::MAX;
+        }
+    }
+
+    /// Zero: the array is allocated up front for every rank.
+    fn approx_entry_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// Open list, node store and statistics of a best-first search, kept between
+/// searches so their allocations can be reused.
+pub struct AStarBuffers<S: WeightedSearchSpace + ?Sized, O = (), N = HashMap<<S as SearchSpace>::State, usize>> {
+    open: BinaryHeap<OpenEntry<S::Cost>>,
+    best: N,
+    nodes: Vec<Node<S>>,
+    stats: SearchStats,
+    observer: O,
+    revisit: RevisitPolicy,
+    tie_break: TieBreak,
+    cost_bound: Option<S::Cost>,
+    bound_reached: bool,
+    dominance: bool,
+    dominance_keys: HashMap<u64, Vec<usize>>,
+    closest: Option<(S::Cost, usize)>,
+    keep_closed: bool,
+    closed: Vec<ClosedNode<S::State, S::Cost>>,
+    f_layers: Option<FLayers<S::Cost>>,
+}
+
+impl<S: WeightedSearchSpace + ?Sized> AStarBuffers<S> {
+    pub fn new() -> AStarBuffers<S> {
+        AStarBuffers::with_node_index(HashMap::new())
+    }
+
+    /// Buffers sized up front for searches storing about `states` nodes.
+    pub fn with_capacity(states: usize) -> AStarBuffers<S> {
+        let mut buffers = AStarBuffers::new();
+        buffers.reserve(states);
+        buffers
+    }
+}
+
+impl<S: WeightedSearchSpace + ?Sized, N: NodeIndex<S::State>> AStarBuffers<S, (), N> {
+    /// Buffers keeping track of the node of each state in `index`, such as
+    /// `RankedNodes` for rankable states, instead of a hash map.
+    pub fn with_node_index(index: N) -> AStarBuffers<S, (), N> {
+        AStarBuffers {
+            open: BinaryHeap::new(),
+            best: index,
+            nodes: Vec::new(),
+            stats: SearchStats::new(),
+            observer: (),
+            revisit: RevisitPolicy::default(),
+            tie_break: TieBreak::default(),
+            cost_bound: None,
+            bound_reached: false,
+            dominance: false,
+            dominance_keys: HashMap::new(),
+            closest: None,
+            keep_closed: false,
+            closed: Vec::new(),
+            f_layers: None,
+        }
+    }
+}
+
+impl<S: WeightedSearchSpace + ?Sized> Default for AStarBuffers<S> {
+    fn default() -> AStarBuffers<S> {
+        AStarBuffers::new()
+    }
+}
+
+impl<S, O, N> AStarBuffers<S, O, N>
+where S: WeightedSearchSpace + ?Sized, O: Observer<S::State, S::Action, NodeCost<S::Cost>>, N: NodeIndex<S::State> {
+    /// Reports the events of subsequent searches to `observer`.
+    pub fn with_observer<P>(self, observer: P) -> AStarBuffers<S, P, N>
+    where P: Observer<S::State, S::Action, NodeCost<S::Cost>> {
+        AStarBuffers {
+            open: self.open,
+            best: self.best,
+            nodes: self.nodes,
+            stats: self.stats,
+            observer,
+            revisit: self.revisit,
+            tie_break: self.tie_break,
+            cost_bound: self.cost_bound,
+            bound_reached: self.bound_reached,
+            dominance: self.dominance,
+            dominance_keys: self.dominance_keys,
+            closest: self.closest,
+            keep_closed: self.keep_closed,
+            closed: self.closed,
+            f_layers: self.f_layers,
+        }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+
+    /// Enables recording of per-depth node counts for subsequent searches.
+    pub fn profiled(mut self) -> AStarBuffers<S, O, N> {
+        self.stats.profile = Some(LayerProfile::new());
+        self.f_layers = None;
+        self
+    }
+
+    /// Enables recording of node counts per f-layer for subsequent searches,
+    /// `layer` numbering the layer of each f-value: `|&f| f as usize` for
+    /// small whole-number costs, or a band of costs per layer for larger or
+    /// fractional ones. Expansions count in the layer of the f they leave
+    /// the open list at and successors in that of their own `g + h`, which
+    /// takes an extra heuristic estimate of each successor.
+    pub fn profiled_by_f<F>(mut self, layer: F) -> AStarBuffers<S, O, N> where F: Fn(&S::Cost) -> usize + 'static {
+        self.stats.profile = Some(LayerProfile::new());
+        self.f_layers = Some(Box::new(layer));
+        self
+    }
+
+    /// Sets what subsequent searches do on reaching a state again.
+    pub fn with_revisit(mut self, revisit: RevisitPolicy) -> AStarBuffers<S, O, N> {
+        self.revisit = revisit;
+        self
+    }
+
+    /// Sets how subsequent A* and PEA* searches pick among open nodes of
+    /// equal f.
+    pub fn with_tie_break(mut self, tie_break: TieBreak) -> AStarBuffers<S, O, N> {
+        self.tie_break = tie_break;
+        self
+    }
+
+    /// Limits subsequent A*, PEA* and focal searches to paths costing at most
+    /// `bound`: nodes whose f exceeds it are dropped, which with an
+    /// admissible heuristic only loses paths over the bound.
+    pub fn with_cost_bound(mut self, bound: Option<S::Cost>) -> AStarBuffers<S, O, N> {
+        self.cost_bound = bound;
+        self
+    }
+
+    /// Makes subsequent A*, PEA* and focal searches drop generated states
+    /// dominated by a state already reached at no greater cost, as told by
+    /// `WeightedSearchSpace::dominates`. Each check scans the stored nodes
+    /// with the same `WeightedSearchSpace::dominance_key`.
+    pub fn with_dominance(mut self, dominance: bool) -> AStarBuffers<S, O, N> {
+        self.dominance = dominance;
+        self
+    }
+
+    /// Makes subsequent A*, PEA* and focal searches hand over their node
+    /// store once they finish, for `closed_list` to return.
+    pub fn with_closed_list(mut self, keep_closed: bool) -> AStarBuffers<S, O, N> {
+        self.keep_closed = keep_closed;
+        self
+    }
+
+    /// Every node stored by the last search, indexed as their `parent`
+    /// fields refer to them, with the start first. Empty unless enabled by
+    /// `with_closed_list`. With revisits a state may be stored more than
+    /// once, the later node holding the cheaper path.
+    pub fn closed_list(&self) -> &[ClosedNode<S::State, S::Cost>] {
+        &self.closed
+    }
+
+    pub fn take_closed_list(&mut self) -> Vec<ClosedNode<S::State, S::Cost>> {
+        mem::take(&mut self.closed)
+    }
+
+    /// Whether the last search dropped nodes over the cost bound, so that
+    /// finding no path does not prove there is none.
+    pub fn bound_reached(&self) -> bool {
+        self.bound_reached
+    }
+
+    /// Statistics of the last search.
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    // Whether the node at `index` is the one to search for its state.
+    fn is_current(&self, search_space: &S, index: usize) -> bool {
+        let key = search_space.canonicalize(&self.nodes[index].state);
+        self.revisit == RevisitPolicy::AlwaysRevisit || self.best.get(key.as_ref().unwrap_or(&self.nodes[index].state)) == Some(index)
+    }
+
+    // Profile layer of a node at `depth` whose f-value `f` gives.
+    fn layer<F>(&self, depth: usize, f: F) -> usize where F: FnOnce() -> S::Cost {
+        match self.f_layers {
+            Some(ref layer) if self.stats.profile.is_some() => layer(&f()),
+            _ => depth,
+        }
+    }
+
+    // Whether a state seen at no more than `g` dominates `state`.
+    fn is_dominated(&self, search_space: &S, state: &S::State, g: S::Cost) -> bool {
+        self.dominance && self.dominance_keys.get(&search_space.dominance_key(state)).is_some_and(|indices| {
+            indices.iter().map(|&index| &self.nodes[index])
+                .any(|node| node.g <= g && search_space.dominates(&node.state, state))
+        })
+    }
+
+    // Files the node at `index` under its dominance key for `is_dominated`.
+    fn note_dominance(&mut self, search_space: &S, index: usize) {
+        if self.dominance {
+            let key = search_space.dominance_key(&self.nodes[index].state);
+            self.dominance_keys.entry(key).or_default().push(index);
+        }
+    }
+
+    fn exceeds_bound(&mut self, f: S::Cost) -> bool {
+        let exceeds = self.cost_bound.is_some_and(|bound| f > bound);
+        self.bound_reached |= exceeds;
+        exceeds
+    }
+
+    /// Reserves room for about `states` more nodes in the open list, the node
+    /// store and the node index.
+    pub fn reserve(&mut self, states: usize) {
+        self.open.reserve(states);
+        self.nodes.reserve(states);
+        self.best.reserve(states);
+    }
+
+    pub fn clear(&mut self) {
+        self.open.clear();
+        self.best.clear();
+        self.nodes.clear();
+        self.dominance_keys.clear();
+        self.closest = None;
+        self.closed.clear();
+    }
+
+    // Clears the buffers after a search, moving its nodes to the closed list
+    // if asked to.
+    fn finish(&mut self) {
+        let nodes = mem::take(&mut self.nodes);
+        self.clear();
+        if self.keep_closed {
+            self.closed = nodes.into_iter().map(|node| ClosedNode {
+                state: node.state,
+                g: node.g,
+                parent: node.parent,
+                depth: node.depth,
+                expanded: node.expanded,
+            }).collect();
+        } else {
+            self.nodes = nodes;
+            self.nodes.clear();
+        }
+    }
+
+    // Keeps track of the stored node with the lowest estimate, the first
+    // one among equals.
+    fn note_closest(&mut self, h: S::Cost, index: usize) {
+        if self.closest.is_none_or(|(closest, _)| h < closest) {
+            self.closest = Some((h, index));
+        }
+    }
+
+    // Actions and cost of the path to the stored node with the lowest
+    // estimate, leaving the node store intact.
+    fn closest_path(&self) -> Solution<S> where S::Action: Clone {
+        let (_, mut index) = self.closest?;
+        let g = self.nodes[index].g;
+        let mut actions = vec![];
+        while let Some(parent) = self.nodes[index].parent {
+            actions.extend(self.nodes[index].action.clone());
+            index = parent;
+        }
+        actions.reverse();
+        Some((actions, g))
+    }
+
+    fn push_open(&mut self, f: S::Cost, g: S::Cost, index: usize) {
+        self.open.push(OpenEntry { f, tie: self.tie_break.key(g, index), index });
+    }
+
+    fn path_to(&mut self, mut index: usize) -> Vec<S::Action> {
+        let mut actions = vec![];
+        while let Some(parent) = self.nodes[index].parent {
+            actions.extend(self.nodes[index].action.take());
+            index = parent;
+        }
+        actions.reverse();
+        actions
+    }
+
+    fn begin<H>(&mut self, search_space: &S, start: S::State, heuristic: &H)
+    where H: Heuristic<S::State, S::Cost> {
+        self.clear();
+        self.stats.clear();
+        self.stats.set_entry_bytes(mem::size_of::<Node<S>>() + self.best.approx_entry_bytes(),
+                                   mem::size_of::<OpenEntry<S::Cost>>());
+
+        self.bound_reached = false;
+        let f = heuristic.estimate(&start);
+        if !self.exceeds_bound(f) {
+            self.push_open(f, S::Cost::default(), 0);
+        }
+        self.note_closest(f, 0);
+        self.best.insert(search_space.canonicalize(&start).unwrap_or_else(|| start.clone()), 0);
+        self.nodes.push(Node { state: start, parent: None, action: None, g: S::Cost::default(), depth: 0, expanded: false });
+        self.note_dominance(search_space, 0);
+        self.stats.record_open(1);
+    }
+
+    fn step<G, H>(&mut self, search_space: &S, goal: &G, heuristic: &H, threshold: Option<S::Cost>)
+        -> Step<Solution<S>>
+    where G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost> {
+        let OpenEntry { f, index, .. } = match self.open.pop() {
+            Some(entry) => entry,
+            None => return Step::Finished(None),
+        };
+        if !self.is_current(search_space, index) {
+            return Step::Continue;
+        }
+        let Node { g, depth, .. } = self.nodes[index];
+        let cost = NodeCost { g, f };
+        if goal.is_goal(&self.nodes[index].state) {
+            self.observer.on_goal(&self.nodes[index].state, depth, &cost);
+            return Step::Finished(Some((self.path_to(index), g)));
+        }
+
+        let layer = self.layer(depth, || f);
+        self.stats.record_expanded(layer);
+        let reexpanding = mem::replace(&mut self.nodes[index].expanded, true);
+        #[cfg(feature = "tracing")]
+        {
+            if trace::sampled(&self.stats) {
+                ::tracing::trace!(depth, g = ?g, f = ?f, expanded = self.stats.expanded, "expand");
+            }
+        }
+        self.observer.on_expand(&self.nodes[index].state, depth, &cost);
+        let mut held_back: Option<S::Cost> = None;
+        for (action, state) in search_space.expand_weighted(&self.nodes[index].state, ExpandContext { depth, cost: g }) {
+            let child_g = g + search_space.step_cost(&self.nodes[index].state, &action, &state);
+            // Partial expansion holds back successors above the threshold
+            // before they are counted or reported: they are generated when
+            // their parent is re-expanded at their f, which skips those below
+            // it as an earlier expansion generated them already.
+            let mut child_h = None;
+            if let Some(threshold) = threshold {
+                let h = heuristic.estimate(&state);
+                if child_g + h > f + threshold {
+                    held_back = Some(held_back.map_or(child_g + h, |held| held.min(child_g + h)));
+                    continue;
+                }
+                if reexpanding && child_g + h < f {
+                    continue;
+                }
+                child_h = Some(h);
+            }
+            let layer = self.layer(depth + 1, || child_g + child_h.unwrap_or_else(|| heuristic.estimate(&state)));
+            self.stats.record_generated(layer);
+            let key = search_space.canonicalize(&state);
+            if let Some(known) = self.best.get(key.as_ref().unwrap_or(&state)) {
+                if !self.revisit.revisits(self.nodes[known].g, child_g) {
+                    self.stats.record_duplicate(layer);
+                    self.observer.on_duplicate(&state, depth + 1);
+                    continue;
+                }
+            }
+            if search_space.is_dead_end(&state) {
+                self.stats.record_dead_end();
+                self.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            if self.is_dominated(search_space, &state, child_g) {
+                self.stats.record_dominated();
+                self.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            let child_h = child_h.unwrap_or_else(|| heuristic.estimate(&state));
+            let child_f = child_g + child_h;
+            if self.exceeds_bound(child_f) {
+                continue;
+            }
+            let child_cost = NodeCost { g: child_g, f: child_f };
+            if self.observer.is_pruned(&state, depth + 1, &child_cost) {
+                self.stats.record_pruned();
+                self.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            self.observer.on_generate(&self.nodes[index].state, &action, &state, depth + 1, &child_cost);
+            self.best.insert(key.unwrap_or_else(|| state.clone()), self.nodes.len());
+            let child = self.nodes.len();
+            self.push_open(child_f, child_g, child);
+            self.nodes.push(Node {
+                state, parent: Some(index), action: Some(action), g: child_g, depth: depth + 1, expanded: false,
+            });
+            self.note_dominance(search_space, child);
+            self.note_closest(child_h, child);
+            self.stats.record_depth(depth + 1);
+        }
+        if let Some(f) = held_back {
+            self.push_open(f, g, index);
+        }
+        self.stats.record_open(self.open.len());
+        Step::Continue
+    }
+}
+
+/// Best-first search advanced one open-list entry per step.
+pub struct AStarSession<'a, S: WeightedSearchSpace + ?Sized + 'a, G, H, O: 'a = (),
+                         N: 'a = HashMap<<S as SearchSpace>::State, usize>> {
+    buffers: &'a mut AStarBuffers<S, O, N>,
+    search_space: &'a S,
+    goal: G,
+    heuristic: H,
+    threshold: Option<S::Cost>,
+    clock: Stopwatch,
+    resumed_at: Duration,
+}
+
+impl<'a, S, G, H, O, N> AStarSession<'a, S, G, H, O, N>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost>,
+      O: Observer<S::State, S::Action, NodeCost<S::Cost>>, N: NodeIndex<S::State> {
+    /// Starts a search; with a `threshold` it runs as Partial Expansion A*.
+    pub fn new(buffers: &'a mut AStarBuffers<S, O, N>, search_space: &'a S, start: S::State, goal: G, heuristic: H,
+               threshold: Option<S::Cost>) -> AStarSession<'a, S, G, H, O, N> {
+        let clock = Stopwatch::start();
+        buffers.begin(search_space, start, &heuristic);
+        let mut session = AStarSession { buffers, search_space, goal, heuristic, threshold, clock,
+                                         resumed_at: Duration::default() };
+        session.time();
+        session
+    }
+
+    /// Statistics of the search so far, timed from its start, including the
+    /// time taken before it was checkpointed if it was restored.
+    pub fn stats(&mut self) -> &SearchStats {
+        self.time();
+        &self.buffers.stats
+    }
+
+    fn time(&mut self) {
+        self.buffers.stats.elapsed = self.elapsed();
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.resumed_at + self.clock.elapsed()
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.buffers.observer
+    }
+
+    /// Actions and cost of the path to the node estimated closest to the
+    /// goal so far, for acting on when the search is given up before it
+    /// finishes. `None` once the search has finished.
+    pub fn partial(&self) -> Option<(Vec<S::Action>, S::Cost)> where S::Action: Clone {
+        self.buffers.closest_path()
+    }
+
+    /// Saves the search so far to the file at `path` for `restore` to resume,
+    /// replacing the checkpoint already there only once written in full.
+    #[cfg(all(feature = "json", feature = "std"))]
+    pub fn checkpoint<P: AsRef<::std::path::Path>>(&self, path: P) -> Result<(), CheckpointError>
+    where S::State: Serialize, S::Action: Serialize, S::Cost: Serialize {
+        let buffers = &*self.buffers;
+        let saved = SavedSearch {
+            nodes: buffers.nodes.iter().map(|node| SavedNode {
+                state: &node.state,
+                parent: node.parent,
+                action: node.action.as_ref(),
+                g: node.g,
+                depth: node.depth,
+                expanded: node.expanded,
+            }).collect(),
+            open: buffers.open.iter().map(|entry| (entry.f, entry.index)).collect(),
+            closest: buffers.closest,
+            bound_reached: buffers.bound_reached,
+            stats: SearchStats { elapsed: self.elapsed(), ..buffers.stats.clone() },
+        };
+        let fingerprint = buffers.nodes.first()
+            .map_or(0, |start| checkpoint::space_fingerprint(self.search_space, &start.state));
+        checkpoint::write(path, fingerprint, &saved)
+    }
+
+    /// Resumes the search saved to the file at `path` by `checkpoint`, after
+    /// checking that it was taken on a space shaped like `search_space`. The
+    /// goal, heuristic and threshold are not saved and are given again, while
+    /// settings such as the revisit policy come from `buffers`.
+    #[cfg(all(feature = "json", feature = "std"))]
+    pub fn restore<P: AsRef<::std::path::Path>>(buffers: &'a mut AStarBuffers<S, O, N>, search_space: &'a S, path: P,
+                                                goal: G, heuristic: H, threshold: Option<S::Cost>)
+        -> Result<AStarSession<'a, S, G, H, O, N>, CheckpointError>
+    where S::State: DeserializeOwned, S::Action: DeserializeOwned, S::Cost: DeserializeOwned {
+        let (fingerprint, saved) = checkpoint::read::<SavedSearch<S::State, S::Action, S::Cost>, P>(path)?;
+        let expected = saved.nodes.first()
+            .map_or(0, |start| checkpoint::space_fingerprint(search_space, &start.state));
+        if expected != fingerprint {
+            return Err(CheckpointError::SpaceMismatch);
+        }
+        let count = saved.nodes.len();
+        if saved.open.iter().any(|&(_, index)| index >= count)
+            || saved.closest.is_some_and(|(_, index)| index >= count)
+            || saved.nodes.iter().enumerate().any(|(index, node)| node.parent.is_some_and(|parent| parent >= index)) {
+            return Err(CheckpointError::Corrupted);
+        }
+        buffers.clear();
+        for node in saved.nodes {
+            // A state stored more than once is known by its latest node, as
+            // while searching.
+            let key = search_space.canonicalize(&node.state).unwrap_or_else(|| node.state.clone());
+            buffers.best.insert(key, buffers.nodes.len());
+            buffers.nodes.push(Node {
+                state: node.state,
+                parent: node.parent,
+                action: node.action,
+                g: node.g,
+                depth: node.depth,
+                expanded: node.expanded,
+            });
+            let index = buffers.nodes.len() - 1;
+            buffers.note_dominance(search_space, index);
+        }
+        for (f, index) in saved.open {
+            let g = buffers.nodes[index].g;
+            buffers.push_open(f, g, index);
+        }
+        buffers.closest = saved.closest;
+        buffers.bound_reached = saved.bound_reached;
+        let resumed_at = saved.stats.elapsed;
+        buffers.stats = saved.stats;
+        Ok(AStarSession { buffers, search_space, goal, heuristic, threshold, clock: Stopwatch::start(), resumed_at })
+    }
+}
+
+impl<'a, S, G, H, O, N> SearchSession for AStarSession<'a, S, G, H, O, N>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost>,
+      O: Observer<S::State, S::Action, NodeCost<S::Cost>>, N: NodeIndex<S::State> {
+    type Output = Option<(Vec<S::Action>, S::Cost)>;
+
+    fn step(&mut self) -> Step<Option<(Vec<S::Action>, S::Cost)>> {
+        let step = self.buffers.step(self.search_space, &self.goal, &self.heuristic, self.threshold);
+        if let Step::Finished(_) = step {
+            self.buffers.finish();
+            self.time();
+        }
+        step
+    }
+
+    fn cut_off(&self) -> Option<Cutoff> {
+        if self.buffers.bound_reached { Some(Cutoff::CostBound) } else { None }
+    }
+}
+
+/// Best-first search on f = g + h.
+///
+/// With a `threshold` this is Partial Expansion A*: an expansion only keeps
+/// successors whose f is within `threshold` of the parent's stored F, and the
+/// parent goes back to the open list with F raised to the smallest f it held
+/// back. Without one every successor is kept, which is plain A*.
+pub fn best_first<S, G, H, O, N>(buffers: &mut AStarBuffers<S, O, N>, search_space: &S, start: S::State, goal: G,
+                              heuristic: H, threshold: Option<S::Cost>) -> Option<(Vec<S::Action>, S::Cost)>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost>,
+      O: Observer<S::State, S::Action, NodeCost<S::Cost>>, N: NodeIndex<S::State> {
+    #[cfg(feature = "tracing")]
+    let _span = trace::search_span(if threshold.is_some() { "pea_star" } else { "astar" }).entered();
+    let started = Stopwatch::start();
+    buffers.begin(search_space, start, &heuristic);
+    let found = loop {
+        if let Step::Finished(found) = buffers.step(search_space, &goal, &heuristic, threshold) {
+            break found;
+        }
+    };
+    buffers.finish();
+    buffers.stats.elapsed = started.elapsed();
+    #[cfg(feature = "tracing")]
+    trace::finished(found.is_some(), &buffers.stats);
+    found
+}
+
+/// Focal search (A*-epsilon): of the open nodes whose f is within `factor`
+/// times the smallest f on the open list, expands the one `focal_heuristic`
+/// rates lowest, breaking ties by f. With an admissible `heuristic` the path
+/// found costs at most `factor` times the cheapest, while `focal_heuristic`
+/// may be inadmissible, such as an estimate of the actions left to the goal.
+pub fn focal<S, G, H, F, D, O, N>(buffers: &mut AStarBuffers<S, O, N>, search_space: &S, start: S::State, goal: G,
+                               heuristic: H, focal_heuristic: F, factor: f64) -> Option<(Vec<S::Action>, S::Cost)>
+where S: WeightedSearchSpace + ?Sized, S::Cost: Scale, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost>,
+      F: Heuristic<S::State, D>, D: Ord, O: Observer<S::State, S::Action, NodeCost<S::Cost>>, N: NodeIndex<S::State> {
+    #[cfg(feature = "tracing")]
+    let _span = trace::search_span("focal").entered();
+    let started = Stopwatch::start();
+    buffers.begin(search_space, start, &heuristic);
+    // The open list is kept ordered by f instead, next to the focal list.
+    buffers.open.clear();
+    let start_f = heuristic.estimate(&buffers.nodes[0].state);
+    let mut f_values = vec![start_f];
+    let mut open = BTreeSet::new();
+    if !buffers.bound_reached {
+        open.insert((start_f, 0));
+    }
+    let mut focal = BinaryHeap::new();
+    focal.push(Reverse((focal_heuristic.estimate(&buffers.nodes[0].state), start_f, 0)));
+    let mut bound = start_f.scale(factor).max(start_f);
+    let found = loop {
+        let lowest = match open.iter().next() {
+            Some(&(f, _)) => f,
+            None => break None,
+        };
+        // Nodes join the focal list as the bound rises to take them in.
+        let raised = lowest.scale(factor).max(lowest);
+        if raised > bound {
+            for &(f, index) in open.range((Bound::Excluded((bound, usize::MAX)), Bound::Included((raised, usize::MAX)))) {
+                focal.push(Reverse((focal_heuristic.estimate(&buffers.nodes[index].state), f, index)));
+            }
+            bound = raised;
+        }
+        let Reverse((_, f, index)) = focal.pop().expect("the lowest open node is in focus");
+        if !open.remove(&(f, index)) {
+            continue;
+        }
+        let Node { g, depth, .. } = buffers.nodes[index];
+        let cost = NodeCost { g, f };
+        if goal.is_goal(&buffers.nodes[index].state) {
+            buffers.observer.on_goal(&buffers.nodes[index].state, depth, &cost);
+            break Some((buffers.path_to(index), g));
+        }
+        let layer = buffers.layer(depth, || f);
+        buffers.stats.record_expanded(layer);
+        buffers.nodes[index].expanded = true;
+        buffers.observer.on_expand(&buffers.nodes[index].state, depth, &cost);
+        for (action, state) in search_space.expand_weighted(&buffers.nodes[index].state, ExpandContext { depth, cost: g }) {
+            let child_g = g + search_space.step_cost(&buffers.nodes[index].state, &action, &state);
+            let layer = buffers.layer(depth + 1, || child_g + heuristic.estimate(&state));
+            buffers.stats.record_generated(layer);
+            let key = search_space.canonicalize(&state);
+            if let Some(known) = buffers.best.get(key.as_ref().unwrap_or(&state)) {
+                if !buffers.revisit.revisits(buffers.nodes[known].g, child_g) {
+                    buffers.stats.record_duplicate(layer);
+                    buffers.observer.on_duplicate(&state, depth + 1);
+                    continue;
+                }
+                if buffers.revisit != RevisitPolicy::AlwaysRevisit {
+                    open.remove(&(f_values[known], known));
+                }
+            }
+            if search_space.is_dead_end(&state) {
+                buffers.stats.record_dead_end();
+                buffers.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            if buffers.is_dominated(search_space, &state, child_g) {
+                buffers.stats.record_dominated();
+                buffers.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            let child_f = child_g + heuristic.estimate(&state);
+            if buffers.exceeds_bound(child_f) {
+                continue;
+            }
+            let child_cost = NodeCost { g: child_g, f: child_f };
+            if buffers.observer.is_pruned(&state, depth + 1, &child_cost) {
+                buffers.stats.record_pruned();
+                buffers.observer.on_prune(&state, depth + 1);
+                continue;
+            }
+            buffers.observer.on_generate(&buffers.nodes[index].state, &action, &state, depth + 1, &child_cost);
+            let child = buffers.nodes.len();
+            if child_f <= bound {
+                focal.push(Reverse((focal_heuristic.estimate(&state), child_f, child)));
+            }
+            buffers.best.insert(key.unwrap_or_else(|| state.clone()), child);
+            open.insert((child_f, child));
+            f_values.push(child_f);
+            buffers.nodes.push(Node {
+                state, parent: Some(index), action: Some(action), g: child_g, depth: depth + 1, expanded: false,
+            });
+            buffers.note_dominance(search_space, child);
+            buffers.stats.record_depth(depth + 1);
+        }
+        buffers.stats.record_open(open.len());
+    };
+    buffers.finish();
+    buffers.stats.elapsed = started.elapsed();
+    #[cfg(feature = "tracing")]
+    trace::finished(found.is_some(), &buffers.stats);
+    found
+}
+
+/// Dijkstra's algorithm from `start` to every reachable state, giving the
+/// cost of the cheapest path to each. Distances from a goal over reversed
+/// actions make an exact heuristic for that goal.
+///
+/// States are keyed by their canonical form, so symmetric states share one
+/// entry, and dead ends are left out along with whatever is only reachable
+/// through them.
+pub fn distance_map<S>(search_space: &S, start: S::State) -> HashMap<S::State, S::Cost>
+where S: WeightedSearchSpace + ?Sized {
+    let key = |state: &S::State| search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+    let mut distances = HashMap::new();
+    // States generated, each with the number of actions it was reached by.
+    let mut states = vec![(start, 0)];
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((S::Cost::default(), 0)));
+    let mut successors = Vec::new();
+    while let Some(Reverse((g, index))) = open.pop() {
+        let (ref parent, depth) = states[index];
+        let canonical = key(parent);
+        if distances.contains_key(&canonical) {
+            continue;
+        }
+        for (action, state) in search_space.expand_weighted(parent, ExpandContext { depth, cost: g }) {
+            if !distances.contains_key(&key(&state)) && !search_space.is_dead_end(&state) {
+                successors.push((g + search_space.step_cost(parent, &action, &state), state));
+            }
+        }
+        for (cost, state) in successors.drain(..) {
+            open.push(Reverse((cost, states.len())));
+            states.push((state, depth + 1));
+        }
+        distances.insert(canonical, g);
+    }
+    distances
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use cache::CachedSpace;
+    use generators::{random_graph, random_grid};
+    use grid::manhattan;
+    use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+    use record::{SearchTrace, TraceEvent};
+    use session::{SearchLimits, SearchOutcome, SearchSession};
+    use stats::LayerCounts;
+    use std::mem;
+    use visited::{permutation_rank, RankableState, RevisitPolicy};
+    use super::{AStarBuffers, Node, RankedNodes, TieBreak};
+
+    // Weighted digraph over 0..6 where the cheapest route to 5 takes the
+    // most edges.
+    struct Roads;
+
+    const EDGES: &[(u32, u32, u64)] = &[
+        (0, 1, 1), (1, 2, 1), (2, 3, 1), (3, 5, 1),
+        (0, 4, 2), (4, 5, 3),
+        (0, 5, 10),
+    ];
+
+    impl SearchSpace for Roads {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            EDGES.iter()
+                 .filter(|edge| edge.0 == *state)
+                 .map(|edge| (edge.1, edge.1))
+                 .collect::<Vec<_>>()
+                 .into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Roads {
+        type Cost = u64;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            EDGES.iter().find(|edge| edge.0 == *state && edge.1 == *action).unwrap().2
+        }
+    }
+
+    #[test]
+    pub fn test_astar() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        assert_eq!(Roads.astar(0, 5, h), Some((vec![1, 2, 3, 5], 4)));
+        assert_eq!(Roads.astar(4, 5, h), Some((vec![5], 3)));
+        assert_eq!(Roads.astar(5, 5, h), Some((vec![], 0)));
+        assert_eq!(Roads.astar(5, 0, h), None);
+    }
+
+    #[test]
+    pub fn test_astar_with_capacity() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        let mut buffers = AStarBuffers::with_capacity(8);
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, h), Some((vec![1, 2, 3, 5], 4)));
+        buffers.reserve(16);
+        assert_eq!(Roads.astar_with(&mut buffers, 5, 0, h), None);
+        assert_eq!(Roads.astar_with(&mut buffers, 4, 5, h), Some((vec![5], 3)));
+    }
+
+    #[test]
+    pub fn test_revisit_policy() {
+        let zero = |_: &u32| 0;
+        let mut buffers = AStarBuffers::new().with_revisit(RevisitPolicy::NeverRevisit);
+        // The direct road to 5 is generated first and then kept.
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, zero), Some((vec![5], 10)));
+        let mut buffers = buffers.with_revisit(RevisitPolicy::AlwaysRevisit);
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, zero), Some((vec![1, 2, 3, 5], 4)));
+        let mut buffers = buffers.with_revisit(RevisitPolicy::RevisitIfCheaper);
+        assert_eq!(Roads.focal_with(&mut buffers, 0, 5, zero, zero, 1.0), Some((vec![1, 2, 3, 5], 4)));
+        let found = Roads.anytime(0, 5, zero, 1.0).with_revisit(RevisitPolicy::NeverRevisit).last();
+        assert_eq!(found.map(|found| found.cost), Some(10));
+    }
+
+    #[test]
+    pub fn test_tie_break() {
+        let grid = random_grid(30, 30, 0.0, &mut StdRng::seed_from_u64(1));
+        let to_goal = |cell: &_| manhattan(*cell, (29, 29));
+        let expanded = |tie_break| {
+            let mut buffers = AStarBuffers::new().with_tie_break(tie_break);
+            assert_eq!(grid.astar_with(&mut buffers, (0, 0), (29, 29), to_goal).map(|found| found.1), Some(58));
+            buffers.stats().expanded
+        };
+        // Every node on an open grid has the same f; going deep first heads
+        // straight for the goal while oldest first floods the grid.
+        assert_eq!(expanded(TieBreak::HigherG), 58);
+        assert!(expanded(TieBreak::Fifo) > 800);
+        assert_eq!(expanded(TieBreak::Random(7)), expanded(TieBreak::Random(7)));
+    }
+
+    #[test]
+    pub fn test_cost_bound() {
+        let zero = |_: &u32| 0;
+        let mut buffers = AStarBuffers::new().with_cost_bound(Some(4));
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, zero), Some((vec![1, 2, 3, 5], 4)));
+        let mut buffers = buffers.with_cost_bound(Some(3));
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, zero), None);
+        assert!(buffers.bound_reached());
+        assert_eq!(Roads.focal_with(&mut buffers, 0, 5, zero, zero, 2.0), None);
+        let outcome = Roads.astar_session(&mut buffers, 0, 5, zero).run_limited(&SearchLimits::new());
+        assert_eq!(outcome, SearchOutcome::CostBoundExceeded);
+        let outcome = Roads.astar_session(&mut buffers, 5, 0, zero).run_limited(&SearchLimits::new());
+        assert_eq!(outcome, SearchOutcome::Exhausted);
+        // The bound also holds for the start, whose f is its estimate.
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, |_: &u32| 4), None);
+
+        let mut search = Roads.anytime(0, 5, zero, 1.0).with_cost_bound(3);
+        assert_eq!(search.next(), None);
+        assert!(search.bound_reached());
+        assert_eq!(Roads.anytime(0, 5, zero, 1.0).with_cost_bound(5).last().map(|found| found.cost), Some(4));
+    }
+
+    // Roads with a toll for entering 2.
+    struct Tolled;
+
+    impl SearchSpace for Tolled {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            Roads.expand(state)
+        }
+    }
+
+    impl WeightedSearchSpace for Tolled {
+        type Cost = u64;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            Roads.cost(state, action)
+        }
+
+        fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+            if *state == 2 { 5 } else { 0 }
+        }
+    }
+
+    #[test]
+    pub fn test_arrival_cost() {
+        let zero = |_: &u32| 0;
+        assert_eq!(Tolled.astar(0, 5, zero), Some((vec![4, 5], 5)));
+        assert_eq!(Tolled.astar(0, 3, zero), Some((vec![1, 2, 3], 8)));
+        assert_eq!(Tolled.focal(0, 5, zero, zero, 1.0), Some((vec![4, 5], 5)));
+        assert_eq!(Tolled.branch_and_bound(0, 5, zero, None), Some((vec![4, 5], 5)));
+        assert_eq!(Tolled.anytime(0, 5, zero, 1.0).last().map(|found| found.cost), Some(5));
+        assert_eq!(Tolled.distance_map(0)[&2], 7);
+    }
+
+    // Walking or running along a line on a fuel budget: running burns twice
+    // the fuel for the same cost, so it never pays off.
+    struct Fuel;
+
+    impl SearchSpace for Fuel {
+        type State = (u32, u32);
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &(position, fuel): &Self::State) -> Self::Iterator {
+            (1..=2).filter(|&burn| burn <= fuel).map(|burn| (burn, (position + 1, fuel - burn))).collect::<Vec<_>>()
+                .into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Fuel {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+
+        fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+            a.0 == b.0 && a.1 >= b.1
+        }
+
+        fn dominance_key(&self, state: &Self::State) -> u64 {
+            u64::from(state.0)
+        }
+    }
+
+    struct Reach(u32);
+
+    impl SearchGoal<(u32, u32)> for Reach {
+        fn is_goal(&self, state: &(u32, u32)) -> bool {
+            state.0 == self.0
+        }
+    }
+
+    #[test]
+    pub fn test_dominance() {
+        let zero = |_: &(u32, u32)| 0;
+        let mut plain = AStarBuffers::new();
+        assert_eq!(Fuel.astar_with(&mut plain, (0, 8), Reach(4), zero), Some((vec![1, 1, 1, 1], 4)));
+        assert_eq!(plain.stats().dominated, 0);
+        let mut buffers = AStarBuffers::new().with_dominance(true);
+        assert_eq!(Fuel.astar_with(&mut buffers, (0, 8), Reach(4), zero), Some((vec![1, 1, 1, 1], 4)));
+        assert_eq!(buffers.stats().dominated, 4);
+        assert!(buffers.stats().expanded < plain.stats().expanded);
+        assert_eq!(Fuel.focal_with(&mut buffers, (0, 8), Reach(4), zero, zero, 1.5).map(|found| found.1), Some(4));
+        assert!(buffers.stats().dominated > 0);
+
+        // Wrappers pass dominance through.
+        let cached = CachedSpace::new(Fuel, 64);
+        let mut buffers = AStarBuffers::new().with_dominance(true);
+        assert_eq!(cached.astar_with(&mut buffers, (0, 8), Reach(4), zero), Some((vec![1, 1, 1, 1], 4)));
+        assert_eq!(buffers.stats().dominated, 4);
+        assert_eq!(cached.dominance_key(&(3, 1)), 3);
+    }
+
+    #[test]
+    pub fn test_closed_list() {
+        let zero = |_: &u32| 0;
+        let mut buffers = AStarBuffers::new();
+        Roads.astar_with(&mut buffers, 0, 5, zero);
+        assert!(buffers.closed_list().is_empty());
+
+        let mut buffers = buffers.with_closed_list(true);
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, zero), Some((vec![1, 2, 3, 5], 4)));
+        let closed = buffers.take_closed_list();
+        assert_eq!((closed[0].state, closed[0].parent), (0, None));
+        let distances = Roads.distance_map(0);
+        assert!(closed.iter().filter(|node| node.expanded).all(|node| node.g == distances[&node.state]));
+        let mut index = closed.iter().rposition(|node| node.state == 5).unwrap();
+        let mut states = vec![];
+        while let Some(parent) = closed[index].parent {
+            states.push(closed[index].state);
+            index = parent;
+        }
+        assert_eq!(states, vec![5, 3, 2, 1]);
+        assert!(buffers.closed_list().is_empty());
+    }
+
+    #[test]
+    pub fn test_distance_map() {
+        let distances = Roads.distance_map(0);
+        assert_eq!(distances.len(), 6);
+        assert_eq!((distances[&0], distances[&3], distances[&4], distances[&5]), (0, 3, 2, 4));
+        assert_eq!(Roads.distance_map(4).len(), 2);
+
+        let graph = random_graph(200, 3, 20, &mut StdRng::seed_from_u64(3));
+        let distances = graph.distance_map(0);
+        for node in 0..200 {
+            assert_eq!(distances.get(&node).copied(), graph.astar(0, node, |_: &usize| 0).map(|found| found.1));
+        }
+    }
+
+    // Walk along a line that looks the same either way from 0, with walls
+    // four steps out.
+    struct Walled;
+
+    impl SearchSpace for Walled {
+        type State = i32;
+        type Action = i32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &state: &Self::State) -> Self::Iterator {
+            vec![(-1, state - 1), (1, state + 1)].into_iter()
+        }
+
+        fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+            Some(state.abs())
+        }
+
+        fn is_dead_end(&self, state: &Self::State) -> bool {
+            state.abs() >= 4
+        }
+    }
+
+    impl WeightedSearchSpace for Walled {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    #[test]
+    pub fn test_distance_map_canonical() {
+        let distances = Walled.distance_map(-2);
+        let mut keys: Vec<_> = distances.keys().copied().collect();
+        keys.sort_unstable();
+        assert_eq!(keys, [0, 1, 2, 3]);
+        assert_eq!((distances[&0], distances[&1], distances[&2], distances[&3]), (2, 1, 0, 1));
+    }
+
+    #[test]
+    pub fn test_pea_star() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        for threshold in 0..12 {
+            assert_eq!(Roads.pea_star(0, 5, h, threshold), Some((vec![1, 2, 3, 5], 4)));
+            assert_eq!(Roads.pea_star(5, 0, h, threshold), None);
+        }
+
+        // Held back successors are neither counted nor reported: each of
+        // them is generated once, when it enters the open list.
+        let mut buffers = AStarBuffers::new().with_observer(SearchTrace::new());
+        assert_eq!(Roads.pea_star_with(&mut buffers, 0, 5, h, 0).unwrap().1, 4);
+        let generated: Vec<_> = buffers.observer().events().iter().filter_map(|event| match *event {
+            TraceEvent::Generate { state, .. } => Some(state),
+            _ => None,
+        }).collect();
+        assert_eq!(generated, [1, 2, 4, 3, 5]);
+        assert_eq!((buffers.stats().generated, buffers.stats().duplicates), (5, 0));
+    }
+
+    #[test]
+    pub fn test_astar_stats() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        let mut buffers = AStarBuffers::new();
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, h).unwrap().1, 4);
+        let astar = buffers.stats().clone();
+        assert_eq!((astar.expanded, astar.generated, astar.duplicates), (5, 7, 0));
+        assert_eq!((astar.max_open, astar.max_depth), (3, 4));
+        assert!(astar.peak_memory_bytes > 0 && astar.peak_memory_bytes <= astar.approx_memory_bytes());
+
+        assert_eq!(Roads.pea_star_with(&mut buffers, 0, 5, h, 0).unwrap().1, 4);
+        let pea = buffers.stats();
+        assert!(pea.max_open <= astar.max_open);
+        assert!(pea.expanded > astar.expanded);
+    }
+
+    #[test]
+    pub fn test_astar_profile() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        let counts = |expanded, generated, duplicates| LayerCounts { expanded, generated, duplicates };
+        let mut buffers = AStarBuffers::new().profiled();
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, h).unwrap().1, 4);
+        let by_depth = buffers.stats().profile.clone().unwrap();
+        assert_eq!(by_depth.layers()[..2], [counts(1, 0, 0), counts(2, 3, 0)]);
+
+        // Costs band in pairs: 0..2, 2..4, 4..6 and so on.
+        let mut buffers = buffers.profiled_by_f(|&f| f as usize / 2);
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, h).unwrap().1, 4);
+        let by_f = buffers.stats().profile.clone().unwrap();
+        assert_eq!(by_f.layers(), &[counts(1, 0, 0), counts(3, 3, 0), counts(1, 3, 0), counts(0, 0, 0), counts(0, 0, 0), counts(0, 1, 0)]);
+        assert_eq!(by_f.total(), by_depth.total());
+
+        let mut buffers = buffers.profiled();
+        assert_eq!(Roads.astar_with(&mut buffers, 0, 5, h).unwrap().1, 4);
+        assert_eq!(buffers.stats().profile.as_ref(), Some(&by_depth));
+    }
+
+    #[test]
+    pub fn test_ranked_nodes() {
+        // Permutations of four items, reordered by swapping neighbours at a
+        // cost of the larger item swapped plus one.
+        #[derive(Clone, PartialEq, Eq, Hash)]
+        struct Perm([u8; 4]);
+
+        impl RankableState for Perm {
+            fn rank(&self) -> usize {
+                permutation_rank(&self.0)
+            }
+        }
+
+        struct Swaps;
+
+        impl SearchSpace for Swaps {
+            type State = Perm;
+            type Action = usize;
+            type Iterator = IntoIter<(usize, Perm)>;
+
+            fn expand(&self, state: &Perm) -> Self::Iterator {
+                (0..3).map(|at| {
+                    let mut swapped = state.0;
+                    swapped.swap(at, at + 1);
+                    (at, Perm(swapped))
+                }).collect::<Vec<_>>().into_iter()
+            }
+        }
+
+        impl WeightedSearchSpace for Swaps {
+            type Cost = u32;
+
+            fn cost(&self, state: &Perm, &at: &usize) -> u32 {
+                u32::from(state.0[at].max(state.0[at + 1])) + 1
+            }
+        }
+
+        let mut ranked = AStarBuffers::with_node_index(RankedNodes::new(24));
+        for start in &[[3, 2, 1, 0], [1, 3, 0, 2], [0, 1, 2, 3]] {
+            let expected = Swaps.astar(Perm(*start), Perm([0, 1, 2, 3]), |_: &Perm| 0);
+            assert_eq!(Swaps.astar_with(&mut ranked, Perm(*start), Perm([0, 1, 2, 3]), |_: &Perm| 0), expected);
+        }
+        assert_eq!(ranked.stats().closed_entry_bytes, mem::size_of::<Node<Swaps>>());
+    }
+
+    #[test]
+    pub fn test_focal() {
+        let h = |state: &u32| if *state == 5 { 0 } else { 1 };
+        let steps = |state: &u32| 5 - *state;
+        assert_eq!(Roads.focal(0, 5, h, steps, 1.0), Some((vec![1, 2, 3, 5], 4)));
+        assert_eq!(Roads.focal(5, 0, h, steps, 2.0), None);
+
+        let mut grid = random_grid(40, 40, 0.25, &mut StdRng::seed_from_u64(4));
+        let (start, goal) = ((0, 0), (39, 39));
+        grid.set_blocked(start, false);
+        grid.set_blocked(goal, false);
+        let to_goal = |cell: &_| manhattan(*cell, goal);
+        let mut plain = AStarBuffers::new();
+        let optimal = grid.astar_with(&mut plain, start, goal, to_goal).unwrap().1;
+        for &factor in &[1.0, 1.2, 2.0] {
+            let mut buffers = AStarBuffers::new();
+            let (actions, cost) = grid.focal_with(&mut buffers, start, goal, to_goal, to_goal, factor).unwrap();
+            assert_eq!(actions.len() as u64, cost);
+            assert!(cost >= optimal && cost as f64 <= optimal as f64 * factor);
+            if factor > 1.0 {
+                assert!(buffers.stats().expanded < plain.stats().expanded);
+            }
+        }
+    }
+}
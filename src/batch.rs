@@ -0,0 +1,200 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::mem;
+
+use observer::Observer;
+use search::SearchGoal;
+use stats::SearchStats;
+use stopwatch::Stopwatch;
+#[cfg(feature = "tracing")]
+use trace;
+use visited::{Visited, VisitedSet};
+
+/// Search space whose successor function works on many states at once, e.g.
+/// when expansion is backed by a batched model evaluation.
+pub trait BatchSearchSpace {
+    type State: Hash + Clone + Eq;
+    type Action;
+
+    /// Expands every state in `states`, returning their successors in order.
+    fn expand_batch(&self, states: &[Self::State]) -> Vec<Vec<(Self::Action, Self::State)>>;
+
+    /// Representative of the symmetry class of `state`, used in its place for
+    /// duplicate detection; `None` keeps `state` itself.
+    fn canonicalize(&self, _state: &Self::State) -> Option<Self::State> {
+        None
+    }
+
+    /// Whether no goal can be reached from `state`; such states are dropped
+    /// as they are generated.
+    fn is_dead_end(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    /// Number of states a search is expected to visit, for sizing its visited
+    /// set and node store up front; 0 lets them grow as needed.
+    fn expected_states(&self) -> usize {
+        0
+    }
+
+    /// Breadth-first search handing up to `batch_size` frontier states to
+    /// each `expand_batch` call.
+    fn bfs_batched<G>(&self, start: Self::State, goal: G, batch_size: usize) -> Option<Vec<Self::Action>>
+    where G: SearchGoal<Self::State> {
+        self.bfs_batched_with(&mut SearchStats::new(), (), start, goal, batch_size)
+    }
+
+    /// Like `bfs_batched`, recording the run into `stats` and reporting its
+    /// events to `observer`.
+    fn bfs_batched_with<G, O>(&self, stats: &mut SearchStats, mut observer: O, start: Self::State, goal: G,
+                              batch_size: usize) -> Option<Vec<Self::Action>>
+    where G: SearchGoal<Self::State>, O: Observer<Self::State, Self::Action> {
+        assert!(batch_size > 0, "batch size must be positive");
+        #[cfg(feature = "tracing")]
+        let _span = trace::search_span("bfs_batched").entered();
+        let started = Stopwatch::start();
+        stats.clear();
+        let found = bfs_batched(self, stats, &mut observer, start, goal, batch_size);
+        stats.elapsed = started.elapsed();
+        #[cfg(feature = "tracing")]
+        trace::finished(found.is_some(), stats);
+        found
+    }
+}
+
+fn bfs_batched<S, G, O>(search_space: &S, stats: &mut SearchStats, observer: &mut O, start: S::State, goal: G,
+                       batch_size: usize) -> Option<Vec<S::Action>>
+where S: BatchSearchSpace + ?Sized, G: SearchGoal<S::State>, O: Observer<S::State, S::Action> {
+    if goal.is_goal(&start) {
+        observer.on_goal(&start, 0, &());
+        return Some(vec![]);
+    }
+
+    let expected = search_space.expected_states();
+    let mut visited = Visited::with_capacity(expected);
+    stats.set_entry_bytes(mem::size_of::<Node<S::Action, S::State>>() + visited.approx_entry_bytes(),
+                          mem::size_of::<usize>());
+    visited.insert(search_space.canonicalize(&start).as_ref().unwrap_or(&start));
+    let mut nodes = Vec::with_capacity(expected.max(1));
+    nodes.push((None, start, 0));
+    let mut queue: VecDeque<usize> = Some(0).into_iter().collect();
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut batch_indices = Vec::with_capacity(batch_size);
+
+    while !queue.is_empty() {
+        batch.clear();
+        batch_indices.clear();
+        while batch.len() < batch_size {
+            match queue.pop_front() {
+                Some(index) => {
+                    batch.push(nodes[index].1.clone());
+                    stats.record_expanded(nodes[index].2);
+                    #[cfg(feature = "tracing")]
+                    {
+                        if trace::sampled(stats) {
+                            ::tracing::trace!(depth = nodes[index].2, expanded = stats.expanded, "expand");
+                        }
+                    }
+                    observer.on_expand(&nodes[index].1, nodes[index].2, &());
+                    batch_indices.push(index);
+                }
+                None => break,
+            }
+        }
+
+        let expanded = search_space.expand_batch(&batch);
+        assert_eq!(expanded.len(), batch.len(), "expand_batch must return one entry per state");
+        for (&parent, successors) in batch_indices.iter().zip(expanded) {
+            let depth = nodes[parent].2 + 1;
+            for (action, state) in successors {
+                stats.record_generated(depth);
+                if !visited.insert(search_space.canonicalize(&state).as_ref().unwrap_or(&state)) {
+                    stats.record_duplicate(depth);
+                    observer.on_duplicate(&state, depth);
+                    continue;
+                }
+                if search_space.is_dead_end(&state) {
+                    stats.record_dead_end();
+                    observer.on_prune(&state, depth);
+                    continue;
+                }
+                if observer.is_pruned(&state, depth, &()) {
+                    stats.record_pruned();
+                    observer.on_prune(&state, depth);
+                    continue;
+                }
+                observer.on_generate(&nodes[parent].1, &action, &state, depth, &());
+                let found = goal.is_goal(&state);
+                if found {
+                    observer.on_goal(&state, depth, &());
+                }
+                nodes.push((Some((parent, action)), state, depth));
+                stats.record_depth(depth);
+                if found {
+                    let last = nodes.len() - 1;
+                    return Some(path_to(nodes, last));
+                }
+                queue.push_back(nodes.len() - 1);
+            }
+        }
+        stats.record_open(queue.len());
+    }
+    None
+}
+
+type Node<A, S> = (Option<(usize, A)>, S, usize);
+
+fn path_to<A, S>(mut nodes: Vec<Node<A, S>>, mut index: usize) -> Vec<A> {
+    let mut actions = vec![];
+    while let Some((parent, action)) = nodes[index].0.take() {
+        actions.push(action);
+        index = parent;
+    }
+    actions.reverse();
+    actions
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::RefCell;
+    use stats::SearchStats;
+    use super::BatchSearchSpace;
+
+    struct Doubling(RefCell<Vec<usize>>);
+
+    impl BatchSearchSpace for Doubling {
+        type State = u32;
+        type Action = char;
+
+        fn expand_batch(&self, states: &[u32]) -> Vec<Vec<(char, u32)>> {
+            self.0.borrow_mut().push(states.len());
+            states.iter().map(|&state| {
+                vec![('d', state * 2), ('i', state + 1)].into_iter().filter(|&(_, s)| s < 64).collect()
+            }).collect()
+        }
+
+        fn expected_states(&self) -> usize {
+            63
+        }
+    }
+
+    #[test]
+    pub fn test_bfs_batched() {
+        let space = Doubling(RefCell::new(vec![]));
+        assert_eq!(space.bfs_batched(1, 1, 4).unwrap(), vec![]);
+        assert_eq!(space.bfs_batched(1, 10, 4).unwrap(), vec!['d', 'd', 'i', 'd']);
+        assert_eq!(*space.0.borrow(), vec![1, 1, 2, 3]);
+
+        space.0.borrow_mut().clear();
+        assert!(space.bfs_batched(1, 100, 8).is_none());
+        assert!(space.0.borrow().iter().all(|&size| size <= 8));
+        assert_eq!(space.0.borrow().iter().sum::<usize>(), 63);
+
+        let mut stats = SearchStats::new();
+        assert!(space.bfs_batched_with(&mut stats, (), 1, 100, 8).is_none());
+        assert_eq!((stats.expanded, stats.generated), (63, 93));
+        assert_eq!(stats.generated - stats.duplicates, 62);
+        assert_eq!(stats.max_depth, 10);
+    }
+}
@@ -0,0 +1,244 @@
+//! Spaces whose actions can be undone: searching them backwards and from
+//! both ends at once.
+
+use alloc::vec::Vec;
+use core::mem;
+
+use hashbrown::HashMap;
+
+use path::Path;
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Action that can be undone by another: if `action` leads from `s` to `t`,
+/// then `action.invert()` leads from `t` back to `s`.
+pub trait InvertibleAction {
+    fn invert(&self) -> Self;
+}
+
+/// The space searched backwards, derived from one whose actions can all be
+/// undone: expanding a state yields its predecessors, each with the action
+/// leading from it to the state, as needed by backward searches and for
+/// building heuristics from distances to a goal; see
+/// `SearchSpace::reversed`. Costs are those of the actions taken forwards.
+pub struct ReversedSpace<S> {
+    search_space: S,
+}
+
+impl<S> ReversedSpace<S> {
+    pub fn new(search_space: S) -> ReversedSpace<S> {
+        ReversedSpace { search_space }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+/// Predecessors of a `ReversedSpace` expansion.
+pub struct Predecessors<I> {
+    successors: I,
+}
+
+impl<A, T, I> Iterator for Predecessors<I> where I: Iterator<Item=(A, T)>, A: InvertibleAction {
+    type Item = (A, T);
+
+    fn next(&mut self) -> Option<(A, T)> {
+        self.successors.next().map(|(action, state)| (action.invert(), state))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.successors.size_hint()
+    }
+}
+
+impl<S> SearchSpace for ReversedSpace<S> where S: SearchSpace, S::Action: InvertibleAction {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = Predecessors<S::Iterator>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        Predecessors { successors: self.search_space.expand(state) }
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    /// Forward dead ends: no goal is reached from them, so no path leads
+    /// back to one from the goal either.
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S> WeightedSearchSpace for ReversedSpace<S>
+where S: WeightedSearchSpace, S::Action: InvertibleAction + PartialEq {
+    type Cost = S::Cost;
+
+    /// Cost of `action` taken forwards from the predecessor it leads from,
+    /// which takes expanding `state` again to find; searches go by
+    /// `step_cost` instead.
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.expand(state)
+            .find(|(taken, _)| taken == action)
+            .map(|(_, predecessor)| self.search_space.cost(&predecessor, action))
+            .expect("action leading to the state")
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, predecessor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(predecessor, action, state)
+    }
+}
+
+// Link of a state to the neighbour it was reached through and the action
+// between them, taken from the neighbour on the forward side and towards it
+// on the backward side, with the state's distance from its side's root.
+type Tree<S, A> = HashMap<S, (Option<(S, A)>, usize)>;
+
+/// Breadth-first search from `start` and from `goal` at once, each side
+/// expanding its smaller frontier a layer at a time, until they meet. Both
+/// sides expand with `SearchSpace::expand`; the backward side inverts the
+/// actions it follows, so every action must be invertible. Returns a path
+/// with the fewest actions, its second half stitched on with the actions
+/// leading towards `goal`.
+pub fn meet_in_the_middle<S>(search_space: &S, start: S::State, goal: S::State) -> Option<Path<S::State, S::Action>>
+where S: SearchSpace + ?Sized, S::Action: InvertibleAction {
+    let mut forward: Tree<S::State, S::Action> = HashMap::new();
+    let mut backward: Tree<S::State, S::Action> = HashMap::new();
+    forward.insert(start.clone(), (None, 0));
+    backward.insert(goal.clone(), (None, 0));
+    let mut forward_frontier = vec![start];
+    let mut backward_frontier = vec![goal];
+    let mut meeting = forward_frontier.iter().find(|state| backward.contains_key(*state)).cloned();
+    while meeting.is_none() && !forward_frontier.is_empty() && !backward_frontier.is_empty() {
+        meeting = if forward_frontier.len() <= backward_frontier.len() {
+            expand_layer(search_space, &mut forward_frontier, &mut forward, &backward, |action| action)
+        } else {
+            expand_layer(search_space, &mut backward_frontier, &mut backward, &forward, |action: S::Action| action.invert())
+        };
+    }
+    let meeting = meeting?;
+
+    let mut steps = Vec::new();
+    let mut at = meeting.clone();
+    while let Some((Some((parent, action)), _)) = forward.remove(&at) {
+        steps.push((action, mem::replace(&mut at, parent)));
+    }
+    let mut path = Path::new(at);
+    for (action, state) in steps.into_iter().rev() {
+        path.push(action, state);
+    }
+    let mut at = meeting;
+    while let Some((Some((next, action)), _)) = backward.remove(&at) {
+        path.push(action, next.clone());
+        at = next;
+    }
+    Some(path)
+}
+
+// Expands every state of `frontier` into the next layer of `tree`, returning
+// the state joining it to `other` with the fewest actions in total, if any.
+fn expand_layer<S, F>(search_space: &S, frontier: &mut Vec<S::State>, tree: &mut Tree<S::State, S::Action>,
+                      other: &Tree<S::State, S::Action>, link: F) -> Option<S::State>
+where S: SearchSpace + ?Sized, F: Fn(S::Action) -> S::Action {
+    let mut meeting: Option<(usize, S::State)> = None;
+    for state in mem::take(frontier) {
+        let depth = tree[&state].1 + 1;
+        for (action, successor) in search_space.expand(&state) {
+            if tree.contains_key(&successor) {
+                continue;
+            }
+            if let Some(&(_, other_depth)) = other.get(&successor) {
+                if meeting.as_ref().is_none_or(|(total, _)| depth + other_depth < *total) {
+                    meeting = Some((depth + other_depth, successor.clone()));
+                }
+            }
+            tree.insert(successor.clone(), (Some((state.clone(), link(action))), depth));
+            frontier.push(successor);
+        }
+    }
+    meeting.map(|(_, state)| state)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::InvertibleAction;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    enum Move { Add(i32), Double, Halve }
+
+    impl InvertibleAction for Move {
+        fn invert(&self) -> Move {
+            match *self {
+                Move::Add(step) => Move::Add(-step),
+                Move::Double => Move::Halve,
+                Move::Halve => Move::Double,
+            }
+        }
+    }
+
+    // Integers within ±1000 reached by adding or taking one, doubling, or
+    // halving even numbers.
+    struct Numbers;
+
+    impl SearchSpace for Numbers {
+        type State = i32;
+        type Action = Move;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &state: &Self::State) -> Self::Iterator {
+            let mut moves = vec![(Move::Add(1), state + 1), (Move::Add(-1), state - 1), (Move::Double, state * 2)];
+            if state % 2 == 0 {
+                moves.push((Move::Halve, state / 2));
+            }
+            moves.retain(|&(_, next)| next.abs() <= 1000);
+            moves.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Numbers {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, action: &Self::Action) -> Self::Cost {
+            match *action {
+                Move::Add(_) => 1,
+                Move::Double => 3,
+                Move::Halve => 2,
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_meet_in_the_middle() {
+        let path = Numbers.meet_in_the_middle(3, 25).unwrap();
+        assert_eq!((path.start(), path.end(), path.len()), (&3, &25, 4));
+        assert_eq!(path.len(), Numbers.bfs_iter(3).find(|&(_, state)| state == 25).unwrap().0);
+        for (from, action, to) in path.steps() {
+            assert_eq!(Numbers.expand(from).find(|(taken, _)| taken == action).map(|(_, next)| next), Some(*to));
+        }
+
+        for goal in -40..40 {
+            let path = Numbers.meet_in_the_middle(7, goal).unwrap();
+            assert_eq!(Some(path.len()), Numbers.bfs_iter(7).find(|&(_, state)| state == goal).map(|(depth, _)| depth));
+        }
+        assert_eq!(Numbers.meet_in_the_middle(5, 5).map(|path| path.states().to_vec()), Some(vec![5]));
+        assert_eq!(Numbers.meet_in_the_middle(5, 5000), None);
+    }
+
+    #[test]
+    pub fn test_reversed() {
+        let reversed = Numbers.reversed();
+        assert!(reversed.expand(&24).any(|step| step == (Move::Double, 12)));
+        assert_eq!(reversed.cost(&24, &Move::Double), 3);
+        let to_goal = reversed.distance_map(25);
+        for start in -20..20 {
+            assert_eq!(Some(to_goal[&start]), Numbers.astar(start, 25, |_: &i32| 0).map(|found| found.1));
+        }
+    }
+}
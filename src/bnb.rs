@@ -0,0 +1,162 @@
+//! Depth-first branch and bound over weighted search spaces.
+
+use alloc::vec::Vec;
+
+use anytime::SearchResult;
+use heuristic::Heuristic;
+use search::{ExpandContext, SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// Depth-first branch and bound: explores the search tree depth first,
+/// without a visited set, and cuts every branch whose cost so far plus
+/// `bound` cannot beat the best goal found. `bound` must never overestimate
+/// the remaining cost.
+///
+/// `incumbent` is the cost of a solution known beforehand, such as one from
+/// a heuristic warm start; only strictly cheaper paths are then returned.
+/// Actions are cloned whenever a better goal is found.
+pub fn depth_first<S, G, B>(search_space: &S, start: S::State, goal: G, bound: B, incumbent: Option<S::Cost>)
+    -> Option<(Vec<S::Action>, S::Cost)>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, G: SearchGoal<S::State>, B: Heuristic<S::State, S::Cost> {
+    AnytimeBranchAndBound::new(search_space, start, goal, bound, incumbent).last().map(|found| (found.actions, found.cost))
+}
+
+/// Node to visit: depth, action leading there, state and cost so far.
+type Branch<S> = (usize, Option<<S as SearchSpace>::Action>, <S as SearchSpace>::State, <S as WeightedSearchSpace>::Cost);
+
+/// Depth-first branch and bound as an anytime search, yielding each goal
+/// that beats the ones before it. A solution's lower bound is the least
+/// cost plus `bound` over the branches still to explore; once none are
+/// left, the last solution is yielded again as proven optimal.
+pub struct AnytimeBranchAndBound<'a, S: WeightedSearchSpace + ?Sized + 'a, G, B> {
+    search_space: &'a S,
+    goal: G,
+    bound: B,
+    best: Option<S::Cost>,
+    path: Vec<S::Action>,
+    stack: Vec<Branch<S>>,
+    found: Option<SearchResult<S::Action, S::Cost>>,
+}
+
+impl<'a, S, G, B> AnytimeBranchAndBound<'a, S, G, B>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, G: SearchGoal<S::State>, B: Heuristic<S::State, S::Cost> {
+    pub fn new(search_space: &'a S, start: S::State, goal: G, bound: B, incumbent: Option<S::Cost>)
+        -> AnytimeBranchAndBound<'a, S, G, B> {
+        AnytimeBranchAndBound {
+            search_space,
+            goal,
+            bound,
+            best: incumbent,
+            path: Vec::new(),
+            stack: vec![(0, None, start, S::Cost::default())],
+            found: None,
+        }
+    }
+}
+
+impl<'a, S, G, B> Iterator for AnytimeBranchAndBound<'a, S, G, B>
+where S: WeightedSearchSpace + ?Sized, S::Action: Clone, G: SearchGoal<S::State>, B: Heuristic<S::State, S::Cost> {
+    type Item = SearchResult<S::Action, S::Cost>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((depth, action, state, cost)) = self.stack.pop() {
+            self.path.truncate(depth);
+            if let Some(action) = action {
+                self.path.push(action);
+            }
+            if self.best.is_some_and(|best| cost + self.bound.estimate(&state) >= best) {
+                continue;
+            }
+            if self.goal.is_goal(&state) {
+                self.best = Some(cost);
+                let lower_bound = self.stack.iter()
+                    .map(|(_, _, state, cost)| *cost + self.bound.estimate(state))
+                    .fold(cost, |lowest, f| lowest.min(f));
+                let found = SearchResult { actions: self.path.clone(), cost, lower_bound };
+                self.found = Some(found.clone());
+                return Some(found);
+            }
+            let first = self.stack.len();
+            for (action, successor) in self.search_space.expand_weighted(&state, ExpandContext { depth: self.path.len(), cost }) {
+                if self.search_space.is_dead_end(&successor) {
+                    continue;
+                }
+                let cost = cost + self.search_space.step_cost(&state, &action, &successor);
+                self.stack.push((self.path.len(), Some(action), successor, cost));
+            }
+            // Visit successors in the order they were generated.
+            self.stack[first..].reverse();
+        }
+        match self.found.take() {
+            Some(mut found) if !found.is_optimal() => {
+                found.lower_bound = found.cost;
+                Some(found)
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+    use super::{depth_first, AnytimeBranchAndBound};
+
+    // Picks, one item at a time, whether to take it; the cost is the value
+    // of the items left out, so the cheapest path keeps the most value.
+    struct Pick;
+
+    const ITEMS: &[(u32, u32)] = &[(5, 4), (4, 3), (3, 2), (6, 5)];
+    const CAPACITY: u32 = 9;
+
+    impl SearchSpace for Pick {
+        // Items decided and weight taken.
+        type State = (usize, u32);
+        type Action = bool;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &(item, weight): &Self::State) -> Self::Iterator {
+            match ITEMS.get(item) {
+                Some(&(_, w)) if weight + w <= CAPACITY => {
+                    vec![(true, (item + 1, weight + w)), (false, (item + 1, weight))]
+                }
+                Some(_) => vec![(false, (item + 1, weight))],
+                None => vec![],
+            }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Pick {
+        type Cost = u32;
+
+        fn cost(&self, &(item, _): &Self::State, &take: &Self::Action) -> Self::Cost {
+            if take { 0 } else { ITEMS[item].0 }
+        }
+    }
+
+    struct AllDecided;
+
+    impl SearchGoal<(usize, u32)> for AllDecided {
+        fn is_goal(&self, &(item, _): &(usize, u32)) -> bool {
+            item == ITEMS.len()
+        }
+    }
+
+    #[test]
+    pub fn test_branch_and_bound() {
+        assert_eq!(depth_first(&Pick, (0, 0), AllDecided, |_: &_| 0, None), Some((vec![true, true, true, false], 6)));
+        assert_eq!(Pick.branch_and_bound((0, 0), AllDecided, |_: &_| 0, Some(6)), None);
+        assert_eq!(Pick.branch_and_bound((0, 0), AllDecided, |_: &_| 0, Some(7)).map(|found| found.1), Some(6));
+    }
+
+    #[test]
+    pub fn test_anytime_branch_and_bound() {
+        let results: Vec<_> = AnytimeBranchAndBound::new(&Pick, (0, 0), AllDecided, |_: &_| 0, None).collect();
+        assert!(results.windows(2).all(|pair| pair[1].cost <= pair[0].cost));
+        assert!(results.iter().all(|result| result.lower_bound <= 6));
+        let last = results.last().unwrap();
+        assert!(last.is_optimal());
+        assert_eq!((&last.actions[..], last.cost), (&[true, true, true, false][..], 6));
+        assert_eq!(Pick.anytime_branch_and_bound((0, 0), AllDecided, |_: &_| 0, Some(6)).count(), 0);
+    }
+}
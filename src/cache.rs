@@ -0,0 +1,310 @@
+use alloc::collections::BTreeMap;
+use alloc::vec::{IntoIter, Vec};
+use core::cell::{Cell, RefCell};
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+
+struct Lru<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, (V, u64)>,
+    order: BTreeMap<u64, K>,
+}
+
+impl<K, V> Lru<K, V> where K: Hash + Clone + Eq {
+    fn new(capacity: usize) -> Lru<K, V> {
+        Lru {
+            capacity,
+            tick: 0,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+        }
+    }
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        let tick = self.tick;
+        match self.entries.get_mut(key) {
+            None => None,
+            Some(entry) => {
+                self.tick += 1;
+                let key = self.order.remove(&entry.1).expect("lru order out of sync");
+                self.order.insert(tick, key);
+                entry.1 = tick;
+                Some(&entry.0)
+            }
+        }
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.capacity == 0 {
+            return;
+        }
+        if let Some((_, old)) = self.entries.remove(&key) {
+            self.order.remove(&old);
+        }
+        while self.entries.len() >= self.capacity {
+            let oldest = *self.order.keys().next().expect("lru order out of sync");
+            let evicted = self.order.remove(&oldest).expect("lru order out of sync");
+            self.entries.remove(&evicted);
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (value, self.tick));
+        self.tick += 1;
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+type Successors<S> = Vec<(<S as SearchSpace>::Action, <S as SearchSpace>::State)>;
+
+/// Wraps a search space and memoizes the successors of up to `capacity`
+/// recently expanded states, evicting the least recently used ones first.
+///
+/// Successors are memoized by state, which only holds for spaces whose
+/// successors do not depend on the `ExpandContext`. Spaces that override
+/// `expand_at` or `expand_weighted` to look at it must be wrapped with
+/// `with_context_dependent(true)`, which sends those expansions straight to
+/// the wrapped space.
+pub struct CachedSpace<S: SearchSpace> {
+    search_space: S,
+    cache: RefCell<Lru<S::State, Successors<S>>>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+    context_dependent: bool,
+}
+
+impl<S: SearchSpace> CachedSpace<S> {
+    pub fn new(search_space: S, capacity: usize) -> CachedSpace<S> {
+        CachedSpace {
+            search_space,
+            cache: RefCell::new(Lru::new(capacity)),
+            hits: Cell::new(0),
+            misses: Cell::new(0),
+            context_dependent: false,
+        }
+    }
+
+    /// Whether the successors of a state depend on where the search reached
+    /// it. If so, only plain `expand` calls are memoized.
+    pub fn with_context_dependent(mut self, context_dependent: bool) -> CachedSpace<S> {
+        self.context_dependent = context_dependent;
+        self
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+
+    pub fn len(&self) -> usize {
+        self.cache.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.get()
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.get()
+    }
+
+    pub fn clear(&self) {
+        self.cache.borrow_mut().clear();
+    }
+
+    // Successors by `expand`, bypassing the cache if they depend on the
+    // context `expand` is given.
+    fn in_context<F>(&self, state: &S::State, expand: F) -> IntoIter<(S::Action, S::State)>
+    where S::Action: Clone, F: FnOnce() -> Successors<S> {
+        if self.context_dependent {
+            return expand().into_iter();
+        }
+        self.cached(state, expand)
+    }
+
+    fn cached<F>(&self, state: &S::State, expand: F) -> IntoIter<(S::Action, S::State)>
+    where S::Action: Clone, F: FnOnce() -> Successors<S> {
+        if let Some(successors) = self.cache.borrow_mut().get(state) {
+            self.hits.set(self.hits.get() + 1);
+            return successors.clone().into_iter();
+        }
+        self.misses.set(self.misses.get() + 1);
+        let successors = expand();
+        self.cache.borrow_mut().insert(state.clone(), successors.clone());
+        successors.into_iter()
+    }
+}
+
+impl<S: SearchSpace> SearchSpace for CachedSpace<S> where S::Action: Clone {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.cached(state, || self.search_space.expand(state).collect())
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.in_context(state, || self.search_space.expand_at(state, context).collect())
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S: WeightedSearchSpace> WeightedSearchSpace for CachedSpace<S> where S::Action: Clone {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, action, successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.in_context(state, || self.search_space.expand_weighted(state, context).collect())
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::Cell;
+    use std::vec::IntoIter;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::manhattan;
+    use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+    use super::CachedSpace;
+
+    struct Chain(Cell<usize>);
+
+    impl SearchSpace for Chain {
+        type State = u32;
+        type Action = ();
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            self.0.set(self.0.get() + 1);
+            if *state < 5 { vec![((), state + 1)] } else { vec![] }.into_iter()
+        }
+    }
+
+    // Chain that ends three steps from wherever the search starts.
+    struct Shallow;
+
+    impl SearchSpace for Shallow {
+        type State = u32;
+        type Action = ();
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            vec![((), state + 1)].into_iter()
+        }
+
+        fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+            if context.depth < 3 { self.expand(state) } else { vec![].into_iter() }
+        }
+    }
+
+    impl WeightedSearchSpace for Shallow {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    #[test]
+    pub fn test_cached_space() {
+        let cs = CachedSpace::new(Chain(Cell::new(0)), 16);
+        assert_eq!(cs.dfs(0, 5).unwrap().len(), 5);
+        assert_eq!(cs.dfs(0, 5).unwrap().len(), 5);
+        assert_eq!(cs.inner().0.get(), 5);
+        assert_eq!((cs.hits(), cs.misses()), (5, 5));
+
+        let cs = CachedSpace::new(Chain(Cell::new(0)), 2);
+        cs.dfs(0, 5);
+        assert_eq!(cs.len(), 2);
+        cs.dfs(3, 5);
+        assert_eq!(cs.inner().0.get(), 5);
+        cs.dfs(0, 1);
+        assert_eq!(cs.inner().0.get(), 6);
+    }
+
+    #[test]
+    pub fn test_cached_weighted_space() {
+        let grid = random_grid(20, 20, 0.2, &mut StdRng::seed_from_u64(3));
+        let cs = CachedSpace::new(grid.clone(), 400);
+        let to_goal = |&cell: &(usize, usize)| manhattan(cell, (19, 19));
+        let expected = grid.astar((0, 0), (19, 19), to_goal);
+        assert_eq!(cs.astar((0, 0), (19, 19), to_goal), expected);
+        let misses = cs.misses();
+        assert_eq!(cs.astar((0, 0), (19, 19), to_goal), expected);
+        assert_eq!(cs.misses(), misses);
+    }
+
+    #[test]
+    pub fn test_cached_expand_at() {
+        let cs = CachedSpace::new(Shallow, 16).with_context_dependent(true);
+        assert_eq!(cs.dfs(0, 3).map(|path| path.len()), Some(3));
+        assert_eq!(cs.dfs(0, 4), None);
+        let cs = CachedSpace::new(Shallow, 16).with_context_dependent(true);
+        assert_eq!(cs.astar(0, 3, |_: &u32| 0).map(|found| found.1), Some(3));
+        assert_eq!(cs.astar(0, 4, |_: &u32| 0), None);
+    }
+
+    #[test]
+    pub fn test_cached_mixed_contexts() {
+        // State 3 is a leaf three steps from 0 but not one step from 2.
+        let cs = CachedSpace::new(Shallow, 16).with_context_dependent(true);
+        assert_eq!(cs.dfs(0, 4), None);
+        assert_eq!(cs.dfs(2, 4).map(|path| path.len()), Some(2));
+        assert_eq!(cs.astar(0, 4, |_: &u32| 0), None);
+        assert_eq!(cs.astar(2, 4, |_: &u32| 0).map(|found| found.1), Some(2));
+        assert_eq!(cs.expand_at(&3, ExpandContext { depth: 3, cost: () }).count(), 0);
+        assert_eq!(cs.expand_at(&3, ExpandContext { depth: 1, cost: () }).count(), 1);
+        assert_eq!(cs.misses(), 0);
+
+        // Plain expansions are still memoized.
+        assert_eq!(cs.expand(&3).count(), 1);
+        assert_eq!(cs.expand(&3).count(), 1);
+        assert_eq!((cs.hits(), cs.misses()), (1, 1));
+    }
+}
@@ -0,0 +1,166 @@
+//! Saving searches in progress to files and resuming them, behind the
+//! `json` and `std` features, so that a long search survives its process
+//! being stopped.
+//!
+//! A checkpoint file holds a header line, `chappie-checkpoint`, the format
+//! version, a fingerprint of the search space and a checksum of the rest,
+//! followed by the search state as JSON. The fingerprint hashes the start
+//! state and its successors, which catches resuming against a different
+//! space in most cases, not all.
+
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::Path;
+
+use fnv::FnvHasher;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use search::SearchSpace;
+
+/// Version of the checkpoint format written.
+pub const CHECKPOINT_VERSION: u32 = 1;
+
+const MAGIC: &str = "chappie-checkpoint";
+
+/// Why a checkpoint could not be written or read back.
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    /// The search state could not be encoded or decoded.
+    Format(serde_json::Error),
+    /// The file is not a checkpoint or was written by another version.
+    UnsupportedVersion,
+    /// The contents do not match their checksum.
+    Corrupted,
+    /// The checkpoint was taken on a different search space.
+    SpaceMismatch,
+}
+
+impl fmt::Display for CheckpointError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "checkpoint I/O failed: {}", error),
+            CheckpointError::Format(error) => write!(f, "invalid checkpoint contents: {}", error),
+            CheckpointError::UnsupportedVersion => write!(f, "not a checkpoint of a supported version"),
+            CheckpointError::Corrupted => write!(f, "checkpoint checksum mismatch"),
+            CheckpointError::SpaceMismatch => write!(f, "checkpoint taken on a different search space"),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> CheckpointError {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(error: serde_json::Error) -> CheckpointError {
+        CheckpointError::Format(error)
+    }
+}
+
+/// Hash of `start` and the successors `search_space` expands it into,
+/// stable across runs of the same build.
+pub fn space_fingerprint<S>(search_space: &S, start: &S::State) -> u64 where S: SearchSpace + ?Sized {
+    let mut hasher = FnvHasher::default();
+    start.hash(&mut hasher);
+    let mut successors = 0usize;
+    for (_, successor) in search_space.expand(start) {
+        successor.hash(&mut hasher);
+        successors += 1;
+    }
+    successors.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn checksum(body: &[u8]) -> u64 {
+    let mut hasher = FnvHasher::default();
+    hasher.write(body);
+    hasher.finish()
+}
+
+/// Writes `state` with the space `fingerprint` to `path`, through a
+/// temporary file renamed over it, so that a crash while writing leaves the
+/// previous checkpoint intact.
+pub(crate) fn write<T: Serialize, P: AsRef<Path>>(path: P, fingerprint: u64, state: &T) -> Result<(), CheckpointError> {
+    let body = serde_json::to_vec(state)?;
+    let mut contents = format!("{} {} {:016x} {:016x}\n", MAGIC, CHECKPOINT_VERSION, fingerprint, checksum(&body))
+        .into_bytes();
+    contents.extend_from_slice(&body);
+    let path = path.as_ref();
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".tmp");
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)?;
+    Ok(())
+}
+
+/// Reads a checkpoint written by `write`, returning its space fingerprint
+/// and state.
+pub(crate) fn read<T: DeserializeOwned, P: AsRef<Path>>(path: P) -> Result<(u64, T), CheckpointError> {
+    let contents = fs::read(path)?;
+    let split = contents.iter().position(|&byte| byte == b'\n').ok_or(CheckpointError::UnsupportedVersion)?;
+    let (header, body) = (&contents[..split], &contents[split + 1..]);
+    let header = std::str::from_utf8(header).map_err(|_| CheckpointError::UnsupportedVersion)?;
+    let fields: Vec<&str> = header.split(' ').collect();
+    let version = CHECKPOINT_VERSION.to_string();
+    if fields.len() != 4 || fields[0] != MAGIC || fields[1] != version {
+        return Err(CheckpointError::UnsupportedVersion);
+    }
+    let hex = |field: &str| u64::from_str_radix(field, 16).map_err(|_| CheckpointError::Corrupted);
+    let (fingerprint, expected) = (hex(fields[2])?, hex(fields[3])?);
+    if checksum(body) != expected {
+        return Err(CheckpointError::Corrupted);
+    }
+    Ok((fingerprint, serde_json::from_slice(body)?))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::fs;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use astar::{AStarBuffers, AStarSession};
+    use generators::random_grid;
+    use grid::{manhattan, Cell};
+    use search::WeightedSearchSpace;
+    use session::{SearchLimits, SearchOutcome, SearchSession};
+    use super::CheckpointError;
+
+    #[test]
+    pub fn test_checkpoint() {
+        let grid = random_grid(30, 30, 0.2, &mut StdRng::seed_from_u64(12));
+        let to_goal = |cell: &Cell| manhattan(*cell, (29, 29));
+        let path = std::env::temp_dir().join(format!("chappie-checkpoint-{}", std::process::id()));
+
+        let mut buffers = AStarBuffers::new();
+        let mut session = grid.astar_session(&mut buffers, (0, 0), (29, 29), to_goal);
+        assert_eq!(session.run_limited(&SearchLimits::new().with_max_steps(50)), SearchOutcome::NodeLimit);
+        session.checkpoint(&path).unwrap();
+        let expanded = session.stats().expanded;
+
+        let mut buffers = AStarBuffers::new();
+        let mut session = AStarSession::restore(&mut buffers, &grid, &path, (29, 29), to_goal, None).unwrap();
+        assert_eq!(session.stats().expanded, expanded);
+        assert_eq!(session.run(), grid.astar((0, 0), (29, 29), to_goal));
+
+        let other = random_grid(30, 30, 0.2, &mut StdRng::seed_from_u64(13));
+        let mut buffers = AStarBuffers::new();
+        let restored = AStarSession::restore(&mut buffers, &other, &path, (29, 29), to_goal, None);
+        assert!(matches!(restored, Err(CheckpointError::SpaceMismatch)));
+
+        let mut contents = fs::read(&path).unwrap();
+        let last = contents.len() - 2;
+        contents[last] ^= 1;
+        fs::write(&path, contents).unwrap();
+        let restored = AStarSession::restore(&mut buffers, &grid, &path, (29, 29), to_goal, None);
+        assert!(matches!(restored, Err(CheckpointError::Corrupted)));
+        fs::remove_file(&path).unwrap();
+    }
+}
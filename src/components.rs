@@ -0,0 +1,346 @@
+//! Reachability, strongly connected components and dependency order of the
+//! states in a search space.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt::{self, Debug};
+use core::hash::Hash;
+
+use hashbrown::{HashMap, HashSet};
+
+use graph::Graph;
+use search::SearchSpace;
+
+/// Breadth-first traversal yielding `(depth, state)` for every state
+/// reachable from the start, each once and in order of depth, so a depth
+/// limit never hides a state that is also reachable closer to the start.
+pub struct Reachable<'a, S: SearchSpace + ?Sized + 'a> {
+    search_space: &'a S,
+    seen: HashSet<S::State>,
+    queue: VecDeque<(usize, S::State)>,
+    max_depth: Option<usize>,
+    max_states: Option<usize>,
+    yielded: usize,
+}
+
+impl<'a, S: SearchSpace + ?Sized> Reachable<'a, S> {
+    pub fn new(search_space: &'a S, start: S::State) -> Reachable<'a, S> {
+        let mut seen = HashSet::new();
+        seen.insert(start.clone());
+        Reachable {
+            search_space,
+            seen,
+            queue: VecDeque::from(vec![(0, start)]),
+            max_depth: None,
+            max_states: None,
+            yielded: 0,
+        }
+    }
+
+    /// Leaves out states more than `max_depth` actions away from the start.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Stops after yielding `max_states` states.
+    pub fn with_max_states(mut self, max_states: usize) -> Self {
+        self.max_states = Some(max_states);
+        self
+    }
+
+    /// Rest of the traversal collected into a set.
+    pub fn into_set(self) -> HashSet<S::State> {
+        self.map(|(_, state)| state).collect()
+    }
+}
+
+impl<'a, S: SearchSpace + ?Sized> Iterator for Reachable<'a, S> {
+    type Item = (usize, S::State);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.max_states.is_some_and(|max_states| self.yielded == max_states) {
+            return None;
+        }
+        let (depth, state) = self.queue.pop_front()?;
+        if self.max_depth.is_none_or(|max_depth| depth < max_depth) {
+            for (_, successor) in self.search_space.expand(&state) {
+                if self.seen.insert(successor.clone()) {
+                    self.queue.push_back((depth + 1, successor));
+                }
+            }
+        }
+        self.yielded += 1;
+        Some((depth, state))
+    }
+}
+
+/// Strongly connected components, numbered in topological order of the
+/// condensation: no edge leads from a component to an earlier one.
+#[derive(Clone, Debug)]
+pub struct Components<T: Hash + Eq> {
+    components: Vec<Vec<T>>,
+    index: HashMap<T, usize>,
+    condensation: Graph,
+}
+
+impl<T: Hash + Eq> Components<T> {
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn components(&self) -> &[Vec<T>] {
+        &self.components
+    }
+
+    /// Number of the component holding `state`, if it was reached.
+    pub fn component_of(&self, state: &T) -> Option<usize> {
+        self.index.get(state).copied()
+    }
+
+    /// Acyclic graph with a node per component and a unit-cost edge wherever
+    /// some action leads from one component to another.
+    pub fn condensation(&self) -> &Graph {
+        &self.condensation
+    }
+}
+
+/// Tarjan's algorithm over the states reachable from `roots`, run without
+/// recursion. Gives up with `None` once more than `max_states` states are
+/// reached, so that it can be pointed at spaces too large to enumerate.
+pub fn strongly_connected<S, I>(search_space: &S, roots: I, max_states: usize) -> Option<Components<S::State>>
+where S: SearchSpace + ?Sized, I: IntoIterator<Item=S::State> {
+    // States by discovery number, with their lowest reachable number.
+    let mut states: Vec<S::State> = Vec::new();
+    let mut numbers: HashMap<S::State, usize> = HashMap::new();
+    let mut low: Vec<usize> = Vec::new();
+    let mut on_stack: Vec<bool> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut component: Vec<usize> = Vec::new();
+    let mut found: Vec<Vec<usize>> = Vec::new();
+    let mut edges: Vec<(usize, usize)> = Vec::new();
+    let mut calls: Vec<(usize, S::Iterator)> = Vec::new();
+
+    for root in roots {
+        if numbers.contains_key(&root) {
+            continue;
+        }
+        let mut next = Some(root);
+        loop {
+            if let Some(state) = next.take() {
+                if states.len() == max_states {
+                    return None;
+                }
+                let number = states.len();
+                numbers.insert(state.clone(), number);
+                low.push(number);
+                on_stack.push(true);
+                component.push(usize::MAX);
+                stack.push(number);
+                calls.push((number, search_space.expand(&state)));
+                states.push(state);
+            }
+            let (number, successor) = match calls.last_mut() {
+                Some((number, successors)) => (*number, successors.next()),
+                None => break,
+            };
+            match successor {
+                Some((_, successor)) => match numbers.get(&successor) {
+                    Some(&known) => {
+                        edges.push((number, known));
+                        if on_stack[known] {
+                            low[number] = low[number].min(known);
+                        }
+                    }
+                    None => {
+                        edges.push((number, states.len()));
+                        next = Some(successor);
+                    }
+                },
+                None => {
+                    calls.pop();
+                    if let Some(&(parent, _)) = calls.last() {
+                        low[parent] = low[parent].min(low[number]);
+                    }
+                    if low[number] == number {
+                        let mut members = Vec::new();
+                        loop {
+                            let member = stack.pop().expect("the component root is on the stack");
+                            on_stack[member] = false;
+                            component[member] = found.len();
+                            members.push(member);
+                            if member == number {
+                                break;
+                            }
+                        }
+                        found.push(members);
+                    }
+                }
+            }
+        }
+    }
+
+    // Tarjan finds sinks first, so reverse the numbering.
+    let count = found.len();
+    let mut condensation = Graph::new(count);
+    let mut links: Vec<(usize, usize)> = edges.into_iter()
+        .map(|(from, to)| (count - 1 - component[from], count - 1 - component[to]))
+        .filter(|(from, to)| from != to)
+        .collect();
+    links.sort_unstable();
+    links.dedup();
+    for (from, to) in links {
+        condensation.add_edge(from, to, 1);
+    }
+    let mut states: Vec<Option<S::State>> = states.into_iter().map(Some).collect();
+    let components: Vec<Vec<S::State>> = found.into_iter().rev()
+        .map(|members| members.into_iter().rev().map(|member| states[member].take().expect("states are in one component"))
+             .collect())
+        .collect();
+    let index = numbers.into_iter().map(|(state, number)| (state, count - 1 - component[number])).collect();
+    Some(Components { components, index, condensation })
+}
+
+/// Cycle met while ordering states: the actions and states leading from a
+/// state on the cycle all the way around to it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CycleError<A, S> {
+    pub cycle: Vec<(A, S)>,
+}
+
+impl<A, S: Debug> fmt::Display for CycleError<A, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cycle through")?;
+        for (_, state) in &self.cycle {
+            write!(f, " {:?}", state)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A: Debug, S: Debug> std::error::Error for CycleError<A, S> {}
+
+/// States in dependency order, or the cycle that prevents one.
+pub type TopologicalOrder<S> =
+    Result<Vec<<S as SearchSpace>::State>, CycleError<<S as SearchSpace>::Action, <S as SearchSpace>::State>>;
+
+/// State being visited: action leading there, the state and its successors.
+type Visit<S> = (Option<<S as SearchSpace>::Action>, <S as SearchSpace>::State, <S as SearchSpace>::Iterator);
+
+/// States reachable from `roots` with every state after all the states its
+/// actions lead to, so that when actions point at dependencies, each state
+/// comes after what it depends on. Fails on the first cycle found.
+pub fn topological_sort<S, I>(search_space: &S, roots: I) -> TopologicalOrder<S>
+where S: SearchSpace + ?Sized, I: IntoIterator<Item=S::State> {
+    // Position on the path for states being visited, `None` once finished.
+    let mut positions: HashMap<S::State, Option<usize>> = HashMap::new();
+    let mut path: Vec<Visit<S>> = Vec::new();
+    let mut order = Vec::new();
+
+    for root in roots {
+        if positions.contains_key(&root) {
+            continue;
+        }
+        positions.insert(root.clone(), Some(0));
+        path.push((None, root.clone(), search_space.expand(&root)));
+        while let Some(&mut (_, _, ref mut successors)) = path.last_mut() {
+            match successors.next() {
+                Some((action, state)) => match positions.get(&state) {
+                    Some(&Some(position)) => {
+                        let mut cycle: Vec<_> = path.drain(position + 1..)
+                            .map(|(action, state, _)| (action.expect("only roots have no action"), state))
+                            .collect();
+                        cycle.push((action, state));
+                        return Err(CycleError { cycle });
+                    }
+                    Some(&None) => (),
+                    None => {
+                        positions.insert(state.clone(), Some(path.len()));
+                        let successors = search_space.expand(&state);
+                        path.push((Some(action), state, successors));
+                    }
+                },
+                None => {
+                    let (_, state, _) = path.pop().expect("state to finish");
+                    positions.insert(state.clone(), None);
+                    order.push(state);
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use graph::Graph;
+    use search::SearchSpace;
+    use super::strongly_connected;
+
+    #[test]
+    pub fn test_reachable() {
+        // 0 -> 1 -> 2 -> 3, with a shortcut 0 -> 3 and 4 unreachable.
+        let mut graph = Graph::new(5);
+        for &(from, to) in &[(0, 1), (1, 2), (2, 3), (0, 3), (3, 0)] {
+            graph.add_edge(from, to, 1);
+        }
+        let all: Vec<(usize, usize)> = graph.reachable_from(0).collect();
+        assert_eq!(all, vec![(0, 0), (1, 1), (1, 3), (2, 2)]);
+        let near = graph.reachable_from(0).with_max_depth(1).into_set();
+        assert!(near.len() == 3 && near.contains(&3) && !near.contains(&2));
+        assert_eq!(graph.reachable_from(0).with_max_states(2).count(), 2);
+        assert_eq!(graph.reachable_from(4).into_set().len(), 1);
+    }
+
+    #[test]
+    pub fn test_strongly_connected() {
+        // 0 -> {1 2} -> {3 4 5} and a self-loop on 6, reached from 5.
+        let mut graph = Graph::new(8);
+        for &(from, to) in &[(0, 1), (1, 2), (2, 1), (2, 3), (3, 4), (4, 5), (5, 3), (1, 5), (5, 6), (6, 6)] {
+            graph.add_edge(from, to, 1);
+        }
+        let components = graph.strongly_connected_components(vec![0], 100).unwrap();
+        assert_eq!(components.len(), 4);
+        let mut sorted: Vec<Vec<usize>> = components.components().to_vec();
+        sorted.iter_mut().for_each(|members| members.sort());
+        assert_eq!(sorted, vec![vec![0], vec![1, 2], vec![3, 4, 5], vec![6]]);
+        assert_eq!((components.component_of(&4), components.component_of(&7)), (Some(2), None));
+        let condensation = components.condensation();
+        assert_eq!(condensation.edge_count(), 3);
+        assert_eq!(condensation.find_cycle(0), None);
+        assert!((0..4).all(|from| condensation.edges(from).iter().all(|edge| edge.to > from)));
+
+        let all = strongly_connected(&graph, 0..8, 8).unwrap();
+        assert_eq!((all.len(), all.component_of(&7)), (5, Some(0)));
+        assert!(strongly_connected(&graph, 0..8, 7).is_none());
+    }
+
+    #[test]
+    pub fn test_topological_sort() {
+        // Each node depends on the nodes its edges lead to.
+        let mut graph = Graph::new(6);
+        for &(from, to) in &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 4)] {
+            graph.add_edge(from, to, 1);
+        }
+        let order = graph.topological_sort(vec![0, 5]).unwrap();
+        assert_eq!(order.len(), 6);
+        let position = |node: usize| order.iter().position(|&n| n == node).unwrap();
+        for from in 0..6 {
+            assert!(graph.edges(from).iter().all(|edge| position(edge.to) < position(from)));
+        }
+        assert_eq!(graph.topological_sort(vec![3]), Ok(vec![4, 3]));
+
+        graph.add_edge(4, 1, 1);
+        let error = graph.topological_sort(vec![0]).unwrap_err();
+        let cycle: Vec<usize> = error.cycle.iter().map(|&(_, state)| state).collect();
+        assert_eq!(cycle, vec![3, 4, 1]);
+        assert_eq!(error.cycle.last(), Some(&(0, 1)));
+        assert_eq!(format!("{}", error), "cycle through 3 4 1");
+        assert_eq!(graph.topological_sort(vec![2]).map_err(|error| error.cycle.len()), Err::<Vec<usize>, _>(3));
+    }
+}
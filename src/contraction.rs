@@ -0,0 +1,331 @@
+//! Contraction hierarchies: graph nodes are contracted one by one, least
+//! important first, with shortcut edges standing in for the cheapest paths
+//! through each contracted node. A query then only climbs the hierarchy from
+//! both ends, settling a small fraction of the graph.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+use hashbrown::HashMap;
+
+use graph::Graph;
+
+/// Nodes a witness search may settle before giving up, in which case the
+/// shortcut is added whether or not a witness exists.
+const WITNESS_LIMIT: usize = 500;
+
+/// Edge of the hierarchy. `via` is the contracted node a shortcut stands in
+/// for, so that `from -> via -> to` unpacks it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+struct Arc {
+    to: usize,
+    cost: u64,
+    via: Option<usize>,
+}
+
+/// Search tree of a query: each reached node's distance and the node and
+/// shortcut it was reached by.
+type Tree = HashMap<usize, (u64, Option<(usize, Option<usize>)>)>;
+
+/// Graph of the nodes not yet contracted, keeping the cheapest edge between
+/// each pair of nodes.
+struct Remaining {
+    outgoing: Vec<HashMap<usize, (u64, Option<usize>)>>,
+    incoming: Vec<HashMap<usize, (u64, Option<usize>)>>,
+    /// Contracted neighbours of each node.
+    contracted: Vec<usize>,
+}
+
+impl Remaining {
+    fn new(graph: &Graph) -> Remaining {
+        let nodes = graph.node_count();
+        let mut remaining = Remaining {
+            outgoing: vec![HashMap::new(); nodes],
+            incoming: vec![HashMap::new(); nodes],
+            contracted: vec![0; nodes],
+        };
+        for from in 0..nodes {
+            for edge in graph.edges(from).iter().filter(|edge| edge.to != from) {
+                remaining.link(from, edge.to, edge.cost, None);
+            }
+        }
+        remaining
+    }
+
+    fn link(&mut self, from: usize, to: usize, cost: u64, via: Option<usize>) {
+        if self.outgoing[from].get(&to).is_none_or(|&(known, _)| cost < known) {
+            self.outgoing[from].insert(to, (cost, via));
+            self.incoming[to].insert(from, (cost, via));
+        }
+    }
+
+    // Shortcuts replacing `node`: one for each pair of neighbours whose
+    // cheapest path runs through it.
+    fn shortcuts(&self, node: usize) -> Vec<(usize, usize, u64)> {
+        let mut shortcuts = Vec::new();
+        for (&from, &(into, _)) in &self.incoming[node] {
+            let limit = self.outgoing[node].iter().filter(|&(&to, _)| to != from).map(|(_, &(out, _))| into + out).max();
+            let limit = match limit {
+                Some(limit) => limit,
+                None => continue,
+            };
+            let witnesses = self.witnesses(from, node, limit);
+            for (&to, &(out, _)) in &self.outgoing[node] {
+                if to != from && witnesses.get(&to).is_none_or(|&witness| witness > into + out) {
+                    shortcuts.push((from, to, into + out));
+                }
+            }
+        }
+        shortcuts
+    }
+
+    // Distances from `from` up to `limit` avoiding `node`.
+    fn witnesses(&self, from: usize, node: usize, limit: u64) -> HashMap<usize, u64> {
+        let mut distances = HashMap::new();
+        let mut open = BinaryHeap::new();
+        let mut settled = 0;
+        distances.insert(from, 0);
+        open.push(Reverse((0, from)));
+        while let Some(Reverse((distance, at))) = open.pop() {
+            if distances[&at] < distance {
+                continue;
+            }
+            settled += 1;
+            if distance > limit || settled > WITNESS_LIMIT {
+                break;
+            }
+            for (&to, &(cost, _)) in &self.outgoing[at] {
+                if to != node && distances.get(&to).is_none_or(|&known| distance + cost < known) {
+                    distances.insert(to, distance + cost);
+                    open.push(Reverse((distance + cost, to)));
+                }
+            }
+        }
+        distances
+    }
+
+    // Edge difference plus contracted neighbours: nodes whose removal adds
+    // few shortcuts go first, spread out over the graph.
+    fn priority(&self, node: usize) -> i64 {
+        let removed = self.incoming[node].len() + self.outgoing[node].len();
+        self.shortcuts(node).len() as i64 - removed as i64 + self.contracted[node] as i64
+    }
+}
+
+/// Contraction hierarchy of a static weighted `Graph`, answering exact
+/// shortest-path queries by a bidirectional search that only follows edges
+/// towards later-contracted nodes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ContractionHierarchy {
+    /// Position of each node in the contraction order.
+    rank: Vec<usize>,
+    /// Edges from each node to higher-ranked nodes.
+    upward: Vec<Vec<Arc>>,
+    /// Edges into each node from higher-ranked nodes, with `to` holding
+    /// their source.
+    downward: Vec<Vec<Arc>>,
+}
+
+impl ContractionHierarchy {
+    /// Orders and contracts every node of `graph`. Nodes are taken by lazily
+    /// updated priority: when the cheapest-looking node turns out dearer
+    /// than the next one, it goes back on the queue.
+    pub fn new(graph: &Graph) -> ContractionHierarchy {
+        let nodes = graph.node_count();
+        let mut remaining = Remaining::new(graph);
+        let mut hierarchy = ContractionHierarchy {
+            rank: vec![0; nodes],
+            upward: vec![Vec::new(); nodes],
+            downward: vec![Vec::new(); nodes],
+        };
+        let mut queue: BinaryHeap<Reverse<(i64, usize)>> =
+            (0..nodes).map(|node| Reverse((remaining.priority(node), node))).collect();
+        let mut rank = 0;
+        while let Some(Reverse((_, node))) = queue.pop() {
+            let priority = remaining.priority(node);
+            if queue.peek().is_some_and(|&Reverse((next, _))| priority > next) {
+                queue.push(Reverse((priority, node)));
+                continue;
+            }
+            for (from, to, cost) in remaining.shortcuts(node) {
+                remaining.link(from, to, cost, Some(node));
+            }
+            let outgoing = core::mem::take(&mut remaining.outgoing[node]);
+            let incoming = core::mem::take(&mut remaining.incoming[node]);
+            for (to, (cost, via)) in outgoing {
+                remaining.incoming[to].remove(&node);
+                remaining.contracted[to] += 1;
+                hierarchy.upward[node].push(Arc { to, cost, via });
+            }
+            for (from, (cost, via)) in incoming {
+                remaining.outgoing[from].remove(&node);
+                remaining.contracted[from] += 1;
+                hierarchy.downward[node].push(Arc { to: from, cost, via });
+            }
+            hierarchy.rank[node] = rank;
+            rank += 1;
+        }
+        hierarchy
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.rank.len()
+    }
+
+    /// Number of edges in the hierarchy, shortcuts included.
+    pub fn edge_count(&self) -> usize {
+        self.upward.iter().chain(&self.downward).map(Vec::len).sum()
+    }
+
+    /// Number of shortcuts added by contraction.
+    pub fn shortcut_count(&self) -> usize {
+        self.upward.iter().chain(&self.downward).flatten().filter(|arc| arc.via.is_some()).count()
+    }
+
+    /// Position of `node` in the contraction order.
+    pub fn rank(&self, node: usize) -> usize {
+        self.rank[node]
+    }
+
+    /// Cost of the cheapest path from `start` to `goal`.
+    pub fn distance(&self, start: usize, goal: usize) -> Option<u64> {
+        self.meet(start, goal).map(|(_, cost, _, _)| cost)
+    }
+
+    /// Cheapest path from `start` to `goal` as the nodes along it, `start`
+    /// and `goal` included, and its cost.
+    pub fn path(&self, start: usize, goal: usize) -> Option<(Vec<usize>, u64)> {
+        let (meeting, cost, forward, backward) = self.meet(start, goal)?;
+        let mut arcs = Vec::new();
+        let mut at = meeting;
+        while let Some((from, via)) = forward[&at].1 {
+            arcs.push((from, at, via));
+            at = from;
+        }
+        arcs.reverse();
+        at = meeting;
+        while let Some((to, via)) = backward[&at].1 {
+            arcs.push((at, to, via));
+            at = to;
+        }
+        let mut nodes = vec![start];
+        for (from, to, via) in arcs {
+            self.unpack(from, to, via, &mut nodes);
+        }
+        Some((nodes, cost))
+    }
+
+    // Bidirectional upward search: the meeting node, the cost through it and
+    // both search trees.
+    fn meet(&self, start: usize, goal: usize) -> Option<(usize, u64, Tree, Tree)> {
+        let mut trees = [HashMap::new(), HashMap::new()];
+        let mut open = [BinaryHeap::new(), BinaryHeap::new()];
+        for (side, &root) in [start, goal].iter().enumerate() {
+            trees[side].insert(root, (0, None));
+            open[side].push(Reverse((0, root)));
+        }
+        let mut best: Option<(u64, usize)> = None;
+        loop {
+            let side = match (open[0].peek(), open[1].peek()) {
+                (Some(&Reverse((a, _))), Some(&Reverse((b, _)))) => if a <= b { 0 } else { 1 },
+                (Some(_), None) => 0,
+                (None, Some(_)) => 1,
+                (None, None) => break,
+            };
+            let Reverse((distance, node)) = open[side].pop().expect("side has open nodes");
+            if best.is_some_and(|(cost, _)| distance >= cost) {
+                open[side].clear();
+                continue;
+            }
+            if trees[side][&node].0 < distance {
+                continue;
+            }
+            if let Some(&(other, _)) = trees[1 - side].get(&node) {
+                if best.is_none_or(|(cost, _)| distance + other < cost) {
+                    best = Some((distance + other, node));
+                }
+            }
+            let arcs = if side == 0 { &self.upward[node] } else { &self.downward[node] };
+            for arc in arcs {
+                let through = distance + arc.cost;
+                if trees[side].get(&arc.to).is_none_or(|&(known, _)| through < known) {
+                    trees[side].insert(arc.to, (through, Some((node, arc.via))));
+                    open[side].push(Reverse((through, arc.to)));
+                }
+            }
+        }
+        let [forward, backward] = trees;
+        best.map(|(cost, meeting)| (meeting, cost, forward, backward))
+    }
+
+    // Edge `from -> to` of the hierarchy, looked up on its lower-ranked end.
+    fn arc(&self, from: usize, to: usize) -> &Arc {
+        let (arcs, end) = if self.rank[from] < self.rank[to] { (&self.upward[from], to) } else { (&self.downward[to], from) };
+        arcs.iter().find(|arc| arc.to == end).expect("shortcut halves are in the hierarchy")
+    }
+
+    // Appends the nodes after `from` on the original path of an edge.
+    fn unpack(&self, from: usize, to: usize, via: Option<usize>, nodes: &mut Vec<usize>) {
+        match via {
+            Some(via) => {
+                self.unpack(from, via, self.arc(from, via).via, nodes);
+                self.unpack(via, to, self.arc(via, to).via, nodes);
+            }
+            None => nodes.push(to),
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_graph;
+    use graph::Graph;
+    use search::WeightedSearchSpace;
+    use super::ContractionHierarchy;
+
+    fn path_cost(graph: &Graph, nodes: &[usize]) -> Option<u64> {
+        nodes.windows(2).map(|pair| graph.edges(pair[0]).iter().filter(|edge| edge.to == pair[1]).map(|edge| edge.cost).min())
+            .sum()
+    }
+
+    #[test]
+    pub fn test_contraction_hierarchy() {
+        let graph = random_graph(400, 3, 20, &mut StdRng::seed_from_u64(5));
+        let hierarchy = ContractionHierarchy::new(&graph);
+        assert_eq!(hierarchy.node_count(), 400);
+        assert!(hierarchy.edge_count() >= hierarchy.shortcut_count());
+        for start in (0..400).step_by(37) {
+            for goal in (0..400).step_by(23) {
+                let expected = graph.astar(start, goal, |_: &usize| 0).map(|found| found.1);
+                assert_eq!(hierarchy.distance(start, goal), expected, "{} -> {}", start, goal);
+                if let Some((nodes, cost)) = hierarchy.path(start, goal) {
+                    assert_eq!((nodes[0], nodes[nodes.len() - 1]), (start, goal));
+                    assert_eq!(path_cost(&graph, &nodes), Some(cost));
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_contraction_hierarchy_line() {
+        // A one-way line with a dearer bypass, and a node nothing reaches.
+        let mut graph = Graph::new(5);
+        graph.add_edge(0, 1, 2);
+        graph.add_edge(1, 2, 2);
+        graph.add_edge(2, 3, 2);
+        graph.add_edge(0, 3, 7);
+        graph.add_edge(3, 3, 1);
+        let hierarchy = ContractionHierarchy::new(&graph);
+        assert_eq!(hierarchy.path(0, 3), Some((vec![0, 1, 2, 3], 6)));
+        assert_eq!(hierarchy.path(2, 2), Some((vec![2], 0)));
+        assert_eq!(hierarchy.distance(3, 0), None);
+        assert_eq!(hierarchy.distance(0, 4), None);
+        let ranks: Vec<usize> = (0..5).map(|node| hierarchy.rank(node)).collect();
+        assert!((0..5).all(|rank| ranks.contains(&rank)));
+    }
+}
@@ -0,0 +1,372 @@
+use core::cmp::Ordering;
+use core::fmt;
+use core::ops::Add;
+use core::time::Duration;
+
+// Costs need no numeric trait of their own: any `Copy + Ord + Add + Default`
+// type will do, with `Default` as the cost of the empty path. The traits
+// and wrappers here cover what some searches ask for beyond that.
+
+/// Floating-point cost ordered by `total_cmp`, so that it meets the `Ord`
+/// bound on `WeightedSearchSpace::Cost` and never panics on comparison.
+/// Costs are expected to be finite and non-negative; a NaN sorts past the
+/// infinities, and `WeightedSearchSpace::try_astar` reports one as an
+/// error.
+#[derive(Clone, Copy, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct FloatCost<F>(pub F);
+
+macro_rules! float_cost {
+    ($float:ty) => {
+        impl PartialEq for FloatCost<$float> {
+            fn eq(&self, other: &Self) -> bool {
+                self.cmp(other) == Ordering::Equal
+            }
+        }
+
+        impl Eq for FloatCost<$float> {}
+
+        impl PartialOrd for FloatCost<$float> {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for FloatCost<$float> {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl Add for FloatCost<$float> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                FloatCost(self.0 + other.0)
+            }
+        }
+
+        impl From<$float> for FloatCost<$float> {
+            fn from(cost: $float) -> Self {
+                FloatCost(cost)
+            }
+        }
+
+        impl fmt::Display for FloatCost<$float> {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+    };
+}
+
+float_cost!(f32);
+float_cost!(f64);
+
+/// Cost that can tell values no search can order sensibly, such as NaN,
+/// from valid ones; see `WeightedSearchSpace::try_astar`.
+pub trait ValidCost {
+    fn is_valid(&self) -> bool;
+}
+
+macro_rules! always_valid {
+    ($($cost:ty),*) => {
+        $(
+            impl ValidCost for $cost {
+                fn is_valid(&self) -> bool {
+                    true
+                }
+            }
+        )*
+    };
+}
+
+always_valid!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, Duration);
+
+impl ValidCost for FloatCost<f32> {
+    fn is_valid(&self) -> bool {
+        !self.0.is_nan()
+    }
+}
+
+impl ValidCost for FloatCost<f64> {
+    fn is_valid(&self) -> bool {
+        !self.0.is_nan()
+    }
+}
+
+/// Cost component that adds without wrapping around.
+pub trait SaturatingAdd {
+    fn saturating_add(self, other: Self) -> Self;
+}
+
+macro_rules! saturating_add {
+    ($($int:ty),*) => {
+        $(
+            impl SaturatingAdd for $int {
+                fn saturating_add(self, other: Self) -> Self {
+                    <$int>::saturating_add(self, other)
+                }
+            }
+        )*
+    };
+}
+
+saturating_add!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl SaturatingAdd for Duration {
+    fn saturating_add(self, other: Self) -> Self {
+        Duration::saturating_add(self, other)
+    }
+}
+
+// Float sums run to infinity rather than wrapping.
+impl<F> SaturatingAdd for FloatCost<F> where FloatCost<F>: Add<Output=FloatCost<F>> {
+    fn saturating_add(self, other: Self) -> Self {
+        self + other
+    }
+}
+
+/// Cost that adds saturating, so that sums of large costs stay large
+/// instead of wrapping around to small ones.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Saturating<T>(pub T);
+
+impl<T: SaturatingAdd> Add for Saturating<T> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Saturating(self.0.saturating_add(other.0))
+    }
+}
+
+/// Cost that can be multiplied by a suboptimality factor, as when bounding
+/// how far a search may stray from the cheapest path.
+pub trait Scale {
+    /// `self` times `factor`, rounded down for whole-number costs.
+    fn scale(self, factor: f64) -> Self;
+}
+
+macro_rules! scale {
+    ($($int:ty),*) => {
+        $(
+            impl Scale for $int {
+                fn scale(self, factor: f64) -> Self {
+                    (self as f64 * factor) as $int
+                }
+            }
+        )*
+    };
+}
+
+scale!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl Scale for FloatCost<f32> {
+    fn scale(self, factor: f64) -> Self {
+        FloatCost((self.0 as f64 * factor) as f32)
+    }
+}
+
+impl Scale for FloatCost<f64> {
+    fn scale(self, factor: f64) -> Self {
+        FloatCost(self.0 * factor)
+    }
+}
+
+impl Scale for Duration {
+    fn scale(self, factor: f64) -> Self {
+        self.mul_f64(factor)
+    }
+}
+
+/// Cost that can be read as a plain number, as when weighing choices by
+/// their cost.
+pub trait ToFloat {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! to_float {
+    ($($int:ty),*) => {
+        $(
+            impl ToFloat for $int {
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+            }
+        )*
+    };
+}
+
+to_float!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl ToFloat for FloatCost<f32> {
+    fn to_f64(self) -> f64 {
+        self.0 as f64
+    }
+}
+
+impl ToFloat for FloatCost<f64> {
+    fn to_f64(self) -> f64 {
+        self.0
+    }
+}
+
+// In seconds.
+impl ToFloat for Duration {
+    fn to_f64(self) -> f64 {
+        self.as_secs_f64()
+    }
+}
+
+impl<T: ToFloat> ToFloat for Saturating<T> {
+    fn to_f64(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+impl<T: ValidCost> ValidCost for Saturating<T> {
+    fn is_valid(&self) -> bool {
+        self.0.is_valid()
+    }
+}
+
+impl<T: Scale> Scale for Saturating<T> {
+    fn scale(self, factor: f64) -> Self {
+        Saturating(self.0.scale(factor))
+    }
+}
+
+/// Tuple of costs ordered lexicographically: the first component is
+/// minimized first and later ones only break its ties, as in minimizing risk
+/// and then distance. Components add saturating, so a search never sees a
+/// sum wrap around to a small cost.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Lexicographic<T>(pub T);
+
+macro_rules! lexicographic {
+    ($($name:ident $index:tt),*) => {
+        impl<$($name: SaturatingAdd),*> Add for Lexicographic<($($name,)*)> {
+            type Output = Self;
+
+            fn add(self, other: Self) -> Self {
+                Lexicographic(($((self.0).$index.saturating_add((other.0).$index),)*))
+            }
+        }
+
+        impl<$($name: ValidCost),*> ValidCost for Lexicographic<($($name,)*)> {
+            fn is_valid(&self) -> bool {
+                $((self.0).$index.is_valid())&&*
+            }
+        }
+
+        // Componentwise, so a suboptimality bound holds on each cost alike.
+        impl<$($name: Scale),*> Scale for Lexicographic<($($name,)*)> {
+            fn scale(self, factor: f64) -> Self {
+                Lexicographic(($((self.0).$index.scale(factor),)*))
+            }
+        }
+    };
+}
+
+lexicographic!(A 0, B 1);
+lexicographic!(A 0, B 1, C 2);
+lexicographic!(A 0, B 1, C 2, D 3);
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use std::time::Duration;
+    use graph::Graph;
+    use super::{FloatCost, Lexicographic, Saturating, Scale};
+
+    #[test]
+    pub fn test_float_cost() {
+        let mut costs = vec![FloatCost(2.5f64), FloatCost(0.0), FloatCost(1.25)];
+        costs.sort();
+        assert_eq!(costs, [FloatCost(0.0), FloatCost(1.25), FloatCost(2.5)]);
+        assert_eq!(FloatCost(0.5f32) + FloatCost(0.25), FloatCost(0.75));
+        assert_eq!(FloatCost::<f32>::default().to_string(), "0");
+        assert_eq!((FloatCost(2.0f64).scale(1.25), 7u32.scale(1.5), (-3i64).scale(2.0)), (FloatCost(2.5), 10, -6));
+    }
+
+    // Routes from 0 to 3 costing (risk, distance).
+    struct Routes;
+
+    const ROUTES: &[(u8, u8, (u8, u32))] = &[(0, 1, (0, 10)), (1, 3, (1, 10)), (0, 2, (0, 1)), (2, 3, (2, 1)),
+                                            (0, 3, (1, 50))];
+
+    impl SearchSpace for Routes {
+        type State = u8;
+        type Action = u8;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            ROUTES.iter().filter(|route| route.0 == *state).map(|route| (route.1, route.1)).collect::<Vec<_>>()
+                .into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Routes {
+        type Cost = Lexicographic<(u8, u32)>;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            Lexicographic(ROUTES.iter().find(|route| route.0 == *state && route.1 == *action).unwrap().2)
+        }
+    }
+
+    #[test]
+    pub fn test_lexicographic_cost() {
+        assert!(Lexicographic((0u8, 100u32)) < Lexicographic((1, 0)));
+        assert_eq!(Lexicographic((u8::MAX, 1u64)) + Lexicographic((1, u64::MAX)), Lexicographic((u8::MAX, u64::MAX)));
+        assert_eq!(Lexicographic((1i32, FloatCost(0.5f64), 2u16)) + Lexicographic((2, FloatCost(0.25), 3)),
+                   Lexicographic((3, FloatCost(0.75), 5)));
+        assert_eq!(Lexicographic((3u8, FloatCost(0.5f64), 7u32)).scale(1.5), Lexicographic((4, FloatCost(0.75), 10)));
+        // The shortest route is riskier; of the two least risky, the shorter
+        // wins.
+        assert_eq!(Routes.astar(0, 3, |_: &u8| Lexicographic::default()),
+                   Some((vec![1, 3], Lexicographic((1, 20)))));
+        assert_eq!(Routes.branch_and_bound(0, 3, |_: &u8| Lexicographic::default(), None),
+                   Some((vec![1, 3], Lexicographic((1, 20)))));
+    }
+
+    // Graph whose edge costs are travel times in minutes.
+    struct Timetable(Graph);
+
+    impl SearchSpace for Timetable {
+        type State = usize;
+        type Action = usize;
+        type Iterator = <Graph as SearchSpace>::Iterator;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            self.0.expand(state)
+        }
+    }
+
+    impl WeightedSearchSpace for Timetable {
+        type Cost = Duration;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            Duration::from_secs(60 * self.0.cost(state, action))
+        }
+    }
+
+    #[test]
+    pub fn test_generic_costs() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 1, 5);
+        graph.add_edge(1, 2, 5);
+        graph.add_edge(0, 2, 12);
+        let timetable = Timetable(graph);
+        let zero = |_: &usize| Duration::ZERO;
+        assert_eq!(timetable.astar(0, 2, zero).map(|found| found.1), Some(Duration::from_secs(600)));
+        assert_eq!(timetable.focal(0, 2, zero, |_: &usize| 0, 1.5).map(|found| found.1.as_secs()), Some(600));
+        assert_eq!(Duration::from_secs(10).scale(1.5), Duration::from_secs(15));
+
+        assert_eq!(Saturating(250u8) + Saturating(10), Saturating(u8::MAX));
+        assert_eq!(Saturating(Duration::MAX) + Saturating(Duration::from_secs(1)), Saturating(Duration::MAX));
+        assert_eq!(Saturating(7u32).scale(2.0), Saturating(14));
+    }
+}
@@ -0,0 +1,73 @@
+//! Terrain cost maps stored in `ndarray` arrays, behind the `ndarray`
+//! feature.
+//!
+//! A two-dimensional `f32` array indexed `[y, x]` is a four-connected grid
+//! search space whose moves cost the value of the cell entered. Costs should
+//! be non-negative; NaN and infinite cells are obstacles.
+
+use alloc::vec::{IntoIter, Vec};
+
+use ndarray::{ArrayBase, Data, Ix2};
+
+use cost::FloatCost;
+use grid::{manhattan, Cell, Direction};
+use search::{SearchSpace, WeightedSearchSpace};
+
+fn entry_cost<S: Data<Elem=f32>>(map: &ArrayBase<S, Ix2>, (x, y): Cell) -> Option<f32> {
+    map.get((y, x)).cloned().filter(|cost| cost.is_finite())
+}
+
+impl<S: Data<Elem=f32>> SearchSpace for ArrayBase<S, Ix2> {
+    type State = Cell;
+    type Action = Direction;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        Direction::ALL.iter()
+            .filter_map(|&direction| direction.step(*state).map(|cell| (direction, cell)))
+            .filter(|&(_, cell)| entry_cost(self, cell).is_some())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<S: Data<Elem=f32>> WeightedSearchSpace for ArrayBase<S, Ix2> {
+    type Cost = FloatCost<f32>;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        FloatCost(action.step(*state).and_then(|cell| entry_cost(self, cell)).unwrap_or(f32::INFINITY))
+    }
+}
+
+/// Admissible heuristic towards `goal`: the Manhattan distance times the
+/// cheapest cell cost in `map`.
+pub fn lower_bound<S: Data<Elem=f32>>(map: &ArrayBase<S, Ix2>, goal: Cell) -> impl Fn(&Cell) -> FloatCost<f32> {
+    let cheapest = map.iter().cloned().filter(|cost| cost.is_finite()).fold(f32::INFINITY, f32::min);
+    let cheapest = if cheapest.is_finite() { cheapest.max(0.0) } else { 0.0 };
+    move |cell: &Cell| FloatCost(manhattan(*cell, goal) as f32 * cheapest)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use ndarray::{arr2, s};
+    use cost::FloatCost;
+    use grid::Direction;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::lower_bound;
+
+    #[test]
+    pub fn test_cost_map() {
+        let map = arr2(&[[1.0, 1.0, 1.0],
+                         [1.0, f32::NAN, 0.5],
+                         [4.0, f32::INFINITY, 0.5]]);
+        let (actions, cost) = map.astar((0, 2), (2, 2), lower_bound(&map, (2, 2))).unwrap();
+        assert_eq!(cost, FloatCost(5.0));
+        assert_eq!(actions.first(), Some(&Direction::North));
+
+        assert_eq!(lower_bound(&map, (2, 2))(&(0, 0)), FloatCost(2.0));
+        assert_eq!(map.expand(&(1, 0)).count(), 2);
+
+        let column = map.slice(s![.., 1..2]);
+        assert_eq!(column.dfs((0, 0), (0, 2)), None);
+    }
+}
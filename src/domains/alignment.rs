@@ -0,0 +1,139 @@
+//! Edit distance and sequence alignment as shortest paths through the
+//! alignment grid.
+
+use alloc::vec::{IntoIter, Vec};
+
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Step of an alignment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Edit {
+    /// Pairs the next elements of both sequences, equal or not.
+    Pair,
+    /// Skips the next element of the first sequence.
+    Delete,
+    /// Skips the next element of the second sequence.
+    Insert,
+}
+
+/// Alignment of two sequences under a gap cost and a substitution cost
+/// given by `substitution`, which should be zero for equal elements.
+///
+/// A state is how far each sequence has been consumed, from `(0, 0)` to
+/// `goal()`, and an action is an `Edit`.
+pub struct Alignment<'a, T: 'a, F> {
+    a: &'a [T],
+    b: &'a [T],
+    gap: u32,
+    substitution: F,
+}
+
+fn unit<T: PartialEq>(a: &T, b: &T) -> u32 {
+    if a == b { 0 } else { 1 }
+}
+
+impl<'a, T: PartialEq> Alignment<'a, T, fn(&T, &T) -> u32> {
+    /// Alignment whose cost is the Levenshtein distance.
+    pub fn levenshtein(a: &'a [T], b: &'a [T]) -> Alignment<'a, T, fn(&T, &T) -> u32> {
+        Alignment::new(a, b, 1, unit)
+    }
+}
+
+impl<'a, T, F> Alignment<'a, T, F> where F: Fn(&T, &T) -> u32 {
+    pub fn new(a: &'a [T], b: &'a [T], gap: u32, substitution: F) -> Alignment<'a, T, F> {
+        Alignment { a, b, gap, substitution }
+    }
+
+    pub fn goal(&self) -> (usize, usize) {
+        (self.a.len(), self.b.len())
+    }
+
+    /// Cost of the gaps the rest of the sequences need at least, to make up
+    /// their difference in length; never more than the remaining cost.
+    pub fn remaining_gaps(&self, &(i, j): &(usize, usize)) -> u32 {
+        ((self.a.len() - i).abs_diff(self.b.len() - j) as u32) * self.gap
+    }
+
+    /// Cheapest alignment and its cost, found by A* on `remaining_gaps`.
+    pub fn align(&self) -> (Vec<Edit>, u32) {
+        self.astar((0, 0), self.goal(), |state: &_| self.remaining_gaps(state)).expect("sequences always align")
+    }
+}
+
+impl<'a, T, F> SearchSpace for Alignment<'a, T, F> where F: Fn(&T, &T) -> u32 {
+    type State = (usize, usize);
+    type Action = Edit;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, &(i, j): &Self::State) -> Self::Iterator {
+        let mut successors = Vec::with_capacity(3);
+        if i < self.a.len() && j < self.b.len() {
+            successors.push((Edit::Pair, (i + 1, j + 1)));
+        }
+        if i < self.a.len() {
+            successors.push((Edit::Delete, (i + 1, j)));
+        }
+        if j < self.b.len() {
+            successors.push((Edit::Insert, (i, j + 1)));
+        }
+        successors.into_iter()
+    }
+}
+
+impl<'a, T, F> WeightedSearchSpace for Alignment<'a, T, F> where F: Fn(&T, &T) -> u32 {
+    type Cost = u32;
+
+    fn cost(&self, &(i, j): &Self::State, edit: &Self::Action) -> Self::Cost {
+        match *edit {
+            Edit::Pair => (self.substitution)(&self.a[i], &self.b[j]),
+            Edit::Delete | Edit::Insert => self.gap,
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use super::{Alignment, Edit};
+
+    fn levenshtein(a: &[u8], b: &[u8]) -> u32 {
+        let mut row: Vec<u32> = (0..=b.len() as u32).collect();
+        for (i, x) in a.iter().enumerate() {
+            let mut diagonal = row[0];
+            row[0] = i as u32 + 1;
+            for (j, y) in b.iter().enumerate() {
+                let next = (diagonal + (x != y) as u32).min(row[j] + 1).min(row[j + 1] + 1);
+                diagonal = row[j + 1];
+                row[j + 1] = next;
+            }
+        }
+        row[b.len()]
+    }
+
+    #[test]
+    pub fn test_edit_distance() {
+        let (edits, cost) = Alignment::levenshtein(b"kitten", b"sitting").align();
+        assert_eq!(cost, 3);
+        assert_eq!(edits.iter().filter(|&&edit| edit == Edit::Insert).count(), 1);
+
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..20 {
+            let a: Vec<u8> = (0..rng.gen_range(0..30)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+            let b: Vec<u8> = (0..rng.gen_range(0..30)).map(|_| rng.gen_range(b'a'..b'e')).collect();
+            assert_eq!(Alignment::levenshtein(&a, &b).align().1, levenshtein(&a, &b));
+        }
+    }
+
+    #[test]
+    pub fn test_substitution_matrix() {
+        // Transitions (A<->G, C<->T) are likelier than transversions.
+        let purine = |base: &u8| *base == b'A' || *base == b'G';
+        let score = |a: &u8, b: &u8| if a == b { 0 } else if purine(a) == purine(b) { 1 } else { 3 };
+        let alignment = Alignment::new(b"GATTACA", b"GACTATA", 2, score);
+        assert_eq!(alignment.align(), (vec![Edit::Pair; 7], 2));
+        let gapped = Alignment::new(b"GATTACA", b"GCATGCT", 2, score);
+        assert!(gapped.align().1 <= Alignment::new(b"GATTACA", b"GCATGCT", 2, |_: &u8, _: &u8| 3).align().1);
+    }
+}
@@ -0,0 +1,663 @@
+//! Rubik's cubes: the 2x2x2 pocket cube and the 3x3x3 cube, with pattern
+//! databases over corners and edges and IDA* solvers using them.
+//!
+//! Corners are numbered URF, UFL, ULB, UBR, DFR, DLF, DBL, DRB and edges UR,
+//! UF, UL, UB, DR, DF, DL, DB, FR, FL, BL, BR. The pocket cube is only
+//! turned at its U, R and F faces: that leaves the DBL corner in place, as
+//! turning the opposite faces only differs by a rotation of the whole cube,
+//! and so folds the 24 orientations of the cube into one state.
+//!
+//! The 3x3x3 cube has its centres fixed and is reduced by its 24 rotations
+//! instead: rotating a position, turning it and rotating it back is another
+//! position as far from solved, so `RubiksCube` canonicalizes each position
+//! to the least of its rotations, and the pattern databases are also looked
+//! up on the position rotated to bring the F and R layers up, which sees
+//! groups of edges the databases themselves do not. The 24 mirror images
+//! are not used, as reflecting a cube reverses the direction its corners
+//! twist in.
+//!
+//! The 3x3x3 databases are small ones - corner twists and places, edge
+//! flips, and the places of three groups of four edges - built in a moment
+//! at startup. They solve short scrambles, not random positions, which
+//! would take the far larger databases of e.g. Korf's solver.
+
+use alloc::collections::VecDeque;
+use alloc::vec::{IntoIter, Vec};
+use core::hash::Hash;
+
+use rand::Rng;
+
+use heuristic::Heuristic;
+use ida;
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+use visited::permutation_rank;
+
+const FIXED: usize = 6;
+
+// Corner and edge permutation and orientation of a clockwise quarter turn of
+// each face, in the convention that the turned cube's cubie at `i` comes
+// from `PERMUTATION[i]`.
+const TURNS: [([u8; 8], [u8; 8]); 6] = [
+    ([3, 0, 1, 2, 4, 5, 6, 7], [0, 0, 0, 0, 0, 0, 0, 0]),
+    ([4, 1, 2, 0, 7, 5, 6, 3], [2, 0, 0, 1, 1, 0, 0, 2]),
+    ([1, 5, 2, 3, 0, 4, 6, 7], [1, 2, 0, 0, 2, 1, 0, 0]),
+    ([0, 1, 2, 3, 5, 6, 7, 4], [0, 0, 0, 0, 0, 0, 0, 0]),
+    ([0, 2, 6, 3, 4, 1, 5, 7], [0, 1, 2, 0, 0, 2, 1, 0]),
+    ([0, 1, 3, 7, 4, 5, 2, 6], [0, 0, 1, 2, 0, 0, 2, 1]),
+];
+const EDGE_TURNS: [([u8; 12], [u8; 12]); 6] = [
+    ([3, 0, 1, 2, 4, 5, 6, 7, 8, 9, 10, 11], [0; 12]),
+    ([8, 1, 2, 3, 11, 5, 6, 7, 4, 9, 10, 0], [0; 12]),
+    ([0, 9, 2, 3, 4, 8, 6, 7, 1, 5, 10, 11], [0, 1, 0, 0, 0, 1, 0, 0, 1, 1, 0, 0]),
+    ([0, 1, 2, 3, 5, 6, 7, 4, 8, 9, 10, 11], [0; 12]),
+    ([0, 1, 10, 3, 4, 5, 9, 7, 8, 2, 6, 11], [0; 12]),
+    ([0, 1, 2, 11, 4, 5, 6, 10, 8, 9, 3, 7], [0, 0, 0, 1, 0, 0, 0, 1, 0, 0, 1, 1]),
+];
+
+// Groups of edges whose places each get a pattern database.
+const EDGE_GROUPS: [[u8; 4]; 3] = [[0, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11]];
+
+// Quarter rotations of the whole cube about the R face, as x, and about the
+// U face, as y, in the convention of the turns. Together they generate all
+// 24 rotations.
+const X: Cube3x3 = Cube3x3 {
+    corners: Cube { permutation: [4, 5, 1, 0, 7, 6, 2, 3], orientation: [2, 1, 2, 1, 1, 2, 1, 2] },
+    edges: [8, 5, 9, 1, 11, 7, 10, 3, 4, 6, 2, 0],
+    flips: [0, 1, 0, 1, 0, 1, 0, 1, 0, 0, 0, 0],
+};
+const Y: Cube3x3 = Cube3x3 {
+    corners: Cube { permutation: [3, 0, 1, 2, 7, 4, 5, 6], orientation: [0; 8] },
+    edges: [3, 0, 1, 2, 7, 4, 5, 6, 11, 8, 9, 10],
+    flips: [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1],
+};
+
+// Rotations by x or y that, taken one after another, visit each of the 24
+// rotations of the cube once.
+const ROTATION_WALK: [Cube3x3; 23] = [X, X, X, Y, X, Y, X, X, X, Y, X, Y, X, X, X, Y, X, X, X, Y, X, Y, X];
+
+/// Face of a turn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Face {
+    Up,
+    Right,
+    Front,
+    Down,
+    Left,
+    Back,
+}
+
+impl Face {
+    pub const ALL: [Face; 6] = [Face::Up, Face::Right, Face::Front, Face::Down, Face::Left, Face::Back];
+
+    /// Face across the cube from this one, whose turns commute with its.
+    pub fn opposite(self) -> Face {
+        Face::ALL[(self as usize + 3) % 6]
+    }
+}
+
+/// Turn of a face by one, two or three clockwise quarter turns.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Turn {
+    pub face: Face,
+    pub quarters: u8,
+}
+
+macro_rules! turns {
+    ($($face:ident),*) => {
+        [$(Turn { face: Face::$face, quarters: 1 }, Turn { face: Face::$face, quarters: 2 },
+           Turn { face: Face::$face, quarters: 3 }),*]
+    };
+}
+
+impl Turn {
+    /// Every turn of every face.
+    pub const ALL: [Turn; 18] = turns!(Up, Right, Front, Down, Left, Back);
+
+    /// Turns of the U, R and F faces, which the pocket cube is solved with.
+    pub const URF: [Turn; 9] = turns!(Up, Right, Front);
+
+    /// Turn undoing this one.
+    pub fn inverse(self) -> Turn {
+        Turn { face: self.face, quarters: 4 - self.quarters }
+    }
+}
+
+/// Position of a 2x2x2 cube, or of the corners of a 3x3x3 one: which corner
+/// sits in each place and how it is twisted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cube {
+    permutation: [u8; 8],
+    orientation: [u8; 8],
+}
+
+impl Default for Cube {
+    fn default() -> Cube {
+        Cube::solved()
+    }
+}
+
+impl Cube {
+    pub fn solved() -> Cube {
+        Cube { permutation: [0, 1, 2, 3, 4, 5, 6, 7], orientation: [0; 8] }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Cube::solved()
+    }
+
+    pub fn apply(self, turn: Turn) -> Cube {
+        let (permutation, orientation) = TURNS[turn.face as usize];
+        let mut cube = self;
+        for _ in 0..turn.quarters {
+            let before = cube;
+            for i in 0..8 {
+                let from = permutation[i] as usize;
+                cube.permutation[i] = before.permutation[from];
+                cube.orientation[i] = (before.orientation[from] + orientation[i]) % 3;
+            }
+        }
+        cube
+    }
+
+    /// Cube reached from the solved one by `turns` random turns of the U, R
+    /// and F faces.
+    pub fn scramble<R: Rng>(turns: usize, rng: &mut R) -> Cube {
+        (0..turns).fold(Cube::solved(), |cube, _| cube.apply(Turn::URF[rng.gen_range(0..Turn::URF.len())]))
+    }
+
+    // Twists of the moving corners but the last, which the others determine.
+    fn orientation_rank(&self) -> usize {
+        (0..8).filter(|&i| i != FIXED).take(6).fold(0, |rank, i| rank * 3 + self.orientation[i] as usize)
+    }
+
+    // Lehmer code of the places of the seven moving corners.
+    fn permutation_rank(&self) -> usize {
+        let corners: Vec<u8> = (0..8).filter(|&i| i != FIXED).map(|i| self.permutation[i]).collect();
+        (0..7).fold(0, |rank, i| {
+            rank * (7 - i) + corners[i + 1..].iter().filter(|&&later| later < corners[i]).count()
+        })
+    }
+
+    // Twists of all corners but the last, when none stays in place.
+    fn full_orientation_rank(&self) -> usize {
+        self.orientation[..7].iter().fold(0, |rank, &twist| rank * 3 + twist as usize)
+    }
+}
+
+/// The 2x2x2 cube as a search space: an action turns the U, R or F face, at
+/// unit cost, so path costs count moves in the half-turn metric.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PocketCube;
+
+impl SearchSpace for PocketCube {
+    type State = Cube;
+    type Action = Turn;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, cube: &Self::State) -> Self::Iterator {
+        Turn::URF.iter().map(|&turn| (turn, cube.apply(turn))).collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl WeightedSearchSpace for PocketCube {
+    type Cost = u32;
+
+    fn cost(&self, _cube: &Self::State, _turn: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+/// Position of a 3x3x3 cube: its corners, and which edge sits in each place
+/// and whether it is flipped. Centres never move.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Cube3x3 {
+    corners: Cube,
+    edges: [u8; 12],
+    flips: [u8; 12],
+}
+
+impl Default for Cube3x3 {
+    fn default() -> Cube3x3 {
+        Cube3x3::solved()
+    }
+}
+
+impl Cube3x3 {
+    pub fn solved() -> Cube3x3 {
+        Cube3x3 { corners: Cube::solved(), edges: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11], flips: [0; 12] }
+    }
+
+    pub fn is_solved(&self) -> bool {
+        *self == Cube3x3::solved()
+    }
+
+    pub fn corners(&self) -> Cube {
+        self.corners
+    }
+
+    pub fn apply(self, turn: Turn) -> Cube3x3 {
+        let (permutation, flips) = EDGE_TURNS[turn.face as usize];
+        let mut cube = Cube3x3 { corners: self.corners.apply(turn), ..self };
+        for _ in 0..turn.quarters {
+            let before = cube;
+            for i in 0..12 {
+                let from = permutation[i] as usize;
+                cube.edges[i] = before.edges[from];
+                cube.flips[i] = before.flips[from] ^ flips[i];
+            }
+        }
+        cube
+    }
+
+    /// Cube reached from the solved one by `turns` random turns.
+    pub fn scramble<R: Rng>(turns: usize, rng: &mut R) -> Cube3x3 {
+        (0..turns).fold(Cube3x3::solved(), |cube, _| cube.apply(Turn::ALL[rng.gen_range(0..Turn::ALL.len())]))
+    }
+
+    /// Least of the 24 rotations of this position, which all of them share
+    /// and which is as many turns from solved.
+    pub fn canonical(&self) -> Cube3x3 {
+        let mut rotated = *self;
+        ROTATION_WALK.iter().fold(*self, |least, rotation| {
+            rotated = rotated.rotated(rotation);
+            least.min(rotated)
+        })
+    }
+
+    // This position as seen with the whole cube turned by `rotation`, with
+    // the centres back in place.
+    fn rotated(&self, rotation: &Cube3x3) -> Cube3x3 {
+        rotation.inverse().then(self).then(rotation)
+    }
+
+    // This position followed by `other`, both taken as rearrangements of the
+    // solved cube as the turns are.
+    fn then(&self, other: &Cube3x3) -> Cube3x3 {
+        let mut cube = *self;
+        for i in 0..8 {
+            let from = other.corners.permutation[i] as usize;
+            cube.corners.permutation[i] = self.corners.permutation[from];
+            cube.corners.orientation[i] = (self.corners.orientation[from] + other.corners.orientation[i]) % 3;
+        }
+        for i in 0..12 {
+            let from = other.edges[i] as usize;
+            cube.edges[i] = self.edges[from];
+            cube.flips[i] = self.flips[from] ^ other.flips[i];
+        }
+        cube
+    }
+
+    // Rearrangement undoing this one.
+    fn inverse(&self) -> Cube3x3 {
+        let mut cube = *self;
+        for i in 0..8 {
+            let to = self.corners.permutation[i] as usize;
+            cube.corners.permutation[to] = i as u8;
+            cube.corners.orientation[to] = (3 - self.corners.orientation[i]) % 3;
+        }
+        for i in 0..12 {
+            let to = self.edges[i] as usize;
+            cube.edges[to] = i as u8;
+            cube.flips[to] = self.flips[i];
+        }
+        cube
+    }
+
+    // Flips of all edges but the last, which the others determine.
+    fn flip_rank(&self) -> usize {
+        self.flips[..11].iter().fold(0, |rank, &flip| rank * 2 + flip as usize)
+    }
+
+    // Places of the edges in `group`, ranked among the 12 * 11 * 10 * 9
+    // ways of placing four edges.
+    fn group_rank(&self, group: &[u8; 4]) -> usize {
+        let mut places = [0; 4];
+        for (place, edge) in self.edges.iter().enumerate() {
+            if let Some(i) = group.iter().position(|member| member == edge) {
+                places[i] = place;
+            }
+        }
+        (0..4).fold(0, |rank, i| {
+            rank * (12 - i) + places[i] - places[..i].iter().filter(|&&earlier| earlier < places[i]).count()
+        })
+    }
+}
+
+/// The 3x3x3 cube as a search space: an action turns any face, at unit
+/// cost, so path costs count moves in the half-turn metric. Positions that
+/// are rotations of each other are told apart but detected as duplicates.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RubiksCube;
+
+impl SearchSpace for RubiksCube {
+    type State = Cube3x3;
+    type Action = Turn;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, cube: &Self::State) -> Self::Iterator {
+        Turn::ALL.iter().map(|&turn| (turn, cube.apply(turn))).collect::<Vec<_>>().into_iter()
+    }
+
+    fn canonicalize(&self, cube: &Self::State) -> Option<Self::State> {
+        Some(cube.canonical())
+    }
+}
+
+impl WeightedSearchSpace for RubiksCube {
+    type Cost = u32;
+
+    fn cost(&self, _cube: &Self::State, _turn: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+/// Pattern databases of the corner twists and of the corner places, each
+/// giving the exact number of turns to solve that part alone; their maximum
+/// is an admissible heuristic.
+#[derive(Clone, Debug)]
+pub struct CornerPdb {
+    orientation: Vec<u8>,
+    permutation: Vec<u8>,
+}
+
+impl CornerPdb {
+    pub fn new() -> CornerPdb {
+        CornerPdb {
+            orientation: distances(729, Cube::solved(), &Turn::URF, Cube::apply, Cube::orientation_rank),
+            permutation: distances(5040, Cube::solved(), &Turn::URF, Cube::apply, Cube::permutation_rank),
+        }
+    }
+
+    /// Number of entries in the tables.
+    pub fn len(&self) -> usize {
+        self.orientation.len() + self.permutation.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fewest U, R and F turns solving `cube`, found by IDA*.
+    pub fn solve(&self, cube: Cube) -> Vec<Turn> {
+        solve(&Turn::URF, Cube::apply, cube, Cube::solved(), self)
+    }
+}
+
+impl Default for CornerPdb {
+    fn default() -> CornerPdb {
+        CornerPdb::new()
+    }
+}
+
+impl Heuristic<Cube, u32> for CornerPdb {
+    fn estimate(&self, cube: &Cube) -> u32 {
+        self.orientation[cube.orientation_rank()].max(self.permutation[cube.permutation_rank()]) as u32
+    }
+}
+
+impl Heuristic<Cube, u32> for &CornerPdb {
+    fn estimate(&self, cube: &Cube) -> u32 {
+        (**self).estimate(cube)
+    }
+}
+
+/// Pattern databases of the 3x3x3 cube: corner twists, corner places, edge
+/// flips, and the places of each of three groups of four edges. Each gives
+/// the exact number of turns to solve that part alone, so their maximum is
+/// an admissible heuristic.
+#[derive(Clone, Debug)]
+pub struct CubePdb {
+    corner_orientation: Vec<u8>,
+    corner_permutation: Vec<u8>,
+    flips: Vec<u8>,
+    groups: Vec<Vec<u8>>,
+}
+
+impl CubePdb {
+    pub fn new() -> CubePdb {
+        let corner_permutation = |cube: &Cube3x3| permutation_rank(&cube.corners.permutation);
+        CubePdb {
+            corner_orientation: distances(2187, Cube3x3::solved(), &Turn::ALL, Cube3x3::apply,
+                                          |cube: &Cube3x3| cube.corners.full_orientation_rank()),
+            corner_permutation: distances(40320, Cube3x3::solved(), &Turn::ALL, Cube3x3::apply, corner_permutation),
+            flips: distances(2048, Cube3x3::solved(), &Turn::ALL, Cube3x3::apply, Cube3x3::flip_rank),
+            groups: EDGE_GROUPS.iter().map(|group| {
+                distances(11880, Cube3x3::solved(), &Turn::ALL, Cube3x3::apply, |cube: &Cube3x3| cube.group_rank(group))
+            }).collect(),
+        }
+    }
+
+    /// Number of entries in the tables.
+    pub fn len(&self) -> usize {
+        self.corner_orientation.len() + self.corner_permutation.len() + self.flips.len()
+            + self.groups.iter().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fewest turns solving `cube`, found by IDA*.
+    pub fn solve(&self, cube: Cube3x3) -> Vec<Turn> {
+        solve(&Turn::ALL, Cube3x3::apply, cube, Cube3x3::solved(), self)
+    }
+
+    // Greatest of the databases' distances for `cube` as it stands.
+    fn lookup(&self, cube: &Cube3x3) -> u8 {
+        let corners = self.corner_orientation[cube.corners.full_orientation_rank()]
+            .max(self.corner_permutation[permutation_rank(&cube.corners.permutation)]);
+        EDGE_GROUPS.iter().zip(&self.groups).map(|(group, table)| table[cube.group_rank(group)])
+            .fold(self.flips[cube.flip_rank()], u8::max)
+            .max(corners)
+    }
+}
+
+impl Default for CubePdb {
+    fn default() -> CubePdb {
+        CubePdb::new()
+    }
+}
+
+impl Heuristic<Cube3x3, u32> for CubePdb {
+    /// Greatest distance the databases give for `cube` and for it rotated to
+    /// bring its F and then its R layer up.
+    fn estimate(&self, cube: &Cube3x3) -> u32 {
+        let front_up = cube.rotated(&X);
+        let right_up = cube.rotated(&Y).rotated(&X);
+        self.lookup(cube).max(self.lookup(&front_up)).max(self.lookup(&right_up)) as u32
+    }
+}
+
+impl Heuristic<Cube3x3, u32> for &CubePdb {
+    fn estimate(&self, cube: &Cube3x3) -> u32 {
+        (**self).estimate(cube)
+    }
+}
+
+// Breadth-first distances from `solved`, by `turns`, of the part of a cube
+// indexed by `rank`. Turns act on that part alone, so the first cube of each
+// rank reached is reached in the fewest turns solving the part.
+fn distances<T, A, K>(size: usize, solved: T, turns: &[Turn], apply: A, rank: K) -> Vec<u8>
+where T: Copy, A: Fn(T, Turn) -> T, K: Fn(&T) -> usize {
+    let mut table = vec![u8::MAX; size];
+    table[rank(&solved)] = 0;
+    let mut queue = VecDeque::new();
+    queue.push_back(solved);
+    while let Some(cube) = queue.pop_front() {
+        let distance = table[rank(&cube)];
+        for &turn in turns {
+            let next = apply(cube, turn);
+            let slot = &mut table[rank(&next)];
+            if *slot == u8::MAX {
+                *slot = distance + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+    table
+}
+
+// Fewest of `turns` taking `cube` to `solved`, found by IDA*.
+fn solve<T, H>(turns: &'static [Turn], apply: fn(T, Turn) -> T, cube: T, solved: T, heuristic: H) -> Vec<Turn>
+where T: Copy + Eq + Hash, H: Heuristic<T, u32> {
+    let space = Sequenced { turns, apply };
+    let heuristic = |&(cube, _): &(T, Option<Face>)| heuristic.estimate(&cube);
+    ida::ida_star(&space, (cube, None), Solved(solved), heuristic).expect("solvable cube").0
+}
+
+// Cube turned by `turns`, with the face turned last kept with each position
+// so that a face is never turned twice in a row, nor after its opposite
+// face, which commutes with it, as either only repeats a sequence of no
+// more turns.
+struct Sequenced<T> {
+    turns: &'static [Turn],
+    apply: fn(T, Turn) -> T,
+}
+
+impl<T: Copy + Eq + Hash> SearchSpace for Sequenced<T> {
+    type State = (T, Option<Face>);
+    type Action = Turn;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, &(cube, last): &Self::State) -> Self::Iterator {
+        self.turns.iter()
+            .filter(|turn| last.is_none_or(|last| {
+                turn.face != last && !(turn.face == last.opposite() && (turn.face as usize) < last as usize)
+            }))
+            .map(|&turn| (turn, ((self.apply)(cube, turn), Some(turn.face))))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<T: Copy + Eq + Hash> WeightedSearchSpace for Sequenced<T> {
+    type Cost = u32;
+
+    fn cost(&self, _state: &Self::State, _turn: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+// Goal of a `Sequenced` search: the position, whichever face was turned last.
+struct Solved<T>(T);
+
+impl<T: PartialEq> SearchGoal<(T, Option<Face>)> for Solved<T> {
+    fn is_goal(&self, (cube, _): &(T, Option<Face>)) -> bool {
+        *cube == self.0
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use heuristic::Heuristic;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{Cube, Cube3x3, CornerPdb, CubePdb, Face, PocketCube, RubiksCube, Turn, ROTATION_WALK};
+
+    #[test]
+    pub fn test_cube_turns() {
+        for &turn in &Turn::ALL {
+            assert_eq!(Cube::solved().apply(turn).apply(turn.inverse()), Cube::solved());
+        }
+        let sexy = [Turn { face: Face::Right, quarters: 1 }, Turn { face: Face::Up, quarters: 1 },
+                    Turn { face: Face::Right, quarters: 3 }, Turn { face: Face::Up, quarters: 3 }];
+        let once = sexy.iter().fold(Cube::solved(), |cube, &turn| cube.apply(turn));
+        assert!(!once.is_solved());
+        let six = (0..6).fold(Cube::solved(), |cube, _| sexy.iter().fold(cube, |cube, &turn| cube.apply(turn)));
+        assert!(six.is_solved());
+    }
+
+    #[test]
+    pub fn test_cube_pdb() {
+        let pdb = CornerPdb::new();
+        assert!(pdb.orientation.iter().chain(&pdb.permutation).all(|&distance| distance <= 11));
+        assert_eq!(pdb.estimate(&Cube::solved()), 0);
+
+        let mut rng = StdRng::seed_from_u64(11);
+        for _ in 0..3 {
+            let start = Cube::scramble(30, &mut rng);
+            let (turns, cost) = PocketCube.astar(start, Cube::solved(), &pdb).unwrap();
+            assert!(cost <= 11 && pdb.estimate(&start) <= cost);
+            assert!(turns.iter().fold(start, |cube, &turn| cube.apply(turn)).is_solved());
+            assert_eq!(pdb.solve(start).len() as u32, cost);
+        }
+    }
+
+    #[test]
+    pub fn test_cube3x3_turns() {
+        for &turn in &Turn::ALL {
+            assert_eq!(Cube3x3::solved().apply(turn).apply(turn.inverse()), Cube3x3::solved());
+            let quarter = Turn { quarters: 1, ..turn };
+            assert_eq!((0..turn.quarters).fold(Cube3x3::solved(), |cube, _| cube.apply(quarter)),
+                       Cube3x3::solved().apply(turn));
+        }
+        // R U has order 105 on the full cube.
+        let ru = [Turn { face: Face::Right, quarters: 1 }, Turn { face: Face::Up, quarters: 1 }];
+        let mut cube = Cube3x3::solved();
+        for times in 1..=105 {
+            cube = ru.iter().fold(cube, |cube, &turn| cube.apply(turn));
+            assert_eq!(cube.is_solved(), times == 105);
+        }
+        // Twists and flips stay balanced.
+        let scrambled = Cube3x3::scramble(40, &mut StdRng::seed_from_u64(5));
+        assert_eq!(scrambled.corners().orientation.iter().sum::<u8>() % 3, 0);
+        assert_eq!(scrambled.flips.iter().sum::<u8>() % 2, 0);
+        assert_eq!(Face::Down.opposite(), Face::Up);
+    }
+
+    #[test]
+    pub fn test_cube3x3_symmetry() {
+        // Every rotation takes each turn to a turn.
+        let turned: Vec<Cube3x3> = Turn::ALL.iter().map(|&turn| Cube3x3::solved().apply(turn)).collect();
+        let mut rotation = Cube3x3::solved();
+        let mut rotations = vec![rotation];
+        for step in &ROTATION_WALK {
+            rotation = rotation.then(step);
+            rotations.push(rotation);
+            assert!(turned.iter().all(|cube| turned.contains(&cube.rotated(&rotation))));
+        }
+        rotations.sort_unstable();
+        rotations.dedup();
+        assert_eq!(rotations.len(), 24);
+
+        let cube = Cube3x3::scramble(20, &mut StdRng::seed_from_u64(3));
+        assert_eq!(cube.then(&cube.inverse()), Cube3x3::solved());
+        for rotation in &rotations {
+            assert_eq!(cube.rotated(rotation).canonical(), cube.canonical());
+        }
+        assert_eq!(Cube3x3::solved().canonical(), Cube3x3::solved());
+        assert_eq!(RubiksCube.canonicalize(&Cube3x3::solved().apply(Turn::ALL[0])),
+                   RubiksCube.canonicalize(&Cube3x3::solved().apply(Turn::ALL[3])));
+
+        // Looking the databases up on rotated positions only raises the
+        // estimate.
+        let pdb = CubePdb::new();
+        assert!(pdb.estimate(&cube) >= pdb.lookup(&cube) as u32);
+    }
+
+    #[test]
+    pub fn test_cube3x3_ida() {
+        let pdb = CubePdb::new();
+        assert_eq!(pdb.len(), 2187 + 40320 + 2048 + 3 * 11880);
+        assert!(pdb.corner_permutation.iter().chain(&pdb.flips).all(|&distance| distance < u8::MAX));
+        assert!(pdb.solve(Cube3x3::solved()).is_empty());
+
+        let mut rng = StdRng::seed_from_u64(13);
+        for _ in 0..3 {
+            let start = Cube3x3::scramble(7, &mut rng);
+            let turns = pdb.solve(start);
+            assert!(turns.len() <= 7 && pdb.estimate(&start) as usize <= turns.len());
+            assert!(turns.iter().fold(start, |cube, &turn| cube.apply(turn)).is_solved());
+        }
+
+        // Optimal as A* over the same space finds.
+        let start = Cube3x3::scramble(5, &mut rng);
+        let (_, cost) = RubiksCube.astar(start, Cube3x3::solved(), &pdb).unwrap();
+        assert_eq!(pdb.solve(start).len() as u32, cost);
+    }
+}
@@ -0,0 +1,157 @@
+//! The 0/1 knapsack problem, solved by branch and bound with the fractional
+//! relaxation as its bound.
+
+use alloc::vec::{IntoIter, Vec};
+use core::cmp::Ordering;
+
+use heuristic::Heuristic;
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// Item of a knapsack instance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Item {
+    pub value: u64,
+    pub weight: u64,
+}
+
+/// 0/1 knapsack instance: choose items of greatest total value within the
+/// capacity.
+///
+/// Items are decided in order of falling value per weight. As a search space
+/// a state is the number of items decided and the weight taken, an action
+/// whether to take the next item, and the cost of leaving an item out is its
+/// value, so a cheapest path keeps the most value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Knapsack {
+    capacity: u64,
+    items: Vec<Item>,
+    /// Indices into `items`, best value per weight first.
+    order: Vec<usize>,
+}
+
+impl Knapsack {
+    pub fn new(capacity: u64, items: Vec<Item>) -> Knapsack {
+        let mut order: Vec<usize> = (0..items.len()).collect();
+        order.sort_by(|&a, &b| by_density(items[b], items[a]));
+        Knapsack { capacity, items, order }
+    }
+
+    pub fn items(&self) -> &[Item] {
+        &self.items
+    }
+
+    /// Goal matching every state with all items decided.
+    pub fn decided(&self) -> Decided {
+        Decided(self.items.len())
+    }
+
+    /// Lower bound on the value still to be left out: what the items not yet
+    /// decided would lose if they could be cut to fit.
+    pub fn lower_bound(&self, &(decided, weight): &(usize, u64)) -> u64 {
+        let mut room = self.capacity - weight;
+        let mut lost = 0;
+        for &index in &self.order[decided..] {
+            let item = self.items[index];
+            if item.weight <= room {
+                room -= item.weight;
+            } else {
+                // Value is whole, so the part that fits rounds down.
+                let fits = (item.value as u128 * room as u128 / item.weight as u128) as u64;
+                lost += item.value - fits;
+                room = 0;
+            }
+        }
+        lost
+    }
+
+    /// Indices of the items in a most valuable packing, in increasing
+    /// order, and their total value.
+    pub fn solve(&self) -> (Vec<usize>, u64) {
+        let (taken, lost) = self.branch_and_bound((0, 0), self.decided(), self, None)
+            .expect("taking nothing always fits");
+        let mut chosen: Vec<usize> = taken.iter().zip(&self.order).filter(|(&take, _)| take).map(|(_, &i)| i).collect();
+        chosen.sort_unstable();
+        (chosen, self.items.iter().map(|item| item.value).sum::<u64>() - lost)
+    }
+}
+
+fn by_density(a: Item, b: Item) -> Ordering {
+    (a.value as u128 * b.weight as u128).cmp(&(b.value as u128 * a.weight as u128))
+}
+
+/// Goal of a `Knapsack` search: every item decided.
+pub struct Decided(usize);
+
+impl SearchGoal<(usize, u64)> for Decided {
+    fn is_goal(&self, state: &(usize, u64)) -> bool {
+        state.0 == self.0
+    }
+}
+
+impl SearchSpace for Knapsack {
+    type State = (usize, u64);
+    type Action = bool;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, &(decided, weight): &Self::State) -> Self::Iterator {
+        let item = match self.order.get(decided) {
+            Some(&index) => self.items[index],
+            None => return Vec::new().into_iter(),
+        };
+        let mut successors = Vec::with_capacity(2);
+        if weight + item.weight <= self.capacity {
+            successors.push((true, (decided + 1, weight + item.weight)));
+        }
+        successors.push((false, (decided + 1, weight)));
+        successors.into_iter()
+    }
+}
+
+impl WeightedSearchSpace for Knapsack {
+    type Cost = u64;
+
+    fn cost(&self, &(decided, _): &Self::State, &take: &Self::Action) -> Self::Cost {
+        if take { 0 } else { self.items[self.order[decided]].value }
+    }
+}
+
+impl Heuristic<(usize, u64), u64> for &Knapsack {
+    fn estimate(&self, state: &(usize, u64)) -> u64 {
+        self.lower_bound(state)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use super::{Item, Knapsack};
+
+    fn dynamic_programming(capacity: u64, items: &[Item]) -> u64 {
+        let mut best = vec![0; capacity as usize + 1];
+        for item in items {
+            for room in (item.weight as usize..=capacity as usize).rev() {
+                best[room] = best[room].max(best[room - item.weight as usize] + item.value);
+            }
+        }
+        best[capacity as usize]
+    }
+
+    #[test]
+    pub fn test_knapsack() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for _ in 0..20 {
+            let items: Vec<Item> = (0..20).map(|_| Item { value: rng.gen_range(1..100), weight: rng.gen_range(0..50) })
+                .collect();
+            let capacity = rng.gen_range(0..300);
+            let knapsack = Knapsack::new(capacity, items.clone());
+            let (chosen, value) = knapsack.solve();
+            assert_eq!(value, dynamic_programming(capacity, &items));
+            assert_eq!(chosen.iter().map(|&i| items[i].value).sum::<u64>(), value);
+            assert!(chosen.iter().map(|&i| items[i].weight).sum::<u64>() <= capacity);
+            assert!(knapsack.lower_bound(&(0, 0)) <= items.iter().map(|item| item.value).sum::<u64>() - value);
+        }
+        assert_eq!(Knapsack::new(5, vec![]).solve(), (vec![], 0));
+    }
+}
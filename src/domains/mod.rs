@@ -0,0 +1,12 @@
+//! Ready-made problem domains, as showcases and stress tests for the
+//! search algorithms.
+
+pub mod alignment;
+pub mod cube;
+pub mod knapsack;
+pub mod puzzles;
+pub mod queens;
+pub mod sliding;
+pub mod sokoban;
+pub mod tsp;
+pub mod words;
@@ -0,0 +1,296 @@
+//! Classic small puzzles: missionaries and cannibals, water jugs and the
+//! Towers of Hanoi. They make handy examples and regression fixtures.
+
+use alloc::vec::{IntoIter, Vec};
+
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// People left on the starting bank, and where the boat is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bank {
+    pub missionaries: u8,
+    pub cannibals: u8,
+    pub boat_on_start: bool,
+}
+
+/// People rowing across.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Crossing {
+    pub missionaries: u8,
+    pub cannibals: u8,
+}
+
+/// Missionaries and cannibals: ferry everyone across a river in a small
+/// boat without ever leaving missionaries outnumbered on either bank.
+/// Each crossing costs 1.
+///
+/// ```
+/// use chappie::domains::puzzles::RiverCrossing;
+/// use chappie::search::WeightedSearchSpace;
+///
+/// let river = RiverCrossing::new(3, 2);
+/// let (crossings, _) = river.astar(river.start(), river.goal(), |_: &_| 0).unwrap();
+/// assert_eq!(crossings.len(), 11);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RiverCrossing {
+    /// Missionaries, and cannibals, to carry across.
+    people: u8,
+    boat: u8,
+}
+
+impl RiverCrossing {
+    pub fn new(people: u8, boat: u8) -> RiverCrossing {
+        RiverCrossing { people, boat }
+    }
+
+    pub fn start(&self) -> Bank {
+        Bank { missionaries: self.people, cannibals: self.people, boat_on_start: true }
+    }
+
+    pub fn goal(&self) -> Bank {
+        Bank { missionaries: 0, cannibals: 0, boat_on_start: false }
+    }
+
+    fn is_safe(&self, bank: &Bank) -> bool {
+        let safe = |missionaries, cannibals| missionaries == 0 || missionaries >= cannibals;
+        safe(bank.missionaries, bank.cannibals)
+            && safe(self.people - bank.missionaries, self.people - bank.cannibals)
+    }
+}
+
+impl SearchSpace for RiverCrossing {
+    type State = Bank;
+    type Action = Crossing;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, bank: &Self::State) -> Self::Iterator {
+        // People on the boat's bank.
+        let (missionaries, cannibals) = if bank.boat_on_start {
+            (bank.missionaries, bank.cannibals)
+        } else {
+            (self.people - bank.missionaries, self.people - bank.cannibals)
+        };
+        let mut successors = Vec::new();
+        for m in 0..=missionaries.min(self.boat) {
+            for c in 0..=cannibals.min(self.boat - m) {
+                if m + c == 0 || (m > 0 && m < c) {
+                    continue;
+                }
+                let next = if bank.boat_on_start {
+                    Bank { missionaries: bank.missionaries - m, cannibals: bank.cannibals - c, boat_on_start: false }
+                } else {
+                    Bank { missionaries: bank.missionaries + m, cannibals: bank.cannibals + c, boat_on_start: true }
+                };
+                if self.is_safe(&next) {
+                    successors.push((Crossing { missionaries: m, cannibals: c }, next));
+                }
+            }
+        }
+        successors.into_iter()
+    }
+}
+
+impl WeightedSearchSpace for RiverCrossing {
+    type Cost = u32;
+
+    fn cost(&self, _bank: &Self::State, _crossing: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+/// Move of the water jugs puzzle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Pour {
+    Fill(usize),
+    Empty(usize),
+    /// Pour from the first jug into the second until one is empty or the
+    /// other full.
+    Into(usize, usize),
+}
+
+/// Water jugs: measure out an amount with unmarked jugs by filling, emptying
+/// and pouring between them. A state is the water in each jug and every
+/// move costs 1.
+///
+/// ```
+/// use chappie::domains::puzzles::WaterJugs;
+/// use chappie::search::WeightedSearchSpace;
+///
+/// let jugs = WaterJugs::new(vec![3, 5]);
+/// let (pours, _) = jugs.astar(jugs.start(), jugs.holding(4), |_: &_| 0).unwrap();
+/// assert_eq!(pours.len(), 6);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WaterJugs {
+    capacities: Vec<u32>,
+}
+
+impl WaterJugs {
+    pub fn new(capacities: Vec<u32>) -> WaterJugs {
+        WaterJugs { capacities }
+    }
+
+    /// All jugs empty.
+    pub fn start(&self) -> Vec<u32> {
+        vec![0; self.capacities.len()]
+    }
+
+    /// Goal matching any state with a jug holding exactly `amount`.
+    pub fn holding(&self, amount: u32) -> Holding {
+        Holding(amount)
+    }
+}
+
+/// Goal of a `WaterJugs` search: some jug holds the amount.
+pub struct Holding(u32);
+
+impl SearchGoal<Vec<u32>> for Holding {
+    fn is_goal(&self, jugs: &Vec<u32>) -> bool {
+        jugs.contains(&self.0)
+    }
+}
+
+impl SearchSpace for WaterJugs {
+    type State = Vec<u32>;
+    type Action = Pour;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, jugs: &Self::State) -> Self::Iterator {
+        let mut successors = Vec::new();
+        for i in 0..jugs.len() {
+            if jugs[i] < self.capacities[i] {
+                let mut next = jugs.clone();
+                next[i] = self.capacities[i];
+                successors.push((Pour::Fill(i), next));
+            }
+            if jugs[i] > 0 {
+                let mut next = jugs.clone();
+                next[i] = 0;
+                successors.push((Pour::Empty(i), next));
+            }
+            for j in 0..jugs.len() {
+                let poured = jugs[i].min(self.capacities[j] - jugs[j]);
+                if i != j && poured > 0 {
+                    let mut next = jugs.clone();
+                    next[i] -= poured;
+                    next[j] += poured;
+                    successors.push((Pour::Into(i, j), next));
+                }
+            }
+        }
+        successors.into_iter()
+    }
+}
+
+impl WeightedSearchSpace for WaterJugs {
+    type Cost = u32;
+
+    fn cost(&self, _jugs: &Self::State, _pour: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+/// Towers of Hanoi: move a stack of disks from the first peg to the last,
+/// one disk at a time and never onto a smaller disk. A state gives the peg
+/// of each disk, smallest first, and an action the pegs moved from and to.
+///
+/// ```
+/// use chappie::domains::puzzles::Hanoi;
+/// use chappie::search::WeightedSearchSpace;
+///
+/// let hanoi = Hanoi::new(4);
+/// let (moves, _) = hanoi.astar(hanoi.start(), hanoi.goal(), |_: &_| 0).unwrap();
+/// assert_eq!(moves.len(), 15);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Hanoi {
+    disks: usize,
+}
+
+impl Hanoi {
+    pub fn new(disks: usize) -> Hanoi {
+        Hanoi { disks }
+    }
+
+    pub fn start(&self) -> Vec<u8> {
+        vec![0; self.disks]
+    }
+
+    pub fn goal(&self) -> Vec<u8> {
+        vec![2; self.disks]
+    }
+}
+
+impl SearchSpace for Hanoi {
+    type State = Vec<u8>;
+    type Action = (u8, u8);
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, pegs: &Self::State) -> Self::Iterator {
+        // The smallest disk on a peg is the one on top.
+        let top = |peg: u8| pegs.iter().position(|&on| on == peg);
+        let mut successors = Vec::new();
+        for from in 0..3 {
+            if let Some(disk) = top(from) {
+                for to in (0..3).filter(|&to| to != from) {
+                    if top(to).is_none_or(|other| other > disk) {
+                        let mut next = pegs.clone();
+                        next[disk] = to;
+                        successors.push(((from, to), next));
+                    }
+                }
+            }
+        }
+        successors.into_iter()
+    }
+}
+
+impl WeightedSearchSpace for Hanoi {
+    type Cost = u32;
+
+    fn cost(&self, _pegs: &Self::State, _move: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{Bank, Hanoi, RiverCrossing, WaterJugs};
+
+    #[test]
+    pub fn test_river_crossing() {
+        let river = RiverCrossing::new(3, 2);
+        assert_eq!(river.astar(river.start(), river.goal(), |_: &_| 0).map(|found| found.1), Some(11));
+        assert!(river.dfs(river.start(), river.goal()).is_some());
+        let crowded = RiverCrossing::new(4, 2);
+        assert_eq!(crowded.dfs(crowded.start(), crowded.goal()), None);
+        let unsafe_bank = Bank { missionaries: 1, cannibals: 2, boat_on_start: false };
+        assert!(!river.dfs_iter(river.start()).any(|(_, bank)| bank == unsafe_bank));
+    }
+
+    #[test]
+    pub fn test_water_jugs() {
+        let jugs = WaterJugs::new(vec![3, 5]);
+        let (pours, cost) = jugs.astar(jugs.start(), jugs.holding(4), |_: &_| 0).unwrap();
+        assert_eq!((pours.len(), cost), (6, 6));
+        assert!(jugs.dfs(jugs.start(), jugs.holding(6)).is_none());
+        let three = WaterJugs::new(vec![8, 5, 3]);
+        assert_eq!(three.astar(vec![8, 0, 0], vec![4, 4, 0], |_: &_| 0).map(|found| found.1), Some(7));
+    }
+
+    #[test]
+    pub fn test_hanoi() {
+        for disks in 1..6 {
+            let hanoi = Hanoi::new(disks);
+            let (moves, cost) = hanoi.astar(hanoi.start(), hanoi.goal(), |_: &_| 0).unwrap();
+            assert_eq!(cost, (1 << disks) - 1);
+            assert_eq!(moves[0], (0, if disks % 2 == 1 { 2 } else { 1 }));
+        }
+        assert_eq!(Hanoi::new(3).dfs_iter(Hanoi::new(3).start()).count(), 27);
+    }
+}
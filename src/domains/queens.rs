@@ -0,0 +1,169 @@
+//! The N-queens puzzle, as a search space for backtracking and as a
+//! min-conflicts local search.
+
+use alloc::vec::{IntoIter, Vec};
+
+use rand::Rng;
+
+use search::{SearchGoal, SearchSpace};
+
+/// Places queens row by row on an `n` by `n` board. A state lists the
+/// columns of the queens placed so far and an action is the next queen's
+/// column; only columns not attacked by an earlier queen are offered, so a
+/// depth-first search is a backtracking solver.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Queens {
+    n: usize,
+}
+
+impl Queens {
+    pub fn new(n: usize) -> Queens {
+        Queens { n }
+    }
+
+    /// Goal matching every complete placement.
+    pub fn complete(&self) -> Complete {
+        Complete(self.n)
+    }
+}
+
+/// Goal of a `Queens` search: all queens placed.
+pub struct Complete(usize);
+
+impl SearchGoal<Vec<usize>> for Complete {
+    fn is_goal(&self, state: &Vec<usize>) -> bool {
+        state.len() == self.0
+    }
+}
+
+fn attacks(row: usize, column: usize, other_row: usize, other_column: usize) -> bool {
+    column == other_column || row.abs_diff(other_row) == column.abs_diff(other_column)
+}
+
+impl SearchSpace for Queens {
+    type State = Vec<usize>;
+    type Action = usize;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        let row = state.len();
+        if row == self.n {
+            return Vec::new().into_iter();
+        }
+        (0..self.n)
+            .filter(|&column| !state.iter().enumerate().any(|(other_row, &other)| attacks(row, column, other_row, other)))
+            .map(|column| {
+                let mut next = state.clone();
+                next.push(column);
+                (column, next)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Whether `columns` places one queen per row with no two attacking.
+pub fn is_solution(columns: &[usize]) -> bool {
+    let n = columns.len();
+    columns.iter().all(|&column| column < n) && (0..n).all(|row| {
+        (row + 1..n).all(|other| !attacks(row, columns[row], other, columns[other]))
+    })
+}
+
+// Queens per column and per diagonal.
+struct Counts {
+    n: usize,
+    columns: Vec<u32>,
+    sums: Vec<u32>,
+    differences: Vec<u32>,
+}
+
+impl Counts {
+    fn new(n: usize) -> Counts {
+        Counts { n, columns: vec![0; n], sums: vec![0; 2 * n - 1], differences: vec![0; 2 * n - 1] }
+    }
+
+    fn conflicts(&self, row: usize, column: usize) -> u32 {
+        self.columns[column] + self.sums[row + column] + self.differences[row + self.n - 1 - column]
+    }
+
+    fn update(&mut self, row: usize, column: usize, add: bool) {
+        let slots = [&mut self.columns[column], &mut self.sums[row + column],
+                     &mut self.differences[row + self.n - 1 - column]];
+        for slot in slots {
+            if add { *slot += 1 } else { *slot -= 1 }
+        }
+    }
+
+    // Places each queen on the less attacked of a few sampled columns, which
+    // keeps the start linear in `n`.
+    fn place<R: Rng>(&mut self, rng: &mut R) -> Vec<usize> {
+        (0..self.n).map(|row| {
+            let column = (0..8).map(|_| rng.gen_range(0..self.n))
+                .min_by_key(|&column| self.conflicts(row, column)).unwrap();
+            self.update(row, column, true);
+            column
+        }).collect()
+    }
+}
+
+/// Min-conflicts local search: starting from a greedy placement, repeatedly
+/// moves a queen under attack to a column where it is attacked least,
+/// restarting when progress stalls. Returns the columns of a solution, or
+/// `None` after `max_steps` moves.
+pub fn min_conflicts<R: Rng>(n: usize, max_steps: usize, rng: &mut R) -> Option<Vec<usize>> {
+    if n == 0 {
+        return Some(Vec::new());
+    }
+    let restart = 2 * n + 100;
+    let mut counts = Counts::new(n);
+    let mut columns = counts.place(rng);
+    for step in 1..=max_steps {
+        // A queen conflicts with itself three times over.
+        let conflicted: Vec<usize> = (0..n).filter(|&row| counts.conflicts(row, columns[row]) > 3).collect();
+        if conflicted.is_empty() {
+            return Some(columns);
+        }
+        if step % restart == 0 {
+            counts = Counts::new(n);
+            columns = counts.place(rng);
+            continue;
+        }
+        let row = conflicted[rng.gen_range(0..conflicted.len())];
+        counts.update(row, columns[row], false);
+        let fewest = (0..n).map(|column| counts.conflicts(row, column)).min().unwrap();
+        let best: Vec<usize> = (0..n).filter(|&column| counts.conflicts(row, column) == fewest).collect();
+        columns[row] = best[rng.gen_range(0..best.len())];
+        counts.update(row, columns[row], true);
+    }
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use search::SearchSpace;
+    use super::{is_solution, min_conflicts, Queens};
+
+    #[test]
+    pub fn test_queens_backtracking() {
+        let queens = Queens::new(8);
+        let solution = queens.dfs(vec![], queens.complete()).unwrap();
+        assert!(is_solution(&solution));
+        assert_eq!(queens.dfs_iter(vec![]).filter(|(_, state)| state.len() == 8).count(), 92);
+        assert_eq!(Queens::new(3).dfs(vec![], Queens::new(3).complete()), None);
+    }
+
+    #[test]
+    pub fn test_queens_min_conflicts() {
+        let mut rng = StdRng::seed_from_u64(5);
+        for &n in &[8, 50, 1000, 5000] {
+            let solution = min_conflicts(n, 10_000, &mut rng).unwrap();
+            assert_eq!(solution.len(), n);
+            assert!(is_solution(&solution));
+        }
+        assert!(!is_solution(&[0, 1]));
+        assert_eq!(min_conflicts(3, 1000, &mut rng), None);
+    }
+}
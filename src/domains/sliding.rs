@@ -0,0 +1,294 @@
+//! Sliding-tile puzzles such as the 8- and 15-puzzle, with Manhattan
+//! distance and additive pattern database heuristics.
+
+use alloc::collections::VecDeque;
+use alloc::vec::{IntoIter, Vec};
+
+use hashbrown::HashMap;
+use rand::Rng;
+
+use grid::Direction;
+use heuristic::Heuristic;
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Puzzle position packed four bits per cell, row by row; tile 0 is the
+/// blank.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Board(u64);
+
+impl Board {
+    /// Tile at cell `position`.
+    pub fn get(self, position: usize) -> u8 {
+        (self.0 >> (4 * position) & 0xf) as u8
+    }
+
+    fn swap(self, a: usize, b: usize) -> Board {
+        let (ta, tb) = (self.get(a) as u64, self.get(b) as u64);
+        let cleared = self.0 & !(0xf << (4 * a)) & !(0xf << (4 * b));
+        Board(cleared | tb << (4 * a) | ta << (4 * b))
+    }
+}
+
+/// Sliding-tile puzzle on a board of at most 16 cells. The goal has the
+/// tiles in order with the blank last. An action is the direction the blank
+/// moves in, at unit cost.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlidingPuzzle {
+    width: usize,
+    height: usize,
+}
+
+impl SlidingPuzzle {
+    pub fn new(width: usize, height: usize) -> SlidingPuzzle {
+        assert!(width * height <= 16 && width * height >= 2, "boards have 2 to 16 cells");
+        SlidingPuzzle { width, height }
+    }
+
+    pub fn cells(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn goal(&self) -> Board {
+        let tiles: Vec<u8> = (1..self.cells() as u8).chain(Some(0)).collect();
+        self.board(&tiles)
+    }
+
+    /// Board from tiles listed row by row, which must be a permutation of
+    /// `0..cells`.
+    pub fn board(&self, tiles: &[u8]) -> Board {
+        let mut sorted = tiles.to_vec();
+        sorted.sort_unstable();
+        assert!(sorted.iter().cloned().eq(0..self.cells() as u8), "tiles {:?} are not a permutation", tiles);
+        Board(tiles.iter().enumerate().fold(0, |packed, (position, &tile)| packed | (tile as u64) << (4 * position)))
+    }
+
+    pub fn tiles(&self, board: Board) -> Vec<u8> {
+        (0..self.cells()).map(|position| board.get(position)).collect()
+    }
+
+    fn blank(&self, board: Board) -> usize {
+        (0..self.cells()).find(|&position| board.get(position) == 0).expect("board has a blank")
+    }
+
+    fn home(&self, tile: u8) -> usize {
+        if tile == 0 { self.cells() - 1 } else { tile as usize - 1 }
+    }
+
+    fn distance(&self, a: usize, b: usize) -> u32 {
+        ((a % self.width).abs_diff(b % self.width) + (a / self.width).abs_diff(b / self.width)) as u32
+    }
+
+    fn neighbour(&self, position: usize, direction: Direction) -> Option<usize> {
+        direction.step((position % self.width, position / self.width))
+            .filter(|&(x, y)| x < self.width && y < self.height)
+            .map(|(x, y)| y * self.width + x)
+    }
+
+    /// Sum of the Manhattan distances of the tiles to their goal cells.
+    pub fn manhattan(&self, board: Board) -> u32 {
+        (0..self.cells())
+            .filter(|&position| board.get(position) != 0)
+            .map(|position| self.distance(position, self.home(board.get(position))))
+            .sum()
+    }
+
+    /// Whether the goal can be reached from `board`.
+    pub fn is_solvable(&self, board: Board) -> bool {
+        let tiles: Vec<u8> = self.tiles(board).into_iter().filter(|&tile| tile != 0).collect();
+        let inversions = (0..tiles.len())
+            .map(|i| tiles[i + 1..].iter().filter(|&&later| later < tiles[i]).count())
+            .sum::<usize>();
+        if self.width % 2 == 1 {
+            inversions % 2 == 0
+        } else {
+            let blank_row_from_bottom = self.height - self.blank(board) / self.width;
+            (inversions + blank_row_from_bottom) % 2 == 1
+        }
+    }
+
+    /// Board `moves` random blank moves away from the goal.
+    pub fn scramble<R: Rng>(&self, moves: usize, rng: &mut R) -> Board {
+        let mut board = self.goal();
+        for _ in 0..moves {
+            let successors: Vec<_> = self.expand(&board).collect();
+            board = successors[rng.gen_range(0..successors.len())].1;
+        }
+        board
+    }
+}
+
+impl SearchSpace for SlidingPuzzle {
+    type State = Board;
+    type Action = Direction;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        let blank = self.blank(*state);
+        Direction::ALL.iter()
+            .filter_map(|&direction| self.neighbour(blank, direction).map(|to| (direction, state.swap(blank, to))))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl WeightedSearchSpace for SlidingPuzzle {
+    type Cost = u32;
+
+    fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+struct Pattern {
+    tiles: Vec<u8>,
+    // Fewest moves of pattern tiles to bring them home, keyed by their cells.
+    distances: HashMap<u64, u8>,
+}
+
+impl Pattern {
+    fn key(cells: impl Iterator<Item=usize>) -> u64 {
+        cells.enumerate().fold(0, |key, (index, cell)| key | (cell as u64) << (4 * index))
+    }
+
+    // 0-1 breadth-first search backwards from the goal over boards where
+    // only the pattern tiles and the blank are told apart.
+    fn build(puzzle: &SlidingPuzzle, tiles: &[u8]) -> Pattern {
+        // Abstract state: the cells of the pattern tiles followed by the blank.
+        let goal: Vec<usize> = tiles.iter().map(|&tile| puzzle.home(tile)).chain(Some(puzzle.cells() - 1)).collect();
+        let blank = tiles.len();
+        let mut seen: HashMap<u64, u8> = HashMap::new();
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+        queue.push_back((goal, 0u8));
+        while let Some((cells, distance)) = queue.pop_front() {
+            let key = Pattern::key(cells.iter().cloned());
+            if seen.get(&key).is_some_and(|&known| known <= distance) {
+                continue;
+            }
+            seen.insert(key, distance);
+            let pattern_key = Pattern::key(cells[..blank].iter().cloned());
+            let best = distances.entry(pattern_key).or_insert(distance);
+            *best = (*best).min(distance);
+            for &direction in &Direction::ALL {
+                let to = match puzzle.neighbour(cells[blank], direction) {
+                    Some(to) => to,
+                    None => continue,
+                };
+                let mut next = cells.clone();
+                next[blank] = to;
+                match cells[..blank].iter().position(|&cell| cell == to) {
+                    Some(moved) => {
+                        next[moved] = cells[blank];
+                        queue.push_back((next, distance + 1));
+                    }
+                    None => queue.push_front((next, distance)),
+                }
+            }
+        }
+        Pattern { tiles: tiles.to_vec(), distances }
+    }
+}
+
+/// Additive pattern database: the tiles are split into disjoint patterns,
+/// and for each pattern the fewest moves of its own tiles needed to bring
+/// them home is precomputed for every placement. The sum over the patterns
+/// is admissible and at least as strong as the Manhattan distance when
+/// patterns have more than one tile.
+pub struct AdditivePdb {
+    puzzle: SlidingPuzzle,
+    patterns: Vec<Pattern>,
+}
+
+impl AdditivePdb {
+    /// Builds the tables for `patterns`, disjoint sets of non-blank tiles.
+    /// Tiles left out of every pattern count their Manhattan distance.
+    pub fn new(puzzle: SlidingPuzzle, patterns: &[&[u8]]) -> AdditivePdb {
+        let mut used = Vec::new();
+        for &tile in patterns.iter().flat_map(|pattern| pattern.iter()) {
+            assert!(tile != 0 && (tile as usize) < puzzle.cells(), "tile {} is not on the board", tile);
+            assert!(!used.contains(&tile), "tile {} is in two patterns", tile);
+            used.push(tile);
+        }
+        let mut patterns: Vec<Pattern> = patterns.iter().map(|tiles| Pattern::build(&puzzle, tiles)).collect();
+        let rest: Vec<u8> = (1..puzzle.cells() as u8).filter(|tile| !used.contains(tile)).collect();
+        patterns.extend(rest.iter().map(|&tile| Pattern { tiles: vec![tile], distances: HashMap::new() }));
+        AdditivePdb { puzzle, patterns }
+    }
+
+    /// Number of stored table entries.
+    pub fn len(&self) -> usize {
+        self.patterns.iter().map(|pattern| pattern.distances.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Heuristic<Board, u32> for AdditivePdb {
+    fn estimate(&self, board: &Board) -> u32 {
+        let mut cells = [0; 16];
+        for position in 0..self.puzzle.cells() {
+            cells[board.get(position) as usize] = position;
+        }
+        self.patterns.iter().map(|pattern| match pattern.tiles[..] {
+            [tile] if pattern.distances.is_empty() => self.puzzle.distance(cells[tile as usize], self.puzzle.home(tile)),
+            _ => {
+                let key = Pattern::key(pattern.tiles.iter().map(|&tile| cells[tile as usize]));
+                pattern.distances[&key] as u32
+            }
+        }).sum()
+    }
+}
+
+impl Heuristic<Board, u32> for &AdditivePdb {
+    fn estimate(&self, board: &Board) -> u32 {
+        (**self).estimate(board)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use astar::AStarBuffers;
+    use heuristic::Heuristic;
+    use search::WeightedSearchSpace;
+    use super::{AdditivePdb, SlidingPuzzle};
+
+    #[test]
+    pub fn test_sliding_puzzle() {
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let board = puzzle.board(&[1, 2, 3, 4, 5, 6, 0, 7, 8]);
+        assert_eq!(puzzle.manhattan(board), 2);
+        assert!(puzzle.is_solvable(board));
+        assert!(!puzzle.is_solvable(puzzle.board(&[2, 1, 3, 4, 5, 6, 7, 8, 0])));
+        assert_eq!(puzzle.astar(board, puzzle.goal(), |board: &_| puzzle.manhattan(*board)).map(|found| found.1),
+                   Some(2));
+
+        let fifteen = SlidingPuzzle::new(4, 4);
+        assert!(fifteen.is_solvable(fifteen.scramble(50, &mut StdRng::seed_from_u64(0))));
+        assert!(!fifteen.is_solvable(fifteen.board(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 15, 14, 0])));
+    }
+
+    #[test]
+    pub fn test_additive_pdb() {
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let pdb = AdditivePdb::new(puzzle, &[&[1, 2, 3, 4], &[5, 6, 7, 8]]);
+        assert_eq!(pdb.len(), 2 * 9 * 8 * 7 * 6);
+        assert_eq!(pdb.estimate(&puzzle.goal()), 0);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..5 {
+            let board = puzzle.scramble(40, &mut rng);
+            let mut manhattan = AStarBuffers::new();
+            let mut patterns = AStarBuffers::new();
+            let optimal = puzzle.astar_with(&mut manhattan, board, puzzle.goal(), |b: &_| puzzle.manhattan(*b));
+            let found = puzzle.astar_with(&mut patterns, board, puzzle.goal(), &pdb);
+            assert_eq!(found.map(|found| found.1), optimal.map(|found| found.1));
+            assert!(pdb.estimate(&board) >= puzzle.manhattan(board));
+            assert!(patterns.stats().expanded <= manhattan.stats().expanded);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Sokoban, searched over box pushes: the player's walk between pushes is
+//! folded into each action, so states differ only by box placement and the
+//! region the player can reach.
+
+use alloc::collections::VecDeque;
+use alloc::vec::{IntoIter, Vec};
+
+use formats::ParseError;
+use grid::{Cell, Direction};
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// Boxes and player of a Sokoban position.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SokobanState {
+    /// Topmost, then leftmost, cell the player can reach, so that positions
+    /// differing only by where the player stands within a region are equal.
+    player: usize,
+    /// Box cells in increasing order.
+    boxes: Vec<usize>,
+}
+
+/// Push of the box on `from` one cell in `direction`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Push {
+    pub from: Cell,
+    pub direction: Direction,
+}
+
+/// Sokoban board: walls, goals and the cells a box can never leave for a
+/// goal. An action pushes one box, at unit cost.
+#[derive(Clone, Debug)]
+pub struct Sokoban {
+    width: usize,
+    height: usize,
+    walls: Vec<bool>,
+    goals: Vec<bool>,
+    dead: Vec<bool>,
+    prune_deadlocks: bool,
+}
+
+impl Sokoban {
+    /// Reads a level in the usual text notation: `#` wall, `.` goal, `$` box,
+    /// `*` box on goal, `@` player, `+` player on goal, anything else floor.
+    pub fn parse(text: &str) -> Result<(Sokoban, SokobanState), ParseError> {
+        let rows: Vec<&str> = text.lines().filter(|row| !row.trim().is_empty()).collect();
+        let width = rows.iter().map(|row| row.chars().count()).max().unwrap_or(0);
+        let height = rows.len();
+        let mut walls = vec![false; width * height];
+        let mut goals = vec![false; width * height];
+        let (mut player, mut boxes) = (None, Vec::new());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                let cell = y * width + x;
+                walls[cell] = c == '#';
+                goals[cell] = matches!(c, '.' | '*' | '+');
+                if matches!(c, '$' | '*') {
+                    boxes.push(cell);
+                }
+                if matches!(c, '@' | '+') {
+                    if player.is_some() {
+                        return Err(ParseError::new(y + 1, "second player"));
+                    }
+                    player = Some(cell);
+                }
+            }
+        }
+        let player = player.ok_or_else(|| ParseError::new(height.max(1), "no player"))?;
+        if boxes.len() != goals.iter().filter(|&&goal| goal).count() {
+            return Err(ParseError::new(height.max(1), "boxes and goals differ in number"));
+        }
+        let mut sokoban = Sokoban { width, height, walls, goals, dead: Vec::new(), prune_deadlocks: true };
+        sokoban.dead = sokoban.dead_cells();
+        let state = sokoban.state(player, boxes);
+        Ok((sokoban, state))
+    }
+
+    /// Turns off pruning of positions with a box on a dead cell.
+    pub fn without_deadlock_pruning(mut self) -> Sokoban {
+        self.prune_deadlocks = false;
+        self
+    }
+
+    /// Whether a box on `cell` can never reach any goal.
+    pub fn is_dead(&self, cell: Cell) -> bool {
+        self.index(cell).is_some_and(|cell| self.dead[cell])
+    }
+
+    /// Static deadlock test: whether some box is on a dead cell, so that
+    /// the position cannot be solved.
+    pub fn is_deadlocked(&self, state: &SokobanState) -> bool {
+        state.boxes.iter().any(|&cell| self.dead[cell])
+    }
+
+    pub fn is_solved(&self, state: &SokobanState) -> bool {
+        state.boxes.iter().all(|&cell| self.goals[cell])
+    }
+
+    /// Goal matching every solved position, whatever the player's place.
+    pub fn solved(&self) -> Solved<'_> {
+        Solved(self)
+    }
+
+    pub fn boxes(&self, state: &SokobanState) -> Vec<Cell> {
+        state.boxes.iter().map(|&cell| self.cell(cell)).collect()
+    }
+
+    fn cell(&self, index: usize) -> Cell {
+        (index % self.width, index / self.width)
+    }
+
+    fn index(&self, (x, y): Cell) -> Option<usize> {
+        if x < self.width && y < self.height { Some(y * self.width + x) } else { None }
+    }
+
+    fn neighbour(&self, index: usize, direction: Direction) -> Option<usize> {
+        direction.step(self.cell(index)).and_then(|cell| self.index(cell)).filter(|&next| !self.walls[next])
+    }
+
+    // Cells from which a box can be pushed to a goal, found by pulling
+    // boxes away from the goals.
+    fn dead_cells(&self) -> Vec<bool> {
+        let mut live = self.goals.clone();
+        let mut queue: VecDeque<usize> = (0..live.len()).filter(|&cell| live[cell]).collect();
+        while let Some(cell) = queue.pop_front() {
+            for &direction in &Direction::ALL {
+                let pulled = self.neighbour(cell, direction)
+                    .filter(|&to| self.neighbour(to, direction).is_some());
+                if let Some(to) = pulled {
+                    if !live[to] {
+                        live[to] = true;
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+        (0..live.len()).map(|cell| !live[cell] && !self.walls[cell]).collect()
+    }
+
+    // Cells the player reaches from `player` without moving a box.
+    fn reachable(&self, player: usize, boxes: &[usize]) -> Vec<bool> {
+        let mut seen = vec![false; self.walls.len()];
+        seen[player] = true;
+        let mut queue = VecDeque::new();
+        queue.push_back(player);
+        while let Some(cell) = queue.pop_front() {
+            for &direction in &Direction::ALL {
+                if let Some(next) = self.neighbour(cell, direction) {
+                    if !seen[next] && boxes.binary_search(&next).is_err() {
+                        seen[next] = true;
+                        queue.push_back(next);
+                    }
+                }
+            }
+        }
+        seen
+    }
+
+    fn state(&self, player: usize, mut boxes: Vec<usize>) -> SokobanState {
+        boxes.sort_unstable();
+        let player = self.reachable(player, &boxes).iter().position(|&seen| seen).expect("player reaches itself");
+        SokobanState { player, boxes }
+    }
+}
+
+/// Goal of a `Sokoban` search: every box on a goal.
+pub struct Solved<'a>(&'a Sokoban);
+
+impl<'a> SearchGoal<SokobanState> for Solved<'a> {
+    fn is_goal(&self, state: &SokobanState) -> bool {
+        self.0.is_solved(state)
+    }
+}
+
+impl SearchSpace for Sokoban {
+    type State = SokobanState;
+    type Action = Push;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        let reachable = self.reachable(state.player, &state.boxes);
+        let mut successors = Vec::new();
+        for (index, &from) in state.boxes.iter().enumerate() {
+            for &direction in &Direction::ALL {
+                // The player must be able to stand behind the box.
+                if !self.neighbour(from, opposite(direction)).is_some_and(|behind| reachable[behind]) {
+                    continue;
+                }
+                let to = match self.neighbour(from, direction) {
+                    Some(to) if state.boxes.binary_search(&to).is_err() => to,
+                    _ => continue,
+                };
+                let mut boxes = state.boxes.clone();
+                boxes[index] = to;
+                successors.push((Push { from: self.cell(from), direction }, self.state(from, boxes)));
+            }
+        }
+        successors.into_iter()
+    }
+
+    /// Deadlocked positions, unless deadlock pruning is off.
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.prune_deadlocks && self.is_deadlocked(state)
+    }
+}
+
+impl WeightedSearchSpace for Sokoban {
+    type Cost = u32;
+
+    fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+fn opposite(direction: Direction) -> Direction {
+    match direction {
+        Direction::North => Direction::South,
+        Direction::East => Direction::West,
+        Direction::South => Direction::North,
+        Direction::West => Direction::East,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use astar::AStarBuffers;
+    use cache::CachedSpace;
+    use grid::Direction;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{Push, Sokoban};
+
+    const LEVEL: &str = "
+#######
+#.  # #
+#  $  #
+# $@  #
+#.    #
+#######
+";
+
+    #[test]
+    pub fn test_sokoban() {
+        let (sokoban, start) = Sokoban::parse(LEVEL).unwrap();
+        assert_eq!(sokoban.boxes(&start), [(3, 2), (2, 3)]);
+        assert!(sokoban.is_dead((5, 4)) && sokoban.is_dead((5, 2)) && !sokoban.is_dead((1, 3)));
+        assert!(!sokoban.is_deadlocked(&start));
+
+        let mut pruned = AStarBuffers::new();
+        let (pushes, cost) = sokoban.astar_with(&mut pruned, start.clone(), sokoban.solved(), |_: &_| 0).unwrap();
+        assert_eq!(cost, 5);
+        assert_eq!(pushes.len(), 5);
+
+        let unpruned_sokoban = sokoban.clone().without_deadlock_pruning();
+        let mut unpruned = AStarBuffers::new();
+        let found = unpruned_sokoban.astar_with(&mut unpruned, start.clone(), unpruned_sokoban.solved(), |_: &_| 0);
+        assert_eq!(found.map(|found| found.1), Some(5));
+        assert!(pruned.stats().expanded < unpruned.stats().expanded);
+        assert!(pruned.stats().dead_ends > 0 && unpruned.stats().dead_ends == 0);
+        let stuck = sokoban.state(start.player, vec![sokoban.index((5, 2)).unwrap(), start.boxes[1]]);
+        assert!(sokoban.is_dead_end(&stuck) && !unpruned_sokoban.is_dead_end(&stuck));
+        assert!(CachedSpace::new(sokoban.clone(), 16).is_dead_end(&stuck));
+
+        let (sokoban, start) = Sokoban::parse("#####\n#@$.#\n#####").unwrap();
+        assert_eq!(sokoban.astar(start, sokoban.solved(), |_: &_| 0),
+                   Some((vec![Push { from: (2, 1), direction: Direction::East }], 1)));
+        assert_eq!(Sokoban::parse("#####\n#@$ #\n#####").unwrap_err().message, "boxes and goals differ in number");
+    }
+}
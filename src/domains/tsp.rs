@@ -0,0 +1,283 @@
+//! The travelling salesman problem, solved exactly by branch and bound from
+//! a nearest-neighbour and 2-opt warm start, bounded below by Held-Karp
+//! 1-trees.
+
+use alloc::vec::{IntoIter, Vec};
+
+use heuristic::Heuristic;
+use local::{nearest_neighbour, tour_length, two_opt};
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+// Subgradient steps tightening each 1-tree bound.
+const ASCENT_STEPS: usize = 30;
+
+/// Symmetric travelling salesman instance of up to 64 cities given by its
+/// distance matrix.
+///
+/// Tours start and end at city 0. As a search space a state is a `Route`,
+/// and an action is the next city; the last action returns to city 0.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tsp {
+    distances: Vec<Vec<u64>>,
+}
+
+/// Position of a partial tour: the cities visited so far, as a bitmask, and
+/// the city it is at. The order they were visited in is the path of
+/// actions leading here, which is all that later costs depend on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Route {
+    visited: u64,
+    city: usize,
+}
+
+impl Route {
+    pub fn city(&self) -> usize {
+        self.city
+    }
+
+    pub fn has_visited(&self, city: usize) -> bool {
+        self.visited & 1 << city != 0
+    }
+
+    pub fn visited_count(&self) -> usize {
+        self.visited.count_ones() as usize
+    }
+}
+
+impl Tsp {
+    pub fn new(distances: Vec<Vec<u64>>) -> Tsp {
+        let n = distances.len();
+        assert!(n > 0, "no cities");
+        assert!(n <= 64, "more than 64 cities");
+        assert!(distances.iter().all(|row| row.len() == n), "distance matrix is not square");
+        Tsp { distances }
+    }
+
+    pub fn len(&self) -> usize {
+        self.distances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.distances.is_empty()
+    }
+
+    pub fn distance(&self, from: usize, to: usize) -> u64 {
+        self.distances[from][to]
+    }
+
+    /// Length of the closed tour visiting `tour` in order.
+    pub fn tour_cost(&self, tour: &[usize]) -> u64 {
+        tour_length(tour, |from, to| self.distance(from, to))
+    }
+
+    /// Route at city 0 before any move.
+    pub fn start(&self) -> Route {
+        Route { visited: 1, city: 0 }
+    }
+
+    /// Goal matching every route back at city 0 after visiting all cities.
+    pub fn complete(&self) -> Complete {
+        Complete(self.all())
+    }
+
+    fn all(&self) -> u64 {
+        u64::MAX >> (64 - self.len())
+    }
+
+    /// Lower bound on the cost of finishing `route`.
+    ///
+    /// The route so far acts as a single node entered from its end and left
+    /// into city 0. Any way of finishing it is a spanning path over the
+    /// unvisited cities plus one edge from each side of that node, so a
+    /// minimum spanning tree plus the cheapest such edges - a 1-tree - is
+    /// never longer. Held-Karp penalties on cities the tree uses more or
+    /// less than twice then raise the bound over a few subgradient steps.
+    pub fn lower_bound(&self, route: &Route) -> u64 {
+        if route.visited == self.all() {
+            return if route.city == 0 { 0 } else { self.distance(route.city, 0) };
+        }
+        let unvisited: Vec<usize> = (0..self.len()).filter(|&city| !route.has_visited(city)).collect();
+        let mut penalties = vec![0; unvisited.len()];
+        let mut degrees = vec![0; unvisited.len()];
+        let mut best = 0;
+        let mut step = 0;
+        for ascent in 0..ASCENT_STEPS {
+            let bound = self.one_tree(route.city, &unvisited, &penalties, &mut degrees);
+            best = best.max(bound);
+            if degrees.iter().all(|&degree| degree == 2) {
+                break;
+            }
+            if ascent == 0 {
+                step = (bound / (2 * unvisited.len() as i64)).max(1);
+            } else if ascent % 4 == 0 {
+                step /= 2;
+                if step == 0 {
+                    break;
+                }
+            }
+            for (penalty, &degree) in penalties.iter_mut().zip(&degrees) {
+                *penalty += step * (degree - 2);
+            }
+        }
+        best.max(0) as u64
+    }
+
+    // Weight of the 1-tree over `cities` joined to a route ending at `last`,
+    // under `penalties`, filling in how many of its edges meet each city.
+    fn one_tree(&self, last: usize, cities: &[usize], penalties: &[i64], degrees: &mut [i64]) -> i64 {
+        let cost = |i: usize, j: usize| self.distance(cities[i], cities[j]) as i64 + penalties[i] + penalties[j];
+        for degree in degrees.iter_mut() {
+            *degree = 0;
+        }
+
+        // Prim's algorithm, remembering which tree city each one is nearest.
+        let mut in_tree = vec![false; cities.len()];
+        let mut nearest: Vec<(i64, usize)> = (0..cities.len()).map(|i| (cost(0, i), 0)).collect();
+        in_tree[0] = true;
+        let mut weight = 0;
+        for _ in 1..cities.len() {
+            let next = (0..cities.len()).filter(|&i| !in_tree[i]).min_by_key(|&i| nearest[i].0).unwrap();
+            in_tree[next] = true;
+            weight += nearest[next].0;
+            degrees[next] += 1;
+            degrees[nearest[next].1] += 1;
+            for i in 0..cities.len() {
+                if !in_tree[i] && cost(next, i) < nearest[i].0 {
+                    nearest[i] = (cost(next, i), next);
+                }
+            }
+        }
+
+        let (enter, leave) = (0..cities.len()).fold((0, 0), |(enter, leave), i| {
+            let enter_cost = |i: usize| self.distance(last, cities[i]) as i64 + penalties[i];
+            let leave_cost = |i: usize| self.distance(cities[i], 0) as i64 + penalties[i];
+            (if enter_cost(i) < enter_cost(enter) { i } else { enter },
+             if leave_cost(i) < leave_cost(leave) { i } else { leave })
+        });
+        degrees[enter] += 1;
+        degrees[leave] += 1;
+        weight += self.distance(last, cities[enter]) as i64 + penalties[enter];
+        weight += self.distance(cities[leave], 0) as i64 + penalties[leave];
+        weight - 2 * penalties.iter().sum::<i64>()
+    }
+
+    /// Shortest tour, starting at city 0, and its length.
+    pub fn solve(&self) -> (Vec<usize>, u64) {
+        let distance = |from, to| self.distance(from, to);
+        let mut tour = nearest_neighbour(self.len(), distance);
+        two_opt(&mut tour, distance);
+        let warm = self.tour_cost(&tour);
+        match self.branch_and_bound(self.start(), self.complete(), self, Some(warm)) {
+            Some((mut cities, cost)) => {
+                cities.pop();
+                cities.insert(0, 0);
+                (cities, cost)
+            }
+            None => (tour, warm),
+        }
+    }
+}
+
+/// Goal of a `Tsp` search: every city visited and the route back at city 0.
+pub struct Complete(u64);
+
+impl SearchGoal<Route> for Complete {
+    fn is_goal(&self, route: &Route) -> bool {
+        route.visited == self.0 && route.city == 0
+    }
+}
+
+impl SearchSpace for Tsp {
+    type State = Route;
+    type Action = usize;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, route: &Self::State) -> Self::Iterator {
+        let next: Vec<usize> = if route.visited != self.all() {
+            (0..self.len()).filter(|&city| !route.has_visited(city)).collect()
+        } else if route.city != 0 {
+            vec![0]
+        } else {
+            vec![]
+        };
+        next.into_iter().map(|city| (city, Route { visited: route.visited | 1 << city, city }))
+            .collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl WeightedSearchSpace for Tsp {
+    type Cost = u64;
+
+    fn cost(&self, route: &Self::State, &city: &Self::Action) -> Self::Cost {
+        self.distance(route.city, city)
+    }
+}
+
+impl Heuristic<Route, u64> for &Tsp {
+    fn estimate(&self, route: &Route) -> u64 {
+        self.lower_bound(route)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use local::{nearest_neighbour, two_opt};
+    use search::SearchSpace;
+    use super::Tsp;
+
+    fn brute_force(tsp: &Tsp, tour: &mut Vec<usize>, best: &mut u64) {
+        if tour.len() == tsp.len() {
+            *best = (*best).min(tsp.tour_cost(tour));
+            return;
+        }
+        for city in 1..tsp.len() {
+            if !tour.contains(&city) {
+                tour.push(city);
+                brute_force(tsp, tour, best);
+                tour.pop();
+            }
+        }
+    }
+
+    fn random_tsp(n: usize, rng: &mut StdRng) -> Tsp {
+        let points: Vec<(i64, i64)> = (0..n).map(|_| (rng.gen_range(0..100), rng.gen_range(0..100))).collect();
+        // Manhattan distances keep the instance symmetric and metric.
+        Tsp::new(points.iter().map(|a| points.iter().map(|b| a.0.abs_diff(b.0) + a.1.abs_diff(b.1)).collect())
+            .collect())
+    }
+
+    #[test]
+    pub fn test_tsp() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..5 {
+            let tsp = random_tsp(8, &mut rng);
+            let (tour, cost) = tsp.solve();
+            let mut best = u64::MAX;
+            brute_force(&tsp, &mut vec![0], &mut best);
+            assert_eq!(cost, best);
+            assert_eq!(tsp.tour_cost(&tour), cost);
+            let mut sorted = tour.clone();
+            sorted.sort_unstable();
+            assert_eq!(sorted, (0..8).collect::<Vec<_>>());
+
+            let distance = |from, to| tsp.distance(from, to);
+            let mut warm = nearest_neighbour(tsp.len(), distance);
+            let nearest = tsp.tour_cost(&warm);
+            two_opt(&mut warm, distance);
+            assert!(best <= tsp.tour_cost(&warm) && tsp.tour_cost(&warm) <= nearest);
+
+            // The bound never overshoots what is left of the optimal tour.
+            let mut route = tsp.start();
+            let mut left = cost;
+            for &city in tour[1..].iter().chain(Some(&0)) {
+                assert!(tsp.lower_bound(&route) <= left);
+                left -= tsp.distance(route.city(), city);
+                route = tsp.expand(&route).find(|&(next, _)| next == city).unwrap().1;
+            }
+            assert_eq!((tsp.lower_bound(&route), left), (0, 0));
+        }
+        assert_eq!(Tsp::new(vec![vec![0]]).solve(), (vec![0], 0));
+    }
+}
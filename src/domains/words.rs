@@ -0,0 +1,141 @@
+//! Word ladders: turn one word into another by changing a letter at a time,
+//! passing only through words of a dictionary.
+
+use alloc::string::String;
+use alloc::vec::{IntoIter, Vec};
+
+use hashbrown::{HashMap, HashSet};
+
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Word ladder over a dictionary given at runtime. A state is a word and an
+/// action is the word moved to, at unit cost; neighbours differ in exactly
+/// one letter and have the same length.
+#[derive(Clone, Debug, Default)]
+pub struct WordLadder {
+    words: HashSet<String>,
+    /// Words by letter position and the word with that letter taken out.
+    buckets: HashMap<(usize, String), Vec<String>>,
+}
+
+impl WordLadder {
+    pub fn new<I, S>(words: I) -> WordLadder where I: IntoIterator<Item=S>, S: Into<String> {
+        let mut ladder = WordLadder::default();
+        for word in words {
+            ladder.insert(word.into());
+        }
+        ladder
+    }
+
+    /// Dictionary read from a word list, one word per line; blank lines and
+    /// surrounding whitespace are ignored.
+    pub fn from_list(text: &str) -> WordLadder {
+        WordLadder::new(text.lines().map(str::trim).filter(|word| !word.is_empty()))
+    }
+
+    pub fn insert(&mut self, word: String) {
+        if self.words.contains(&word) {
+            return;
+        }
+        for key in keys(&word) {
+            self.buckets.entry(key).or_default().push(word.clone());
+        }
+        self.words.insert(word);
+    }
+
+    pub fn contains(&self, word: &str) -> bool {
+        self.words.contains(word)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+}
+
+fn keys(word: &str) -> impl Iterator<Item=(usize, String)> + '_ {
+    word.char_indices().enumerate().map(move |(position, (at, letter))| {
+        let mut rest = String::with_capacity(word.len() - letter.len_utf8());
+        rest.push_str(&word[..at]);
+        rest.push_str(&word[at + letter.len_utf8()..]);
+        (position, rest)
+    })
+}
+
+/// Number of positions at which two words of the same length differ, a lower
+/// bound on the rungs between them; words of different lengths count every
+/// position of the longer one.
+pub fn hamming(a: &str, b: &str) -> u32 {
+    let (a_len, b_len) = (a.chars().count(), b.chars().count());
+    let differing = a.chars().zip(b.chars()).filter(|(a, b)| a != b).count();
+    (differing + a_len.max(b_len) - a_len.min(b_len)) as u32
+}
+
+impl SearchSpace for WordLadder {
+    type State = String;
+    type Action = String;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        keys(state)
+            .filter_map(|key| self.buckets.get(&key))
+            .flat_map(|words| words.iter().filter(|&word| word != state))
+            .map(|word| (word.clone(), word.clone()))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl WeightedSearchSpace for WordLadder {
+    type Cost = u32;
+
+    fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::string::String;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{hamming, WordLadder};
+
+    const WORDS: &str = "
+cold
+cord
+card
+ward
+warm
+word
+worm
+corm
+wore
+core
+    ";
+
+    #[test]
+    pub fn test_word_ladder() {
+        let ladder = WordLadder::from_list(WORDS);
+        assert_eq!(ladder.len(), 10);
+        assert!(ladder.contains("worm") && !ladder.contains("wart"));
+        assert_eq!(hamming("cold", "warm"), 4);
+        assert_eq!(hamming("word", "words"), 1);
+
+        let goal = String::from("warm");
+        let (rungs, cost) = ladder.astar(String::from("cold"), goal.clone(), |word: &String| hamming(word, &goal))
+            .unwrap();
+        assert_eq!(cost, 4);
+        assert_eq!(rungs.last(), Some(&goal));
+        assert!(rungs.windows(2).all(|pair| hamming(&pair[0], &pair[1]) == 1));
+        assert_eq!(ladder.dfs_iter(String::from("cold")).count(), 10);
+
+        let mut ladder = ladder;
+        ladder.insert(String::from("wart"));
+        assert_eq!(ladder.astar(String::from("cold"), String::from("wart"), |_: &String| 0).map(|found| found.1),
+                   Some(4));
+        assert_eq!(ladder.dfs(String::from("cold"), String::from("colds")), None);
+    }
+}
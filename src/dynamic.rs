@@ -0,0 +1,176 @@
+//! Object-safe counterparts of the search space traits, for choosing spaces
+//! at runtime, keeping different ones in a collection or passing them
+//! across plugin boundaries.
+
+use alloc::boxed::Box;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::ops::Add;
+
+use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+
+/// Successors of a type-erased expansion.
+pub type DynSuccessors<'a, T, A> = Box<dyn Iterator<Item=(A, T)> + 'a>;
+
+/// `SearchSpace` with its concrete type and iterator erased, implemented by
+/// every search space whose successor iterator lives for `'a`. The trait
+/// object `dyn DynSearchSpace` is itself a `SearchSpace` with the boxed
+/// iterator, so every search runs on it.
+pub trait DynSearchSpace<'a, T, A> {
+    fn expand_dyn(&self, state: &T) -> DynSuccessors<'a, T, A>;
+
+    fn expand_at_dyn(&self, state: &T, context: ExpandContext) -> DynSuccessors<'a, T, A>;
+
+    fn canonicalize_dyn(&self, state: &T) -> Option<T>;
+
+    fn is_dead_end_dyn(&self, state: &T) -> bool;
+}
+
+impl<'a, S> DynSearchSpace<'a, S::State, S::Action> for S where S: SearchSpace, S::Iterator: 'a {
+    fn expand_dyn(&self, state: &S::State) -> DynSuccessors<'a, S::State, S::Action> {
+        Box::new(self.expand(state))
+    }
+
+    fn expand_at_dyn(&self, state: &S::State, context: ExpandContext) -> DynSuccessors<'a, S::State, S::Action> {
+        Box::new(self.expand_at(state, context))
+    }
+
+    fn canonicalize_dyn(&self, state: &S::State) -> Option<S::State> {
+        self.canonicalize(state)
+    }
+
+    fn is_dead_end_dyn(&self, state: &S::State) -> bool {
+        self.is_dead_end(state)
+    }
+}
+
+/// `WeightedSearchSpace` with its concrete type and iterator erased; see
+/// `DynSearchSpace`.
+pub trait DynWeightedSearchSpace<'a, T, A, C>: DynSearchSpace<'a, T, A> {
+    fn cost_dyn(&self, state: &T, action: &A) -> C;
+
+    fn arrival_cost_dyn(&self, state: &T) -> C;
+
+    fn step_cost_dyn(&self, state: &T, action: &A, successor: &T) -> C;
+
+    fn dominates_dyn(&self, a: &T, b: &T) -> bool;
+
+    fn dominance_key_dyn(&self, state: &T) -> u64;
+
+    fn expand_weighted_dyn(&self, state: &T, context: ExpandContext<C>) -> DynSuccessors<'a, T, A>;
+}
+
+impl<'a, S> DynWeightedSearchSpace<'a, S::State, S::Action, S::Cost> for S
+where S: WeightedSearchSpace, S::Iterator: 'a {
+    fn cost_dyn(&self, state: &S::State, action: &S::Action) -> S::Cost {
+        self.cost(state, action)
+    }
+
+    fn arrival_cost_dyn(&self, state: &S::State) -> S::Cost {
+        self.arrival_cost(state)
+    }
+
+    fn step_cost_dyn(&self, state: &S::State, action: &S::Action, successor: &S::State) -> S::Cost {
+        self.step_cost(state, action, successor)
+    }
+
+    fn dominates_dyn(&self, a: &S::State, b: &S::State) -> bool {
+        self.dominates(a, b)
+    }
+
+    fn dominance_key_dyn(&self, state: &S::State) -> u64 {
+        self.dominance_key(state)
+    }
+
+    fn expand_weighted_dyn(&self, state: &S::State, context: ExpandContext<S::Cost>)
+        -> DynSuccessors<'a, S::State, S::Action> {
+        Box::new(self.expand_weighted(state, context))
+    }
+}
+
+macro_rules! dyn_search_space {
+    ($space:ty $(, $param:ident)*) => {
+        impl<'a, T, A $(, $param)*> SearchSpace for $space where T: Hash + Clone + Eq {
+            type State = T;
+            type Action = A;
+            type Iterator = DynSuccessors<'a, T, A>;
+
+            fn expand(&self, state: &T) -> Self::Iterator {
+                self.expand_dyn(state)
+            }
+
+            fn expand_at(&self, state: &T, context: ExpandContext) -> Self::Iterator {
+                self.expand_at_dyn(state, context)
+            }
+
+            fn canonicalize(&self, state: &T) -> Option<T> {
+                self.canonicalize_dyn(state)
+            }
+
+            fn is_dead_end(&self, state: &T) -> bool {
+                self.is_dead_end_dyn(state)
+            }
+        }
+    };
+}
+
+dyn_search_space!(dyn DynSearchSpace<'a, T, A> + 'a);
+dyn_search_space!(dyn DynWeightedSearchSpace<'a, T, A, C> + 'a, C);
+
+impl<'a, T, A, C> WeightedSearchSpace for dyn DynWeightedSearchSpace<'a, T, A, C> + 'a
+where T: Hash + Clone + Eq, C: Copy + Ord + Add<Output=C> + Default + Debug {
+    type Cost = C;
+
+    fn cost(&self, state: &T, action: &A) -> C {
+        self.cost_dyn(state, action)
+    }
+
+    fn arrival_cost(&self, state: &T) -> C {
+        self.arrival_cost_dyn(state)
+    }
+
+    fn step_cost(&self, state: &T, action: &A, successor: &T) -> C {
+        self.step_cost_dyn(state, action, successor)
+    }
+
+    fn dominates(&self, a: &T, b: &T) -> bool {
+        self.dominates_dyn(a, b)
+    }
+
+    fn dominance_key(&self, state: &T) -> u64 {
+        self.dominance_key_dyn(state)
+    }
+
+    fn expand_weighted(&self, state: &T, context: ExpandContext<C>) -> Self::Iterator {
+        self.expand_weighted_dyn(state, context)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::{random_graph, random_grid};
+    use grid::{manhattan, Cell, Direction};
+    use ordering::OrderedSpace;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{DynSearchSpace, DynWeightedSearchSpace};
+
+    #[test]
+    pub fn test_dyn_search_space() {
+        let grid = random_grid(10, 10, 0.2, &mut StdRng::seed_from_u64(5));
+        let spaces: Vec<Box<dyn DynWeightedSearchSpace<Cell, Direction, u64>>> = vec![
+            Box::new(grid.clone()),
+            Box::new(OrderedSpace::new(grid.clone(), |_: &Cell, _: &Direction, next: &Cell| manhattan(*next, (9, 9)))),
+        ];
+        let to_goal = |cell: &Cell| manhattan(*cell, (9, 9));
+        for space in &spaces {
+            assert_eq!(space.astar((0, 0), (9, 9), to_goal), grid.astar((0, 0), (9, 9), to_goal));
+            assert_eq!(space.bfs_iter((0, 0)).count(), grid.bfs_iter((0, 0)).count());
+        }
+
+        let graph = random_graph(50, 3, 10, &mut StdRng::seed_from_u64(6));
+        let unweighted: &dyn DynSearchSpace<usize, usize> = &graph;
+        assert_eq!(unweighted.dfs(0, 49), graph.dfs(0, 49));
+    }
+}
@@ -0,0 +1,244 @@
+//! Compact binary encoding of paths, for storing many solutions cheaply.
+//!
+//! An encoded path starts with the format version and a flags byte,
+//! followed by the anchor interval when anchors are present, the number of
+//! actions, the start state and then each action as a LEB128 varint. With
+//! anchors every `interval`-th state follows the action leading to it, so
+//! decoding can check that replaying the actions through the search space
+//! reaches the same states.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+
+use grid::{Direction, Heading};
+use path::Path;
+use search::SearchSpace;
+
+/// Version of the encoding written by `Path::encode`.
+pub const PATH_FORMAT_VERSION: u8 = 1;
+
+const ANCHORED: u8 = 1;
+
+/// Action numbered for encoding, small numbers for the common actions
+/// taking the fewest bytes.
+pub trait ActionCode {
+    fn code(&self) -> u64;
+}
+
+/// State written out as bytes for encoding.
+pub trait StateCode: Sized {
+    fn write(&self, out: &mut Vec<u8>);
+
+    /// Reads a state written by `write` off the front of `input`.
+    fn read(input: &mut &[u8]) -> Option<Self>;
+}
+
+macro_rules! int_codes {
+    ($($int:ty),*) => {
+        $(
+            impl ActionCode for $int {
+                fn code(&self) -> u64 {
+                    *self as u64
+                }
+            }
+
+            impl StateCode for $int {
+                fn write(&self, out: &mut Vec<u8>) {
+                    write_varint(out, *self as u64);
+                }
+
+                fn read(input: &mut &[u8]) -> Option<$int> {
+                    read_varint(input).and_then(|value| <$int>::try_from(value).ok())
+                }
+            }
+        )*
+    };
+}
+
+int_codes!(u8, u16, u32, u64, usize);
+
+impl<A: StateCode, B: StateCode> StateCode for (A, B) {
+    fn write(&self, out: &mut Vec<u8>) {
+        self.0.write(out);
+        self.1.write(out);
+    }
+
+    fn read(input: &mut &[u8]) -> Option<(A, B)> {
+        Some((A::read(input)?, B::read(input)?))
+    }
+}
+
+impl ActionCode for Direction {
+    fn code(&self) -> u64 {
+        Direction::ALL.iter().position(|direction| direction == self).expect("direction in ALL") as u64
+    }
+}
+
+impl ActionCode for Heading {
+    fn code(&self) -> u64 {
+        Heading::ALL.iter().position(|heading| heading == self).expect("heading in ALL") as u64
+    }
+}
+
+/// Why an encoded path could not be decoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    UnsupportedVersion(u8),
+    /// The input ended in the middle of the path.
+    Truncated,
+    /// Anchors were flagged with an interval of zero.
+    InvalidAnchorInterval,
+    /// A state could not be read.
+    InvalidState,
+    /// No successor of the state reached is taken by the action at this
+    /// index.
+    InvalidAction(usize),
+    /// Replaying the actions up to this index reached a different state
+    /// than the anchor recorded there.
+    AnchorMismatch(usize),
+    /// Input was left over after the path.
+    TrailingBytes,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnsupportedVersion(version) => write!(f, "unsupported path format version {}", version),
+            DecodeError::Truncated => write!(f, "encoded path truncated"),
+            DecodeError::InvalidAnchorInterval => write!(f, "anchor interval of zero"),
+            DecodeError::InvalidState => write!(f, "invalid state"),
+            DecodeError::InvalidAction(index) => write!(f, "action {} leads nowhere", index),
+            DecodeError::AnchorMismatch(index) => write!(f, "state after action {} does not match its anchor", index),
+            DecodeError::TrailingBytes => write!(f, "trailing bytes after path"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(input: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (index, &byte) in input.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            *input = &input[index + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+impl<S: StateCode, A: ActionCode> Path<S, A> {
+    /// The path encoded in the current format version, with every
+    /// `anchor_every`-th state recorded for decoding to check against.
+    pub fn encode(&self, anchor_every: Option<usize>) -> Vec<u8> {
+        let anchor_every = anchor_every.filter(|&interval| interval > 0);
+        let mut out = vec![PATH_FORMAT_VERSION, if anchor_every.is_some() { ANCHORED } else { 0 }];
+        if let Some(interval) = anchor_every {
+            write_varint(&mut out, interval as u64);
+        }
+        write_varint(&mut out, self.len() as u64);
+        self.start().write(&mut out);
+        for (index, (_, action, state)) in self.steps().enumerate() {
+            write_varint(&mut out, action.code());
+            if anchor_every.is_some_and(|interval| (index + 1) % interval == 0) {
+                state.write(&mut out);
+            }
+        }
+        out
+    }
+}
+
+impl<S: StateCode + Eq, A: ActionCode> Path<S, A> {
+    /// Decodes a path written by `encode`, replaying its actions from the
+    /// start through `search_space` to recover the states passed.
+    pub fn decode<T>(search_space: &T, mut input: &[u8]) -> Result<Path<S, A>, DecodeError>
+    where T: SearchSpace<State=S, Action=A> + ?Sized {
+        let input = &mut input;
+        let (&version, rest) = input.split_first().ok_or(DecodeError::Truncated)?;
+        if version != PATH_FORMAT_VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let (&flags, rest) = rest.split_first().ok_or(DecodeError::Truncated)?;
+        *input = rest;
+        let anchor_every = if flags & ANCHORED != 0 {
+            match read_varint(input).ok_or(DecodeError::Truncated)? {
+                0 => return Err(DecodeError::InvalidAnchorInterval),
+                interval => Some(interval as usize),
+            }
+        } else {
+            None
+        };
+        let len = read_varint(input).ok_or(DecodeError::Truncated)? as usize;
+        let start = S::read(input).ok_or(DecodeError::InvalidState)?;
+        let mut path = Path::new(start);
+        for index in 0..len {
+            let code = read_varint(input).ok_or(DecodeError::Truncated)?;
+            let (action, state) = search_space.expand(path.end())
+                .find(|(action, _)| action.code() == code)
+                .ok_or(DecodeError::InvalidAction(index))?;
+            let anchored = anchor_every.is_some_and(|interval| (index + 1) % interval == 0);
+            if anchored && S::read(input).ok_or(DecodeError::InvalidState)? != state {
+                return Err(DecodeError::AnchorMismatch(index));
+            }
+            path.push(action, state);
+        }
+        if !input.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Cell, Direction};
+    use path::Path;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{DecodeError, PATH_FORMAT_VERSION};
+
+    #[test]
+    pub fn test_encode_path() {
+        let grid = random_grid(30, 30, 0.2, &mut StdRng::seed_from_u64(8));
+        let (actions, _) = grid.astar((0, 0), (29, 29), |cell: &Cell| manhattan(*cell, (29, 29))).unwrap();
+        let mut path = Path::new((0, 0));
+        for action in actions {
+            let next = grid.expand(path.end()).find(|(taken, _)| *taken == action).unwrap().1;
+            path.push(action, next);
+        }
+
+        let bare = path.encode(None);
+        assert_eq!(bare.len(), 2 + 1 + 2 + path.len());
+        assert_eq!(Path::decode(&grid, &bare), Ok(path.clone()));
+        let anchored = path.encode(Some(10));
+        assert_eq!(anchored.len(), bare.len() + 1 + path.len() / 10 * 2);
+        assert_eq!(Path::decode(&grid, &anchored), Ok(path.clone()));
+        assert_eq!(Path::<Cell, Direction>::new((3, 300)).encode(None), vec![PATH_FORMAT_VERSION, 0, 0, 3, 0xac, 0x02]);
+
+        let mut wrong = anchored.clone();
+        wrong[0] = 9;
+        assert_eq!(Path::decode(&grid, &wrong), Err(DecodeError::UnsupportedVersion(9)));
+        assert_eq!(Path::decode(&grid, &anchored[..anchored.len() - 1]), Err(DecodeError::Truncated));
+        assert_eq!(Path::decode(&grid, &[&bare[..], &[0]].concat()), Err(DecodeError::TrailingBytes));
+        // Swapping the first action for another that is possible there
+        // leaves the path on a different cell by the first anchor.
+        let first = grid.expand(&(0, 0)).map(|(action, _)| action).find(|&action| action != path.actions()[0]).unwrap();
+        let mut wrong = anchored.clone();
+        wrong[6] = Direction::ALL.iter().position(|&direction| direction == first).unwrap() as u8;
+        assert!(matches!(Path::decode(&grid, &wrong), Err(DecodeError::AnchorMismatch(_) | DecodeError::InvalidAction(_))));
+        assert_eq!(Path::decode(&grid, &[PATH_FORMAT_VERSION, 1, 0, 1, 0, 0, 1]), Err(DecodeError::InvalidAnchorInterval));
+    }
+}
@@ -0,0 +1,318 @@
+//! Errors of the fallible `try_*` search entry points.
+
+use alloc::vec::{self, Vec};
+use core::cell::{Cell, RefCell};
+use core::convert::Infallible;
+use core::fmt;
+use core::hash::Hash;
+
+use cost::ValidCost;
+use heuristic::Heuristic;
+use search::{ExpandContext, SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// Limit a search ran into before it could finish.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    Nodes,
+    Time,
+    Cost,
+}
+
+/// Why a search could not give an answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchError<E = Infallible> {
+    /// Expanding a state failed.
+    Expansion(E),
+    LimitExceeded(Limit),
+    /// The heuristic fell by more than the cost of an action, so the path
+    /// found might not be the cheapest.
+    InconsistentHeuristic,
+    /// An action cost or heuristic estimate was not a valid cost, such as a
+    /// NaN float.
+    InvalidCost,
+    Cancelled,
+}
+
+impl<E: fmt::Display> fmt::Display for SearchError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SearchError::Expansion(error) => write!(f, "expansion failed: {}", error),
+            SearchError::LimitExceeded(limit) => write!(f, "{:?} limit exceeded", limit),
+            SearchError::InconsistentHeuristic => write!(f, "inconsistent heuristic"),
+            SearchError::InvalidCost => write!(f, "invalid cost"),
+            SearchError::Cancelled => write!(f, "search cancelled"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<E: fmt::Debug + fmt::Display> std::error::Error for SearchError<E> {}
+
+/// Successors of a state in a `TrySearchSpace`, or why they are unknown.
+pub type TryExpansion<T> =
+    Result<Vec<(<T as TrySearchSpace>::Action, <T as TrySearchSpace>::State)>, <T as TrySearchSpace>::Error>;
+
+/// Cheapest path with its cost, if any, or why the search gave up.
+pub type TrySolution<S> =
+    Result<Option<(Vec<<S as SearchSpace>::Action>, <S as WeightedSearchSpace>::Cost)>, SearchError>;
+
+/// Search space whose expansion can fail, such as one read from a database
+/// or over the network.
+pub trait TrySearchSpace {
+    type State: Hash + Clone + Eq;
+    type Action;
+    type Error;
+
+    fn try_expand(&self, state: &Self::State) -> TryExpansion<Self>;
+
+    /// Depth-first search for `goal` that stops at the first failed
+    /// expansion.
+    fn try_dfs<G>(&self, start: Self::State, goal: G) -> Result<Option<Vec<Self::Action>>, SearchError<Self::Error>>
+    where G: SearchGoal<Self::State> {
+        let space = Fallible { search_space: self, error: RefCell::new(None) };
+        let found = space.dfs(start, goal);
+        match space.error.into_inner() {
+            Some(error) => Err(SearchError::Expansion(error)),
+            None => Ok(found),
+        }
+    }
+}
+
+// Search space expanding nothing once an expansion has failed, so that the
+// search winds down and the error can be picked up afterwards.
+struct Fallible<'a, T: TrySearchSpace + ?Sized + 'a> {
+    search_space: &'a T,
+    error: RefCell<Option<T::Error>>,
+}
+
+impl<'a, T: TrySearchSpace + ?Sized> SearchSpace for Fallible<'a, T> {
+    type State = T::State;
+    type Action = T::Action;
+    type Iterator = vec::IntoIter<(T::Action, T::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        if self.error.borrow().is_some() {
+            return Vec::new().into_iter();
+        }
+        self.search_space.try_expand(state).unwrap_or_else(|error| {
+            *self.error.borrow_mut() = Some(error);
+            Vec::new()
+        }).into_iter()
+    }
+}
+
+/// A* through a space that checks every expansion for invalid costs and
+/// estimates, and for an action whose cost is less than the fall in
+/// `heuristic` along it; see `WeightedSearchSpace::try_astar`.
+pub(crate) fn checked_astar<S, G, H>(search_space: &S, start: S::State, goal: G, heuristic: H) -> TrySolution<S>
+where S: WeightedSearchSpace + ?Sized, S::Cost: ValidCost, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    let space = Checked { search_space, heuristic: &heuristic, failure: Cell::new(None) };
+    // The open list only sees estimates once checked, so a NaN cannot upset
+    // its order.
+    let found = space.astar(start, goal, |state: &S::State| {
+        let estimate = heuristic.estimate(state);
+        if !estimate.is_valid() {
+            space.failure.set(Some(SearchError::InvalidCost));
+            return S::Cost::default();
+        }
+        estimate
+    });
+    match space.failure.get() {
+        Some(error) => Err(error),
+        None => Ok(found),
+    }
+}
+
+struct Checked<'a, S: ?Sized + 'a, H: 'a> {
+    search_space: &'a S,
+    heuristic: &'a H,
+    failure: Cell<Option<SearchError>>,
+}
+
+impl<'a, S, H> SearchSpace for Checked<'a, S, H>
+where S: WeightedSearchSpace + ?Sized, S::Cost: ValidCost, H: Heuristic<S::State, S::Cost> {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = vec::IntoIter<(S::Action, S::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.expand_weighted(state, ExpandContext::default())
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.check(state, || self.search_space.expand_at(state, context).collect())
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<'a, S, H> WeightedSearchSpace for Checked<'a, S, H>
+where S: WeightedSearchSpace + ?Sized, S::Cost: ValidCost, H: Heuristic<S::State, S::Cost> {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        let cost = self.search_space.step_cost(state, action, successor);
+        if !cost.is_valid() {
+            self.failure.set(Some(SearchError::InvalidCost));
+            return S::Cost::default();
+        }
+        cost
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.check(state, || self.search_space.expand_weighted(state, context).collect())
+    }
+}
+
+impl<'a, S, H> Checked<'a, S, H>
+where S: WeightedSearchSpace + ?Sized, S::Cost: ValidCost, H: Heuristic<S::State, S::Cost> {
+    // Successors from `expand`, or none once any cost or estimate on the way
+    // to them fails its check.
+    fn check<F>(&self, state: &S::State, expand: F) -> vec::IntoIter<(S::Action, S::State)>
+    where F: FnOnce() -> Vec<(S::Action, S::State)> {
+        if self.failure.get().is_some() {
+            return Vec::new().into_iter();
+        }
+        let estimate = self.heuristic.estimate(state);
+        let successors = expand();
+        for (action, successor) in &successors {
+            let (cost, next) = (self.search_space.step_cost(state, action, successor), self.heuristic.estimate(successor));
+            let failure = if !(estimate.is_valid() && cost.is_valid() && next.is_valid()) {
+                SearchError::InvalidCost
+            } else if estimate > cost + next {
+                SearchError::InconsistentHeuristic
+            } else {
+                continue;
+            };
+            self.failure.set(Some(failure));
+            return Vec::new().into_iter();
+        }
+        successors.into_iter()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::convert::Infallible;
+    use std::sync::atomic::AtomicBool;
+    use std::vec::IntoIter;
+    use cost::FloatCost;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use session::{SearchLimits, SearchSession};
+    use super::{Limit, SearchError, TrySearchSpace};
+
+    // Integers counting up to 9, of which 7 cannot be expanded.
+    struct Flaky;
+
+    impl TrySearchSpace for Flaky {
+        type State = u32;
+        type Action = ();
+        type Error = &'static str;
+
+        fn try_expand(&self, &state: &u32) -> Result<Vec<((), u32)>, &'static str> {
+            match state {
+                7 => Err("lost connection"),
+                9 => Ok(vec![]),
+                _ => Ok(vec![((), state + 1)]),
+            }
+        }
+    }
+
+    // Line 0 -> 1 -> 2 with unit costs.
+    struct Line;
+
+    impl SearchSpace for Line {
+        type State = u32;
+        type Action = ();
+        type Iterator = IntoIter<((), u32)>;
+
+        fn expand(&self, &state: &u32) -> Self::Iterator {
+            if state < 2 { vec![((), state + 1)] } else { vec![] }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Line {
+        type Cost = u32;
+
+        fn cost(&self, _state: &u32, _action: &()) -> u32 {
+            1
+        }
+    }
+
+    // Line 0 -> 1 -> 2 priced in floats, with the last step priced by `last`.
+    struct Priced {
+        last: f64,
+    }
+
+    impl SearchSpace for Priced {
+        type State = u32;
+        type Action = ();
+        type Iterator = IntoIter<((), u32)>;
+
+        fn expand(&self, &state: &u32) -> Self::Iterator {
+            Line.expand(&state)
+        }
+    }
+
+    impl WeightedSearchSpace for Priced {
+        type Cost = FloatCost<f64>;
+
+        fn cost(&self, &state: &u32, _action: &()) -> FloatCost<f64> {
+            FloatCost(if state == 1 { self.last } else { 0.5 })
+        }
+    }
+
+    #[test]
+    pub fn test_invalid_cost() {
+        let zero = |_: &u32| FloatCost(0.0);
+        assert_eq!(Priced { last: 0.25 }.try_astar(0, 2, zero), Ok(Some((vec![(), ()], FloatCost(0.75)))));
+        assert_eq!(Priced { last: f64::NAN }.try_astar(0, 2, zero), Err(SearchError::InvalidCost));
+        let nan_at_start = |&state: &u32| FloatCost(if state == 0 { f64::NAN } else { 0.0 });
+        assert_eq!(Priced { last: 0.25 }.try_astar(0, 2, nan_at_start), Err(SearchError::InvalidCost));
+        assert_eq!(format!("{}", SearchError::<Infallible>::InvalidCost), "invalid cost");
+    }
+
+    #[test]
+    pub fn test_search_error() {
+        assert_eq!(Flaky.try_dfs(0, 5).map(|found| found.map(|actions| actions.len())), Ok(Some(5)));
+        let error = Flaky.try_dfs(0, 9).unwrap_err();
+        assert_eq!(error, SearchError::Expansion("lost connection"));
+        assert_eq!(format!("{}", error), "expansion failed: lost connection");
+
+        assert_eq!(Line.try_astar(0, 2, |&state: &u32| 2 - state), Ok(Some((vec![(), ()], 2))));
+        assert_eq!(Line.try_astar(0, 3, |_: &u32| 0), Ok(None));
+        assert_eq!(Line.try_astar(0, 2, |&state: &u32| if state == 1 { 0 } else { 2 - state }),
+                   Err(SearchError::InconsistentHeuristic));
+
+        let limits = SearchLimits::new().with_max_steps(1);
+        assert_eq!(Line.dfs_session(0, 2).try_run(&limits), Err(SearchError::LimitExceeded(Limit::Nodes)));
+        assert_eq!(Line.dfs_session(0, 2).try_run(&SearchLimits::new()), Ok(Some(vec![(), ()])));
+        let cancel = AtomicBool::new(true);
+        let outcome = Line.dfs_session(0, 2).try_run(&SearchLimits::new().with_cancel(&cancel));
+        assert_eq!(outcome, Err(SearchError::Cancelled));
+    }
+}
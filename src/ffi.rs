@@ -0,0 +1,207 @@
+//! C interface for building graphs and finding paths in them.
+//!
+//! The matching declarations are in `include/chappie.h`. Build a library for
+//! linking with e.g.
+//! `cargo rustc --release --features ffi --crate-type staticlib`.
+//! Graphs and paths are opaque handles that must be released with
+//! `chappie_graph_free` and `chappie_path_free`.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ffi::c_void;
+use core::ptr;
+
+use batch::BatchSearchSpace;
+use graph::Graph;
+use search::WeightedSearchSpace;
+
+/// A path found by one of the searches.
+pub struct FoundPath {
+    nodes: Vec<usize>,
+    cost: u64,
+}
+
+/// Heuristic callback receiving a node and the caller's `user_data`.
+pub type HeuristicFn = extern "C" fn(node: usize, user_data: *mut c_void) -> u64;
+
+fn found(graph: &Graph, start: usize, actions: Vec<usize>) -> *mut FoundPath {
+    let nodes = graph.walk(start, &actions);
+    let cost = nodes.iter().zip(&actions).map(|(&node, action)| graph.cost(&node, action)).sum();
+    Box::into_raw(Box::new(FoundPath { nodes, cost }))
+}
+
+fn in_range(graph: &Graph, start: usize, goal: usize) -> bool {
+    start < graph.node_count() && goal < graph.node_count()
+}
+
+#[no_mangle]
+pub extern "C" fn chappie_graph_new(nodes: usize) -> *mut Graph {
+    Box::into_raw(Box::new(Graph::new(nodes)))
+}
+
+/// # Safety
+///
+/// `graph` must be null or come from `chappie_graph_new` and not have been
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_graph_free(graph: *mut Graph) {
+    if !graph.is_null() {
+        drop(Box::from_raw(graph));
+    }
+}
+
+/// # Safety
+///
+/// `graph` must be a live handle from `chappie_graph_new`.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_graph_add_node(graph: *mut Graph) -> usize {
+    (*graph).add_node()
+}
+
+/// Adds an edge, returning 0 on success and -1 if a node is out of range.
+///
+/// # Safety
+///
+/// `graph` must be a live handle from `chappie_graph_new`.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_graph_add_edge(graph: *mut Graph, from: usize, to: usize, cost: u64) -> i32 {
+    let graph = &mut *graph;
+    if !in_range(graph, from, to) {
+        return -1;
+    }
+    graph.add_edge(from, to, cost);
+    0
+}
+
+/// Path with the fewest edges, or null if `goal` is unreachable.
+///
+/// # Safety
+///
+/// `graph` must be a live handle from `chappie_graph_new`.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_bfs(graph: *const Graph, start: usize, goal: usize) -> *mut FoundPath {
+    let graph = &*graph;
+    if !in_range(graph, start, goal) {
+        return ptr::null_mut();
+    }
+    match graph.bfs_batched(start, goal, 64) {
+        Some(actions) => found(graph, start, actions),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Cheapest path, or null if `goal` is unreachable.
+///
+/// # Safety
+///
+/// `graph` must be a live handle from `chappie_graph_new`.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_dijkstra(graph: *const Graph, start: usize, goal: usize) -> *mut FoundPath {
+    chappie_astar(graph, start, goal, None, ptr::null_mut())
+}
+
+/// Cheapest path found by A* guided by `heuristic`, which is called with
+/// `user_data`; a null heuristic makes this Dijkstra's algorithm.
+///
+/// # Safety
+///
+/// `graph` must be a live handle from `chappie_graph_new`, and `heuristic`
+/// must be safe to call with `user_data`.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_astar(graph: *const Graph, start: usize, goal: usize, heuristic: Option<HeuristicFn>,
+                                       user_data: *mut c_void) -> *mut FoundPath {
+    let graph = &*graph;
+    if !in_range(graph, start, goal) {
+        return ptr::null_mut();
+    }
+    let estimate = |node: &usize| heuristic.map_or(0, |heuristic| heuristic(*node, user_data));
+    match graph.astar(start, goal, estimate) {
+        Some((actions, _)) => found(graph, start, actions),
+        None => ptr::null_mut(),
+    }
+}
+
+/// Number of nodes on the path, both ends included.
+///
+/// # Safety
+///
+/// `path` must be a live handle returned by a search.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_path_len(path: *const FoundPath) -> usize {
+    (*path).nodes.len()
+}
+
+/// The path's nodes, valid until the path is freed.
+///
+/// # Safety
+///
+/// `path` must be a live handle returned by a search.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_path_nodes(path: *const FoundPath) -> *const usize {
+    (*path).nodes.as_ptr()
+}
+
+/// # Safety
+///
+/// `path` must be a live handle returned by a search.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_path_cost(path: *const FoundPath) -> u64 {
+    (*path).cost
+}
+
+/// # Safety
+///
+/// `path` must be null or a handle returned by a search that has not been
+/// freed already.
+#[no_mangle]
+pub unsafe extern "C" fn chappie_path_free(path: *mut FoundPath) {
+    if !path.is_null() {
+        drop(Box::from_raw(path));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::ffi::c_void;
+    use std::ptr;
+    use std::slice;
+    use super::*;
+
+    extern "C" fn remaining(node: usize, user_data: *mut c_void) -> u64 {
+        let goal = unsafe { *(user_data as *const usize) };
+        goal.saturating_sub(node) as u64
+    }
+
+    #[test]
+    pub fn test_ffi() {
+        unsafe {
+            let graph = chappie_graph_new(3);
+            let last = chappie_graph_add_node(graph);
+            assert_eq!(last, 3);
+            assert_eq!(chappie_graph_add_edge(graph, 0, 3, 10), 0);
+            for node in 0..3 {
+                assert_eq!(chappie_graph_add_edge(graph, node, node + 1, 1), 0);
+            }
+            assert_eq!(chappie_graph_add_edge(graph, 0, 4, 1), -1);
+
+            let path = chappie_bfs(graph, 0, 3);
+            assert_eq!((chappie_path_len(path), chappie_path_cost(path)), (2, 10));
+            chappie_path_free(path);
+
+            let mut goal = 3usize;
+            let path = chappie_astar(graph, 0, 3, Some(remaining), &mut goal as *mut usize as *mut c_void);
+            assert_eq!(slice::from_raw_parts(chappie_path_nodes(path), chappie_path_len(path)), &[0, 1, 2, 3]);
+            assert_eq!(chappie_path_cost(path), 3);
+            chappie_path_free(path);
+
+            let path = chappie_dijkstra(graph, 0, 3);
+            assert_eq!(chappie_path_cost(path), 3);
+            chappie_path_free(path);
+
+            assert!(chappie_dijkstra(graph, 3, 0).is_null());
+            assert!(chappie_bfs(graph, 0, 7).is_null());
+            chappie_graph_free(graph);
+            chappie_path_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Readers for the 9th DIMACS Implementation Challenge shortest path
+//! formats: `.gr` graphs and `.co` coordinates.
+//!
+//! DIMACS nodes are numbered from 1; node `i` of the file is node `i - 1` of
+//! the graph.
+
+use alloc::vec::Vec;
+use core::str::{FromStr, SplitWhitespace};
+
+use graph::Graph;
+use super::ParseError;
+
+fn field<T: FromStr>(fields: &mut SplitWhitespace, line: usize, what: &str) -> Result<T, ParseError> {
+    let text = fields.next().ok_or_else(|| ParseError::new(line, format!("missing {}", what)))?;
+    text.parse().map_err(|_| ParseError::new(line, format!("bad {} {:?}", what, text)))
+}
+
+fn node(fields: &mut SplitWhitespace, line: usize, nodes: usize) -> Result<usize, ParseError> {
+    let node: usize = field(fields, line, "node")?;
+    if node == 0 || node > nodes {
+        return Err(ParseError::new(line, format!("node {} out of range 1..={}", node, nodes)));
+    }
+    Ok(node - 1)
+}
+
+// Lines other than comments and blank ones, with their numbers.
+fn records(text: &str) -> impl Iterator<Item=(usize, &str)> {
+    text.lines().enumerate()
+        .map(|(number, line)| (number + 1, line.trim()))
+        .filter(|&(_, line)| !line.is_empty() && !line.starts_with('c'))
+}
+
+/// Reads a `.gr` file: a `p sp <nodes> <arcs>` line followed by
+/// `a <from> <to> <cost>` arcs.
+pub fn parse_dimacs(text: &str) -> Result<Graph, ParseError> {
+    let mut graph: Option<Graph> = None;
+    for (line, record) in records(text) {
+        let mut fields = record.split_whitespace();
+        match (fields.next(), graph.as_mut()) {
+            (Some("p"), None) => {
+                if fields.next() != Some("sp") {
+                    return Err(ParseError::new(line, "expected `p sp <nodes> <arcs>`"));
+                }
+                graph = Some(Graph::new(field(&mut fields, line, "node count")?));
+            }
+            (Some("a"), Some(graph)) => {
+                let nodes = graph.node_count();
+                let from = node(&mut fields, line, nodes)?;
+                let to = node(&mut fields, line, nodes)?;
+                graph.add_edge(from, to, field(&mut fields, line, "cost")?);
+            }
+            (Some("a"), None) => return Err(ParseError::new(line, "arc before the problem line")),
+            (Some("p"), Some(_)) => return Err(ParseError::new(line, "second problem line")),
+            _ => return Err(ParseError::new(line, format!("unexpected line {:?}", record))),
+        }
+    }
+    graph.ok_or_else(|| ParseError::new(text.lines().count(), "no problem line"))
+}
+
+/// Reads a `.co` file: a `p aux sp co <nodes>` line followed by
+/// `v <node> <x> <y>` coordinates, returned indexed by graph node. Nodes
+/// without a `v` line are at the origin.
+pub fn parse_dimacs_coordinates(text: &str) -> Result<Vec<(i64, i64)>, ParseError> {
+    let mut coordinates: Option<Vec<(i64, i64)>> = None;
+    for (line, record) in records(text) {
+        let mut fields = record.split_whitespace();
+        match (fields.next(), coordinates.as_mut()) {
+            (Some("p"), None) => {
+                if fields.by_ref().take(3).ne(["aux", "sp", "co"].iter().cloned()) {
+                    return Err(ParseError::new(line, "expected `p aux sp co <nodes>`"));
+                }
+                coordinates = Some(vec![(0, 0); field(&mut fields, line, "node count")?]);
+            }
+            (Some("v"), Some(coordinates)) => {
+                let node = node(&mut fields, line, coordinates.len())?;
+                coordinates[node] = (field(&mut fields, line, "x")?, field(&mut fields, line, "y")?);
+            }
+            (Some("v"), None) => return Err(ParseError::new(line, "coordinate before the problem line")),
+            (Some("p"), Some(_)) => return Err(ParseError::new(line, "second problem line")),
+            _ => return Err(ParseError::new(line, format!("unexpected line {:?}", record))),
+        }
+    }
+    coordinates.ok_or_else(|| ParseError::new(text.lines().count(), "no problem line"))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::WeightedSearchSpace;
+    use super::{parse_dimacs, parse_dimacs_coordinates};
+
+    #[test]
+    pub fn test_parse_dimacs() {
+        let graph = parse_dimacs("c 9th DIMACS Implementation Challenge\n\
+                                  p sp 3 3\n\
+                                  a 1 2 7\n\
+                                  a 2 3 4\n\
+                                  \n\
+                                  a 1 3 12\n").unwrap();
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 3));
+        assert_eq!(graph.astar(0, 2, |_: &usize| 0).map(|found| found.1), Some(11));
+
+        let coordinates = parse_dimacs_coordinates("p aux sp co 3\nv 1 -73530767 41085396\nv 3 -73503678 41053678\n");
+        assert_eq!(coordinates.unwrap(), [(-73530767, 41085396), (0, 0), (-73503678, 41053678)]);
+
+        let err = parse_dimacs("p sp 2 1\na 1 3 1\n").unwrap_err();
+        assert_eq!((err.line, err.message.as_str()), (2, "node 3 out of range 1..=2"));
+        assert_eq!(parse_dimacs("a 1 2 1\n").unwrap_err().message, "arc before the problem line");
+        assert_eq!(parse_dimacs("p sp 2 1\na 1 2 x\n").unwrap_err().message, "bad cost \"x\"");
+    }
+}
@@ -0,0 +1,374 @@
+//! Graphviz DOT reader.
+//!
+//! Nodes are named by their DOT identifiers. An edge costs its `weight`
+//! attribute, else its `cost` attribute, else its `label` when that is an
+//! integer, else the weight given by an enclosing `edge [...]` statement,
+//! else 1. Undirected graphs get edges in both directions. Graph and node
+//! attributes, ports and the `strict` keyword are accepted but ignored.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use graph::NamedGraph;
+use super::ParseError;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Id(String),
+    Edge { directed: bool },
+    Symbol(char),
+}
+
+// Key, value and the line of the value.
+type Attributes = Vec<(String, String, usize)>;
+
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                let start = line;
+                chars.next();
+                let mut last = ' ';
+                loop {
+                    match chars.next() {
+                        Some('/') if last == '*' => break,
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            last = c;
+                        }
+                        None => return Err(ParseError::new(start, "unterminated comment")),
+                    }
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut id = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') if chars.peek() == Some(&'"') => id.push(chars.next().unwrap()),
+                        Some('\\') if chars.peek() == Some(&'\n') => {
+                            chars.next();
+                            line += 1;
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            id.push(c);
+                        }
+                        None => return Err(ParseError::new(start, "unterminated string")),
+                    }
+                }
+                tokens.push((Token::Id(id), start));
+            }
+            '<' => {
+                let start = line;
+                let mut id = String::new();
+                let mut depth = 1;
+                loop {
+                    let c = chars.next().ok_or_else(|| ParseError::new(start, "unterminated HTML string"))?;
+                    match c {
+                        '<' => depth += 1,
+                        '>' => depth -= 1,
+                        '\n' => line += 1,
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                    id.push(c);
+                }
+                tokens.push((Token::Id(id), start));
+            }
+            '-' if chars.peek() == Some(&'>') => {
+                chars.next();
+                tokens.push((Token::Edge { directed: true }, line));
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                chars.next();
+                tokens.push((Token::Edge { directed: false }, line));
+            }
+            '{' | '}' | '[' | ']' | '=' | ';' | ',' | ':' => tokens.push((Token::Symbol(c), line)),
+            c if c.is_alphanumeric() || c == '_' || c == '.' || c == '-' => {
+                let mut id = String::new();
+                id.push(c);
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_' || c == '.') {
+                        break;
+                    }
+                    id.push(c);
+                    chars.next();
+                }
+                tokens.push((Token::Id(id), line));
+            }
+            c => return Err(ParseError::new(line, format!("unexpected character {:?}", c))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    directed: bool,
+    named: NamedGraph,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.position).or_else(|| self.tokens.last()).map_or(1, |&(_, line)| line)
+    }
+
+    fn error<M: Into<String>>(&self, message: M) -> ParseError {
+        ParseError::new(self.line(), message)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).map(|(token, _)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: char) -> bool {
+        if self.peek() == Some(&Token::Symbol(symbol)) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, symbol: char) -> Result<(), ParseError> {
+        if self.eat(symbol) { Ok(()) } else { Err(self.error(format!("expected `{}`", symbol))) }
+    }
+
+    fn id(&mut self) -> Result<String, ParseError> {
+        match self.peek() {
+            Some(Token::Id(_)) => match self.next() {
+                Some(Token::Id(id)) => Ok(id),
+                _ => unreachable!(),
+            },
+            _ => Err(self.error("expected an identifier")),
+        }
+    }
+
+    fn keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Id(id)) => id.eq_ignore_ascii_case(keyword),
+            _ => false,
+        }
+    }
+
+    fn graph(&mut self) -> Result<(), ParseError> {
+        if self.keyword("strict") {
+            self.position += 1;
+        }
+        self.directed = if self.keyword("digraph") {
+            true
+        } else if self.keyword("graph") {
+            false
+        } else {
+            return Err(self.error("expected `graph` or `digraph`"));
+        };
+        self.position += 1;
+        if let Some(Token::Id(_)) = self.peek() {
+            self.position += 1;
+        }
+        self.expect('{')?;
+        self.statements(None)?;
+        if self.peek().is_some() {
+            return Err(self.error("unexpected input after the graph"));
+        }
+        Ok(())
+    }
+
+    // Statements up to the closing brace, returning the nodes they mention.
+    fn statements(&mut self, mut edge_weight: Option<u64>) -> Result<Vec<usize>, ParseError> {
+        let mut nodes = Vec::new();
+        while !self.eat('}') {
+            if self.peek().is_none() {
+                return Err(self.error("expected `}`"));
+            }
+            if self.keyword("graph") || self.keyword("node") || self.keyword("edge") {
+                let edge = self.keyword("edge");
+                self.position += 1;
+                let attributes = self.attributes()?;
+                if edge {
+                    edge_weight = Parser::weight(&attributes)?.or(edge_weight);
+                }
+            } else if let (Some(Token::Id(_)), Some(Token::Symbol('='))) =
+                (self.peek(), self.tokens.get(self.position + 1).map(|(token, _)| token)) {
+                self.position += 2;
+                self.id()?;
+            } else {
+                let mut operands = vec![self.operand(edge_weight)?];
+                while let Some(&Token::Edge { directed }) = self.peek() {
+                    if directed != self.directed {
+                        return Err(self.error(if self.directed { "expected `->`" } else { "expected `--`" }));
+                    }
+                    self.position += 1;
+                    operands.push(self.operand(edge_weight)?);
+                }
+                let attributes = self.attributes()?;
+                let cost = Parser::weight(&attributes)?.or(edge_weight).unwrap_or(1);
+                for pair in operands.windows(2) {
+                    for &from in &pair[0] {
+                        for &to in &pair[1] {
+                            self.edge(from, to, cost);
+                        }
+                    }
+                }
+                nodes.extend(operands.into_iter().flatten());
+            }
+            self.eat(';');
+        }
+        Ok(nodes)
+    }
+
+    // A node, or a subgraph standing for all the nodes in it.
+    fn operand(&mut self, edge_weight: Option<u64>) -> Result<Vec<usize>, ParseError> {
+        if self.keyword("subgraph") {
+            self.position += 1;
+            if let Some(Token::Id(_)) = self.peek() {
+                self.position += 1;
+            }
+        }
+        if self.eat('{') {
+            return self.statements(edge_weight);
+        }
+        let name = self.id()?;
+        // Ports and compass points only affect drawing.
+        while self.eat(':') {
+            self.id()?;
+        }
+        Ok(vec![self.named.add_node(&name)])
+    }
+
+    fn attributes(&mut self) -> Result<Attributes, ParseError> {
+        let mut attributes = Vec::new();
+        while self.eat('[') {
+            while !self.eat(']') {
+                let key = self.id()?;
+                self.expect('=')?;
+                let line = self.line();
+                let value = self.id()?;
+                attributes.push((key, value, line));
+                if !self.eat(',') {
+                    self.eat(';');
+                }
+            }
+        }
+        Ok(attributes)
+    }
+
+    fn weight(attributes: &Attributes) -> Result<Option<u64>, ParseError> {
+        let value = |key: &str| attributes.iter().rev().find(|(k, _, _)| k == key);
+        match value("weight").or_else(|| value("cost")) {
+            Some((_, weight, line)) => weight.parse().map(Some).map_err(|_| {
+                ParseError::new(*line, format!("edge weight {:?} is not a non-negative integer", weight))
+            }),
+            None => Ok(value("label").and_then(|(_, label, _)| label.parse().ok())),
+        }
+    }
+
+    fn edge(&mut self, from: usize, to: usize, cost: u64) {
+        let (from, to) = (self.named.name(from).to_owned(), self.named.name(to).to_owned());
+        self.named.add_edge(&from, &to, cost);
+        if !self.directed && from != to {
+            self.named.add_edge(&to, &from, cost);
+        }
+    }
+}
+
+/// Reads a DOT `graph` or `digraph`.
+pub fn parse_dot(text: &str) -> Result<NamedGraph, ParseError> {
+    let mut parser = Parser { tokens: tokenize(text)?, position: 0, directed: true, named: NamedGraph::new() };
+    parser.graph()?;
+    Ok(parser.named)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use formats::ParseError;
+    use graphviz::SearchTree;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::parse_dot;
+
+    #[test]
+    pub fn test_parse_dot() {
+        let named = parse_dot(r#"
+            // Roads between towns.
+            digraph roads {
+                rankdir = LR;
+                node [shape=box];
+                edge [weight=2]
+                depot -> mill -> "old harbour" [label="5"];
+                depot -> market;
+                /* Keyed by the label, then the weight. */
+                market -> "old harbour" [label=ferry, weight=3];
+                { market mill } -> depot [cost=7]
+                subgraph cluster { lighthouse }
+            }
+        "#).unwrap();
+        assert_eq!(named.names(), ["depot", "mill", "old harbour", "market", "lighthouse"]);
+        assert_eq!(named.graph().edge_count(), 6);
+        let (depot, harbour) = (named.node("depot").unwrap(), named.node("old harbour").unwrap());
+        assert_eq!(named.graph().astar(depot, harbour, |_: &usize| 0).map(|found| found.1), Some(5));
+        assert_eq!(named.graph().astar(named.node("mill").unwrap(), depot, |_: &usize| 0).map(|found| found.1),
+                   Some(7));
+
+        let undirected = parse_dot("strict graph { a -- b -- c; }").unwrap();
+        assert_eq!(undirected.graph().edge_count(), 4);
+        assert_eq!(undirected.graph().dfs(2, 0).map(|actions| actions.len()), Some(2));
+
+        assert_eq!(parse_dot("digraph {\n a -- b\n}").unwrap_err(), ParseError::new(2, "expected `->`"));
+        assert_eq!(parse_dot("digraph {\n a -> b [weight=1.5]\n}").unwrap_err().line, 2);
+        assert_eq!(parse_dot("digraph { a -> b").unwrap_err().message, "expected `}`");
+    }
+
+    #[test]
+    pub fn test_dot_round_trip() {
+        struct Tree;
+
+        impl SearchSpace for Tree {
+            type State = u8;
+            type Action = u8;
+            type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+            fn expand(&self, state: &Self::State) -> Self::Iterator {
+                if *state < 4 { vec![(0, 2 * *state + 1), (1, 2 * *state + 2)] } else { vec![] }.into_iter()
+            }
+        }
+
+        let mut tree = SearchTree::new();
+        Tree.dfs_with(&mut SearchBuffers::new().with_observer(&mut tree), 0, 6);
+        let named = parse_dot(&tree.to_dot()).unwrap();
+        assert_eq!(named.graph().node_count(), tree.len());
+        assert_eq!(named.graph().edge_count(), tree.len() - 1);
+        assert_eq!(named.graph().dfs_iter(named.node("n0").unwrap()).count(), tree.len());
+    }
+}
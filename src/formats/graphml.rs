@@ -0,0 +1,251 @@
+//! GraphML reader, behind the `graphml` feature.
+//!
+//! Edges cost the value of their data under a key named `weight`, `cost` or
+//! `length`, else that key's default, else 1; costs must be whole numbers.
+//! Node data under keys named `x` and `y` give the node's coordinates.
+//! Edges follow the graph's `edgedefault` unless they set `directed`, and
+//! undirected edges are added in both directions. Nested graphs, hyperedges
+//! and ports are not supported.
+
+use std::collections::HashMap;
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+
+use graph::NamedGraph;
+use super::ParseError;
+
+const WEIGHT_KEYS: [&str; 3] = ["weight", "cost", "length"];
+
+struct Key {
+    name: String,
+    default: Option<String>,
+}
+
+enum Element {
+    Node { id: String },
+    Edge { source: String, target: String, directed: Option<bool> },
+}
+
+struct GraphmlReader<'a> {
+    text: &'a str,
+    reader: Reader<&'a [u8]>,
+    keys: HashMap<String, Key>,
+    directed: bool,
+    named: NamedGraph,
+}
+
+impl<'a> GraphmlReader<'a> {
+    fn line(&self, position: u64) -> usize {
+        let position = (position as usize).min(self.text.len());
+        self.text.as_bytes()[..position].iter().filter(|&&b| b == b'\n').count() + 1
+    }
+
+    fn error<M: Into<String>>(&self, message: M) -> ParseError {
+        ParseError::new(self.line(self.reader.buffer_position()), message)
+    }
+
+    fn attribute(&self, element: &BytesStart, name: &str) -> Result<Option<String>, ParseError> {
+        let attribute = element.try_get_attribute(name).map_err(|err| self.error(err.to_string()))?;
+        match attribute {
+            Some(attribute) => attribute.unescape_value()
+                .map(|value| Some(value.into_owned()))
+                .map_err(|err| self.error(err.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    fn required(&self, element: &BytesStart, name: &str) -> Result<String, ParseError> {
+        let tag = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+        self.attribute(element, name)?.ok_or_else(|| self.error(format!("<{}> without `{}`", tag, name)))
+    }
+
+    fn event(&mut self) -> Result<Event<'a>, ParseError> {
+        self.reader.read_event().map_err(|err| {
+            ParseError::new(self.line(self.reader.error_position()), err.to_string())
+        })
+    }
+
+    // Text up to the end of the current element.
+    fn text(&mut self) -> Result<String, ParseError> {
+        let mut text = String::new();
+        loop {
+            match self.event()? {
+                Event::Text(content) => text.push_str(&content.unescape().map_err(|err| self.error(err.to_string()))?),
+                Event::CData(content) => text.push_str(&String::from_utf8_lossy(&content)),
+                Event::End(_) => return Ok(text),
+                Event::Eof => return Err(self.error("unexpected end of file")),
+                _ => {}
+            }
+        }
+    }
+
+    fn key(&mut self, element: &BytesStart, empty: bool) -> Result<(), ParseError> {
+        let id = self.required(element, "id")?;
+        let name = self.attribute(element, "attr.name")?.unwrap_or_else(|| id.clone());
+        let mut default = None;
+        if !empty {
+            loop {
+                match self.event()? {
+                    Event::Start(child) if child.local_name().as_ref() == b"default" => default = Some(self.text()?),
+                    Event::End(_) => break,
+                    Event::Eof => return Err(self.error("unexpected end of file")),
+                    _ => {}
+                }
+            }
+        }
+        self.keys.insert(id, Key { name, default });
+        Ok(())
+    }
+
+    // Data of a node or edge, keyed by attribute name.
+    fn data(&mut self) -> Result<HashMap<String, (String, usize)>, ParseError> {
+        let mut data = HashMap::new();
+        loop {
+            match self.event()? {
+                Event::Start(child) if child.local_name().as_ref() == b"data" => {
+                    let key = self.required(&child, "key")?;
+                    let line = self.line(self.reader.buffer_position());
+                    let value = self.text()?;
+                    let name = self.keys.get(&key).map_or(key, |key| key.name.clone());
+                    data.insert(name, (value, line));
+                }
+                Event::Start(child) => {
+                    let tag = String::from_utf8_lossy(child.local_name().as_ref()).into_owned();
+                    self.reader.read_to_end(child.name()).map_err(|err| self.error(err.to_string()))?;
+                    if tag == "graph" {
+                        return Err(self.error("nested graphs are not supported"));
+                    }
+                }
+                Event::End(_) => return Ok(data),
+                Event::Eof => return Err(self.error("unexpected end of file")),
+                _ => {}
+            }
+        }
+    }
+
+    fn value(&self, data: &HashMap<String, (String, usize)>, names: &[&str]) -> Option<(String, usize)> {
+        let line = self.line(self.reader.buffer_position());
+        names.iter().filter_map(|&name| data.get(name).cloned()).next().or_else(|| {
+            names.iter().filter_map(|&name| {
+                self.keys.values().find(|key| key.name == name).and_then(|key| key.default.clone())
+            }).next().map(|default| (default, line))
+        })
+    }
+
+    fn element(&mut self, element: Element, data: HashMap<String, (String, usize)>) -> Result<(), ParseError> {
+        match element {
+            Element::Node { id } => {
+                let node = self.named.add_node(&id);
+                let coordinate = |name: &str| data.get(name).and_then(|(value, _)| value.trim().parse::<f64>().ok());
+                self.named.set_position(node, coordinate("x").and_then(|x| coordinate("y").map(|y| (x, y))));
+            }
+            Element::Edge { source, target, directed } => {
+                let cost = match self.value(&data, &WEIGHT_KEYS) {
+                    Some((value, line)) => parse_weight(&value).ok_or_else(|| {
+                        ParseError::new(line, format!("edge weight {:?} is not a non-negative whole number", value))
+                    })?,
+                    None => 1,
+                };
+                self.named.add_edge(&source, &target, cost);
+                if !directed.unwrap_or(self.directed) && source != target {
+                    self.named.add_edge(&target, &source, cost);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read(mut self) -> Result<NamedGraph, ParseError> {
+        let mut graphs = 0;
+        loop {
+            let (element, empty) = match self.event()? {
+                Event::Start(element) => (element, false),
+                Event::Empty(element) => (element, true),
+                Event::Eof => break,
+                _ => continue,
+            };
+            let item = match element.local_name().as_ref() {
+                b"key" => {
+                    self.key(&element, empty)?;
+                    continue;
+                }
+                b"graph" => {
+                    graphs += 1;
+                    if graphs > 1 {
+                        return Err(self.error("only one graph per file is supported"));
+                    }
+                    self.directed = self.attribute(&element, "edgedefault")?.is_some_and(|d| d == "directed");
+                    continue;
+                }
+                b"node" => Element::Node { id: self.required(&element, "id")? },
+                b"edge" => Element::Edge {
+                    source: self.required(&element, "source")?,
+                    target: self.required(&element, "target")?,
+                    directed: self.attribute(&element, "directed")?.map(|directed| directed == "true"),
+                },
+                b"hyperedge" => return Err(self.error("hyperedges are not supported")),
+                _ => continue,
+            };
+            let data = if empty { HashMap::new() } else { self.data()? };
+            self.element(item, data)?;
+        }
+        if graphs == 0 {
+            return Err(self.error("no <graph> element"));
+        }
+        Ok(self.named)
+    }
+}
+
+fn parse_weight(value: &str) -> Option<u64> {
+    let value = value.trim();
+    value.parse().ok().or_else(|| {
+        let weight: f64 = value.parse().ok()?;
+        if weight >= 0.0 && weight.fract() == 0.0 && weight <= u64::MAX as f64 { Some(weight as u64) } else { None }
+    })
+}
+
+/// Reads the graph of a GraphML document.
+pub fn parse_graphml(text: &str) -> Result<NamedGraph, ParseError> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+    GraphmlReader { text, reader, keys: HashMap::new(), directed: false, named: NamedGraph::new() }.read()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::parse_graphml;
+
+    #[test]
+    pub fn test_parse_graphml() {
+        let named = parse_graphml(r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="edge" attr.name="weight" attr.type="double"><default>4</default></key>
+  <key id="d1" for="node" attr.name="x" attr.type="double"/>
+  <key id="d2" for="node" attr.name="y" attr.type="double"/>
+  <key id="d3" for="node" attr.name="label" attr.type="string"/>
+  <graph id="G" edgedefault="undirected">
+    <node id="depot"><data key="d1">1.5</data><data key="d2">-2</data><data key="d3">Depot &amp; yard</data></node>
+    <node id="mill"/>
+    <edge source="depot" target="mill"><data key="d0">1.0</data></edge>
+    <edge source="mill" target="harbour"/>
+    <edge source="harbour" target="depot" directed="true"><data key="d0">2</data></edge>
+  </graph>
+</graphml>"#).unwrap();
+        assert_eq!(named.names(), ["depot", "mill", "harbour"]);
+        assert_eq!(named.position(0), Some((1.5, -2.0)));
+        assert_eq!(named.position(1), None);
+        assert_eq!(named.graph().edge_count(), 5);
+        assert_eq!(named.graph().astar(0, 2, |_: &usize| 0).map(|found| found.1), Some(5));
+        assert_eq!(named.graph().astar(2, 0, |_: &usize| 0).map(|found| found.1), Some(2));
+        assert_eq!(named.graph().dfs_iter(2).count(), 3);
+
+        let err = parse_graphml("<graphml>\n<graph>\n<edge source=\"a\" target=\"b\">\n<data key=\"weight\">0.5</data>\
+                                 </edge></graph></graphml>").unwrap_err();
+        assert_eq!(err.line, 4);
+        assert!(err.message.contains("\"0.5\""));
+        assert_eq!(parse_graphml("<graphml>\n<graph>\n<node/>\n</graph></graphml>").unwrap_err().line, 3);
+        assert!(parse_graphml("<graphml></graphml>").is_err());
+    }
+}
@@ -0,0 +1,164 @@
+//! JSON graph reader and writer, behind the `json` feature.
+//!
+//! The schema, where every field but the node and edge endpoints is
+//! optional:
+//!
+//! ```json
+//! {
+//!   "directed": true,
+//!   "nodes": [{"id": "depot", "x": 0.0, "y": 1.5}, {"id": "mill"}],
+//!   "edges": [{"from": "depot", "to": "mill", "weight": 4}],
+//!   "path": ["depot", "mill"],
+//!   "cost": 4,
+//!   "stats": {"expanded": 1, "generated": 1, ...}
+//! }
+//! ```
+//!
+//! `directed` defaults to true; an undirected graph gets edges in both
+//! directions. `weight` defaults to 1. Nodes only named by an edge are added
+//! without coordinates. `path`, `cost` and `stats` annotate a search result:
+//! they are written by `to_json` and ignored when reading.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use graph::NamedGraph;
+use stats::SearchStats;
+use super::ParseError;
+
+/// Result of a search over a graph, saved alongside it.
+#[derive(Clone, Debug, Default)]
+pub struct Annotations {
+    /// Nodes of the path found, start and goal included.
+    pub path: Option<Vec<usize>>,
+    pub cost: Option<u64>,
+    pub stats: Option<SearchStats>,
+}
+
+fn directed() -> bool {
+    true
+}
+
+fn unit_weight() -> u64 {
+    1
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonNode {
+    id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    x: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    y: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonEdge {
+    from: String,
+    to: String,
+    #[serde(default = "unit_weight")]
+    weight: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonGraph {
+    #[serde(default = "directed")]
+    directed: bool,
+    #[serde(default)]
+    nodes: Vec<JsonNode>,
+    #[serde(default)]
+    edges: Vec<JsonEdge>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cost: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stats: Option<SearchStats>,
+}
+
+/// Reads a graph in the JSON schema above.
+pub fn parse_json(text: &str) -> Result<NamedGraph, ParseError> {
+    let json: JsonGraph = serde_json::from_str(text)
+        .map_err(|err| ParseError::new(err.line(), format!("{}", err)))?;
+    let mut named = NamedGraph::new();
+    for node in &json.nodes {
+        let index = named.add_node(&node.id);
+        named.set_position(index, node.x.and_then(|x| node.y.map(|y| (x, y))));
+    }
+    for edge in &json.edges {
+        named.add_edge(&edge.from, &edge.to, edge.weight);
+        if !json.directed && edge.from != edge.to {
+            named.add_edge(&edge.to, &edge.from, edge.weight);
+        }
+    }
+    Ok(named)
+}
+
+/// Writes `named` as a directed graph in the JSON schema above, annotated
+/// with a search result.
+pub fn to_json(named: &NamedGraph, annotations: &Annotations) -> String {
+    let graph = named.graph();
+    let nodes = (0..graph.node_count()).map(|node| {
+        let position = named.position(node);
+        JsonNode { id: named.name(node).to_owned(), x: position.map(|p| p.0), y: position.map(|p| p.1) }
+    });
+    let edges = (0..graph.node_count()).flat_map(|node| graph.edges(node).iter().map(move |edge| (node, edge)))
+        .map(|(node, edge)| JsonEdge {
+            from: named.name(node).to_owned(),
+            to: named.name(edge.to).to_owned(),
+            weight: edge.cost,
+        });
+    let json = JsonGraph {
+        directed: true,
+        nodes: nodes.collect(),
+        edges: edges.collect(),
+        path: annotations.path.as_ref().map(|path| path.iter().map(|&node| named.name(node).to_owned()).collect()),
+        cost: annotations.cost,
+        stats: annotations.stats.clone(),
+    };
+    serde_json::to_string(&json).expect("graphs serialize to JSON")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use astar::AStarBuffers;
+    use search::WeightedSearchSpace;
+    use super::{parse_json, to_json, Annotations};
+
+    #[test]
+    pub fn test_json_graph() {
+        let named = parse_json(r#"{
+            "directed": false,
+            "nodes": [{"id": "depot", "x": 0, "y": 0}, {"id": "mill", "x": 3, "y": 4}, {"id": "port"}],
+            "edges": [{"from": "depot", "to": "mill", "weight": 5}, {"from": "mill", "to": "market"}]
+        }"#).unwrap();
+        assert_eq!(named.names(), ["depot", "mill", "port", "market"]);
+        assert_eq!(named.position(1), Some((3.0, 4.0)));
+        assert_eq!(named.position(2), None);
+        assert_eq!(named.graph().edge_count(), 4);
+
+        let mut buffers = AStarBuffers::new();
+        let (actions, cost) = named.graph().astar_with(&mut buffers, 3, 0, |_: &usize| 0).unwrap();
+        let annotations = Annotations {
+            path: Some(named.graph().walk(3, &actions)),
+            cost: Some(cost),
+            stats: None,
+        };
+        let json = to_json(&named, &annotations);
+        assert!(json.starts_with(r#"{"directed":true,"nodes":[{"id":"depot","x":0.0,"y":0.0},"#));
+        assert!(json.ends_with(r#""path":["market","mill","depot"],"cost":6}"#));
+
+        let stats = Annotations { stats: Some(buffers.stats().clone()), ..Annotations::default() };
+        assert!(to_json(&named, &stats).contains(r#""stats":{"expanded":"#));
+
+        let reloaded = parse_json(&json).unwrap();
+        assert_eq!(reloaded.names(), named.names());
+        assert_eq!(reloaded.graph(), named.graph());
+        assert_eq!(reloaded.position(1), named.position(1));
+
+        let err = parse_json("{\n\"edges\": [{\"from\": \"a\"}]\n}").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(err.message.contains("missing field `to`"));
+    }
+}
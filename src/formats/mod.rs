@@ -0,0 +1,35 @@
+//! Readers for graph file formats.
+
+use alloc::string::String;
+use core::fmt;
+
+pub mod dimacs;
+pub mod dot;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod movingai;
+#[cfg(feature = "graphml")]
+pub mod graphml;
+
+/// Error reading a graph file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    /// Line the error was found on, counting from 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub(crate) fn new<M: Into<String>>(line: usize, message: M) -> ParseError {
+        ParseError { line, message: message.into() }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
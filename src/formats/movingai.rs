@@ -0,0 +1,158 @@
+//! Readers for the MovingAI grid pathfinding benchmarks: `.map` files and
+//! the `.scen` scenario files listing queries on them.
+//!
+//! Benchmark maps are eight-connected with octile costs, so scenarios are
+//! run on an `Octile` view of the map.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
+use core::time::Duration;
+
+use astar::AStarBuffers;
+use grid::{octile, Cell, Grid, Octile};
+use search::WeightedSearchSpace;
+use super::ParseError;
+
+/// One query of a scenario file.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Scenario {
+    pub bucket: u32,
+    /// Map file the query runs on.
+    pub map: String,
+    pub width: usize,
+    pub height: usize,
+    pub start: Cell,
+    pub goal: Cell,
+    /// Cost of an optimal octile path.
+    pub optimal: f64,
+}
+
+/// Outcome of running a scenario.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ScenarioRun {
+    /// Cost of the path found, if any.
+    pub cost: Option<f64>,
+    pub optimal: f64,
+    pub expanded: u64,
+    pub elapsed: Duration,
+}
+
+impl ScenarioRun {
+    /// Found cost over optimal cost, 1 for an optimal path.
+    pub fn suboptimality(&self) -> Option<f64> {
+        self.cost.map(|cost| if self.optimal > 0.0 { cost / self.optimal } else { 1.0 })
+    }
+}
+
+fn header<T: FromStr>(line: Option<(usize, &str)>, key: &str) -> Result<T, ParseError> {
+    let (number, line) = line.ok_or_else(|| ParseError::new(0, format!("missing `{}` line", key)))?;
+    let mut fields = line.split_whitespace();
+    match (fields.next(), fields.next().map(str::parse)) {
+        (Some(found), Some(Ok(value))) if found == key => Ok(value),
+        _ => Err(ParseError::new(number, format!("expected `{} <value>`", key))),
+    }
+}
+
+/// Reads a `.map` file. `.`, `G` and `S` cells are open; everything else,
+/// including water, is blocked.
+pub fn parse_map(text: &str) -> Result<Grid, ParseError> {
+    let mut lines = text.lines().enumerate().map(|(number, line)| (number + 1, line.trim_end()));
+    let kind: String = header(lines.next(), "type")?;
+    if kind != "octile" {
+        return Err(ParseError::new(1, format!("unsupported map type {:?}", kind)));
+    }
+    let height = header(lines.next(), "height")?;
+    let width = header(lines.next(), "width")?;
+    match lines.next() {
+        Some((_, "map")) => {}
+        line => return Err(ParseError::new(line.map_or(4, |line| line.0), "expected `map`")),
+    }
+    let mut grid = Grid::new(width, height);
+    for y in 0..height {
+        let (number, row) = lines.next().ok_or_else(|| ParseError::new(5 + y, "missing map rows"))?;
+        if row.chars().count() != width {
+            return Err(ParseError::new(number, format!("row is not {} cells wide", width)));
+        }
+        for (x, c) in row.chars().enumerate() {
+            grid.set_blocked((x, y), !matches!(c, '.' | 'G' | 'S'));
+        }
+    }
+    Ok(grid)
+}
+
+/// Reads a `.scen` file of version 1.
+pub fn parse_scenarios(text: &str) -> Result<Vec<Scenario>, ParseError> {
+    let mut lines = text.lines().enumerate().map(|(number, line)| (number + 1, line.trim()));
+    let version: String = header(lines.next(), "version")?;
+    if version != "1" && version != "1.0" {
+        return Err(ParseError::new(1, format!("unsupported scenario version {:?}", version)));
+    }
+    let mut scenarios = Vec::new();
+    for (number, line) in lines.filter(|(_, line)| !line.is_empty()) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let fields = if fields.len() == 9 { fields } else { line.split_whitespace().collect() };
+        if fields.len() != 9 {
+            return Err(ParseError::new(number, "expected 9 fields"));
+        }
+        let number_at = |index: usize| -> Result<usize, ParseError> {
+            fields[index].parse().map_err(|_| ParseError::new(number, format!("bad number {:?}", fields[index])))
+        };
+        scenarios.push(Scenario {
+            bucket: number_at(0)? as u32,
+            map: fields[1].to_owned(),
+            width: number_at(2)?,
+            height: number_at(3)?,
+            start: (number_at(4)?, number_at(5)?),
+            goal: (number_at(6)?, number_at(7)?),
+            optimal: fields[8].parse().map_err(|_| ParseError::new(number, format!("bad cost {:?}", fields[8])))?,
+        });
+    }
+    Ok(scenarios)
+}
+
+/// Runs `scenario` on `map` with octile A*.
+pub fn run_scenario(map: &Grid, scenario: &Scenario) -> ScenarioRun {
+    run_scenario_with(&mut AStarBuffers::new(), map, scenario)
+}
+
+/// Like `run_scenario`, reusing `buffers` between runs.
+pub fn run_scenario_with<'a>(buffers: &mut AStarBuffers<Octile<'a>>, map: &'a Grid, scenario: &Scenario)
+    -> ScenarioRun {
+    let goal = scenario.goal;
+    let found = Octile(map).astar_with(buffers, scenario.start, goal, |cell: &Cell| octile(*cell, goal));
+    ScenarioRun {
+        cost: found.map(|(_, cost)| cost.0),
+        optimal: scenario.optimal,
+        expanded: buffers.stats().expanded,
+        elapsed: buffers.stats().elapsed,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{parse_map, parse_scenarios, run_scenario};
+
+    #[test]
+    pub fn test_movingai() {
+        let map = parse_map("type octile\nheight 3\nwidth 4\nmap\n....\n.@T.\nG..W\n").unwrap();
+        assert_eq!((map.width(), map.height()), (4, 3));
+        assert!(map.is_blocked((1, 1)) && map.is_blocked((3, 2)) && !map.is_blocked((0, 2)));
+
+        let scenarios = parse_scenarios("version 1\n\
+                                         0\tsmall.map\t4\t3\t0\t2\t3\t0\t5.00000000\n\
+                                         1\tsmall.map\t4\t3\t0\t0\t3\t2\t3.0\n").unwrap();
+        assert_eq!(scenarios.len(), 2);
+        assert_eq!((scenarios[0].start, scenarios[0].goal), ((0, 2), (3, 0)));
+
+        let run = run_scenario(&map, &scenarios[0]);
+        assert!((run.suboptimality().unwrap() - 1.0).abs() < 1e-6);
+        assert_eq!(run_scenario(&map, &scenarios[1]).cost, None);
+
+        assert_eq!(parse_map("type octile\nheight 2\nwidth 2\nmap\n..\n.\n").unwrap_err().line, 6);
+        assert_eq!(parse_scenarios("version 1\n0 a.map 1 1 0 0\n").unwrap_err().line, 2);
+    }
+}
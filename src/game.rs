@@ -0,0 +1,282 @@
+//! Two-player zero-sum game search over a search space whose actions are
+//! moves and whose states are positions, alternating the side to move.
+//!
+//! Values are from the point of view of the side to move, as in negamax: a
+//! position's value is the largest of its successors' values negated.
+//! `negamax` is alpha-beta over a transposition table, and `mtdf` finds the
+//! value at the root by zero-window calls to it, each pass reusing what the
+//! table learned in the passes before.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use search::SearchSpace;
+
+/// Value of a position for the side to move, at the search horizon or when
+/// it has no moves left.
+pub trait Evaluation<T> {
+    fn evaluate(&self, state: &T) -> i32;
+}
+
+impl<T, F> Evaluation<T> for F where F: Fn(&T) -> i32 {
+    fn evaluate(&self, state: &T) -> i32 {
+        self(state)
+    }
+}
+
+/// Largest value a search returns; the smallest is its negation, so that
+/// values can always be negated.
+pub const INFINITY: i32 = i32::MAX;
+
+struct Entry<T> {
+    depth: u32,
+    lower: i32,
+    upper: i32,
+    // Canonical state the move last found best leads to, which picks out
+    // the same move in every position sharing the entry, whatever order
+    // their moves come in.
+    best: Option<T>,
+}
+
+/// Bounds on the values of positions searched to a given depth, with the
+/// best move found in each, keyed by canonical state and recorded by the
+/// canonical state it leads to.
+///
+/// Bounds are only used for a search to the same depth they were found at,
+/// so results match a search without the table; a position searched to
+/// another depth only lends its best move to be tried first.
+pub struct TranspositionTable<T> {
+    entries: HashMap<T, Entry<T>>,
+}
+
+impl<T: Hash + Eq> TranspositionTable<T> {
+    pub fn new() -> TranspositionTable<T> {
+        TranspositionTable { entries: HashMap::new() }
+    }
+
+    /// Number of positions stored.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Records that a search to `depth` within (`alpha`, `beta`) returned
+    // `value`, tightening the bounds known for that depth.
+    fn store(&mut self, key: T, depth: u32, alpha: i32, beta: i32, value: i32, best: Option<T>) {
+        let entry = self.entries.entry(key).or_insert(Entry { depth, lower: -INFINITY, upper: INFINITY, best: None });
+        if entry.depth != depth {
+            *entry = Entry { depth, lower: -INFINITY, upper: INFINITY, best: entry.best.take() };
+        }
+        if value < beta {
+            entry.upper = entry.upper.min(value);
+        }
+        if value > alpha {
+            entry.lower = entry.lower.max(value);
+            entry.best = best;
+        }
+    }
+}
+
+impl<T: Hash + Eq> Default for TranspositionTable<T> {
+    fn default() -> TranspositionTable<T> {
+        TranspositionTable::new()
+    }
+}
+
+/// Fail-soft alpha-beta negamax searching `depth` moves ahead of `state`:
+/// returns its value when that lies strictly between `alpha` and `beta`, an
+/// upper bound on it when at most `alpha`, and a lower bound when at least
+/// `beta`. Bounds and best moves go to `table`, and the best move stored
+/// for a position is searched first.
+pub fn negamax<S, E>(search_space: &S, evaluation: &E, table: &mut TranspositionTable<S::State>, state: &S::State,
+                     depth: u32, mut alpha: i32, mut beta: i32) -> i32
+where S: SearchSpace + ?Sized, E: Evaluation<S::State> {
+    let key = search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+    let mut first = None;
+    if let Some(entry) = table.entries.get(&key) {
+        if entry.depth == depth {
+            if entry.lower >= beta || entry.lower == entry.upper {
+                return entry.lower;
+            }
+            if entry.upper <= alpha {
+                return entry.upper;
+            }
+            alpha = alpha.max(entry.lower);
+            beta = beta.min(entry.upper);
+        }
+        first = entry.best.clone();
+    }
+    if depth == 0 {
+        return evaluation.evaluate(state);
+    }
+    let mut successors: Vec<(S::State, S::State)> = search_space.expand(state)
+        .map(|(_, successor)| (search_space.canonicalize(&successor).unwrap_or_else(|| successor.clone()), successor))
+        .collect();
+    if successors.is_empty() {
+        return evaluation.evaluate(state);
+    }
+    if let Some(position) = first.and_then(|first| successors.iter().position(|(canonical, _)| *canonical == first)) {
+        successors.swap(0, position);
+    }
+    let mut value = -INFINITY;
+    let mut best = None;
+    let mut a = alpha;
+    for (canonical, successor) in successors {
+        let score = -negamax(search_space, evaluation, table, &successor, depth - 1, -beta, -a);
+        if score > value {
+            value = score;
+            best = Some(canonical);
+        }
+        a = a.max(score);
+        if a >= beta {
+            break;
+        }
+    }
+    table.store(key, depth, alpha, beta, value, best);
+    value
+}
+
+/// MTD(f): the value of `state` searched `depth` moves ahead, and the best
+/// move there, by zero-window `negamax` calls converging from `guess`. The
+/// closer the guess, such as the value from a search one move shallower,
+/// the fewer calls; each call relies on `table` keeping what the ones
+/// before it found. An alternative to a full-window search at the root.
+pub fn mtdf<S, E>(search_space: &S, evaluation: &E, table: &mut TranspositionTable<S::State>, state: &S::State,
+                  depth: u32, guess: i32) -> (i32, Option<S::Action>)
+where S: SearchSpace + ?Sized, E: Evaluation<S::State> {
+    let mut value = guess.clamp(-INFINITY, INFINITY);
+    let (mut lower, mut upper) = (-INFINITY, INFINITY);
+    while lower < upper {
+        let beta = if value == lower { value + 1 } else { value };
+        value = negamax(search_space, evaluation, table, state, depth, beta - 1, beta);
+        if value < beta {
+            upper = value;
+        } else {
+            lower = value;
+        }
+    }
+    let key = search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+    let best = table.entries.get(&key).and_then(|entry| entry.best.as_ref());
+    let action = best.and_then(|best| search_space.expand(state).find(|(_, successor)| {
+        search_space.canonicalize(successor).as_ref().unwrap_or(successor) == best
+    }));
+    (value, action.map(|(action, _)| action))
+}
+
+/// `mtdf` to each depth up to `max_depth` in turn, guessing the value found
+/// one move shallower, for the table to order moves by the time the deepest
+/// search runs.
+pub fn iterative_mtdf<S, E>(search_space: &S, evaluation: &E, table: &mut TranspositionTable<S::State>,
+                            state: &S::State, max_depth: u32) -> (i32, Option<S::Action>)
+where S: SearchSpace + ?Sized, E: Evaluation<S::State> {
+    let mut found = (evaluation.evaluate(state), None);
+    for depth in 1..=max_depth {
+        found = mtdf(search_space, evaluation, table, state, depth, found.0);
+    }
+    found
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::SearchSpace;
+    use super::{iterative_mtdf, mtdf, negamax, Evaluation, TranspositionTable, INFINITY};
+
+    // Game on layers of 20 positions where every position has three moves
+    // into the next layer, several of them reaching the same positions.
+    struct Layers;
+
+    impl SearchSpace for Layers {
+        type State = (u32, u32);
+        type Action = u32;
+        type Iterator = ::std::vec::IntoIter<(u32, (u32, u32))>;
+
+        fn expand(&self, &(layer, position): &(u32, u32)) -> Self::Iterator {
+            if (position + layer) % 7 == 6 {
+                return Vec::new().into_iter();
+            }
+            (0..3).map(|action| (action, (layer + 1, (position * 7 + action * 5 + layer) % 20))).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    // Game on layers of a ring of 20 positions, moving one or three steps
+    // either way, symmetric under mirroring the ring, which also reverses
+    // the order of the moves.
+    struct Ring;
+
+    impl SearchSpace for Ring {
+        type State = (u32, u32);
+        type Action = u32;
+        type Iterator = ::std::vec::IntoIter<(u32, (u32, u32))>;
+
+        fn expand(&self, &(layer, position): &(u32, u32)) -> Self::Iterator {
+            [1, 3, 17, 19].iter().map(|&step| (step, (layer + 1, (position + step) % 20))).collect::<Vec<_>>().into_iter()
+        }
+
+        fn canonicalize(&self, &(layer, position): &(u32, u32)) -> Option<(u32, u32)> {
+            Some((layer, position.min((20 - position) % 20)))
+        }
+    }
+
+    fn ring_score(&(layer, position): &(u32, u32)) -> i32 {
+        ((layer * 31 + position * position % 20 * 17) % 41) as i32 - 20
+    }
+
+    fn score(&(layer, position): &(u32, u32)) -> i32 {
+        ((layer * 31 + position * 17) % 41) as i32 - 20
+    }
+
+    // Negamax without pruning or a table.
+    fn value(state: &(u32, u32), depth: u32) -> i32 {
+        let successors: Vec<_> = Layers.expand(state).collect();
+        if depth == 0 || successors.is_empty() {
+            return score(state);
+        }
+        successors.iter().map(|(_, successor)| -value(successor, depth - 1)).max().unwrap()
+    }
+
+    #[test]
+    pub fn test_mtdf() {
+        for start in 0..20 {
+            let start = (0, start);
+            for depth in 0..6 {
+                let expected = value(&start, depth);
+                for &guess in &[-INFINITY, -30, 0, 7, 30, INFINITY] {
+                    let mut table = TranspositionTable::new();
+                    let (found, best) = mtdf(&Layers, &score, &mut table, &start, depth, guess);
+                    assert_eq!(found, expected);
+                    match best {
+                        Some(action) => {
+                            let next = Layers.expand(&start).nth(action as usize).unwrap().1;
+                            assert_eq!(-value(&next, depth - 1), expected);
+                        }
+                        None => assert!(depth == 0 || Layers.expand(&start).next().is_none()),
+                    }
+                }
+                let mut table = TranspositionTable::new();
+                assert_eq!(iterative_mtdf(&Layers, &score, &mut table, &start, depth).0, expected);
+                assert_eq!(negamax(&Layers, &score, &mut table, &start, depth, -INFINITY, INFINITY), expected);
+            }
+        }
+        assert_eq!(Evaluation::evaluate(&score, &(0, 0)), -20);
+
+        // Mirrored positions share entries, each finding its own best move.
+        let mut table = TranspositionTable::new();
+        for start in 0..20 {
+            let start = (0, start);
+            let (found, best) = mtdf(&Ring, &ring_score, &mut table, &start, 4, 0);
+            let next = (1, (start.1 + best.unwrap()) % 20);
+            let mut fresh = TranspositionTable::new();
+            assert_eq!(negamax(&Ring, &ring_score, &mut fresh, &next, 3, -INFINITY, INFINITY), -found);
+            assert_eq!(found, negamax(&Ring, &ring_score, &mut fresh, &start, 4, -INFINITY, INFINITY));
+        }
+    }
+}
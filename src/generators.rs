@@ -0,0 +1,137 @@
+//! Random problem instances for tests and benchmarks.
+//!
+//! Every generator draws from a caller-supplied `Rng`, so a seeded generator
+//! such as `rand_chacha::ChaCha8Rng` reproduces the same instance everywhere.
+
+use alloc::vec::Vec;
+
+use rand::Rng;
+
+use graph::Graph;
+use grid::Grid;
+
+fn edge_cost<R: Rng>(max_cost: u64, rng: &mut R) -> u64 {
+    rng.gen_range(1..=max_cost)
+}
+
+/// Digraph where every node gets `degree` edges to uniformly chosen nodes,
+/// with costs in `1..=max_cost`.
+pub fn random_graph<R: Rng>(nodes: usize, degree: usize, max_cost: u64, rng: &mut R) -> Graph {
+    let mut graph = Graph::new(nodes);
+    for from in 0..nodes {
+        for _ in 0..degree {
+            let to = rng.gen_range(0..nodes);
+            graph.add_edge(from, to, edge_cost(max_cost, rng));
+        }
+    }
+    graph
+}
+
+/// Tree rooted at node 0 with edges pointing away from the root; each
+/// further node hangs off a uniformly chosen earlier one.
+pub fn random_tree<R: Rng>(nodes: usize, max_cost: u64, rng: &mut R) -> Graph {
+    let mut graph = Graph::new(nodes);
+    for child in 1..nodes {
+        let parent = rng.gen_range(0..child);
+        graph.add_edge(parent, child, edge_cost(max_cost, rng));
+    }
+    graph
+}
+
+/// Directed acyclic graph where each edge `i -> j` with `i < j` is present
+/// with probability `edge_probability`.
+pub fn random_dag<R: Rng>(nodes: usize, edge_probability: f64, max_cost: u64, rng: &mut R) -> Graph {
+    let mut graph = Graph::new(nodes);
+    for from in 0..nodes {
+        for to in from + 1..nodes {
+            if rng.gen_bool(edge_probability) {
+                graph.add_edge(from, to, edge_cost(max_cost, rng));
+            }
+        }
+    }
+    graph
+}
+
+/// Undirected scale-free graph grown by preferential attachment
+/// (Barabási–Albert): each new node links to `links` distinct existing nodes
+/// chosen with probability proportional to their degree.
+pub fn scale_free<R: Rng>(nodes: usize, links: usize, max_cost: u64, rng: &mut R) -> Graph {
+    assert!(links > 0, "scale-free graphs need at least one link per node");
+    let mut graph = Graph::new(nodes);
+    // Every edge endpoint, so a uniform pick from it is degree-proportional.
+    let mut endpoints = vec![];
+    let seed = (links + 1).min(nodes);
+    for a in 0..seed {
+        for b in 0..a {
+            graph.add_undirected_edge(a, b, edge_cost(max_cost, rng));
+            endpoints.extend_from_slice(&[a, b]);
+        }
+    }
+    for node in seed..nodes {
+        let mut targets: Vec<usize> = Vec::with_capacity(links);
+        while targets.len() < links {
+            let target = endpoints[rng.gen_range(0..endpoints.len())];
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        for target in targets {
+            graph.add_undirected_edge(node, target, edge_cost(max_cost, rng));
+            endpoints.extend_from_slice(&[node, target]);
+        }
+    }
+    graph
+}
+
+/// Grid where each cell is blocked with probability `density`, except the
+/// top-left and bottom-right corners.
+pub fn random_grid<R: Rng>(width: usize, height: usize, density: f64, rng: &mut R) -> Grid {
+    let mut grid = Grid::new(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            grid.set_blocked((x, y), rng.gen_bool(density));
+        }
+    }
+    if width > 0 && height > 0 {
+        grid.set_blocked((0, 0), false);
+        grid.set_blocked((width - 1, height - 1), false);
+    }
+    grid
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use search::SearchSpace;
+    use super::{random_dag, random_graph, random_grid, random_tree, scale_free};
+
+    #[test]
+    pub fn test_generators_seeded() {
+        let graph = random_graph(100, 3, 10, &mut StdRng::seed_from_u64(1));
+        assert_eq!(graph, random_graph(100, 3, 10, &mut StdRng::seed_from_u64(1)));
+        assert_eq!(graph.edge_count(), 300);
+        assert!((0..100).all(|node| graph.edges(node).iter().all(|edge| edge.cost >= 1 && edge.cost <= 10)));
+
+        let grid = random_grid(20, 10, 0.3, &mut StdRng::seed_from_u64(2));
+        assert_eq!(grid, random_grid(20, 10, 0.3, &mut StdRng::seed_from_u64(2)));
+        assert!(!grid.is_blocked((0, 0)) && !grid.is_blocked((19, 9)));
+    }
+
+    #[test]
+    pub fn test_generator_shapes() {
+        let mut rng = StdRng::seed_from_u64(3);
+
+        let tree = random_tree(50, 1, &mut rng);
+        assert_eq!(tree.edge_count(), 49);
+        assert_eq!(tree.dfs_iter(0).count(), 50);
+
+        let dag = random_dag(30, 0.2, 1, &mut rng);
+        assert!((0..30).all(|node| dag.edges(node).iter().all(|edge| edge.to > node)));
+
+        let graph = scale_free(200, 2, 1, &mut rng);
+        assert_eq!(graph.edge_count(), 2 * (3 + 197 * 2));
+        let max_degree = (0..200).map(|node| graph.edges(node).len()).max().unwrap();
+        assert!(max_degree > 10);
+    }
+}
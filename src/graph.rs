@@ -0,0 +1,199 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::{IntoIter, Vec};
+
+use hashbrown::HashMap;
+
+use batch::BatchSearchSpace;
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Outgoing edge of a `Graph` node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Edge {
+    pub to: usize,
+    pub cost: u64,
+}
+
+/// Directed graph with weighted edges, stored as adjacency lists.
+///
+/// Nodes are numbered from zero. As a search space a state is a node and an
+/// action is the index of the edge taken in the node's edge list, so parallel
+/// edges stay distinguishable.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Graph {
+    adjacency: Vec<Vec<Edge>>,
+}
+
+impl Graph {
+    /// Graph of `nodes` nodes and no edges.
+    pub fn new(nodes: usize) -> Graph {
+        Graph { adjacency: vec![Vec::new(); nodes] }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adjacency.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum()
+    }
+
+    pub fn add_node(&mut self) -> usize {
+        self.adjacency.push(Vec::new());
+        self.adjacency.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, cost: u64) {
+        assert!(to < self.adjacency.len(), "edge target {} out of range", to);
+        self.adjacency[from].push(Edge { to, cost });
+    }
+
+    /// Adds edges in both directions.
+    pub fn add_undirected_edge(&mut self, a: usize, b: usize, cost: u64) {
+        self.add_edge(a, b, cost);
+        self.add_edge(b, a, cost);
+    }
+
+    pub fn edges(&self, node: usize) -> &[Edge] {
+        &self.adjacency[node]
+    }
+
+    /// Nodes visited by following edge indices `actions` from `start`,
+    /// `start` included.
+    pub fn walk(&self, start: usize, actions: &[usize]) -> Vec<usize> {
+        let mut nodes = vec![start];
+        let mut node = start;
+        for &action in actions {
+            node = self.adjacency[node][action].to;
+            nodes.push(node);
+        }
+        nodes
+    }
+}
+
+impl SearchSpace for Graph {
+    type State = usize;
+    type Action = usize;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.adjacency[*state].iter().map(|edge| edge.to).enumerate().collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl WeightedSearchSpace for Graph {
+    type Cost = u64;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.adjacency[*state][*action].cost
+    }
+}
+
+impl BatchSearchSpace for Graph {
+    type State = usize;
+    type Action = usize;
+
+    fn expand_batch(&self, states: &[usize]) -> Vec<Vec<(usize, usize)>> {
+        states.iter().map(|&state| SearchSpace::expand(self, &state).collect()).collect()
+    }
+}
+
+/// `Graph` whose nodes carry unique names, as read from a graph file.
+#[derive(Clone, Debug, Default)]
+pub struct NamedGraph {
+    graph: Graph,
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    positions: Vec<Option<(f64, f64)>>,
+}
+
+impl NamedGraph {
+    pub fn new() -> NamedGraph {
+        NamedGraph::default()
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &self.graph
+    }
+
+    pub fn into_graph(self) -> Graph {
+        self.graph
+    }
+
+    /// Node called `name`, if there is one.
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.index.get(name).cloned()
+    }
+
+    pub fn name(&self, node: usize) -> &str {
+        &self.names[node]
+    }
+
+    /// Node names indexed by node.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Coordinates of `node`, if the file gave any.
+    pub fn position(&self, node: usize) -> Option<(f64, f64)> {
+        self.positions[node]
+    }
+
+    pub fn set_position(&mut self, node: usize, position: Option<(f64, f64)>) {
+        self.positions[node] = position;
+    }
+
+    /// Node called `name`, added if there is none yet.
+    pub fn add_node(&mut self, name: &str) -> usize {
+        if let Some(node) = self.node(name) {
+            return node;
+        }
+        let node = self.graph.add_node();
+        self.names.push(name.to_owned());
+        self.index.insert(name.to_owned(), node);
+        self.positions.push(None);
+        node
+    }
+
+    /// Adds an edge between two named nodes, adding the nodes as needed.
+    pub fn add_edge(&mut self, from: &str, to: &str, cost: u64) {
+        let (from, to) = (self.add_node(from), self.add_node(to));
+        self.graph.add_edge(from, to, cost);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{Graph, NamedGraph};
+
+    #[test]
+    pub fn test_graph() {
+        let mut graph = Graph::new(3);
+        graph.add_edge(0, 2, 5);
+        graph.add_edge(0, 1, 1);
+        graph.add_undirected_edge(1, 2, 1);
+        assert_eq!((graph.node_count(), graph.edge_count()), (3, 4));
+
+        let (actions, cost) = graph.astar(0, 2, |_: &usize| 0).unwrap();
+        assert_eq!((graph.walk(0, &actions), cost), (vec![0, 1, 2], 2));
+        assert_eq!(graph.dfs(0, 2), Some(vec![0]));
+        assert_eq!(graph.dfs(2, 0), None);
+    }
+
+    #[test]
+    pub fn test_named_graph() {
+        let mut named = NamedGraph::new();
+        named.add_edge("a", "b", 2);
+        named.add_edge("b", "a", 3);
+        assert_eq!(named.add_node("c"), 2);
+        assert_eq!((named.node("b"), named.node("d")), (Some(1), None));
+        assert_eq!(named.name(0), "a");
+        named.set_position(2, Some((1.0, 2.5)));
+        assert_eq!((named.position(0), named.position(2)), (None, Some((1.0, 2.5))));
+        assert_eq!(named.graph().edge_count(), 2);
+        assert_eq!(named.graph().astar(1, 0, |_: &usize| 0), Some((vec![0], 3)));
+    }
+}
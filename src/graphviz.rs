@@ -0,0 +1,246 @@
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Debug, Display, Write};
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use observer::{NodeCost, Observer};
+
+/// Cost information that can be shown on a rendered node.
+pub trait CostLabel {
+    fn label(&self) -> Option<String>;
+}
+
+impl CostLabel for () {
+    fn label(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<C: Display> CostLabel for NodeCost<C> {
+    fn label(&self) -> Option<String> {
+        Some(self.to_string())
+    }
+}
+
+struct TreeNode<S, C> {
+    state: S,
+    parent: Option<usize>,
+    cost: Option<C>,
+    expanded: bool,
+    duplicates: u32,
+}
+
+/// Observer recording the part of the state space a search explored, for
+/// rendering as a Graphviz DOT digraph.
+///
+/// Each state keeps the parent it was last reached from, so for cost-based
+/// searches the tree reflects the cheapest known paths. Once a goal is
+/// reached the path to it is highlighted.
+pub struct SearchTree<S, C = ()> {
+    nodes: Vec<TreeNode<S, C>>,
+    index: HashMap<S, usize>,
+    goal: Option<usize>,
+}
+
+impl<S: Hash + Eq + Clone, C: Clone> SearchTree<S, C> {
+    pub fn new() -> SearchTree<S, C> {
+        SearchTree {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            goal: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// States from the root to the goal, if a goal was reached.
+    pub fn goal_path(&self) -> Option<Vec<&S>> {
+        self.goal.map(|goal| {
+            let mut path: Vec<_> = self.ancestry(goal).map(|index| &self.nodes[index].state).collect();
+            path.reverse();
+            path
+        })
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.index.clear();
+        self.goal = None;
+    }
+
+    fn ancestry(&self, index: usize) -> impl Iterator<Item=usize> + '_ {
+        let mut next = Some(index);
+        let mut steps = 0;
+        ::core::iter::from_fn(move || {
+            let index = next?;
+            steps += 1;
+            next = if steps > self.nodes.len() { None } else { self.nodes[index].parent };
+            Some(index)
+        })
+    }
+
+    fn node(&mut self, state: &S) -> usize {
+        if let Some(&index) = self.index.get(state) {
+            return index;
+        }
+        self.index.insert(state.clone(), self.nodes.len());
+        self.nodes.push(TreeNode { state: state.clone(), parent: None, cost: None, expanded: false, duplicates: 0 });
+        self.nodes.len() - 1
+    }
+}
+
+impl<S: Hash + Eq + Clone + Debug, C: Clone + CostLabel> SearchTree<S, C> {
+    /// Renders the recorded tree as a DOT digraph.
+    ///
+    /// Expanded states are drawn solid and states that were only generated
+    /// dashed; the goal path is drawn in red.
+    pub fn to_dot(&self) -> String {
+        let on_path: Vec<usize> = self.goal.map(|goal| self.ancestry(goal).collect()).unwrap_or_default();
+        let mut dot = String::from("digraph search {\n");
+        for (index, node) in self.nodes.iter().enumerate() {
+            let mut label = escape(&format!("{:?}", node.state));
+            if let Some(cost) = node.cost.as_ref().and_then(CostLabel::label) {
+                label.push_str("\\n");
+                label.push_str(&escape(&cost));
+            }
+            if node.duplicates > 0 {
+                let _ = write!(label, "\\n+{} dup", node.duplicates);
+            }
+            let mut attrs = vec![format!("label=\"{}\"", label)];
+            if !node.expanded {
+                attrs.push("style=dashed".to_owned());
+            }
+            if Some(index) == self.goal {
+                attrs.push("shape=doublecircle".to_owned());
+            }
+            if on_path.contains(&index) {
+                attrs.push("color=red".to_owned());
+            }
+            let _ = writeln!(dot, "    n{} [{}];", index, attrs.join(", "));
+        }
+        for (index, node) in self.nodes.iter().enumerate() {
+            if let Some(parent) = node.parent {
+                let style = if on_path.contains(&index) { " [color=red, penwidth=2]" } else { "" };
+                let _ = writeln!(dot, "    n{} -> n{}{};", parent, index, style);
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<S: Hash + Eq + Clone, C: Clone> Default for SearchTree<S, C> {
+    fn default() -> SearchTree<S, C> {
+        SearchTree::new()
+    }
+}
+
+impl<S: Hash + Eq + Clone + Debug, C: Clone + CostLabel> fmt::Display for SearchTree<S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.to_dot())
+    }
+}
+
+impl<S: Hash + Eq + Clone, A, C: Clone> Observer<S, A, C> for SearchTree<S, C> {
+    fn on_expand(&mut self, state: &S, _depth: usize, cost: &C) {
+        let index = self.node(state);
+        let node = &mut self.nodes[index];
+        node.expanded = true;
+        node.cost.get_or_insert_with(|| cost.clone());
+    }
+
+    fn on_generate(&mut self, parent: &S, _action: &A, state: &S, _depth: usize, cost: &C) {
+        let parent = self.node(parent);
+        let index = self.node(state);
+        let node = &mut self.nodes[index];
+        node.parent = Some(parent);
+        node.cost = Some(cost.clone());
+    }
+
+    fn on_duplicate(&mut self, state: &S, _depth: usize) {
+        if let Some(&index) = self.index.get(state) {
+            self.nodes[index].duplicates += 1;
+        }
+    }
+
+    fn on_goal(&mut self, state: &S, _depth: usize, cost: &C) {
+        let index = self.node(state);
+        self.nodes[index].cost.get_or_insert_with(|| cost.clone());
+        self.goal = Some(index);
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use astar::AStarBuffers;
+    use observer::NodeCost;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::SearchTree;
+
+    struct Diamond;
+
+    impl SearchSpace for Diamond {
+        type State = u8;
+        type Action = u8;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![(1, 1), (2, 2)],
+                1 | 2 => vec![(3, 3)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Diamond {
+        type Cost = u32;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            (*state + *action) as u32
+        }
+    }
+
+    #[test]
+    pub fn test_search_tree_dfs() {
+        let mut tree = SearchTree::new();
+        Diamond.dfs_with(&mut SearchBuffers::new().with_observer(&mut tree), 0, 2);
+        assert_eq!(tree.goal_path(), Some(vec![&0, &2]));
+        assert_eq!(tree.to_dot(), "digraph search {
+    n0 [label=\"0\", color=red];
+    n1 [label=\"1\"];
+    n2 [label=\"3\"];
+    n3 [label=\"2\", style=dashed, shape=doublecircle, color=red];
+    n0 -> n1;
+    n1 -> n2;
+    n0 -> n3 [color=red, penwidth=2];
+}
+");
+    }
+
+    #[test]
+    pub fn test_search_tree_astar() {
+        let mut buffers = AStarBuffers::new().with_observer(SearchTree::<u8, NodeCost<u32>>::new());
+        Diamond.astar_with(&mut buffers, 0, 3, |_: &u8| 0);
+        let tree = buffers.into_observer();
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.goal_path(), Some(vec![&0, &1, &3]));
+        let dot = tree.to_dot();
+        assert!(dot.contains("n3 [label=\"3\\ng=5 f=5\\n+1 dup\", style=dashed, shape=doublecircle, color=red];"));
+        assert!(dot.contains("n2 [label=\"2\\ng=2 f=2\"];"));
+    }
+}
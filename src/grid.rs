@@ -0,0 +1,218 @@
+use alloc::vec::{IntoIter, Vec};
+use core::f64::consts::SQRT_2;
+
+use cost::FloatCost;
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Grid cell as `(x, y)`, with `y` growing southwards.
+pub type Cell = (usize, usize);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::North, Direction::East, Direction::South, Direction::West];
+
+    /// Cell one step from `cell` in this direction, if not off the
+    /// top or left edge.
+    pub fn step(self, (x, y): Cell) -> Option<Cell> {
+        match self {
+            Direction::North => y.checked_sub(1).map(|y| (x, y)),
+            Direction::East => Some((x + 1, y)),
+            Direction::South => Some((x, y + 1)),
+            Direction::West => x.checked_sub(1).map(|x| (x, y)),
+        }
+    }
+}
+
+/// Move on an eight-connected grid.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Heading {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 8] = [Heading::North, Heading::NorthEast, Heading::East, Heading::SouthEast,
+                                   Heading::South, Heading::SouthWest, Heading::West, Heading::NorthWest];
+
+    /// The one or, for diagonals, two directions making up this move.
+    pub fn directions(self) -> (Direction, Option<Direction>) {
+        match self {
+            Heading::North => (Direction::North, None),
+            Heading::NorthEast => (Direction::North, Some(Direction::East)),
+            Heading::East => (Direction::East, None),
+            Heading::SouthEast => (Direction::South, Some(Direction::East)),
+            Heading::South => (Direction::South, None),
+            Heading::SouthWest => (Direction::South, Some(Direction::West)),
+            Heading::West => (Direction::West, None),
+            Heading::NorthWest => (Direction::North, Some(Direction::West)),
+        }
+    }
+
+    pub fn is_diagonal(self) -> bool {
+        self.directions().1.is_some()
+    }
+
+    /// Cell one move from `cell` in this heading, if not off the top or
+    /// left edge.
+    pub fn step(self, cell: Cell) -> Option<Cell> {
+        match self.directions() {
+            (first, Some(second)) => first.step(cell).and_then(|cell| second.step(cell)),
+            (first, None) => first.step(cell),
+        }
+    }
+}
+
+/// Four-connected grid of open and blocked cells with unit move costs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    blocked: Vec<bool>,
+}
+
+impl Grid {
+    /// Grid with every cell open.
+    pub fn new(width: usize, height: usize) -> Grid {
+        Grid { width, height, blocked: vec![false; width * height] }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): Cell) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Whether `cell` is blocked; cells outside the grid count as blocked.
+    pub fn is_blocked(&self, cell: Cell) -> bool {
+        !self.contains(cell) || self.blocked[cell.1 * self.width + cell.0]
+    }
+
+    pub fn set_blocked(&mut self, cell: Cell, blocked: bool) {
+        assert!(self.contains(cell), "cell {:?} outside the grid", cell);
+        self.blocked[cell.1 * self.width + cell.0] = blocked;
+    }
+}
+
+/// Manhattan distance, an exact heuristic on an empty four-connected grid.
+pub fn manhattan(a: Cell, b: Cell) -> u64 {
+    (a.0.abs_diff(b.0) + a.1.abs_diff(b.1)) as u64
+}
+
+/// Octile distance, an exact heuristic on an empty `Octile` grid.
+pub fn octile(a: Cell, b: Cell) -> FloatCost<f64> {
+    let (dx, dy) = (a.0.abs_diff(b.0) as f64, a.1.abs_diff(b.1) as f64);
+    FloatCost(dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy))
+}
+
+/// Eight-connected view of a grid, where straight moves cost 1 and diagonal
+/// ones √2. A diagonal move may not cut the corner of a blocked cell.
+#[derive(Clone, Copy, Debug)]
+pub struct Octile<'a>(pub &'a Grid);
+
+impl<'a> SearchSpace for Octile<'a> {
+    type State = Cell;
+    type Action = Heading;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        let open = |direction: Direction| direction.step(*state).is_some_and(|cell| !self.0.is_blocked(cell));
+        Heading::ALL.iter()
+            .filter(|heading| match heading.directions() {
+                (first, Some(second)) => open(first) && open(second),
+                _ => true,
+            })
+            .filter_map(|&heading| heading.step(*state).map(|cell| (heading, cell)))
+            .filter(|&(_, cell)| !self.0.is_blocked(cell))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a> WeightedSearchSpace for Octile<'a> {
+    type Cost = FloatCost<f64>;
+
+    fn cost(&self, _state: &Self::State, action: &Self::Action) -> Self::Cost {
+        FloatCost(if action.is_diagonal() { SQRT_2 } else { 1.0 })
+    }
+}
+
+impl SearchSpace for Grid {
+    type State = Cell;
+    type Action = Direction;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        Direction::ALL.iter()
+            .filter_map(|&direction| direction.step(*state).map(|cell| (direction, cell)))
+            .filter(|&(_, cell)| !self.is_blocked(cell))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl WeightedSearchSpace for Grid {
+    type Cost = u64;
+
+    fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use search::WeightedSearchSpace;
+    use cost::FloatCost;
+    use super::{manhattan, octile, Direction, Grid, Heading, Octile};
+
+    #[test]
+    pub fn test_grid() {
+        let mut grid = Grid::new(3, 3);
+        grid.set_blocked((1, 0), true);
+        grid.set_blocked((1, 1), true);
+        assert!(grid.is_blocked((3, 0)));
+
+        let goal = (2, 0);
+        let (actions, cost) = grid.astar((0, 0), goal, |cell: &_| manhattan(*cell, goal)).unwrap();
+        assert_eq!(cost, 6);
+        assert_eq!(actions, vec![Direction::South, Direction::South, Direction::East, Direction::East,
+                                 Direction::North, Direction::North]);
+
+        grid.set_blocked((1, 2), true);
+        assert_eq!(grid.astar((0, 0), goal, |cell: &_| manhattan(*cell, goal)), None);
+    }
+
+    #[test]
+    pub fn test_octile() {
+        let mut grid = Grid::new(3, 3);
+        let (actions, cost) = Octile(&grid).astar((0, 0), (2, 1), |cell: &_| octile(*cell, (2, 1))).unwrap();
+        assert_eq!(actions.len(), 2);
+        assert_eq!(cost, octile((0, 0), (2, 1)));
+
+        grid.set_blocked((1, 0), true);
+        let (actions, cost) = Octile(&grid).astar((0, 0), (2, 0), |cell: &_| octile(*cell, (2, 0))).unwrap();
+        assert_eq!(actions, vec![Heading::South, Heading::East, Heading::East, Heading::North]);
+        assert_eq!(cost, FloatCost(4.0));
+    }
+}
@@ -0,0 +1,288 @@
+use alloc::vec::Vec;
+use core::cell::{Ref, RefCell};
+use core::hash::Hash;
+use core::ops::Add;
+
+use hashbrown::{HashMap, HashSet};
+
+use cost::Scale;
+use search::{SearchGoal, WeightedSearchSpace};
+
+/// Estimate of the remaining cost from a state to the goal.
+pub trait Heuristic<T, C> {
+    fn estimate(&self, state: &T) -> C;
+
+    /// Wraps the heuristic so that each state is estimated only once, for
+    /// heuristics expensive enough to be worth a hash lookup.
+    fn cached(self) -> CachedHeuristic<Self, T, C> where Self: Sized, T: Hash + Clone + Eq, C: Clone {
+        CachedHeuristic { heuristic: self, estimates: RefCell::new(HashMap::new()) }
+    }
+
+    /// The larger of this estimate and `other`'s, admissible if both are.
+    fn max_with<H>(self, other: H) -> Max<Self, H> where Self: Sized, H: Heuristic<T, C>, C: Ord {
+        Max(self, other)
+    }
+
+    /// Sum of this estimate and `other`'s. Only admissible when no action
+    /// cost is counted by both, as with pattern databases over disjoint
+    /// sets of tiles that each count only the moves of their own tiles.
+    fn plus_disjoint<H>(self, other: H) -> Sum<Self, H> where Self: Sized, H: Heuristic<T, C>, C: Add<Output=C> {
+        Sum(self, other)
+    }
+
+    /// The estimate times `weight`, which for weights above one trades
+    /// admissibility for fewer expansions.
+    fn weighted(self, weight: f64) -> Weighted<Self> where Self: Sized, C: Scale {
+        Weighted(self, weight)
+    }
+}
+
+/// Largest of two estimates; see `Heuristic::max_with`.
+pub struct Max<A, B>(A, B);
+
+impl<T, C: Ord, A: Heuristic<T, C>, B: Heuristic<T, C>> Heuristic<T, C> for Max<A, B> {
+    fn estimate(&self, state: &T) -> C {
+        self.0.estimate(state).max(self.1.estimate(state))
+    }
+}
+
+/// Sum of two estimates; see `Heuristic::plus_disjoint`.
+pub struct Sum<A, B>(A, B);
+
+impl<T, C: Add<Output=C>, A: Heuristic<T, C>, B: Heuristic<T, C>> Heuristic<T, C> for Sum<A, B> {
+    fn estimate(&self, state: &T) -> C {
+        self.0.estimate(state) + self.1.estimate(state)
+    }
+}
+
+/// Scaled estimate; see `Heuristic::weighted`.
+pub struct Weighted<H>(H, f64);
+
+impl<T, C: Scale, H: Heuristic<T, C>> Heuristic<T, C> for Weighted<H> {
+    fn estimate(&self, state: &T) -> C {
+        self.0.estimate(state).scale(self.1)
+    }
+}
+
+/// Largest estimate of any of `heuristics`, for several of one type, such as
+/// pattern databases over different patterns. With none it estimates the
+/// default cost.
+pub struct MaxOf<H>(pub Vec<H>);
+
+impl<T, C: Ord + Default, H: Heuristic<T, C>> Heuristic<T, C> for MaxOf<H> {
+    fn estimate(&self, state: &T) -> C {
+        self.0.iter().map(|heuristic| heuristic.estimate(state)).max().unwrap_or_default()
+    }
+}
+
+impl<T, C, F> Heuristic<T, C> for F where F: Fn(&T) -> C {
+    fn estimate(&self, state: &T) -> C {
+        self(state)
+    }
+}
+
+/// Heuristic remembering the estimate of every state it is asked about; see
+/// `Heuristic::cached`.
+pub struct CachedHeuristic<H, T, C> {
+    heuristic: H,
+    estimates: RefCell<HashMap<T, C>>,
+}
+
+impl<H, T, C> CachedHeuristic<H, T, C> where H: Heuristic<T, C>, T: Hash + Clone + Eq, C: Clone {
+    /// The cached heuristic, to pass to a search while keeping the cache
+    /// for the next one.
+    pub fn heuristic(&self) -> impl Fn(&T) -> C + '_ {
+        move |state| self.estimate(state)
+    }
+
+    /// Number of states estimated so far.
+    pub fn len(&self) -> usize {
+        self.estimates.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.estimates.borrow().is_empty()
+    }
+
+    /// Forgets all estimates, as when the goal changes.
+    pub fn clear(&self) {
+        self.estimates.borrow_mut().clear()
+    }
+
+    pub fn into_inner(self) -> H {
+        self.heuristic
+    }
+}
+
+impl<H, T, C> Heuristic<T, C> for CachedHeuristic<H, T, C>
+where H: Heuristic<T, C>, T: Hash + Clone + Eq, C: Clone {
+    fn estimate(&self, state: &T) -> C {
+        if let Some(estimate) = self.estimates.borrow().get(state) {
+            return estimate.clone();
+        }
+        let estimate = self.heuristic.estimate(state);
+        self.estimates.borrow_mut().insert(state.clone(), estimate.clone());
+        estimate
+    }
+}
+
+/// A heuristic estimate that broke consistency or admissibility.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HeuristicViolation<S, C> {
+    /// `h(state) > cost + h(successor)` for an action leading to `successor`.
+    Inconsistent { state: S, successor: S, h: C, cost: C, successor_h: C },
+    /// `h(state)` exceeds the exact cost `exact` of reaching the goal.
+    Inadmissible { state: S, h: C, exact: C },
+}
+
+/// Debugging wrapper checking a heuristic while a search uses it.
+///
+/// Every state the heuristic is asked about is expanded once more and its
+/// estimate compared against each successor's, which roughly doubles the
+/// cost of the search.
+pub struct HeuristicChecker<'a, S: WeightedSearchSpace + ?Sized + 'a, H> {
+    search_space: &'a S,
+    heuristic: H,
+    checked: RefCell<HashSet<S::State>>,
+    violations: RefCell<Vec<HeuristicViolation<S::State, S::Cost>>>,
+}
+
+impl<'a, S: WeightedSearchSpace + ?Sized, H: Heuristic<S::State, S::Cost>> HeuristicChecker<'a, S, H> {
+    pub fn new(search_space: &'a S, heuristic: H) -> HeuristicChecker<'a, S, H> {
+        HeuristicChecker {
+            search_space,
+            heuristic,
+            checked: RefCell::new(HashSet::new()),
+            violations: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The checked heuristic, to pass to a search.
+    pub fn heuristic(&self) -> impl Fn(&S::State) -> S::Cost + '_ {
+        move |state| self.estimate(state)
+    }
+
+    pub fn estimate(&self, state: &S::State) -> S::Cost {
+        let h = self.heuristic.estimate(state);
+        if self.checked.borrow_mut().insert(state.clone()) {
+            for (action, successor) in self.search_space.expand(state) {
+                let cost = self.search_space.step_cost(state, &action, &successor);
+                let successor_h = self.heuristic.estimate(&successor);
+                if h > cost + successor_h {
+                    self.violations.borrow_mut().push(HeuristicViolation::Inconsistent {
+                        state: state.clone(), successor, h, cost, successor_h,
+                    });
+                }
+            }
+        }
+        h
+    }
+
+    /// Compares the estimate of every state seen so far with its exact
+    /// distance to `goal`, found by uniform-cost search. Only practical on
+    /// small instances.
+    pub fn check_admissible<G: SearchGoal<S::State> + Clone>(&self, goal: G) {
+        let states: Vec<_> = self.checked.borrow().iter().cloned().collect();
+        for state in states {
+            let h = self.heuristic.estimate(&state);
+            if let Some((_, exact)) = self.search_space.astar(state.clone(), goal.clone(), |_: &S::State| S::Cost::default()) {
+                if h > exact {
+                    self.violations.borrow_mut().push(HeuristicViolation::Inadmissible { state, h, exact });
+                }
+            }
+        }
+    }
+
+    pub fn violations(&self) -> Ref<'_, Vec<HeuristicViolation<S::State, S::Cost>>> {
+        self.violations.borrow()
+    }
+
+    pub fn into_violations(self) -> Vec<HeuristicViolation<S::State, S::Cost>> {
+        self.violations.into_inner()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::Cell;
+    use std::vec::IntoIter;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{Heuristic, HeuristicChecker, HeuristicViolation, MaxOf};
+
+    // Path 0 -> 1 -> 2 -> 3 with unit costs, plus a shortcut 0 -> 3 costing 5.
+    struct Line;
+
+    impl SearchSpace for Line {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![(1, 1), (3, 3)],
+                1 | 2 => vec![(*state + 1, *state + 1)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Line {
+        type Cost = u32;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            if *state == 0 && *action == 3 { 5 } else { 1 }
+        }
+    }
+
+    #[test]
+    pub fn test_heuristic_checker() {
+        let exact = HeuristicChecker::new(&Line, |state: &u32| 3 - *state);
+        assert_eq!(Line.astar(0, 3, exact.heuristic()), Some((vec![1, 2, 3], 3)));
+        exact.check_admissible(3);
+        assert!(exact.violations().is_empty());
+
+        let h = |state: &u32| [3, 2, 4, 0][*state as usize];
+        let checker = HeuristicChecker::new(&Line, h);
+        Line.astar(0, 3, checker.heuristic());
+        checker.check_admissible(3);
+        let violations = checker.into_violations();
+        assert!(violations.contains(&HeuristicViolation::Inconsistent { state: 2, successor: 3, h: 4, cost: 1, successor_h: 0 }));
+        assert!(violations.contains(&HeuristicViolation::Inadmissible { state: 2, h: 4, exact: 1 }));
+        assert!(!violations.iter().any(|violation| match *violation {
+            HeuristicViolation::Inconsistent { state, .. } | HeuristicViolation::Inadmissible { state, .. } => state == 0,
+        }));
+    }
+
+    #[test]
+    pub fn test_cached_heuristic() {
+        let calls = Cell::new(0);
+        let cached = (|state: &u32| {
+            calls.set(calls.get() + 1);
+            3 - state
+        }).cached();
+        assert_eq!(Line.astar(0, 3, cached.heuristic()), Some((vec![1, 2, 3], 3)));
+        let first = calls.get();
+        assert_eq!((cached.len(), first), (4, 4));
+        assert_eq!(Line.astar(0, 3, cached.heuristic()), Some((vec![1, 2, 3], 3)));
+        assert_eq!(calls.get(), first);
+        cached.clear();
+        assert!(cached.is_empty());
+        assert_eq!(cached.estimate(&1), 2);
+        assert_eq!(calls.get(), first + 1);
+    }
+
+    #[test]
+    pub fn test_heuristic_combinators() {
+        // Remaining steps to 3, split into those below 2 and those from 2 on.
+        let below = |state: &u32| 2u32.saturating_sub(*state);
+        let above = |state: &u32| 3 - (*state).max(2);
+        let exact = below.plus_disjoint(above);
+        assert_eq!((0..4).map(|state| exact.estimate(&state)).collect::<Vec<_>>(), vec![3, 2, 1, 0]);
+        assert_eq!(below.max_with(above).estimate(&0), 2);
+        assert_eq!(above.weighted(2.5).estimate(&0), 2);
+        assert_eq!(MaxOf(vec![below, below]).estimate(&1), 1);
+        assert_eq!(MaxOf(Vec::<fn(&u32) -> u32>::new()).estimate(&1), 0);
+        assert_eq!(Line.astar(0, 3, below.max_with(above).weighted(1.0)), Some((vec![1, 2, 3], 3)));
+    }
+}
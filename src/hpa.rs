@@ -0,0 +1,294 @@
+//! Hierarchical pathfinding (HPA*) on grids: clusters joined by entrances,
+//! with entrance-to-entrance distances worked out once up front.
+
+use alloc::vec::{IntoIter, Vec};
+
+use hashbrown::HashMap;
+
+use graph::Graph;
+use grid::{manhattan, Cell, Direction, Grid};
+use search::{SearchSpace, WeightedSearchSpace};
+use successors::WeightedSuccessorSpace;
+
+/// Grid restricted to the cells of one rectangle.
+struct ClusterView<'a> {
+    grid: &'a Grid,
+    min: Cell,
+    max: Cell,
+}
+
+impl<'a> ClusterView<'a> {
+    fn contains(&self, (x, y): Cell) -> bool {
+        x >= self.min.0 && x < self.max.0 && y >= self.min.1 && y < self.max.1
+    }
+
+    fn distance(&self, from: Cell, to: Cell) -> Option<(Vec<Direction>, u64)> {
+        self.astar(from, to, |cell: &Cell| manhattan(*cell, to))
+    }
+}
+
+impl<'a> SearchSpace for ClusterView<'a> {
+    type State = Cell;
+    type Action = Direction;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.grid.expand(state).filter(|&(_, cell)| self.contains(cell)).collect::<Vec<_>>().into_iter()
+    }
+}
+
+impl<'a> WeightedSearchSpace for ClusterView<'a> {
+    type Cost = u64;
+
+    fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+        1
+    }
+}
+
+/// Abstract graph of a grid split into square clusters.
+///
+/// Wherever open cells face each other across a cluster border, the border
+/// gets a transition: one in the middle of a short opening, one at each end
+/// of an opening of six cells or more. The cells on either side of a
+/// transition become entrances, joined to each other at cost 1 and to every
+/// entrance of their cluster they reach within the cluster at the cost of
+/// that path. Queries run A* over this graph, so they are fast but may come
+/// out slightly longer than the shortest path.
+pub struct Hierarchy<'a> {
+    grid: &'a Grid,
+    cluster_size: usize,
+    entrances: Vec<Cell>,
+    index: HashMap<Cell, usize>,
+    /// Entrances of each cluster.
+    clusters: Vec<Vec<usize>>,
+    graph: Graph,
+}
+
+impl<'a> Hierarchy<'a> {
+    pub fn new(grid: &'a Grid, cluster_size: usize) -> Hierarchy<'a> {
+        assert!(cluster_size > 0, "clusters must not be empty");
+        let (columns, rows) = (grid.width().div_ceil(cluster_size), grid.height().div_ceil(cluster_size));
+        let mut hierarchy = Hierarchy {
+            grid,
+            cluster_size,
+            entrances: Vec::new(),
+            index: HashMap::new(),
+            clusters: vec![Vec::new(); columns * rows],
+            graph: Graph::new(0),
+        };
+        for row in 0..rows {
+            for column in 0..columns {
+                let (min, max) = hierarchy.bounds(row * columns + column);
+                if max.0 < grid.width() {
+                    let pairs = (min.1..max.1).map(|y| ((max.0 - 1, y), (max.0, y)));
+                    hierarchy.add_transitions(pairs.collect());
+                }
+                if max.1 < grid.height() {
+                    let pairs = (min.0..max.0).map(|x| ((x, max.1 - 1), (x, max.1)));
+                    hierarchy.add_transitions(pairs.collect());
+                }
+            }
+        }
+        for cluster in 0..hierarchy.clusters.len() {
+            let view = hierarchy.view(cluster);
+            let entrances = &hierarchy.clusters[cluster];
+            let mut edges = Vec::new();
+            for (i, &a) in entrances.iter().enumerate() {
+                for &b in &entrances[i + 1..] {
+                    if let Some((_, cost)) = view.distance(hierarchy.entrances[a], hierarchy.entrances[b]) {
+                        edges.push((a, b, cost));
+                    }
+                }
+            }
+            for (a, b, cost) in edges {
+                hierarchy.graph.add_undirected_edge(a, b, cost);
+            }
+        }
+        hierarchy
+    }
+
+    /// Number of entrances, the nodes of the abstract graph.
+    pub fn len(&self) -> usize {
+        self.entrances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entrances.is_empty()
+    }
+
+    pub fn entrances(&self) -> &[Cell] {
+        &self.entrances
+    }
+
+    fn cluster(&self, (x, y): Cell) -> usize {
+        let columns = self.grid.width().div_ceil(self.cluster_size);
+        (y / self.cluster_size) * columns + x / self.cluster_size
+    }
+
+    fn bounds(&self, cluster: usize) -> (Cell, Cell) {
+        let columns = self.grid.width().div_ceil(self.cluster_size);
+        let min = ((cluster % columns) * self.cluster_size, (cluster / columns) * self.cluster_size);
+        let max = ((min.0 + self.cluster_size).min(self.grid.width()),
+                   (min.1 + self.cluster_size).min(self.grid.height()));
+        (min, max)
+    }
+
+    fn view(&self, cluster: usize) -> ClusterView<'a> {
+        let (min, max) = self.bounds(cluster);
+        ClusterView { grid: self.grid, min, max }
+    }
+
+    fn entrance(&mut self, cell: Cell) -> usize {
+        if let Some(&node) = self.index.get(&cell) {
+            return node;
+        }
+        let node = self.graph.add_node();
+        self.entrances.push(cell);
+        self.index.insert(cell, node);
+        let cluster = self.cluster(cell);
+        self.clusters[cluster].push(node);
+        node
+    }
+
+    // Transitions along one border, given as the facing cells on each side.
+    fn add_transitions(&mut self, pairs: Vec<(Cell, Cell)>) {
+        let open = |&(a, b): &(Cell, Cell)| !self.grid.is_blocked(a) && !self.grid.is_blocked(b);
+        let mut openings: Vec<Vec<(Cell, Cell)>> = Vec::new();
+        let mut run = Vec::new();
+        for pair in pairs {
+            if open(&pair) {
+                run.push(pair);
+            } else if !run.is_empty() {
+                openings.push(core::mem::take(&mut run));
+            }
+        }
+        if !run.is_empty() {
+            openings.push(run);
+        }
+        for opening in openings {
+            let chosen = if opening.len() < 6 {
+                vec![opening[opening.len() / 2]]
+            } else {
+                vec![opening[0], opening[opening.len() - 1]]
+            };
+            for (a, b) in chosen {
+                let (a, b) = (self.entrance(a), self.entrance(b));
+                self.graph.add_undirected_edge(a, b, 1);
+            }
+        }
+    }
+
+    /// Abstract path from `start` to `goal`: the cells it passes through,
+    /// start and goal included, where each is a step away from the next or
+    /// in the same cluster, and its cost.
+    pub fn find(&self, start: Cell, goal: Cell) -> Option<(Vec<Cell>, u64)> {
+        if self.grid.is_blocked(start) || self.grid.is_blocked(goal) {
+            return None;
+        }
+        if start == goal {
+            return Some((vec![start], 0));
+        }
+        // The start and goal join the graph as two extra nodes.
+        let (from, to) = (self.len(), self.len() + 1);
+        let (start_view, goal_view) = (self.view(self.cluster(start)), self.view(self.cluster(goal)));
+        let mut links: Vec<(usize, u64)> = self.clusters[self.cluster(start)].iter()
+            .filter_map(|&node| start_view.distance(start, self.entrances[node]).map(|(_, cost)| (node, cost)))
+            .collect();
+        if self.cluster(start) == self.cluster(goal) {
+            links.extend(start_view.distance(start, goal).map(|(_, cost)| (to, cost)));
+        }
+        let goal_links: HashMap<usize, u64> = self.clusters[self.cluster(goal)].iter()
+            .filter_map(|&node| goal_view.distance(self.entrances[node], goal).map(|(_, cost)| (node, cost)))
+            .collect();
+        let space = WeightedSuccessorSpace::new(|&node: &usize| -> Vec<(usize, u64)> {
+            if node == from {
+                return links.clone();
+            }
+            if node == to {
+                return Vec::new();
+            }
+            let mut next: Vec<(usize, u64)> = self.graph.edges(node).iter().map(|edge| (edge.to, edge.cost)).collect();
+            next.extend(goal_links.get(&node).map(|&cost| (to, cost)));
+            next
+        });
+        let cell = |node: usize| if node == from { start } else if node == to { goal } else { self.entrances[node] };
+        let (steps, cost) = space.astar(from, to, |&node: &usize| manhattan(cell(node), goal))?;
+        let mut cells = vec![start];
+        cells.extend(steps.into_iter().map(|(node, _)| cell(node)));
+        Some((cells, cost))
+    }
+
+    /// Expands an abstract path into every cell along it.
+    pub fn refine(&self, waypoints: &[Cell]) -> Vec<Cell> {
+        let mut cells: Vec<Cell> = waypoints.iter().take(1).copied().collect();
+        for pair in waypoints.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            if self.cluster(a) != self.cluster(b) {
+                cells.push(b);
+                continue;
+            }
+            let (directions, _) = self.view(self.cluster(a)).distance(a, b).expect("waypoints are connected");
+            let mut at = a;
+            for direction in directions {
+                at = direction.step(at).expect("paths stay on the grid");
+                cells.push(at);
+            }
+        }
+        cells
+    }
+
+    /// Path from `start` to `goal` through every cell, and its cost.
+    pub fn path(&self, start: Cell, goal: Cell) -> Option<(Vec<Cell>, u64)> {
+        self.find(start, goal).map(|(waypoints, cost)| (self.refine(&waypoints), cost))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Grid};
+    use search::WeightedSearchSpace;
+    use super::Hierarchy;
+
+    #[test]
+    pub fn test_hierarchy() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let grid = random_grid(48, 40, 0.25, &mut rng);
+        let hierarchy = Hierarchy::new(&grid, 8);
+        assert!(!hierarchy.is_empty());
+        let open: Vec<_> = (0..40).flat_map(|y| (0..48).map(move |x| (x, y))).filter(|&cell| !grid.is_blocked(cell))
+            .collect();
+        for _ in 0..30 {
+            let (start, goal) = (open[rng.gen_range(0..open.len())], open[rng.gen_range(0..open.len())]);
+            let optimal = grid.astar(start, goal, |cell: &_| manhattan(*cell, goal)).map(|found| found.1);
+            let found = hierarchy.path(start, goal);
+            assert_eq!(found.is_some(), optimal.is_some(), "{:?} {:?}", start, goal);
+            if let (Some((cells, cost)), Some(optimal)) = (found, optimal) {
+                assert!(cost >= optimal && cost <= optimal + optimal / 4 + 4);
+                assert_eq!(cells.len() as u64, cost + 1);
+                assert_eq!((cells[0], cells[cells.len() - 1]), (start, goal));
+                assert!(cells.windows(2).all(|pair| manhattan(pair[0], pair[1]) == 1));
+                assert!(cells.iter().all(|&cell| !grid.is_blocked(cell)));
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_hierarchy_walls() {
+        // A wall down the middle with a single gap at the bottom.
+        let mut grid = Grid::new(8, 8);
+        for y in 0..7 {
+            grid.set_blocked((4, y), true);
+        }
+        let hierarchy = Hierarchy::new(&grid, 4);
+        assert_eq!(hierarchy.path((3, 0), (3, 0)), Some((vec![(3, 0)], 0)));
+        // The shortest path costs 16, but entrances sit mid-opening.
+        assert_eq!(hierarchy.find((3, 0), (5, 0)).map(|found| found.1), Some(20));
+        assert_eq!(hierarchy.find((1, 1), (2, 2)).map(|found| found.1), Some(2));
+        assert_eq!(hierarchy.find((4, 0), (5, 0)), None);
+        grid.set_blocked((4, 7), true);
+        assert_eq!(Hierarchy::new(&grid, 4).find((3, 0), (5, 0)), None);
+    }
+}
@@ -0,0 +1,171 @@
+//! Iterative deepening A* over weighted search spaces.
+
+use alloc::vec::Vec;
+
+use heuristic::Heuristic;
+use search::{ExpandContext, SearchGoal, WeightedSearchSpace};
+use stats::SearchStats;
+use stopwatch::Stopwatch;
+
+/// Iterative deepening A*: depth-first searches of the paths whose cost so
+/// far plus `heuristic` stays within a bound, starting from the estimate of
+/// the start and raised after each search to the least f that went past it.
+/// Only the current path is kept in memory, so a state reached along many
+/// paths is explored along each of them; `prediction::IdaStarPredictor`
+/// estimates how many nodes an iteration expands.
+///
+/// The first path found is a cheapest as long as `heuristic` never
+/// overestimates. As there is no visited set, an iteration only ends if
+/// actions have positive cost, and the search itself never gives up in a
+/// space with cycles where no goal is reachable; `ida_star_with` takes a
+/// cost bound for that.
+pub fn ida_star<S, G, H>(search_space: &S, start: S::State, goal: G, heuristic: H) -> Option<(Vec<S::Action>, S::Cost)>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost> {
+    ida_star_with(search_space, &mut SearchStats::new(), start, goal, heuristic, None)
+}
+
+/// Like `ida_star`, recording the run into `stats`, with the nodes of every
+/// iteration counted, and giving up once the bound would go past
+/// `cost_bound`.
+pub fn ida_star_with<S, G, H>(search_space: &S, stats: &mut SearchStats, start: S::State, goal: G, heuristic: H,
+                              cost_bound: Option<S::Cost>) -> Option<(Vec<S::Action>, S::Cost)>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost> {
+    let started = Stopwatch::start();
+    stats.clear();
+    let mut iteration = Iteration {
+        search_space,
+        goal: &goal,
+        heuristic: &heuristic,
+        bound: heuristic.estimate(&start),
+        path: Vec::new(),
+        stats,
+    };
+    let found = loop {
+        if cost_bound.is_some_and(|cost_bound| iteration.bound > cost_bound) {
+            break None;
+        }
+        match iteration.probe(&start, S::Cost::default()) {
+            Probe::Found(cost) => break Some((iteration.path, cost)),
+            Probe::Over(Some(next)) => iteration.bound = next,
+            Probe::Over(None) => break None,
+        }
+    };
+    stats.elapsed = started.elapsed();
+    found
+}
+
+// Outcome of a search within the bound: the cost of the goal reached, or the
+// least f that went past the bound, if any did.
+enum Probe<C> {
+    Found(C),
+    Over(Option<C>),
+}
+
+struct Iteration<'a, S: WeightedSearchSpace + ?Sized + 'a, G: 'a, H: 'a> {
+    search_space: &'a S,
+    goal: &'a G,
+    heuristic: &'a H,
+    bound: S::Cost,
+    path: Vec<S::Action>,
+    stats: &'a mut SearchStats,
+}
+
+impl<'a, S, G, H> Iteration<'a, S, G, H>
+where S: WeightedSearchSpace + ?Sized, G: SearchGoal<S::State>, H: Heuristic<S::State, S::Cost> {
+    // Depth-first search from `state`, reached at cost `g`, leaving the path
+    // to the goal in `path` once found.
+    fn probe(&mut self, state: &S::State, g: S::Cost) -> Probe<S::Cost> {
+        let f = g + self.heuristic.estimate(state);
+        if f > self.bound {
+            return Probe::Over(Some(f));
+        }
+        let depth = self.path.len();
+        self.stats.record_depth(depth);
+        if self.goal.is_goal(state) {
+            return Probe::Found(g);
+        }
+        self.stats.record_expanded(depth);
+        let mut over: Option<S::Cost> = None;
+        for (action, successor) in self.search_space.expand_weighted(state, ExpandContext { depth, cost: g }) {
+            self.stats.record_generated(depth + 1);
+            if self.search_space.is_dead_end(&successor) {
+                self.stats.record_dead_end();
+                continue;
+            }
+            let cost = g + self.search_space.step_cost(state, &action, &successor);
+            self.path.push(action);
+            match self.probe(&successor, cost) {
+                Probe::Found(cost) => return Probe::Found(cost),
+                Probe::Over(next) => over = over.into_iter().chain(next).min(),
+            }
+            self.path.pop();
+        }
+        Probe::Over(over)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use stats::SearchStats;
+    use super::{ida_star, ida_star_with};
+
+    // Numbers reached by adding one or two, or by doubling at a cost of
+    // three.
+    struct Numbers;
+
+    impl SearchSpace for Numbers {
+        type State = u32;
+        type Action = char;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &state: &Self::State) -> Self::Iterator {
+            vec![('a', state + 1), ('b', state + 2), ('d', state * 2)].into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Numbers {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, &action: &Self::Action) -> Self::Cost {
+            match action {
+                'a' | 'b' => 1,
+                _ => 3,
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_ida_star() {
+        let zero = |_: &u32| 0;
+        for goal in 1..20 {
+            let found = ida_star(&Numbers, 1, goal, zero);
+            assert_eq!(found.as_ref().map(|found| found.1), Numbers.astar(1, goal, zero).map(|found| found.1));
+            let (actions, _) = found.unwrap();
+            let reached = actions.iter().fold(1, |state, &action| match action {
+                'a' => state + 1,
+                'b' => state + 2,
+                _ => state * 2,
+            });
+            assert_eq!(reached, goal);
+        }
+        assert_eq!(Numbers.ida_star(3, 3, zero), Some((vec![], 0)));
+
+        // Numbers only grow, so smaller ones are out of reach at any bound.
+        let mut stats = SearchStats::new();
+        assert_eq!(ida_star_with(&Numbers, &mut stats, 5, 2, zero, Some(4)), None);
+        assert!(stats.expanded > 0 && stats.max_depth == 4);
+
+        // A tighter heuristic expands fewer nodes.
+        let mut tighter = SearchStats::new();
+        let one = |&state: &u32| match state {
+            20 => 0,
+            0..=19 => 1,
+            _ => 100,
+        };
+        assert_eq!(ida_star_with(&Numbers, &mut stats, 1, 20, zero, None).map(|found| found.1), Some(8));
+        assert_eq!(ida_star_with(&Numbers, &mut tighter, 1, 20, one, None).map(|found| found.1), Some(8));
+        assert!(tighter.expanded < stats.expanded);
+    }
+}
@@ -0,0 +1,388 @@
+//! Landmark (ALT) heuristics: exact distances from a few landmark states,
+//! turned into lower bounds by the triangle inequality.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::hash::Hash;
+use core::ops::{Add, Sub};
+
+use hashbrown::HashMap;
+use rand::Rng;
+
+use astar::AStarBuffers;
+use graph::Graph;
+use search::WeightedSearchSpace;
+
+/// Cheapest-path tree from a root: states in the order they were settled,
+/// with their distance and the index of their parent.
+struct PathTree<T, C> {
+    settled: Vec<(T, C, Option<usize>)>,
+}
+
+impl<T: Hash + Eq + Clone, C: Copy + Ord + Default + Add<Output=C>> PathTree<T, C> {
+    fn grow<S>(search_space: &S, root: T) -> PathTree<T, C> where S: WeightedSearchSpace<State=T, Cost=C> + ?Sized {
+        let mut best: HashMap<T, C> = HashMap::new();
+        let mut found = vec![(root.clone(), C::default(), None)];
+        let mut open = BinaryHeap::new();
+        let mut settled = Vec::new();
+        let mut done: HashMap<T, usize> = HashMap::new();
+        best.insert(root, C::default());
+        open.push(Reverse((C::default(), 0)));
+        while let Some(Reverse((distance, index))) = open.pop() {
+            let (state, _, parent) = found[index].clone();
+            if done.contains_key(&state) || best[&state] < distance {
+                continue;
+            }
+            let parent = parent.map(|parent: usize| done[&found[parent].0]);
+            done.insert(state.clone(), settled.len());
+            settled.push((state.clone(), distance, parent));
+            for (action, successor) in search_space.expand(&state) {
+                let through = distance + search_space.step_cost(&state, &action, &successor);
+                if best.get(&successor).is_none_or(|&known| through < known) {
+                    best.insert(successor.clone(), through);
+                    open.push(Reverse((through, found.len())));
+                    found.push((successor, through, Some(index)));
+                }
+            }
+        }
+        PathTree { settled }
+    }
+}
+
+/// Distances from a set of landmark states, giving the ALT lower bound
+/// `h(s) = max |d(L, s) - d(L, g)|` over landmarks `L`.
+///
+/// The bound holds for spaces whose actions can be undone at the same cost,
+/// so that distances are symmetric, such as undirected road networks.
+/// States a landmark cannot reach are ignored for that landmark.
+#[derive(Clone, Debug, Default)]
+pub struct Landmarks<T: Hash + Eq, C> {
+    landmarks: Vec<T>,
+    distances: Vec<HashMap<T, C>>,
+}
+
+impl<T, C> Landmarks<T, C> where T: Hash + Eq + Clone, C: Copy + Ord + Default + Add<Output=C> + Sub<Output=C> {
+    /// Distances from the given landmarks.
+    pub fn new<S>(search_space: &S, landmarks: Vec<T>) -> Landmarks<T, C>
+    where S: WeightedSearchSpace<State=T, Cost=C> + ?Sized {
+        let mut table = Landmarks { landmarks: Vec::new(), distances: Vec::new() };
+        for landmark in landmarks {
+            table.add(search_space, landmark);
+        }
+        table
+    }
+
+    /// Farthest selection: each landmark is the state farthest from the
+    /// landmarks picked before it, starting from the state farthest from
+    /// `seed`.
+    pub fn farthest<S>(search_space: &S, seed: T, count: usize) -> Landmarks<T, C>
+    where S: WeightedSearchSpace<State=T, Cost=C> + ?Sized {
+        let mut table = Landmarks { landmarks: Vec::new(), distances: Vec::new() };
+        let reached = PathTree::grow(search_space, seed.clone()).settled;
+        while table.landmarks.len() < count {
+            let next = reached.iter()
+                .filter(|(state, _, _)| !table.landmarks.contains(state))
+                .max_by_key(|(state, distance, _)| {
+                    table.distances.iter().map(|distances| distances.get(state).copied().unwrap_or_default())
+                        .min().unwrap_or(*distance)
+                });
+            match next {
+                Some((state, _, _)) => table.add(search_space, state.clone()),
+                None => break,
+            }
+        }
+        table
+    }
+
+    /// Avoid selection: grows a cheapest-path tree from a random root
+    /// reachable from `seed`, weighs each state by how far its current lower
+    /// bound from the root falls short, and picks a leaf of the heaviest
+    /// branch not already holding a landmark, so new landmarks cover what
+    /// the bound handles badly.
+    pub fn avoid<S, R>(search_space: &S, seed: T, count: usize, rng: &mut R) -> Landmarks<T, C>
+    where S: WeightedSearchSpace<State=T, Cost=C> + ?Sized, R: Rng {
+        let mut table = Landmarks { landmarks: Vec::new(), distances: Vec::new() };
+        let reached = PathTree::grow(search_space, seed).settled;
+        // Roots whose every branch already holds a landmark give nothing.
+        let mut attempts = 4 * count;
+        while table.landmarks.len() < count && attempts > 0 {
+            attempts -= 1;
+            let root = reached[rng.gen_range(0..reached.len())].0.clone();
+            let tree = PathTree::grow(search_space, root.clone()).settled;
+            let mut size: Vec<C> = tree.iter()
+                .map(|(state, distance, _)| *distance - table.lower_bound(&root, state))
+                .collect();
+            let mut covered: Vec<bool> = tree.iter().map(|(state, _, _)| table.landmarks.contains(state)).collect();
+            let mut children: Vec<Vec<usize>> = vec![Vec::new(); tree.len()];
+            // Children settle after their parents, so a backward pass sees
+            // every subtree before its root.
+            for index in (1..tree.len()).rev() {
+                let parent = tree[index].2.expect("only the root has no parent");
+                children[parent].push(index);
+                covered[parent] |= covered[index];
+                size[parent] = size[parent] + size[index];
+            }
+            let mut at = 0;
+            while let Some(child) = children[at].iter().copied().filter(|&child| !covered[child])
+                .max_by_key(|&child| size[child]) {
+                at = child;
+            }
+            if !covered[at] {
+                table.add(search_space, tree[at].0.clone());
+            }
+        }
+        table
+    }
+
+    /// Adds `landmark` and its distances.
+    pub fn add<S>(&mut self, search_space: &S, landmark: T) where S: WeightedSearchSpace<State=T, Cost=C> + ?Sized {
+        let tree = PathTree::grow(search_space, landmark.clone());
+        self.distances.push(tree.settled.into_iter().map(|(state, distance, _)| (state, distance)).collect());
+        self.landmarks.push(landmark);
+    }
+
+    pub fn landmarks(&self) -> &[T] {
+        &self.landmarks
+    }
+
+    /// Distance from landmark number `landmark` to `state`.
+    pub fn distance(&self, landmark: usize, state: &T) -> Option<C> {
+        self.distances[landmark].get(state).copied()
+    }
+
+    /// Lower bound on the distance between `from` and `to`.
+    pub fn lower_bound(&self, from: &T, to: &T) -> C {
+        self.distances.iter().filter_map(|distances| {
+            let (a, b) = (*distances.get(from)?, *distances.get(to)?);
+            Some(if a > b { a - b } else { b - a })
+        }).max().unwrap_or_default()
+    }
+
+    /// ALT heuristic for searches towards `goal`.
+    pub fn heuristic<'a>(&'a self, goal: &T) -> impl Fn(&T) -> C + 'a {
+        let to_goal: Vec<Option<C>> = self.distances.iter().map(|distances| distances.get(goal).copied()).collect();
+        move |state| {
+            self.distances.iter().zip(&to_goal).filter_map(|(distances, &b)| {
+                let (a, b) = (*distances.get(state)?, b?);
+                Some(if a > b { a - b } else { b - a })
+            }).max().unwrap_or_default()
+        }
+    }
+}
+
+/// Landmark tables of a directed `Graph`, held per node so that they
+/// serialize, serving A* queries with the ALT heuristic.
+///
+/// Distances are kept both from and to each landmark, so the bound holds
+/// on one-way edges too. When a query expands more than `expansion_ratio`
+/// nodes per node of its path, the index takes it as a sign of a weak bound
+/// and adds a landmark, up to `max_landmarks`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AltIndex {
+    landmarks: Vec<usize>,
+    /// Distance from each landmark to each node.
+    from: Vec<Vec<Option<u64>>>,
+    /// Distance from each node to each landmark.
+    to: Vec<Vec<Option<u64>>>,
+    expansion_ratio: usize,
+    max_landmarks: usize,
+}
+
+impl AltIndex {
+    /// Index of `count` landmarks picked by farthest selection from `seed`.
+    pub fn build(graph: &Graph, seed: usize, count: usize) -> AltIndex {
+        let mut index = AltIndex { expansion_ratio: 8, max_landmarks: 16, ..AltIndex::default() };
+        let reversed = reversed(graph);
+        let seed_distances = distances(graph, seed);
+        while index.len() < count {
+            match index.farthest(&seed_distances) {
+                Some(landmark) => index.insert(graph, &reversed, landmark),
+                None => break,
+            }
+        }
+        index
+    }
+
+    /// Sets how many nodes a query may expand per node of its path before
+    /// a landmark is added.
+    pub fn with_expansion_ratio(mut self, ratio: usize) -> AltIndex {
+        self.expansion_ratio = ratio;
+        self
+    }
+
+    /// Sets how many landmarks queries may grow the index to.
+    pub fn with_max_landmarks(mut self, max_landmarks: usize) -> AltIndex {
+        self.max_landmarks = max_landmarks;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.landmarks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.landmarks.is_empty()
+    }
+
+    pub fn landmarks(&self) -> &[usize] {
+        &self.landmarks
+    }
+
+    /// Adds `landmark` and its distances.
+    pub fn add(&mut self, graph: &Graph, landmark: usize) {
+        self.insert(graph, &reversed(graph), landmark);
+    }
+
+    fn insert(&mut self, graph: &Graph, reversed: &Graph, landmark: usize) {
+        self.from.push(distances(graph, landmark));
+        self.to.push(distances(reversed, landmark));
+        self.landmarks.push(landmark);
+    }
+
+    // Node farthest from the landmarks, or from the seed while there are
+    // none, among those every landmark reaches.
+    fn farthest(&self, seed_distances: &[Option<u64>]) -> Option<usize> {
+        (0..seed_distances.len())
+            .filter(|node| !self.landmarks.contains(node))
+            .filter_map(|node| {
+                let nearest = self.from.iter().map(|from| from[node]).min().unwrap_or(seed_distances[node])?;
+                Some((nearest, node))
+            })
+            .max()
+            .map(|(_, node)| node)
+    }
+
+    /// Lower bound on the cost of a path from `from` to `to`.
+    pub fn lower_bound(&self, from: usize, to: usize) -> u64 {
+        let ahead = self.from.iter().filter_map(|distances| Some(distances[to]?.saturating_sub(distances[from]?)));
+        let behind = self.to.iter().filter_map(|distances| Some(distances[from]?.saturating_sub(distances[to]?)));
+        ahead.chain(behind).max().unwrap_or(0)
+    }
+
+    /// A* from `start` to `goal`, returning the edge indices taken and the
+    /// cost. A query that expands too many nodes adds a landmark for those
+    /// that follow.
+    pub fn query(&mut self, graph: &Graph, start: usize, goal: usize) -> Option<(Vec<usize>, u64)> {
+        self.query_with(&mut AStarBuffers::new(), graph, start, goal)
+    }
+
+    /// `query` reusing `buffers`, which keep the statistics of the search.
+    pub fn query_with(&mut self, buffers: &mut AStarBuffers<Graph>, graph: &Graph, start: usize, goal: usize)
+        -> Option<(Vec<usize>, u64)> {
+        let found = graph.astar_with(buffers, start, goal, |&node: &usize| self.lower_bound(node, goal));
+        let steps = found.as_ref().map_or(0, |(actions, _)| actions.len()) + 1;
+        if buffers.stats().expanded > (self.expansion_ratio * steps) as u64 && self.len() < self.max_landmarks {
+            let seed_distances = distances(graph, start);
+            if let Some(landmark) = self.farthest(&seed_distances) {
+                self.add(graph, landmark);
+            }
+        }
+        found
+    }
+}
+
+// Distance from `root` to every node of `graph`.
+fn distances(graph: &Graph, root: usize) -> Vec<Option<u64>> {
+    let mut distances = vec![None; graph.node_count()];
+    for (node, distance, _) in PathTree::grow(graph, root).settled {
+        distances[node] = Some(distance);
+    }
+    distances
+}
+
+fn reversed(graph: &Graph) -> Graph {
+    let mut reversed = Graph::new(graph.node_count());
+    for from in 0..graph.node_count() {
+        for edge in graph.edges(from) {
+            reversed.add_edge(edge.to, from, edge.cost);
+        }
+    }
+    reversed
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::{Rng, SeedableRng};
+    use rand::rngs::StdRng;
+    use astar::AStarBuffers;
+    use graph::Graph;
+    use search::WeightedSearchSpace;
+    use super::{AltIndex, Landmarks};
+
+    // Undirected grid of roads with random lengths.
+    fn roads(side: usize, rng: &mut StdRng) -> Graph {
+        let mut graph = Graph::new(side * side);
+        for y in 0..side {
+            for x in 0..side {
+                let node = y * side + x;
+                if x + 1 < side {
+                    graph.add_undirected_edge(node, node + 1, rng.gen_range(1..10));
+                }
+                if y + 1 < side {
+                    graph.add_undirected_edge(node, node + side, rng.gen_range(1..10));
+                }
+            }
+        }
+        graph
+    }
+
+    #[test]
+    pub fn test_landmarks() {
+        let mut rng = StdRng::seed_from_u64(8);
+        let graph = roads(20, &mut rng);
+        let farthest = Landmarks::farthest(&graph, 0, 4);
+        let avoid = Landmarks::avoid(&graph, 0, 4, &mut rng);
+        assert_eq!(farthest.landmarks().len(), 4);
+        assert_eq!(avoid.landmarks().len(), 4);
+        let from_seed = Landmarks::new(&graph, vec![0]);
+        let farthest_node = (0..400).max_by_key(|node| from_seed.distance(0, node)).unwrap();
+        assert_eq!(farthest.landmarks()[0], farthest_node);
+        assert_eq!(from_seed.distance(0, &farthest_node), farthest.distance(0, &0));
+
+        for _ in 0..10 {
+            let (start, goal) = (rng.gen_range(0..400), rng.gen_range(0..400));
+            let mut plain = AStarBuffers::new();
+            let optimal = graph.astar_with(&mut plain, start, goal, |_: &usize| 0).unwrap().1;
+            for landmarks in &[&farthest, &avoid] {
+                let mut alt = AStarBuffers::new();
+                assert_eq!(graph.astar_with(&mut alt, start, goal, landmarks.heuristic(&goal)).unwrap().1, optimal);
+                assert!(landmarks.lower_bound(&start, &goal) <= optimal);
+                assert!(alt.stats().expanded <= plain.stats().expanded);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_alt_index() {
+        let mut rng = StdRng::seed_from_u64(9);
+        let mut graph = roads(16, &mut rng);
+        // A one-way shortcut across the map.
+        graph.add_edge(0, 255, 5);
+        let mut index = AltIndex::build(&graph, 0, 2).with_expansion_ratio(1).with_max_landmarks(3);
+        assert_eq!(index.len(), 2);
+        assert!(!index.landmarks().contains(&0));
+        for _ in 0..10 {
+            let (start, goal) = (rng.gen_range(0..256), rng.gen_range(0..256));
+            let optimal = graph.astar(start, goal, |_: &usize| 0).unwrap().1;
+            assert!(index.lower_bound(start, goal) <= optimal);
+            let mut buffers = AStarBuffers::new();
+            let (actions, cost) = index.query_with(&mut buffers, &graph, start, goal).unwrap();
+            assert_eq!((*graph.walk(start, &actions).last().unwrap(), cost), (goal, optimal));
+        }
+        // Poor queries grew the index to its limit.
+        assert_eq!(index.len(), 3);
+        assert!(index.lower_bound(0, 255) <= 5);
+        assert_eq!(index.query(&graph, 0, 255), Some((vec![graph.edges(0).len() - 1], 5)));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_alt_index_serde() {
+        let graph = roads(8, &mut StdRng::seed_from_u64(10));
+        let index = AltIndex::build(&graph, 0, 3);
+        let loaded: AltIndex = ::serde_json::from_str(&::serde_json::to_string(&index).unwrap()).unwrap();
+        assert_eq!(loaded, index);
+    }
+}
@@ -1,3 +1,87 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[macro_use]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate core;
 extern crate fnv;
+extern crate hashbrown;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(test)]
+extern crate pathfinding;
+#[cfg(feature = "python")]
+extern crate pyo3;
+#[cfg(feature = "graphml")]
+extern crate quick_xml;
+extern crate rand;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
+#[cfg(any(feature = "json", all(test, feature = "serde")))]
+extern crate serde_json;
+#[cfg(feature = "smallvec")]
+extern crate smallvec;
+#[cfg(feature = "tracing")]
+#[macro_use]
+extern crate tracing;
 
 pub mod search;
+pub mod stats;
+pub mod observer;
+pub mod graphviz;
+pub mod record;
+pub mod reservoir;
+pub mod session;
+#[cfg(all(feature = "json", feature = "std"))]
+pub mod checkpoint;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod progress;
+#[cfg(feature = "std")]
+pub mod sampling;
+mod stopwatch;
+#[cfg(feature = "tracing")]
+mod trace;
+pub mod visited;
+pub mod visit;
+pub mod metrics;
+pub mod prediction;
+pub mod path;
+pub mod encoding;
+pub mod bidirectional;
+pub mod cache;
+pub mod ordering;
+pub mod adapters;
+pub mod shared;
+pub mod dynamic;
+pub mod batch;
+pub mod heuristic;
+pub mod landmarks;
+pub mod astar;
+pub mod contraction;
+pub mod bnb;
+pub mod ida;
+pub mod local;
+pub mod anytime;
+pub mod game;
+pub mod pareto;
+pub mod pdb;
+pub mod cost;
+pub mod graph;
+pub mod components;
+pub mod turns;
+pub mod grid;
+pub mod hpa;
+#[cfg(feature = "ndarray")]
+pub mod costmap;
+pub mod generators;
+pub mod maze;
+pub mod formats;
+pub mod domains;
+pub mod successors;
+pub mod synthesis;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
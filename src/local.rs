@@ -0,0 +1,81 @@
+//! Local search over tours, improving a solution by small changes until
+//! none helps, for warm starts of exact searches and for good answers to
+//! problems too big for them.
+//!
+//! Tours visit cities `0..n` given by their order, and `distance` must be
+//! symmetric.
+
+use alloc::vec::Vec;
+
+/// Tour from city 0 through `n` cities that always moves on to the nearest
+/// city not yet visited.
+pub fn nearest_neighbour<F>(n: usize, distance: F) -> Vec<usize> where F: Fn(usize, usize) -> u64 {
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut visited = vec![false; n];
+    let mut tour = vec![0];
+    visited[0] = true;
+    while tour.len() < n {
+        let last = tour[tour.len() - 1];
+        let next = (0..n).filter(|&city| !visited[city])
+            .min_by_key(|&city| distance(last, city))
+            .expect("an unvisited city");
+        visited[next] = true;
+        tour.push(next);
+    }
+    tour
+}
+
+/// 2-opt: improves `tour` by reversing stretches of it while that shortens
+/// it, until no single reversal helps. The first city stays first.
+pub fn two_opt<F>(tour: &mut [usize], distance: F) where F: Fn(usize, usize) -> u64 {
+    let n = tour.len();
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..n {
+            for j in i + 1..n {
+                let (a, b) = (tour[i - 1], tour[i]);
+                let (c, d) = (tour[j], tour[(j + 1) % n]);
+                if distance(a, c) + distance(b, d) < distance(a, b) + distance(c, d) {
+                    tour[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+}
+
+/// Length of the closed tour visiting `tour` in order.
+pub fn tour_length<F>(tour: &[usize], distance: F) -> u64 where F: Fn(usize, usize) -> u64 {
+    let closing = match (tour.first(), tour.last()) {
+        (Some(&first), Some(&last)) => distance(last, first),
+        _ => 0,
+    };
+    tour.windows(2).map(|pair| distance(pair[0], pair[1])).sum::<u64>() + closing
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::{nearest_neighbour, tour_length, two_opt};
+
+    #[test]
+    pub fn test_two_opt() {
+        // Corners of a square, with the diagonals longer than the sides.
+        let points = [(0i64, 0i64), (10, 10), (10, 0), (0, 10)];
+        let distance = |a: usize, b: usize| {
+            let (pa, pb) = (points[a], points[b]);
+            pa.0.abs_diff(pb.0) + pa.1.abs_diff(pb.1)
+        };
+        let mut tour = vec![0, 1, 2, 3];
+        assert_eq!(tour_length(&tour, distance), 60);
+        two_opt(&mut tour, distance);
+        assert_eq!(tour_length(&tour, distance), 40);
+        assert_eq!(tour[0], 0);
+
+        let tour = nearest_neighbour(4, distance);
+        assert_eq!(tour_length(&tour, distance), 40);
+        assert!(nearest_neighbour(0, distance).is_empty());
+    }
+}
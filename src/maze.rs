@@ -0,0 +1,207 @@
+//! Perfect maze generators and helpers to solve and draw mazes.
+//!
+//! A maze of `width` by `height` rooms is a `Grid` of `2 * width + 1` by
+//! `2 * height + 1` cells: rooms sit at odd coordinates and the cells
+//! between them are walls, open where a passage was carved.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use rand::Rng;
+
+use grid::{manhattan, Cell, Direction, Grid};
+use search::WeightedSearchSpace;
+
+fn walled(width: usize, height: usize) -> Grid {
+    assert!(width > 0 && height > 0, "mazes need at least one room");
+    let mut grid = Grid::new(2 * width + 1, 2 * height + 1);
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            grid.set_blocked((x, y), x % 2 == 0 || y % 2 == 0);
+        }
+    }
+    grid
+}
+
+fn room_cell((x, y): Cell) -> Cell {
+    (2 * x + 1, 2 * y + 1)
+}
+
+// Opens the wall between two adjacent rooms.
+fn carve(grid: &mut Grid, a: Cell, b: Cell) {
+    let (a, b) = (room_cell(a), room_cell(b));
+    grid.set_blocked(((a.0 + b.0) / 2, (a.1 + b.1) / 2), false);
+}
+
+fn neighbours(width: usize, height: usize, room: Cell) -> impl Iterator<Item=Cell> {
+    Direction::ALL.iter().filter_map(move |direction| direction.step(room)).filter(move |&(x, y)| x < width && y < height)
+}
+
+/// Maze carved by a randomised depth-first search, giving long, winding
+/// corridors.
+pub fn recursive_backtracker<R: Rng>(width: usize, height: usize, rng: &mut R) -> Grid {
+    let mut grid = walled(width, height);
+    let mut visited = vec![false; width * height];
+    let mut stack = vec![(0, 0)];
+    visited[0] = true;
+    while let Some(&room) = stack.last() {
+        let unvisited: Vec<Cell> = neighbours(width, height, room).filter(|&(x, y)| !visited[y * width + x]).collect();
+        if unvisited.is_empty() {
+            stack.pop();
+            continue;
+        }
+        let next = unvisited[rng.gen_range(0..unvisited.len())];
+        visited[next.1 * width + next.0] = true;
+        carve(&mut grid, room, next);
+        stack.push(next);
+    }
+    grid
+}
+
+/// Maze grown by randomised Prim's algorithm, giving many short dead ends.
+pub fn prim<R: Rng>(width: usize, height: usize, rng: &mut R) -> Grid {
+    let mut grid = walled(width, height);
+    let mut visited = vec![false; width * height];
+    let mut frontier: Vec<(Cell, Cell)> = Vec::new();
+    visited[0] = true;
+    frontier.extend(neighbours(width, height, (0, 0)).map(|next| ((0, 0), next)));
+    while !frontier.is_empty() {
+        let (room, next) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+        if visited[next.1 * width + next.0] {
+            continue;
+        }
+        visited[next.1 * width + next.0] = true;
+        carve(&mut grid, room, next);
+        frontier.extend(neighbours(width, height, next)
+            .filter(|&(x, y)| !visited[y * width + x])
+            .map(|after| (next, after)));
+    }
+    grid
+}
+
+fn find(parents: &mut [usize], mut room: usize) -> usize {
+    while parents[room] != room {
+        parents[room] = parents[parents[room]];
+        room = parents[room];
+    }
+    room
+}
+
+/// Maze made by randomised Kruskal's algorithm, opening walls in random
+/// order whenever they join two unconnected regions.
+pub fn kruskal<R: Rng>(width: usize, height: usize, rng: &mut R) -> Grid {
+    let mut grid = walled(width, height);
+    let mut walls: Vec<(Cell, Cell)> = Vec::new();
+    for y in 0..height {
+        for x in 0..width {
+            if x + 1 < width {
+                walls.push(((x, y), (x + 1, y)));
+            }
+            if y + 1 < height {
+                walls.push(((x, y), (x, y + 1)));
+            }
+        }
+    }
+    for i in (1..walls.len()).rev() {
+        walls.swap(i, rng.gen_range(0..=i));
+    }
+    let mut parents: Vec<usize> = (0..width * height).collect();
+    for (a, b) in walls {
+        let (root_a, root_b) = (find(&mut parents, a.1 * width + a.0), find(&mut parents, b.1 * width + b.0));
+        if root_a != root_b {
+            parents[root_a] = root_b;
+            carve(&mut grid, a, b);
+        }
+    }
+    grid
+}
+
+/// Cells of a shortest path from the top-left room to the bottom-right one,
+/// both included.
+pub fn solve(maze: &Grid) -> Option<Vec<Cell>> {
+    let (start, goal) = ((1, 1), (maze.width().saturating_sub(2), maze.height().saturating_sub(2)));
+    let (actions, _) = maze.astar(start, goal, |cell: &Cell| manhattan(*cell, goal))?;
+    let mut cells = vec![start];
+    for direction in actions {
+        let next = direction.step(cells[cells.len() - 1]).expect("path stays on the grid");
+        cells.push(next);
+    }
+    Some(cells)
+}
+
+/// Draws `grid` with `#` for blocked cells, a space for open ones and `*`
+/// for cells on `path`.
+pub fn to_ascii(grid: &Grid, path: &[Cell]) -> String {
+    let mut text = String::with_capacity((grid.width() + 1) * grid.height());
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            text.push(if grid.is_blocked((x, y)) { '#' } else if path.contains(&(x, y)) { '*' } else { ' ' });
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Undirected DOT graph of the open cells of `grid` and the moves between
+/// them.
+pub fn to_dot(grid: &Grid) -> String {
+    let mut dot = String::from("graph maze {\n");
+    for y in 0..grid.height() {
+        for x in 0..grid.width() {
+            if grid.is_blocked((x, y)) {
+                continue;
+            }
+            let _ = writeln!(dot, "    \"{},{}\";", x, y);
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if !grid.is_blocked((nx, ny)) {
+                    let _ = writeln!(dot, "    \"{},{}\" -- \"{},{}\";", x, y, nx, ny);
+                }
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use formats::dot::parse_dot;
+    use grid::Grid;
+    use search::SearchSpace;
+    use super::{kruskal, prim, recursive_backtracker, solve, to_ascii, to_dot};
+
+    fn assert_perfect(maze: &Grid, width: usize, height: usize) {
+        let open = (0..maze.height()).flat_map(|y| (0..maze.width()).map(move |x| (x, y)))
+            .filter(|&cell| !maze.is_blocked(cell))
+            .count();
+        // Every room plus one passage per spanning tree edge, all connected.
+        assert_eq!(open, 2 * width * height - 1);
+        assert_eq!(maze.dfs_iter((1, 1)).count(), open);
+    }
+
+    #[test]
+    pub fn test_maze_generators() {
+        let mut rng = StdRng::seed_from_u64(4);
+        for maze in [recursive_backtracker(8, 5, &mut rng), prim(8, 5, &mut rng), kruskal(8, 5, &mut rng)] {
+            assert_eq!((maze.width(), maze.height()), (17, 11));
+            assert_perfect(&maze, 8, 5);
+            let path = solve(&maze).unwrap();
+            assert_eq!((path[0], path[path.len() - 1]), ((1, 1), (15, 9)));
+        }
+        assert_eq!(kruskal(6, 6, &mut StdRng::seed_from_u64(9)), kruskal(6, 6, &mut StdRng::seed_from_u64(9)));
+    }
+
+    #[test]
+    pub fn test_maze_rendering() {
+        let maze = recursive_backtracker(2, 1, &mut StdRng::seed_from_u64(0));
+        let path = solve(&maze).unwrap();
+        assert_eq!(to_ascii(&maze, &path), "#####\n#***#\n#####\n");
+
+        let graph = parse_dot(&to_dot(&maze)).unwrap();
+        assert_eq!(graph.names(), ["1,1", "2,1", "3,1"]);
+        assert_eq!(graph.graph().edge_count(), 4);
+    }
+}
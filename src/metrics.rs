@@ -0,0 +1,139 @@
+//! Shape of a search space measured by a bounded traversal, for choosing
+//! between algorithms and sizing visited sets ahead of a search.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use hashbrown::HashSet;
+
+use search::SearchSpace;
+
+/// Counts gathered by `measure`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SpaceMetrics {
+    /// Distinct states found, the start included.
+    pub states: u64,
+    /// States whose successors were generated.
+    pub expanded: u64,
+    /// Successors produced by expansions, duplicates included.
+    pub generated: u64,
+    /// Generated successors that were found before.
+    pub duplicates: u64,
+    /// Distinct states found at each depth, in actions from the start.
+    pub depths: Vec<u64>,
+    /// Number of leading entries of `depths` that hold every state at that
+    /// depth; any later one is partial, as the traversal was cut short.
+    pub complete_depths: usize,
+    /// Whether every state reachable from the start was found.
+    pub exhausted: bool,
+}
+
+impl SpaceMetrics {
+    /// Successors generated per expanded state, duplicates included.
+    pub fn average_out_degree(&self) -> f64 {
+        if self.expanded == 0 {
+            return 0.0;
+        }
+        self.generated as f64 / self.expanded as f64
+    }
+
+    /// Share of generated successors that were duplicates.
+    pub fn duplicate_ratio(&self) -> f64 {
+        if self.generated == 0 {
+            return 0.0;
+        }
+        self.duplicates as f64 / self.generated as f64
+    }
+
+    /// The `b` for which a uniform tree of branching factor `b` as deep as
+    /// the complete layers holds as many states as they do, that is
+    /// `1 + b + b^2 + ... + b^d = n`. Unlike the out-degree, it discounts
+    /// duplicates.
+    pub fn effective_branching_factor(&self) -> f64 {
+        let depth = match self.complete_depths {
+            0 | 1 => return 0.0,
+            complete => complete - 1,
+        };
+        let states = self.depths[..self.complete_depths].iter().sum::<u64>() as f64;
+        let tree = |branching: f64| (0..depth).fold(1.0, |size, _| size * branching + 1.0);
+        let (mut low, mut high) = (0.0, states);
+        for _ in 0..64 {
+            let middle = (low + high) / 2.0;
+            if tree(middle) < states {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+/// Breadth-first traversal from `start` that stops expanding once
+/// `max_states` distinct states were found, counting what it sees. States
+/// are told apart by `SearchSpace::canonicalize`; dead ends are counted but
+/// not expanded.
+pub fn measure<S>(search_space: &S, start: S::State, max_states: u64) -> SpaceMetrics
+where S: SearchSpace + ?Sized {
+    let key = |state: &S::State| search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+    let mut metrics = SpaceMetrics { states: 1, depths: vec![1], ..SpaceMetrics::default() };
+    let mut seen = HashSet::new();
+    seen.insert(key(&start));
+    let mut queue = VecDeque::new();
+    queue.push_back((start, 0));
+    while let Some((state, depth)) = queue.pop_front() {
+        if metrics.states >= max_states {
+            metrics.complete_depths = depth + 1;
+            return metrics;
+        }
+        metrics.expanded += 1;
+        for (_, successor) in search_space.expand(&state) {
+            metrics.generated += 1;
+            if !seen.insert(key(&successor)) {
+                metrics.duplicates += 1;
+                continue;
+            }
+            metrics.states += 1;
+            if metrics.depths.len() <= depth + 1 {
+                metrics.depths.push(0);
+            }
+            metrics.depths[depth + 1] += 1;
+            if !search_space.is_dead_end(&successor) {
+                queue.push_back((successor, depth + 1));
+            }
+        }
+    }
+    metrics.complete_depths = metrics.depths.len();
+    metrics.exhausted = true;
+    metrics
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use search::SearchSpace;
+    use super::SpaceMetrics;
+
+    #[test]
+    pub fn test_measure() {
+        let grid = random_grid(5, 5, 0.0, &mut StdRng::seed_from_u64(1));
+        let metrics = grid.measure((0, 0), 1000);
+        assert!(metrics.exhausted);
+        assert_eq!(metrics.states, 25);
+        assert_eq!(metrics.depths, vec![1, 2, 3, 4, 5, 4, 3, 2, 1]);
+        assert_eq!((metrics.expanded, metrics.generated), (25, 80));
+        assert_eq!(metrics.average_out_degree(), 3.2);
+        assert_eq!(metrics.duplicate_ratio(), 56.0 / 80.0);
+
+        let metrics = grid.measure((0, 0), 6);
+        assert!(!metrics.exhausted);
+        assert_eq!((metrics.states, metrics.depths.clone(), metrics.complete_depths), (6, vec![1, 2, 3], 3));
+        assert!((metrics.effective_branching_factor() - (21f64.sqrt() - 1.0) / 2.0).abs() < 1e-9);
+
+        let tree = SpaceMetrics { depths: vec![1, 3, 9, 27], complete_depths: 4, ..SpaceMetrics::default() };
+        assert!((tree.effective_branching_factor() - 3.0).abs() < 1e-9);
+    }
+}
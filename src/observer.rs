@@ -0,0 +1,231 @@
+use core::fmt;
+
+/// Cost annotations of a node in a cost-based search.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct NodeCost<C> {
+    pub g: C,
+    pub f: C,
+}
+
+/// Callbacks invoked by searches as they run.
+///
+/// `C` carries per-node cost information: `()` for uninformed searches and
+/// `NodeCost` for cost-based ones. Every callback defaults to doing nothing.
+pub trait Observer<S, A, C = ()> {
+    /// `state` is about to have its successors generated.
+    fn on_expand(&mut self, _state: &S, _depth: usize, _cost: &C) {}
+
+    /// `state` was generated from `parent` by `action` and kept for
+    /// exploration.
+    fn on_generate(&mut self, _parent: &S, _action: &A, _state: &S, _depth: usize, _cost: &C) {}
+
+    /// A generated `state` was dropped as already seen.
+    fn on_duplicate(&mut self, _state: &S, _depth: usize) {}
+
+    /// `state` was recognized as a goal and ends the search.
+    fn on_goal(&mut self, _state: &S, _depth: usize, _cost: &C) {}
+
+    /// `state` was cut off and its successors will not be explored.
+    fn on_prune(&mut self, _state: &S, _depth: usize) {}
+
+    /// Whether a newly generated `state` should be dropped instead of being
+    /// kept for exploration; see `Prune`.
+    fn is_pruned(&mut self, _state: &S, _depth: usize, _cost: &C) -> bool {
+        false
+    }
+}
+
+/// Observer dropping the generated states `prune` holds for, given the
+/// state, its depth and its cost annotations, and passing every event on to
+/// `observer`. Unlike filtering in `expand`, the predicate sees what only the
+/// search knows, such as g in cost-based searches.
+pub struct Prune<F, O = ()> {
+    prune: F,
+    observer: O,
+}
+
+impl<F> Prune<F> {
+    pub fn new(prune: F) -> Prune<F> {
+        Prune { prune, observer: () }
+    }
+}
+
+impl<F, O> Prune<F, O> {
+    /// Also reports the search's events to `observer`.
+    pub fn with_observer<P>(self, observer: P) -> Prune<F, P> {
+        Prune { prune: self.prune, observer }
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+}
+
+impl<S, A, C, F, O> Observer<S, A, C> for Prune<F, O>
+where F: FnMut(&S, usize, &C) -> bool, O: Observer<S, A, C> {
+    fn on_expand(&mut self, state: &S, depth: usize, cost: &C) {
+        self.observer.on_expand(state, depth, cost)
+    }
+
+    fn on_generate(&mut self, parent: &S, action: &A, state: &S, depth: usize, cost: &C) {
+        self.observer.on_generate(parent, action, state, depth, cost)
+    }
+
+    fn on_duplicate(&mut self, state: &S, depth: usize) {
+        self.observer.on_duplicate(state, depth)
+    }
+
+    fn on_goal(&mut self, state: &S, depth: usize, cost: &C) {
+        self.observer.on_goal(state, depth, cost)
+    }
+
+    fn on_prune(&mut self, state: &S, depth: usize) {
+        self.observer.on_prune(state, depth)
+    }
+
+    fn is_pruned(&mut self, state: &S, depth: usize, cost: &C) -> bool {
+        (self.prune)(state, depth, cost) || self.observer.is_pruned(state, depth, cost)
+    }
+}
+
+impl<C: fmt::Display> fmt::Display for NodeCost<C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "g={} f={}", self.g, self.f)
+    }
+}
+
+impl<S, A, C> Observer<S, A, C> for () {}
+
+impl<S, A, C, O> Observer<S, A, C> for &mut O where O: Observer<S, A, C> + ?Sized {
+    fn on_expand(&mut self, state: &S, depth: usize, cost: &C) {
+        (**self).on_expand(state, depth, cost)
+    }
+
+    fn on_generate(&mut self, parent: &S, action: &A, state: &S, depth: usize, cost: &C) {
+        (**self).on_generate(parent, action, state, depth, cost)
+    }
+
+    fn on_duplicate(&mut self, state: &S, depth: usize) {
+        (**self).on_duplicate(state, depth)
+    }
+
+    fn on_goal(&mut self, state: &S, depth: usize, cost: &C) {
+        (**self).on_goal(state, depth, cost)
+    }
+
+    fn on_prune(&mut self, state: &S, depth: usize) {
+        (**self).on_prune(state, depth)
+    }
+
+    fn is_pruned(&mut self, state: &S, depth: usize, cost: &C) -> bool {
+        (**self).is_pruned(state, depth, cost)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use astar::AStarBuffers;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::{NodeCost, Observer, Prune};
+
+    struct Diamond;
+
+    impl SearchSpace for Diamond {
+        type State = u8;
+        type Action = u8;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![(1, 1), (2, 2)],
+                1 | 2 => vec![(3, 3)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Diamond {
+        type Cost = u32;
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            (*state + *action) as u32
+        }
+    }
+
+    #[derive(Default)]
+    struct Log(Vec<String>);
+
+    impl<C: ::std::fmt::Debug> Observer<u8, u8, C> for Log {
+        fn on_expand(&mut self, state: &u8, depth: usize, cost: &C) {
+            self.0.push(format!("expand {} {} {:?}", state, depth, cost));
+        }
+
+        fn on_generate(&mut self, parent: &u8, _action: &u8, state: &u8, _depth: usize, _cost: &C) {
+            self.0.push(format!("generate {}>{}", parent, state));
+        }
+
+        fn on_duplicate(&mut self, state: &u8, _depth: usize) {
+            self.0.push(format!("duplicate {}", state));
+        }
+
+        fn on_goal(&mut self, state: &u8, _depth: usize, _cost: &C) {
+            self.0.push(format!("goal {}", state));
+        }
+
+        fn on_prune(&mut self, state: &u8, _depth: usize) {
+            self.0.push(format!("prune {}", state));
+        }
+    }
+
+    #[test]
+    pub fn test_dfs_observer() {
+        let mut log = Log::default();
+        let mut buffers = SearchBuffers::new().with_observer(&mut log);
+        assert_eq!(Diamond.dfs_with(&mut buffers, 0, 3), Some(vec![1, 3]));
+        assert_eq!(log.0, vec!["expand 0 0 ()", "generate 0>1", "expand 1 1 ()", "generate 1>3", "goal 3"]);
+
+        let mut iter = Diamond.dfs_iter_with(SearchBuffers::new().with_observer(Log::default()), 0);
+        while let Some((_, state)) = iter.next() {
+            if state == 1 {
+                iter.prune();
+            }
+        }
+        assert_eq!(iter.into_buffers().into_observer().0, vec![
+            "expand 0 0 ()", "generate 0>1", "prune 1", "generate 0>2", "expand 2 1 ()", "generate 2>3",
+            "expand 3 2 ()",
+        ]);
+    }
+
+    #[test]
+    pub fn test_astar_observer() {
+        let mut buffers = AStarBuffers::new().with_observer(Log::default());
+        assert_eq!(Diamond.astar_with(&mut buffers, 0, 3, |_: &u8| 0), Some((vec![1, 3], 5)));
+        assert_eq!(buffers.into_observer().0, vec![
+            "expand 0 0 NodeCost { g: 0, f: 0 }", "generate 0>1", "generate 0>2",
+            "expand 1 1 NodeCost { g: 1, f: 1 }", "generate 1>3",
+            "expand 2 1 NodeCost { g: 2, f: 2 }", "duplicate 3",
+            "goal 3",
+        ]);
+    }
+
+    #[test]
+    pub fn test_prune() {
+        let mut buffers = SearchBuffers::new().with_observer(Prune::new(|&state: &u8, _, _: &()| state == 1));
+        assert_eq!(Diamond.dfs_with(&mut buffers, 0, 3), Some(vec![2, 3]));
+        assert_eq!(buffers.stats().pruned, 1);
+
+        let prune = Prune::new(|_: &u8, _, cost: &NodeCost<u32>| cost.g > 4).with_observer(Log::default());
+        let mut buffers = AStarBuffers::new().with_observer(prune);
+        assert_eq!(Diamond.astar_with(&mut buffers, 0, 2, |_: &u8| 0), Some((vec![2], 2)));
+        assert_eq!(buffers.stats().pruned, 1);
+        assert_eq!(Diamond.astar_with(&mut buffers, 2, 3, |_: &u8| 0), None);
+        assert_eq!(buffers.stats().pruned, 1);
+        assert_eq!(buffers.into_observer().observer().0.last().map(String::as_str), Some("prune 3"));
+    }
+}
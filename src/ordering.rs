@@ -0,0 +1,233 @@
+//! Search space adapters deciding the order successors are explored in,
+//! which in depth-first searches decides how soon good paths are found.
+
+use alloc::vec::{IntoIter, Vec};
+use core::cell::RefCell;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+
+use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+
+type Successors<S> = Vec<(<S as SearchSpace>::Action, <S as SearchSpace>::State)>;
+
+/// Wraps a search space and sorts the successors of every expansion by the
+/// key `rank` gives each `(parent, action, successor)`, lowest first. The
+/// sort is stable, so successors of equal rank keep their generated order.
+pub struct OrderedSpace<S, F> {
+    search_space: S,
+    rank: F,
+}
+
+impl<S, F> OrderedSpace<S, F> {
+    pub fn new(search_space: S, rank: F) -> OrderedSpace<S, F> {
+        OrderedSpace { search_space, rank }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+impl<S, F, K> OrderedSpace<S, F>
+where S: SearchSpace, F: Fn(&S::State, &S::Action, &S::State) -> K, K: Ord {
+    fn order(&self, state: &S::State, successors: S::Iterator) -> IntoIter<(S::Action, S::State)> {
+        let mut successors: Successors<S> = successors.collect();
+        successors.sort_by_cached_key(|(action, successor)| (self.rank)(state, action, successor));
+        successors.into_iter()
+    }
+}
+
+impl<S, F, K> SearchSpace for OrderedSpace<S, F>
+where S: SearchSpace, F: Fn(&S::State, &S::Action, &S::State) -> K, K: Ord {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.order(state, self.search_space.expand(state))
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.order(state, self.search_space.expand_at(state, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S, F, K> WeightedSearchSpace for OrderedSpace<S, F>
+where S: WeightedSearchSpace, F: Fn(&S::State, &S::Action, &S::State) -> K, K: Ord {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, action, successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.order(state, self.search_space.expand_weighted(state, context))
+    }
+}
+
+/// Wraps a search space and shuffles the successors of every expansion
+/// with `rng`, so that restarts explore in different orders and a seeded
+/// generator repeats the same ones.
+pub struct ShuffledSpace<S, R> {
+    search_space: S,
+    rng: RefCell<R>,
+}
+
+impl<S, R> ShuffledSpace<S, R> {
+    pub fn new(search_space: S, rng: R) -> ShuffledSpace<S, R> {
+        ShuffledSpace { search_space, rng: RefCell::new(rng) }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+impl<S, R> ShuffledSpace<S, R> where S: SearchSpace, R: Rng {
+    fn shuffle(&self, successors: S::Iterator) -> IntoIter<(S::Action, S::State)> {
+        let mut successors: Successors<S> = successors.collect();
+        successors.shuffle(&mut *self.rng.borrow_mut());
+        successors.into_iter()
+    }
+}
+
+impl<S, R> SearchSpace for ShuffledSpace<S, R> where S: SearchSpace, R: Rng {
+    type State = S::State;
+    type Action = S::Action;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.shuffle(self.search_space.expand(state))
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        self.shuffle(self.search_space.expand_at(state, context))
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S, R> WeightedSearchSpace for ShuffledSpace<S, R> where S: WeightedSearchSpace, R: Rng {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, action, successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.shuffle(self.search_space.expand_weighted(state, context))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::{OrderedSpace, ShuffledSpace};
+
+    // Numbers counting up to 10 by adding 1, 2 or 5.
+    struct Steps;
+
+    impl SearchSpace for Steps {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            vec![1, 2, 5].into_iter().filter(|step| state + step <= 10).map(|step| (step, state + step))
+                .collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Steps {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    #[test]
+    pub fn test_ordered_space() {
+        assert_eq!(Steps.dfs(0, 10).map(|actions| actions.len()), Some(10));
+        let largest_first = OrderedSpace::new(Steps, |_: &u32, &step: &u32, _: &u32| u32::MAX - step);
+        assert_eq!(largest_first.dfs(0, 10), Some(vec![5, 5]));
+
+        let mut buffers = SearchBuffers::new();
+        let to_goal = OrderedSpace::new(Steps, |_: &u32, _: &u32, &next: &u32| next.abs_diff(7));
+        assert_eq!(to_goal.dfs_with(&mut buffers, 0, 7), Some(vec![5, 2]));
+        assert_eq!(buffers.stats().expanded, 2);
+
+        assert_eq!(largest_first.branch_and_bound(0, 10, |_: &u32| 0, None), Some((vec![5, 5], 2)));
+        assert_eq!(largest_first.astar(0, 10, |_: &u32| 0).map(|found| found.1), Some(2));
+    }
+
+    #[test]
+    pub fn test_shuffled_space() {
+        let path = |seed| ShuffledSpace::new(Steps, StdRng::seed_from_u64(seed)).dfs(0, 10).unwrap();
+        assert_eq!(path(1), path(1));
+        assert!((0..8).any(|seed| path(seed) != path(0)));
+        for seed in 0..8 {
+            assert_eq!(path(seed).iter().sum::<u32>(), 10);
+            let shuffled = ShuffledSpace::new(Steps, StdRng::seed_from_u64(seed));
+            assert_eq!(shuffled.astar(0, 10, |_: &u32| 0).map(|found| found.1), Some(2));
+        }
+    }
+}
@@ -0,0 +1,201 @@
+//! Multi-objective search: costs are vectors and the answer is the Pareto
+//! frontier of paths no other path beats on every objective.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::fmt::Debug;
+use core::ops::Add;
+
+use hashbrown::HashMap;
+
+use heuristic::Heuristic;
+use search::{SearchGoal, SearchSpace};
+
+/// Cost made of several objectives. `Ord` orders costs lexicographically,
+/// which is how the search picks among incomparable ones.
+pub trait VectorCost: Clone + Ord + Debug {
+    fn zero() -> Self;
+    fn add(&self, other: &Self) -> Self;
+    /// Whether every objective of `self` is at most that of `other`.
+    fn all_le(&self, other: &Self) -> bool;
+
+    /// Whether `self` is at least as good on every objective and better on
+    /// one.
+    fn dominates(&self, other: &Self) -> bool {
+        self.all_le(other) && self != other
+    }
+}
+
+impl<C, const N: usize> VectorCost for [C; N] where C: Copy + Ord + Add<Output=C> + Default + Debug {
+    fn zero() -> Self {
+        [C::default(); N]
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        let mut sum = *self;
+        for (objective, &other) in sum.iter_mut().zip(other) {
+            *objective = *objective + other;
+        }
+        sum
+    }
+
+    fn all_le(&self, other: &Self) -> bool {
+        self.iter().zip(other).all(|(a, b)| a <= b)
+    }
+}
+
+/// Search space whose actions cost a vector of objectives.
+pub trait MultiObjectiveSearchSpace: SearchSpace {
+    type Cost: VectorCost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost;
+
+    /// Every Pareto-optimal path to a goal, one per distinct cost, by
+    /// NAMOA*; see `pareto::namoa`.
+    fn pareto_frontier<G, H>(&self, start: Self::State, goal: G, heuristic: H)
+        -> Vec<(Vec<Self::Action>, Self::Cost)>
+    where Self::Action: Clone, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        namoa(self, start, goal, heuristic)
+    }
+}
+
+struct Label<S: MultiObjectiveSearchSpace + ?Sized> {
+    node: usize,
+    g: S::Cost,
+    parent: Option<(usize, S::Action)>,
+    alive: bool,
+}
+
+/// NAMOA*: best-first search over cost labels. Each state keeps the costs
+/// of the non-dominated paths reaching it, and a path is dropped once
+/// another one to the same state, or its estimate against a solution found,
+/// dominates it.
+///
+/// `heuristic` must not overestimate any objective. Paths reaching a state
+/// at an equal cost are merged, so each returned cost comes with one path.
+/// The frontier is returned in lexicographic order of cost.
+pub fn namoa<S, G, H>(search_space: &S, start: S::State, goal: G, heuristic: H) -> Vec<(Vec<S::Action>, S::Cost)>
+where S: MultiObjectiveSearchSpace + ?Sized, S::Action: Clone, G: SearchGoal<S::State>,
+      H: Heuristic<S::State, S::Cost> {
+    let mut nodes: HashMap<S::State, usize> = HashMap::new();
+    let mut states = vec![start.clone()];
+    // Labels reaching each node, open or closed.
+    let mut reaching: Vec<Vec<usize>> = vec![vec![0]];
+    let mut labels: Vec<Label<S>> = vec![Label { node: 0, g: S::Cost::zero(), parent: None, alive: true }];
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((heuristic.estimate(&start), 0)));
+    nodes.insert(start, 0);
+    let mut solutions: Vec<usize> = Vec::new();
+
+    while let Some(Reverse((f, index))) = open.pop() {
+        if !labels[index].alive {
+            continue;
+        }
+        if solutions.iter().any(|&solution| labels[solution].g.all_le(&f)) {
+            labels[index].alive = false;
+            continue;
+        }
+        let node = labels[index].node;
+        let state = states[node].clone();
+        if goal.is_goal(&state) {
+            solutions.push(index);
+            continue;
+        }
+        let g = labels[index].g.clone();
+        for (action, successor) in search_space.expand(&state) {
+            let child_g = g.add(&search_space.cost(&state, &action));
+            let child = match nodes.get(&successor) {
+                Some(&child) => child,
+                None => {
+                    nodes.insert(successor.clone(), states.len());
+                    states.push(successor);
+                    reaching.push(Vec::new());
+                    states.len() - 1
+                }
+            };
+            let known = &reaching[child];
+            if known.iter().any(|&other| labels[other].g.all_le(&child_g)) {
+                continue;
+            }
+            let child_f = child_g.add(&heuristic.estimate(&states[child]));
+            if solutions.iter().any(|&solution| labels[solution].g.all_le(&child_f)) {
+                continue;
+            }
+            let mut kept = Vec::with_capacity(known.len() + 1);
+            for &other in known {
+                if child_g.dominates(&labels[other].g) {
+                    labels[other].alive = false;
+                } else {
+                    kept.push(other);
+                }
+            }
+            kept.push(labels.len());
+            reaching[child] = kept;
+            open.push(Reverse((child_f, labels.len())));
+            labels.push(Label { node: child, g: child_g, parent: Some((index, action)), alive: true });
+        }
+    }
+
+    let mut frontier: Vec<(Vec<S::Action>, S::Cost)> = solutions.into_iter().map(|solution| {
+        let mut actions = Vec::new();
+        let mut index = solution;
+        while let Some((parent, ref action)) = labels[index].parent {
+            actions.push(action.clone());
+            index = parent;
+        }
+        actions.reverse();
+        (actions, labels[solution].g.clone())
+    }).collect();
+    frontier.sort_by(|a, b| a.1.cmp(&b.1));
+    frontier
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::SearchSpace;
+    use super::{MultiObjectiveSearchSpace, VectorCost};
+
+    // Routes from 0 to 4 costing [time, toll].
+    struct Roads;
+
+    const EDGES: &[(u32, u32, [u32; 2])] = &[
+        (0, 1, [1, 5]), (1, 4, [1, 5]),
+        (0, 2, [2, 2]), (2, 4, [2, 2]),
+        (0, 3, [5, 0]), (3, 4, [5, 0]),
+        (2, 3, [1, 1]),
+        (1, 2, [3, 3]),
+    ];
+
+    impl SearchSpace for Roads {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            EDGES.iter().filter(|edge| edge.0 == *state).map(|edge| (edge.1, edge.1)).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    impl MultiObjectiveSearchSpace for Roads {
+        type Cost = [u32; 2];
+
+        fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+            EDGES.iter().find(|edge| edge.0 == *state && edge.1 == *action).unwrap().2
+        }
+    }
+
+    #[test]
+    pub fn test_pareto_frontier() {
+        assert!([1, 2].dominates(&[1, 3]) && ![1, 2].dominates(&[1, 2]) && ![1, 2].dominates(&[2, 1]));
+        let frontier = Roads.pareto_frontier(0, 4, |_: &u32| [0, 0]);
+        // 0-2-3-4 costs [8, 3] and is beaten by nothing; 0-1-2-4 costs [6, 10]
+        // and is dominated by 0-1-4.
+        assert_eq!(frontier, vec![(vec![1, 4], [2, 10]), (vec![2, 4], [4, 4]), (vec![2, 3, 4], [8, 3]),
+                                  (vec![3, 4], [10, 0])]);
+        let informed = Roads.pareto_frontier(0, 4, |&node: &u32| if node == 4 { [0, 0] } else { [1, 0] });
+        assert_eq!(informed, frontier);
+        assert_eq!(Roads.pareto_frontier(4, 0, |_: &u32| [0, 0]), vec![]);
+    }
+}
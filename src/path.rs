@@ -0,0 +1,79 @@
+//! Paths through a search space that keep the states passed along with the
+//! actions taken.
+
+use alloc::vec::Vec;
+
+/// States visited from the start, one more than the actions taken between
+/// them: `actions[i]` leads from `states[i]` to `states[i + 1]`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Path<S, A> {
+    states: Vec<S>,
+    actions: Vec<A>,
+}
+
+impl<S, A> Path<S, A> {
+    /// Path of no actions, standing at `start`.
+    pub fn new(start: S) -> Path<S, A> {
+        Path { states: vec![start], actions: Vec::new() }
+    }
+
+    /// Takes `action`, arriving at `state`.
+    pub fn push(&mut self, action: A, state: S) {
+        self.actions.push(action);
+        self.states.push(state);
+    }
+
+    pub fn start(&self) -> &S {
+        &self.states[0]
+    }
+
+    pub fn end(&self) -> &S {
+        &self.states[self.states.len() - 1]
+    }
+
+    pub fn states(&self) -> &[S] {
+        &self.states
+    }
+
+    pub fn actions(&self) -> &[A] {
+        &self.actions
+    }
+
+    /// `(from, action, to)` for every action taken, in order.
+    pub fn steps(&self) -> impl Iterator<Item=(&S, &A, &S)> {
+        self.states.windows(2).zip(&self.actions).map(|(pair, action)| (&pair[0], action, &pair[1]))
+    }
+
+    /// Number of actions taken.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn into_actions(self) -> Vec<A> {
+        self.actions
+    }
+
+    pub fn into_parts(self) -> (Vec<S>, Vec<A>) {
+        (self.states, self.actions)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::Path;
+
+    #[test]
+    pub fn test_steps() {
+        let mut path = Path::new('a');
+        assert_eq!(path.steps().count(), 0);
+        path.push(1, 'b');
+        path.push(2, 'c');
+        assert_eq!(path.steps().collect::<Vec<_>>(), vec![(&'a', &1, &'b'), (&'b', &2, &'c')]);
+        assert_eq!((path.start(), path.end(), path.len()), (&'a', &'c', 2));
+    }
+}
@@ -0,0 +1,277 @@
+//! Pattern databases: exact goal distances in an abstraction of a search
+//! space, computed once and looked up as a heuristic.
+
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ops::Add;
+
+use hashbrown::HashMap;
+
+use heuristic::Heuristic;
+use search::WeightedSearchSpace;
+use visited::RankableState;
+
+/// Storage of a pattern database's distances.
+pub trait PdbTable<P, C> {
+    fn get(&self, pattern: &P) -> Option<C>;
+    fn insert(&mut self, pattern: &P, distance: C);
+    /// Number of patterns stored.
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<P: Hash + Eq + Clone, C: Copy> PdbTable<P, C> for HashMap<P, C> {
+    fn get(&self, pattern: &P) -> Option<C> {
+        HashMap::get(self, pattern).copied()
+    }
+
+    fn insert(&mut self, pattern: &P, distance: C) {
+        HashMap::insert(self, pattern.clone(), distance);
+    }
+
+    fn len(&self) -> usize {
+        HashMap::len(self)
+    }
+}
+
+/// Dense table indexed by the rank of the pattern, for abstractions whose
+/// patterns rank onto `0..count`.
+#[derive(Clone, Debug)]
+pub struct RankedTable<C> {
+    distances: Vec<Option<C>>,
+}
+
+impl<C: Copy> RankedTable<C> {
+    pub fn new(count: usize) -> RankedTable<C> {
+        RankedTable { distances: vec![None; count] }
+    }
+}
+
+impl<P: RankableState, C: Copy> PdbTable<P, C> for RankedTable<C> {
+    fn get(&self, pattern: &P) -> Option<C> {
+        self.distances[pattern.rank()]
+    }
+
+    fn insert(&mut self, pattern: &P, distance: C) {
+        self.distances[pattern.rank()] = Some(distance);
+    }
+
+    fn len(&self) -> usize {
+        self.distances.iter().filter(|distance| distance.is_some()).count()
+    }
+}
+
+type Marker<T, P, C> = PhantomData<fn(&T) -> (P, C)>;
+
+/// Heuristic looking up the distance to the goal of a state's pattern, its
+/// image under `abstraction`. Patterns the goals cannot be reached from
+/// estimate zero.
+pub struct PatternDatabase<T, P, C, F, D = HashMap<P, C>> {
+    table: D,
+    abstraction: F,
+    marker: Marker<T, P, C>,
+}
+
+impl<T, P, C, F> PatternDatabase<T, P, C, F> where P: Hash + Eq + Clone, C: Copy + Ord + Default + Add<Output=C>, F: Fn(&T) -> P {
+    /// Builds the database by a uniform-cost search outwards from the
+    /// abstract `goals` over `backward`, a search space whose actions lead
+    /// from a pattern to its predecessors in the abstract space. For a
+    /// domain whose actions can all be undone that is the abstract space
+    /// itself, or its `SearchSpace::reversed` for costs that differ by
+    /// direction.
+    pub fn build<A, I>(backward: &A, goals: I, abstraction: F) -> PatternDatabase<T, P, C, F>
+    where A: WeightedSearchSpace<State=P, Cost=C>, I: IntoIterator<Item=P> {
+        PatternDatabase::build_into(HashMap::new(), backward, goals, abstraction)
+    }
+}
+
+impl<T, P, C, F> PatternDatabase<T, P, C, F, RankedTable<C>>
+where P: RankableState + Clone, C: Copy + Ord + Default + Add<Output=C>, F: Fn(&T) -> P {
+    /// Like `build`, storing the distances in a table indexed by the rank of
+    /// the patterns, which must fall below `count`.
+    pub fn build_ranked<A, I>(backward: &A, goals: I, abstraction: F, count: usize)
+        -> PatternDatabase<T, P, C, F, RankedTable<C>>
+    where A: WeightedSearchSpace<State=P, Cost=C>, I: IntoIterator<Item=P> {
+        PatternDatabase::build_into(RankedTable::new(count), backward, goals, abstraction)
+    }
+}
+
+impl<T, P, C, F, D> PatternDatabase<T, P, C, F, D> where C: Copy + Ord + Default, F: Fn(&T) -> P, D: PdbTable<P, C> {
+    fn build_into<A, I>(mut table: D, backward: &A, goals: I, abstraction: F) -> PatternDatabase<T, P, C, F, D>
+    where P: Clone, C: Add<Output=C>, A: WeightedSearchSpace<State=P, Cost=C>, I: IntoIterator<Item=P> {
+        // Entries order by distance; the sequence number keeps patterns,
+        // which need not be ordered, out of the comparison.
+        let mut patterns = Vec::new();
+        let mut open = BinaryHeap::new();
+        for goal in goals {
+            table.insert(&goal, C::default());
+            open.push(Reverse((C::default(), patterns.len())));
+            patterns.push(goal);
+        }
+        while let Some(Reverse((distance, index))) = open.pop() {
+            if table.get(&patterns[index]).is_some_and(|known| known < distance) {
+                continue;
+            }
+            let pattern = patterns[index].clone();
+            for (action, predecessor) in backward.expand(&pattern) {
+                let through = distance + backward.step_cost(&pattern, &action, &predecessor);
+                if table.get(&predecessor).is_none_or(|known| through < known) {
+                    table.insert(&predecessor, through);
+                    open.push(Reverse((through, patterns.len())));
+                    patterns.push(predecessor);
+                }
+            }
+        }
+        PatternDatabase { table, abstraction, marker: PhantomData }
+    }
+
+    /// Stored distance of `pattern` to the nearest abstract goal.
+    pub fn distance(&self, pattern: &P) -> Option<C> {
+        self.table.get(pattern)
+    }
+
+    /// Number of patterns stored.
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.is_empty()
+    }
+}
+
+impl<T, P, C, F, D> Heuristic<T, C> for PatternDatabase<T, P, C, F, D>
+where C: Copy + Ord + Default, F: Fn(&T) -> P, D: PdbTable<P, C> {
+    fn estimate(&self, state: &T) -> C {
+        self.distance(&(self.abstraction)(state)).unwrap_or_default()
+    }
+}
+
+impl<T, P, C, F, D> Heuristic<T, C> for &PatternDatabase<T, P, C, F, D>
+where C: Copy + Ord + Default, F: Fn(&T) -> P, D: PdbTable<P, C> {
+    fn estimate(&self, state: &T) -> C {
+        (**self).estimate(state)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use domains::sliding::{Board, SlidingPuzzle};
+    use astar::AStarBuffers;
+    use heuristic::Heuristic;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use visited::{permutation_rank, RankableState};
+    use super::PatternDatabase;
+
+    // The 8-puzzle with tiles outside the pattern all marked 9.
+    struct Abstract8;
+
+    impl SearchSpace for Abstract8 {
+        type State = [u8; 9];
+        type Action = usize;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, cells: &Self::State) -> Self::Iterator {
+            let blank = cells.iter().position(|&tile| tile == 0).unwrap();
+            let (x, y) = (blank % 3, blank / 3);
+            let mut moves = Vec::new();
+            if x > 0 { moves.push(blank - 1) }
+            if x < 2 { moves.push(blank + 1) }
+            if y > 0 { moves.push(blank - 3) }
+            if y < 2 { moves.push(blank + 3) }
+            moves.into_iter().map(|to| {
+                let mut next = *cells;
+                next.swap(blank, to);
+                (to, next)
+            }).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Abstract8 {
+        type Cost = u32;
+
+        fn cost(&self, _cells: &Self::State, _to: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    fn pattern(board: &Board) -> [u8; 9] {
+        let mut cells = [9; 9];
+        for (position, cell) in cells.iter_mut().enumerate() {
+            if board.get(position) <= 4 {
+                *cell = board.get(position);
+            }
+        }
+        cells
+    }
+
+    #[test]
+    pub fn test_pattern_database() {
+        let puzzle = SlidingPuzzle::new(3, 3);
+        let pdb = PatternDatabase::build(&Abstract8, vec![pattern(&puzzle.goal())], pattern);
+        assert_eq!(pdb.len(), 9 * 8 * 7 * 6 * 5);
+        assert_eq!(pdb.estimate(&puzzle.goal()), 0);
+
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..5 {
+            let board = puzzle.scramble(40, &mut rng);
+            let mut uninformed = AStarBuffers::new();
+            let mut informed = AStarBuffers::new();
+            let optimal = puzzle.astar_with(&mut uninformed, board, puzzle.goal(), |_: &_| 0).unwrap().1;
+            assert_eq!(puzzle.astar_with(&mut informed, board, puzzle.goal(), &pdb).unwrap().1, optimal);
+            assert!(pdb.estimate(&board) <= optimal);
+            assert!(informed.stats().expanded <= uninformed.stats().expanded);
+        }
+    }
+
+    // Permutations of three items, where an action swaps two neighbours.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    struct Order([u8; 3]);
+
+    impl RankableState for Order {
+        fn rank(&self) -> usize {
+            permutation_rank(&self.0)
+        }
+    }
+
+    struct Swaps;
+
+    impl SearchSpace for Swaps {
+        type State = Order;
+        type Action = usize;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, order: &Self::State) -> Self::Iterator {
+            (0..2).map(|i| {
+                let mut next = *order;
+                next.0.swap(i, i + 1);
+                (i, next)
+            }).collect::<Vec<_>>().into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Swaps {
+        type Cost = u8;
+
+        fn cost(&self, _order: &Self::State, _swap: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    #[test]
+    pub fn test_ranked_pattern_database() {
+        let pdb = PatternDatabase::build_ranked(&Swaps, vec![Order([0, 1, 2])], |order: &Order| *order, 6);
+        assert_eq!(pdb.len(), 6);
+        assert_eq!(pdb.distance(&Order([2, 1, 0])), Some(3));
+        assert_eq!(pdb.estimate(&Order([1, 0, 2])), 1);
+    }
+}
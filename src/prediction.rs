@@ -0,0 +1,159 @@
+//! Predicting how many nodes an IDA* iteration expands before running it.
+
+use alloc::collections::BTreeMap;
+use core::time::Duration;
+
+use rand::Rng;
+
+use heuristic::Heuristic;
+use search::{ExpandContext, WeightedSearchSpace};
+use stats::SearchStats;
+
+/// Estimates the size of the tree an IDA* iteration with a given cost bound
+/// expands, by Chen's stratified sampling: each probe walks the tree a level
+/// at a time, keeping one representative of each type of node, (g, h) here,
+/// weighted by how many nodes of that type it stands for. Averaging several
+/// probes gives the estimate, and `eta` turns it into time left once the
+/// expansion rate is known.
+///
+/// The tree is the one IDA* explores without transposition detection: every
+/// path from the start with `g + h` within the bound counts, so actions must
+/// have positive cost.
+pub struct IdaStarPredictor<'a, S: WeightedSearchSpace + ?Sized + 'a, H> {
+    search_space: &'a S,
+    heuristic: H,
+}
+
+impl<'a, S, H> IdaStarPredictor<'a, S, H>
+where S: WeightedSearchSpace + ?Sized, H: Heuristic<S::State, S::Cost> {
+    pub fn new(search_space: &'a S, heuristic: H) -> IdaStarPredictor<'a, S, H> {
+        IdaStarPredictor { search_space, heuristic }
+    }
+
+    /// Nodes expanded by an iteration from `start` with cost bound `bound`
+    /// that runs to completion, averaged over `probes` probes.
+    pub fn predict<R: Rng>(&self, start: S::State, bound: S::Cost, probes: usize, rng: &mut R) -> f64 {
+        if probes == 0 {
+            return 0.0;
+        }
+        (0..probes).map(|_| self.probe(start.clone(), bound, rng)).sum::<f64>() / probes as f64
+    }
+
+    fn probe<R: Rng>(&self, start: S::State, bound: S::Cost, rng: &mut R) -> f64 {
+        let start_h = self.heuristic.estimate(&start);
+        if start_h > bound {
+            return 0.0;
+        }
+        let mut expanded = 0.0;
+        let mut level = BTreeMap::new();
+        level.insert((S::Cost::default(), start_h), (start, 1.0));
+        let mut depth = 0;
+        while !level.is_empty() {
+            let mut next: BTreeMap<_, (S::State, f64)> = BTreeMap::new();
+            for ((g, _), (state, weight)) in level {
+                expanded += weight;
+                for (action, successor) in self.search_space.expand_weighted(&state, ExpandContext { depth, cost: g }) {
+                    let child_g = g + self.search_space.step_cost(&state, &action, &successor);
+                    let child_h = self.heuristic.estimate(&successor);
+                    if child_g + child_h > bound {
+                        continue;
+                    }
+                    match next.get_mut(&(child_g, child_h)) {
+                        Some(&mut (ref mut representative, ref mut total)) => {
+                            *total += weight;
+                            if rng.gen::<f64>() * *total < weight {
+                                *representative = successor;
+                            }
+                        }
+                        None => {
+                            next.insert((child_g, child_h), (successor, weight));
+                        }
+                    }
+                }
+            }
+            level = next;
+            depth += 1;
+        }
+        expanded
+    }
+}
+
+/// Time until a search that has so far recorded `stats` expands
+/// `predicted` nodes in all, at the rate it has expanded them so far. `None`
+/// until it has both expanded nodes and taken measurable time.
+pub fn eta(predicted: f64, stats: &SearchStats) -> Option<Duration> {
+    let seconds = stats.elapsed.as_secs_f64();
+    if stats.expanded == 0 || seconds == 0.0 {
+        return None;
+    }
+    let remaining = (predicted - stats.expanded as f64).max(0.0);
+    Some(Duration::from_secs_f64(remaining * seconds / stats.expanded as f64))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::time::Duration;
+    use std::vec::IntoIter;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Cell, Grid};
+    use search::{SearchSpace, WeightedSearchSpace};
+    use stats::SearchStats;
+    use super::{eta, IdaStarPredictor};
+
+    // Binary tree of unit-cost actions.
+    struct Binary;
+
+    impl SearchSpace for Binary {
+        type State = u64;
+        type Action = u64;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, &state: &Self::State) -> Self::Iterator {
+            vec![(0, state * 2), (1, state * 2 + 1)].into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Binary {
+        type Cost = u64;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    fn ida_star_tree(grid: &Grid, cell: Cell, g: u64, bound: u64) -> u64 {
+        if g + manhattan(cell, (4, 4)) > bound {
+            return 0;
+        }
+        1 + grid.expand(&cell).map(|(action, next)| ida_star_tree(grid, next, g + grid.step_cost(&cell, &action, &next), bound)).sum::<u64>()
+    }
+
+    #[test]
+    pub fn test_predict() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let uniform = IdaStarPredictor::new(&Binary, |_: &u64| 0);
+        assert_eq!(uniform.predict(1, 10, 3, &mut rng), 2047.0);
+        assert_eq!(uniform.predict(1, 10, 0, &mut rng), 0.0);
+
+        let grid = random_grid(5, 5, 0.0, &mut StdRng::seed_from_u64(1));
+        let predictor = IdaStarPredictor::new(&grid, |cell: &Cell| manhattan(*cell, (4, 4)));
+        assert_eq!(predictor.predict((0, 0), 7, 5, &mut rng), 0.0);
+        let exact = ida_star_tree(&grid, (0, 0), 0, 10) as f64;
+        let predicted = predictor.predict((0, 0), 10, 500, &mut rng);
+        assert!((predicted - exact).abs() < exact * 0.1, "{} predicted for {}", predicted, exact);
+    }
+
+    #[test]
+    pub fn test_eta() {
+        let mut stats = SearchStats::new();
+        assert_eq!(eta(2047.0, &stats), None);
+        stats.expanded = 500;
+        assert_eq!(eta(2047.0, &stats), None);
+        stats.elapsed = Duration::from_secs(2);
+        // 1500 nodes to go at 250 a second.
+        assert_eq!(eta(2000.0, &stats), Some(Duration::from_secs(6)));
+        assert_eq!(eta(100.0, &stats), Some(Duration::ZERO));
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+
+use observer::Observer;
+use stopwatch::Stopwatch;
+
+/// Snapshot of a running search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Progress<C = ()> {
+    pub expanded: u64,
+    pub generated: u64,
+    /// Estimated frontier size: states kept but not yet expanded or pruned.
+    pub open: u64,
+    pub max_depth: usize,
+    /// Cost of the most recently expanded state; for A* its `f` is a lower
+    /// bound on the cost of any solution still to be found.
+    pub cost: Option<C>,
+    pub elapsed: Duration,
+    /// Time left, when the expected number of expansions is known.
+    pub eta: Option<Duration>,
+}
+
+/// Observer passing periodic `Progress` updates to a sink.
+///
+/// Updates are sent at most once per interval (one second by default) and
+/// once more when a goal is reached.
+pub struct ProgressReporter<C, F> {
+    sink: F,
+    interval: Duration,
+    expected: Option<u64>,
+    started: Option<Stopwatch>,
+    last_report: Duration,
+    expanded: u64,
+    generated: u64,
+    pruned: u64,
+    max_depth: usize,
+    cost: Option<C>,
+}
+
+impl<C: Clone, F: FnMut(&Progress<C>)> ProgressReporter<C, F> {
+    pub fn new(sink: F) -> ProgressReporter<C, F> {
+        ProgressReporter {
+            sink,
+            interval: Duration::from_secs(1),
+            expected: None,
+            started: None,
+            last_report: Duration::default(),
+            expanded: 0,
+            generated: 0,
+            pruned: 0,
+            max_depth: 0,
+            cost: None,
+        }
+    }
+
+    /// Sets the minimum time between updates.
+    pub fn every(mut self, interval: Duration) -> ProgressReporter<C, F> {
+        self.interval = interval;
+        self
+    }
+
+    /// Enables ETA estimates for a search expected to expand about
+    /// `expansions` states.
+    pub fn expecting(mut self, expansions: u64) -> ProgressReporter<C, F> {
+        self.expected = Some(expansions);
+        self
+    }
+
+    /// Forgets the counters so the reporter can follow another search.
+    pub fn reset(&mut self) {
+        self.started = None;
+        self.last_report = Duration::default();
+        self.expanded = 0;
+        self.generated = 0;
+        self.pruned = 0;
+        self.max_depth = 0;
+        self.cost = None;
+    }
+
+    pub fn progress(&self) -> Progress<C> {
+        let elapsed = self.started.as_ref().map(Stopwatch::elapsed).unwrap_or_default();
+        let eta = match self.expected {
+            Some(expected) if self.expanded > 0 => {
+                let remaining = expected.saturating_sub(self.expanded);
+                Some(elapsed.mul_f64(remaining as f64 / self.expanded as f64))
+            }
+            _ => None,
+        };
+        Progress {
+            expanded: self.expanded,
+            generated: self.generated,
+            open: (1 + self.generated).saturating_sub(self.expanded + self.pruned),
+            max_depth: self.max_depth,
+            cost: self.cost.clone(),
+            elapsed,
+            eta,
+        }
+    }
+
+    fn report(&mut self) {
+        let progress = self.progress();
+        (self.sink)(&progress);
+        self.last_report = progress.elapsed;
+    }
+}
+
+/// Sink forwarding updates over a channel, e.g. to a UI thread. Updates are
+/// dropped once the receiver hangs up.
+pub fn send_to<C: Clone>(sender: Sender<Progress<C>>) -> impl FnMut(&Progress<C>) {
+    move |progress| {
+        let _ = sender.send(progress.clone());
+    }
+}
+
+impl<S, A, C: Clone, F: FnMut(&Progress<C>)> Observer<S, A, C> for ProgressReporter<C, F> {
+    fn on_expand(&mut self, _state: &S, depth: usize, cost: &C) {
+        let elapsed = self.started.get_or_insert_with(Stopwatch::start).elapsed();
+        self.expanded += 1;
+        self.max_depth = self.max_depth.max(depth);
+        self.cost = Some(cost.clone());
+        if elapsed.saturating_sub(self.last_report) >= self.interval {
+            self.report();
+        }
+    }
+
+    fn on_generate(&mut self, _parent: &S, _action: &A, _state: &S, depth: usize, _cost: &C) {
+        self.generated += 1;
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    fn on_goal(&mut self, _state: &S, _depth: usize, _cost: &C) {
+        self.report();
+    }
+
+    fn on_prune(&mut self, _state: &S, _depth: usize) {
+        self.pruned += 1;
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::mpsc::channel;
+    use std::time::Duration;
+    use std::vec::IntoIter;
+    use astar::AStarBuffers;
+    use observer::NodeCost;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::{send_to, ProgressReporter};
+
+    // Binary tree of depth 4 numbered heap-style from 1.
+    struct Tree;
+
+    impl SearchSpace for Tree {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            if *state < 16 {
+                vec![(0, state * 2), (1, state * 2 + 1)].into_iter()
+            } else {
+                vec![].into_iter()
+            }
+        }
+    }
+
+    impl WeightedSearchSpace for Tree {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, action: &Self::Action) -> Self::Cost {
+            action + 1
+        }
+    }
+
+    #[test]
+    pub fn test_progress_callback() {
+        let mut updates = vec![];
+        {
+            let reporter = ProgressReporter::new(|progress: &_| updates.push(progress.clone()))
+                .every(Duration::from_secs(0))
+                .expecting(30);
+            let mut buffers = SearchBuffers::new().with_observer(reporter);
+            assert!(Tree.dfs_with(&mut buffers, 1, 31).is_some());
+        }
+        let last = updates.last().unwrap();
+        assert_eq!((last.expanded, last.generated, last.open, last.max_depth), (30, 30, 1, 4));
+        assert_eq!(last.eta, Some(Duration::from_secs(0)));
+        assert!(updates.windows(2).all(|pair| pair[0].expanded <= pair[1].expanded));
+    }
+
+    #[test]
+    pub fn test_progress_channel() {
+        let (sender, receiver) = channel();
+        let reporter = ProgressReporter::new(send_to(sender)).every(Duration::from_secs(3600));
+        let mut buffers = AStarBuffers::new().with_observer(reporter);
+        assert_eq!(Tree.astar_with(&mut buffers, 1, 16, |_: &u32| 0).map(|found| found.1), Some(4));
+        drop(buffers);
+        let updates: Vec<_> = receiver.iter().collect();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].cost, Some(NodeCost { g: 4, f: 4 }));
+        assert!(updates[0].eta.is_none());
+    }
+}
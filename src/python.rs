@@ -0,0 +1,247 @@
+//! Python bindings exposing the built-in graph and grid spaces.
+//!
+//! Build an importable `chappie` module with maturin, enabling the `python`
+//! feature together with `pyo3/extension-module`.
+
+// The pymethods expansion converts every returned PyErr into itself.
+#![allow(clippy::useless_conversion)]
+
+use std::cell::RefCell;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use batch::BatchSearchSpace;
+use graph::Graph;
+use grid::{manhattan, Cell, Grid};
+use search::{SearchSpace, WeightedSearchSpace};
+
+/// Directed graph with integer edge costs and nodes numbered from zero.
+#[pyclass(name = "Graph")]
+pub struct PyGraph {
+    graph: Graph,
+}
+
+impl PyGraph {
+    fn check(&self, nodes: &[usize]) -> PyResult<()> {
+        match nodes.iter().find(|&&node| node >= self.graph.node_count()) {
+            Some(node) => Err(PyValueError::new_err(format!("node {} out of range", node))),
+            None => Ok(()),
+        }
+    }
+
+    fn costed(&self, start: usize, actions: Vec<usize>) -> (Vec<usize>, u64) {
+        let nodes = self.graph.walk(start, &actions);
+        let cost = nodes.iter().zip(&actions).map(|(&node, action)| self.graph.cost(&node, action)).sum();
+        (nodes, cost)
+    }
+}
+
+#[pymethods]
+impl PyGraph {
+    #[new]
+    #[pyo3(signature = (nodes = 0))]
+    fn new(nodes: usize) -> PyGraph {
+        PyGraph { graph: Graph::new(nodes) }
+    }
+
+    /// Graph from `(from, to, cost)` triples, sized to fit every node.
+    #[staticmethod]
+    fn from_edges(edges: Vec<(usize, usize, u64)>) -> PyGraph {
+        let nodes = edges.iter().map(|&(from, to, _)| from.max(to) + 1).max().unwrap_or(0);
+        let mut graph = Graph::new(nodes);
+        for (from, to, cost) in edges {
+            graph.add_edge(from, to, cost);
+        }
+        PyGraph { graph }
+    }
+
+    fn add_node(&mut self) -> usize {
+        self.graph.add_node()
+    }
+
+    #[pyo3(signature = (from, to, cost = 1))]
+    fn add_edge(&mut self, from: usize, to: usize, cost: u64) -> PyResult<()> {
+        self.check(&[from, to])?;
+        self.graph.add_edge(from, to, cost);
+        Ok(())
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    /// Nodes of a path with the fewest edges, or `None`.
+    fn bfs(&self, start: usize, goal: usize) -> PyResult<Option<Vec<usize>>> {
+        self.check(&[start, goal])?;
+        Ok(self.graph.bfs_batched(start, goal, 64).map(|actions| self.graph.walk(start, &actions)))
+    }
+
+    /// Nodes of some path, found depth-first, or `None`.
+    fn dfs(&self, start: usize, goal: usize) -> PyResult<Option<Vec<usize>>> {
+        self.check(&[start, goal])?;
+        Ok(self.graph.dfs(start, goal).map(|actions| self.graph.walk(start, &actions)))
+    }
+
+    /// Nodes and cost of a cheapest path, or `None`.
+    fn dijkstra(&self, start: usize, goal: usize) -> PyResult<Option<(Vec<usize>, u64)>> {
+        self.check(&[start, goal])?;
+        Ok(self.graph.astar(start, goal, |_: &usize| 0).map(|(actions, _)| self.costed(start, actions)))
+    }
+
+    /// Like `dijkstra`, guided by `heuristic`, a callable estimating the
+    /// remaining cost from a node.
+    fn astar(&self, start: usize, goal: usize, heuristic: PyObject) -> PyResult<Option<(Vec<usize>, u64)>> {
+        self.check(&[start, goal])?;
+        Python::with_gil(|py| {
+            let error = RefCell::new(None);
+            let estimate = |node: &usize| {
+                if error.borrow().is_some() {
+                    return 0;
+                }
+                match heuristic.call1(py, (*node,)).and_then(|h| h.extract::<u64>(py)) {
+                    Ok(h) => h,
+                    Err(err) => {
+                        *error.borrow_mut() = Some(err);
+                        0
+                    }
+                }
+            };
+            let found = self.graph.astar(start, goal, estimate);
+            match error.into_inner() {
+                Some(err) => Err(err),
+                None => Ok(found.map(|(actions, _)| self.costed(start, actions))),
+            }
+        })
+    }
+}
+
+/// Four-connected grid of open and blocked cells with unit move costs.
+#[pyclass(name = "Grid")]
+pub struct PyGrid {
+    grid: Grid,
+}
+
+impl PyGrid {
+    fn check(&self, cells: &[Cell]) -> PyResult<()> {
+        match cells.iter().find(|&&cell| !self.grid.contains(cell)) {
+            Some(cell) => Err(PyValueError::new_err(format!("cell {:?} outside the grid", cell))),
+            None => Ok(()),
+        }
+    }
+
+    fn walk(start: Cell, actions: Vec<<Grid as SearchSpace>::Action>) -> Vec<Cell> {
+        let mut cells = vec![start];
+        for action in actions {
+            let next = action.step(cells[cells.len() - 1]).expect("path stays on the grid");
+            cells.push(next);
+        }
+        cells
+    }
+}
+
+#[pymethods]
+impl PyGrid {
+    #[new]
+    fn new(width: usize, height: usize) -> PyGrid {
+        PyGrid { grid: Grid::new(width, height) }
+    }
+
+    /// Grid drawn as equally long rows of text where `#` marks a blocked
+    /// cell.
+    #[staticmethod]
+    fn from_rows(rows: Vec<String>) -> PyResult<PyGrid> {
+        let width = rows.first().map_or(0, |row| row.chars().count());
+        let mut grid = Grid::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            if row.chars().count() != width {
+                return Err(PyValueError::new_err(format!("row {} is not {} cells wide", y, width)));
+            }
+            for (x, c) in row.chars().enumerate() {
+                grid.set_blocked((x, y), c == '#');
+            }
+        }
+        Ok(PyGrid { grid })
+    }
+
+    #[getter]
+    fn width(&self) -> usize {
+        self.grid.width()
+    }
+
+    #[getter]
+    fn height(&self) -> usize {
+        self.grid.height()
+    }
+
+    fn is_blocked(&self, x: usize, y: usize) -> bool {
+        self.grid.is_blocked((x, y))
+    }
+
+    #[pyo3(signature = (x, y, blocked = true))]
+    fn set_blocked(&mut self, x: usize, y: usize, blocked: bool) -> PyResult<()> {
+        self.check(&[(x, y)])?;
+        self.grid.set_blocked((x, y), blocked);
+        Ok(())
+    }
+
+    /// Cells and length of a shortest path found by A* with the Manhattan
+    /// distance, or `None`.
+    fn astar(&self, start: Cell, goal: Cell) -> PyResult<Option<(Vec<Cell>, u64)>> {
+        self.check(&[start, goal])?;
+        let found = self.grid.astar(start, goal, |cell: &Cell| manhattan(*cell, goal));
+        Ok(found.map(|(actions, cost)| (PyGrid::walk(start, actions), cost)))
+    }
+
+    /// Cells of some path, found depth-first, or `None`.
+    fn dfs(&self, start: Cell, goal: Cell) -> PyResult<Option<Vec<Cell>>> {
+        self.check(&[start, goal])?;
+        Ok(self.grid.dfs(start, goal).map(|actions| PyGrid::walk(start, actions)))
+    }
+}
+
+#[pymodule]
+fn chappie(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyGraph>()?;
+    module.add_class::<PyGrid>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+    use super::chappie;
+
+    #[test]
+    pub fn test_python_bindings() {
+        pyo3::append_to_inittab!(chappie);
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let locals = PyDict::new_bound(py);
+            py.run_bound(r#"
+import chappie
+graph = chappie.Graph.from_edges([(0, 1, 1), (1, 2, 1), (0, 2, 5)])
+assert graph.bfs(0, 2) == [0, 2]
+assert graph.dijkstra(0, 2) == ([0, 1, 2], 2)
+assert graph.astar(0, 2, lambda node: 2 - node) == ([0, 1, 2], 2)
+assert graph.dfs(2, 0) is None
+try:
+    graph.astar(0, 2, lambda node: 1 / 0)
+    raise AssertionError("heuristic error not raised")
+except ZeroDivisionError:
+    pass
+
+grid = chappie.Grid.from_rows([".#.", ".#.", "..."])
+cells, cost = grid.astar((0, 0), (2, 0))
+assert cost == 6 and cells[0] == (0, 0) and cells[-1] == (2, 0)
+grid.set_blocked(1, 2)
+assert grid.astar((0, 0), (2, 0)) is None
+"#, None, Some(&locals)).unwrap();
+        });
+    }
+}
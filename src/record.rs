@@ -0,0 +1,397 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Write};
+use core::hash::Hash;
+
+use hashbrown::HashMap;
+
+use observer::{NodeCost, Observer};
+
+/// A single step of a recorded search.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TraceEvent<S, A, C = ()> {
+    Expand { state: S, depth: usize, cost: C },
+    Generate { parent: S, action: A, state: S, depth: usize, cost: C },
+    Duplicate { state: S, depth: usize },
+    Goal { state: S, depth: usize, cost: C },
+    Prune { state: S, depth: usize },
+}
+
+/// Cost information that can be written into a JSON trace.
+pub trait TraceCost {
+    /// Appends the cost as `,"key":value` members of an object.
+    fn write_json_fields(&self, json: &mut String);
+}
+
+impl TraceCost for () {
+    fn write_json_fields(&self, _json: &mut String) {}
+}
+
+impl<C: Display> TraceCost for NodeCost<C> {
+    fn write_json_fields(&self, json: &mut String) {
+        json.push_str(",\"g\":");
+        write_json_number(json, &self.g);
+        json.push_str(",\"f\":");
+        write_json_number(json, &self.f);
+    }
+}
+
+/// Observer keeping every event of a search in order, exportable as JSON for
+/// stepping through the search in external tools.
+pub struct SearchTrace<S, A, C = ()> {
+    events: Vec<TraceEvent<S, A, C>>,
+}
+
+impl<S, A, C> SearchTrace<S, A, C> {
+    pub fn new() -> SearchTrace<S, A, C> {
+        SearchTrace { events: Vec::new() }
+    }
+
+    pub fn events(&self) -> &[TraceEvent<S, A, C>] {
+        &self.events
+    }
+
+    pub fn into_events(self) -> Vec<TraceEvent<S, A, C>> {
+        self.events
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    /// Steps through the recorded events from the start.
+    pub fn replay(&self) -> Replay<'_, S, A, C> {
+        Replay::new(&self.events)
+    }
+}
+
+impl<S: Hash + Eq + Debug, A: Debug, C: TraceCost> SearchTrace<S, A, C> {
+    /// Renders the trace as a JSON document.
+    ///
+    /// States get numeric ids in order of first appearance, listed under
+    /// `"nodes"` with their `Debug` rendering. `"events"` holds one object per
+    /// step with `"step"`, `"event"` (`expand`, `generate`, `duplicate`, `goal`
+    /// or `prune`), `"node"` and `"depth"`; generate events add `"parent"` and
+    /// `"action"`, and cost-based searches add `"g"` and `"f"`.
+    pub fn to_json(&self) -> String {
+        let mut ids = HashMap::new();
+        let mut states = Vec::new();
+        let mut events = String::new();
+        for (step, event) in self.events.iter().enumerate() {
+            if step > 0 {
+                events.push(',');
+            }
+            let _ = write!(events, "{{\"step\":{}", step);
+            match *event {
+                TraceEvent::Expand { ref state, depth, ref cost } => {
+                    let node = node_id(&mut ids, &mut states, state);
+                    let _ = write!(events, ",\"event\":\"expand\",\"node\":{},\"depth\":{}", node, depth);
+                    cost.write_json_fields(&mut events);
+                }
+                TraceEvent::Generate { ref parent, ref action, ref state, depth, ref cost } => {
+                    let parent = node_id(&mut ids, &mut states, parent);
+                    let node = node_id(&mut ids, &mut states, state);
+                    let _ = write!(events, ",\"event\":\"generate\",\"node\":{},\"depth\":{},\"parent\":{},\"action\":",
+                                   node, depth, parent);
+                    write_json_string(&mut events, &format!("{:?}", action));
+                    cost.write_json_fields(&mut events);
+                }
+                TraceEvent::Duplicate { ref state, depth } => {
+                    let node = node_id(&mut ids, &mut states, state);
+                    let _ = write!(events, ",\"event\":\"duplicate\",\"node\":{},\"depth\":{}", node, depth);
+                }
+                TraceEvent::Goal { ref state, depth, ref cost } => {
+                    let node = node_id(&mut ids, &mut states, state);
+                    let _ = write!(events, ",\"event\":\"goal\",\"node\":{},\"depth\":{}", node, depth);
+                    cost.write_json_fields(&mut events);
+                }
+                TraceEvent::Prune { ref state, depth } => {
+                    let node = node_id(&mut ids, &mut states, state);
+                    let _ = write!(events, ",\"event\":\"prune\",\"node\":{},\"depth\":{}", node, depth);
+                }
+            }
+            events.push('}');
+        }
+
+        let mut json = String::from("{\"nodes\":[");
+        for (id, state) in states.iter().enumerate() {
+            if id > 0 {
+                json.push(',');
+            }
+            let _ = write!(json, "{{\"id\":{},\"state\":", id);
+            write_json_string(&mut json, &format!("{:?}", state));
+            json.push('}');
+        }
+        json.push_str("],\"events\":[");
+        json.push_str(&events);
+        json.push_str("]}");
+        json
+    }
+}
+
+impl<S, A, C> Default for SearchTrace<S, A, C> {
+    fn default() -> SearchTrace<S, A, C> {
+        SearchTrace::new()
+    }
+}
+
+impl<S: Clone, A: Clone, C: Clone> Observer<S, A, C> for SearchTrace<S, A, C> {
+    fn on_expand(&mut self, state: &S, depth: usize, cost: &C) {
+        self.events.push(TraceEvent::Expand { state: state.clone(), depth, cost: cost.clone() });
+    }
+
+    fn on_generate(&mut self, parent: &S, action: &A, state: &S, depth: usize, cost: &C) {
+        self.events.push(TraceEvent::Generate {
+            parent: parent.clone(),
+            action: action.clone(),
+            state: state.clone(),
+            depth,
+            cost: cost.clone(),
+        });
+    }
+
+    fn on_duplicate(&mut self, state: &S, depth: usize) {
+        self.events.push(TraceEvent::Duplicate { state: state.clone(), depth });
+    }
+
+    fn on_goal(&mut self, state: &S, depth: usize, cost: &C) {
+        self.events.push(TraceEvent::Goal { state: state.clone(), depth, cost: cost.clone() });
+    }
+
+    fn on_prune(&mut self, state: &S, depth: usize) {
+        self.events.push(TraceEvent::Prune { state: state.clone(), depth });
+    }
+}
+
+/// Cursor stepping forwards and backwards through recorded search events.
+///
+/// Everything is reconstructed from the events alone, so a search can be
+/// inspected without the search space that produced it.
+pub struct Replay<'a, S: 'a, A: 'a, C: 'a = ()> {
+    events: &'a [TraceEvent<S, A, C>],
+    position: usize,
+}
+
+impl<'a, S, A, C> Replay<'a, S, A, C> {
+    pub fn new(events: &'a [TraceEvent<S, A, C>]) -> Replay<'a, S, A, C> {
+        Replay { events, position: 0 }
+    }
+
+    /// Number of events replayed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position == self.events.len()
+    }
+
+    /// Undoes the last replayed event and returns it.
+    pub fn step_back(&mut self) -> Option<&'a TraceEvent<S, A, C>> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        Some(&self.events[self.position])
+    }
+
+    /// Moves to just after the first `position` events.
+    pub fn seek(&mut self, position: usize) {
+        self.position = position.min(self.events.len());
+    }
+
+    /// Events replayed so far.
+    pub fn history(&self) -> &'a [TraceEvent<S, A, C>] {
+        &self.events[..self.position]
+    }
+}
+
+impl<'a, S: PartialEq, A, C> Replay<'a, S, A, C> {
+    /// Whether `state` has been expanded by this point of the search.
+    pub fn is_expanded(&self, state: &S) -> bool {
+        self.history().iter().any(|event| match *event {
+            TraceEvent::Expand { state: ref expanded, .. } => expanded == state,
+            _ => false,
+        })
+    }
+
+    /// Actions leading to `state` along the parent links known at this point
+    /// of the search, or `None` if it has not been reached yet.
+    pub fn path_to(&self, state: &S) -> Option<Vec<&'a A>> {
+        let history = self.history();
+        let mut actions = vec![];
+        let mut current = state;
+        let mut end = history.len();
+        loop {
+            let generated = history[..end].iter().enumerate().rev().filter_map(|(index, event)| match *event {
+                TraceEvent::Generate { ref parent, ref action, state: ref child, .. } if child == current =>
+                    Some((index, parent, action)),
+                _ => None,
+            }).next();
+            match generated {
+                Some((index, parent, action)) => {
+                    actions.push(action);
+                    current = parent;
+                    end = index;
+                }
+                None if actions.is_empty() && !history.iter().any(|event| event.state() == current) => return None,
+                None => break,
+            }
+        }
+        actions.reverse();
+        Some(actions)
+    }
+}
+
+impl<'a, S, A, C> Iterator for Replay<'a, S, A, C> {
+    type Item = &'a TraceEvent<S, A, C>;
+
+    fn next(&mut self) -> Option<&'a TraceEvent<S, A, C>> {
+        let event = self.events.get(self.position)?;
+        self.position += 1;
+        Some(event)
+    }
+}
+
+impl<S, A, C> TraceEvent<S, A, C> {
+    /// The state the event is about; for generate events the new state.
+    pub fn state(&self) -> &S {
+        match *self {
+            TraceEvent::Expand { ref state, .. } |
+            TraceEvent::Generate { ref state, .. } |
+            TraceEvent::Duplicate { ref state, .. } |
+            TraceEvent::Goal { ref state, .. } |
+            TraceEvent::Prune { ref state, .. } => state,
+        }
+    }
+}
+
+fn node_id<'a, S: Hash + Eq>(ids: &mut HashMap<&'a S, usize>, states: &mut Vec<&'a S>, state: &'a S) -> usize {
+    *ids.entry(state).or_insert_with(|| {
+        states.push(state);
+        states.len() - 1
+    })
+}
+
+fn write_json_number<T: Display>(json: &mut String, value: &T) {
+    let text = value.to_string();
+    match text.parse::<f64>() {
+        Ok(number) if number.is_finite() => json.push_str(&text),
+        _ => write_json_string(json, &text),
+    }
+}
+
+fn write_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use astar::AStarBuffers;
+    use observer::NodeCost;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use super::{SearchTrace, TraceEvent};
+
+    struct Diamond;
+
+    impl SearchSpace for Diamond {
+        type State = u8;
+        type Action = char;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![('a', 1), ('b', 2)],
+                1 | 2 => vec![('c', 3)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Diamond {
+        type Cost = u32;
+
+        fn cost(&self, state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            *state as u32 + 1
+        }
+    }
+
+    #[test]
+    pub fn test_search_trace_dfs() {
+        let mut trace = SearchTrace::new();
+        Diamond.dfs_with(&mut SearchBuffers::new().with_observer(&mut trace), 0, 3);
+        assert_eq!(trace.events()[1], TraceEvent::Generate { parent: 0, action: 'a', state: 1, depth: 1, cost: () });
+        assert_eq!(trace.to_json(), concat!(
+            r#"{"nodes":[{"id":0,"state":"0"},{"id":1,"state":"1"},{"id":2,"state":"3"}],"events":["#,
+            r#"{"step":0,"event":"expand","node":0,"depth":0},"#,
+            r#"{"step":1,"event":"generate","node":1,"depth":1,"parent":0,"action":"'a'"},"#,
+            r#"{"step":2,"event":"expand","node":1,"depth":1},"#,
+            r#"{"step":3,"event":"generate","node":2,"depth":2,"parent":1,"action":"'c'"},"#,
+            r#"{"step":4,"event":"goal","node":2,"depth":2}]}"#));
+    }
+
+    #[test]
+    pub fn test_search_trace_astar() {
+        let mut buffers = AStarBuffers::new().with_observer(SearchTrace::<u8, char, NodeCost<u32>>::new());
+        Diamond.astar_with(&mut buffers, 0, 3, |_: &u8| 0);
+        let json = buffers.into_observer().to_json();
+        assert!(json.contains(r#"{"step":1,"event":"generate","node":1,"depth":1,"parent":0,"action":"'a'","g":1,"f":1}"#));
+        assert!(json.ends_with(r#"{"step":7,"event":"goal","node":3,"depth":2,"g":3,"f":3}]}"#));
+    }
+
+    #[test]
+    pub fn test_replay() {
+        let mut trace = SearchTrace::new();
+        Diamond.dfs_with(&mut SearchBuffers::new().with_observer(&mut trace), 0, 2);
+        let events = trace.into_events();
+
+        let mut replay = super::Replay::new(&events);
+        assert_eq!(replay.path_to(&0), None);
+        assert_eq!(replay.by_ref().take(2).count(), 2);
+        assert_eq!(replay.path_to(&0), Some(vec![]));
+        assert_eq!(replay.path_to(&1), Some(vec![&'a']));
+        assert!(!replay.is_expanded(&1));
+        assert_eq!(replay.path_to(&3), None);
+
+        assert_eq!(replay.by_ref().count(), events.len() - 2);
+        assert!(replay.is_finished());
+        assert!(replay.is_expanded(&3));
+        assert_eq!(replay.path_to(&2), Some(vec![&'b']));
+        assert_eq!(replay.step_back(), Some(&TraceEvent::Goal { state: 2, depth: 1, cost: () }));
+
+        replay.seek(4);
+        assert_eq!(replay.path_to(&3), Some(vec![&'a', &'c']));
+        assert_eq!(replay.history().len(), 4);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    pub fn test_search_trace_serde() {
+        let mut buffers = SearchBuffers::new().with_observer(SearchTrace::new());
+        Diamond.dfs_with(&mut buffers, 0, 3);
+        let stats = buffers.stats().clone();
+        let events = buffers.into_observer().into_events();
+
+        let json = ::serde_json::to_string(&(&events, &stats)).unwrap();
+        let (loaded_events, loaded_stats): (Vec<TraceEvent<u8, char>>, ::stats::SearchStats) =
+            ::serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded_events, events);
+        assert_eq!(loaded_stats, stats);
+    }
+}
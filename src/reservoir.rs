@@ -0,0 +1,117 @@
+//! Uniform random samples of the states a search visits.
+
+use alloc::vec::Vec;
+use core::hash::Hash;
+
+use hashbrown::HashSet;
+use rand::Rng;
+
+use observer::Observer;
+
+/// Observer keeping a uniform random sample of up to `capacity` of the
+/// distinct states a search generates and keeps for exploration, by
+/// reservoir sampling: the sample stays the same size however many states
+/// go by, for estimating properties of huge spaces cheaply. A state kept again, as A* does when it
+/// reopens one or PEA* when it re-expands a parent, is only counted the
+/// first time, so the states offered are remembered besides the sample. The
+/// start state is never generated and so is left out.
+pub struct Reservoir<S, R> {
+    capacity: usize,
+    seen: u64,
+    sample: Vec<S>,
+    offered: HashSet<S>,
+    rng: R,
+}
+
+impl<S: Hash + Eq, R: Rng> Reservoir<S, R> {
+    pub fn new(capacity: usize, rng: R) -> Reservoir<S, R> {
+        Reservoir { capacity, seen: 0, sample: Vec::with_capacity(capacity), offered: HashSet::new(), rng }
+    }
+
+    /// States sampled so far, in no particular order.
+    pub fn sample(&self) -> &[S] {
+        &self.sample
+    }
+
+    /// Number of distinct states the sample was drawn from.
+    pub fn seen(&self) -> u64 {
+        self.seen
+    }
+
+    pub fn into_sample(self) -> Vec<S> {
+        self.sample
+    }
+
+    /// Empties the sample for another search.
+    pub fn clear(&mut self) {
+        self.seen = 0;
+        self.sample.clear();
+        self.offered.clear();
+    }
+
+    fn offer(&mut self, state: &S) where S: Clone {
+        if !self.offered.insert(state.clone()) {
+            return;
+        }
+        self.seen += 1;
+        if self.sample.len() < self.capacity {
+            self.sample.push(state.clone());
+        } else {
+            let index = self.rng.gen_range(0..self.seen);
+            if index < self.capacity as u64 {
+                self.sample[index as usize] = state.clone();
+            }
+        }
+    }
+}
+
+impl<S: Clone + Hash + Eq, A, C, R: Rng> Observer<S, A, C> for Reservoir<S, R> {
+    fn on_generate(&mut self, _parent: &S, _action: &A, state: &S, _depth: usize, _cost: &C) {
+        self.offer(state);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashSet;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use astar::AStarBuffers;
+    use generators::random_grid;
+    use grid::{manhattan, Cell};
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use visited::RevisitPolicy;
+    use super::Reservoir;
+
+    #[test]
+    pub fn test_reservoir() {
+        let grid = random_grid(30, 30, 0.2, &mut StdRng::seed_from_u64(3));
+        let sample = |seed| {
+            let mut buffers = SearchBuffers::new().with_observer(Reservoir::new(10, StdRng::seed_from_u64(seed)));
+            grid.dfs_with(&mut buffers, (0, 0), (100, 100));
+            let stored = buffers.stats().generated - buffers.stats().duplicates;
+            let reservoir = buffers.into_observer();
+            assert_eq!(reservoir.seen(), stored);
+            reservoir.into_sample()
+        };
+        assert_eq!(sample(1).len(), 10);
+        assert_eq!(sample(1), sample(1));
+        assert_ne!(sample(1), sample(2));
+        assert_eq!(sample(1).into_iter().collect::<HashSet<_>>().len(), 10);
+
+        let mut buffers = AStarBuffers::new().with_observer(Reservoir::new(100, StdRng::seed_from_u64(1)));
+        grid.astar_with(&mut buffers, (0, 0), (1, 0), |_: &(usize, usize)| 0);
+        let reservoir = buffers.into_observer();
+        assert!(reservoir.seen() < 100);
+        assert_eq!(reservoir.sample().len() as u64, reservoir.seen());
+
+        // Reopened states are sampled once.
+        let mut buffers = AStarBuffers::new()
+            .with_revisit(RevisitPolicy::AlwaysRevisit)
+            .with_observer(Reservoir::new(1000, StdRng::seed_from_u64(1)));
+        grid.astar_with(&mut buffers, (0, 0), (3, 3), |cell: &Cell| manhattan(*cell, (3, 3)) * 2);
+        assert!(buffers.stats().generated - buffers.stats().duplicates > buffers.observer().seen());
+        let reservoir = buffers.into_observer();
+        assert_eq!(reservoir.sample().iter().collect::<HashSet<_>>().len() as u64, reservoir.seen());
+    }
+}
@@ -0,0 +1,119 @@
+//! Random start-to-goal paths favouring cheap ones, for generating varied
+//! trajectories rather than the single optimal one.
+
+use hashbrown::HashSet;
+use rand::Rng;
+
+use cost::ToFloat;
+use heuristic::Heuristic;
+use path::Path;
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+type Sample<S> = Option<(Path<<S as SearchSpace>::State, <S as SearchSpace>::Action>, <S as WeightedSearchSpace>::Cost)>;
+
+/// Samples paths by a random walk that picks each successor `t` of the
+/// current state with probability proportional to
+/// `exp(-(step cost + heuristic(t)) / temperature)`, a Boltzmann weighting.
+///
+/// With the exact cost to the goal as the heuristic, each step's weight
+/// falls off with how much the step adds to the cheapest path through it,
+/// so cheap paths are sampled most often; a temperature of zero always
+/// follows a cheapest path, while high temperatures approach a uniform
+/// random walk. Walks never visit a state twice.
+pub struct PathSampler<'a, S: WeightedSearchSpace + ?Sized + 'a, H> {
+    search_space: &'a S,
+    heuristic: H,
+    temperature: f64,
+    max_depth: Option<usize>,
+}
+
+impl<'a, S, H> PathSampler<'a, S, H>
+where S: WeightedSearchSpace + ?Sized, S::Cost: ToFloat, H: Heuristic<S::State, S::Cost> {
+    pub fn new(search_space: &'a S, heuristic: H, temperature: f64) -> PathSampler<'a, S, H> {
+        PathSampler { search_space, heuristic, temperature, max_depth: None }
+    }
+
+    /// Gives up on walks longer than `max_depth` actions.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// A path from `start` to `goal` with its cost, or `None` when the walk
+    /// got stuck or ran past the depth limit; sampling again may succeed.
+    pub fn sample<G, R>(&self, start: S::State, goal: &G, rng: &mut R) -> Sample<S>
+    where G: SearchGoal<S::State>, R: Rng {
+        let mut on_path = HashSet::new();
+        on_path.insert(start.clone());
+        let mut path = Path::new(start);
+        let mut cost = S::Cost::default();
+        while !goal.is_goal(path.end()) {
+            if self.max_depth.is_some_and(|max_depth| path.len() >= max_depth) {
+                return None;
+            }
+            let state = path.end();
+            let mut choices: Vec<_> = self.search_space.expand(state)
+                .filter(|(_, successor)| !on_path.contains(successor))
+                .map(|(action, successor)| {
+                    let step = self.search_space.step_cost(state, &action, &successor);
+                    let rank = (step + self.heuristic.estimate(&successor)).to_f64();
+                    (action, successor, step, rank)
+                })
+                .collect();
+            let lowest = choices.iter().map(|choice| choice.3).fold(f64::INFINITY, f64::min);
+            let weights: Vec<f64> = choices.iter()
+                .map(|choice| if self.temperature > 0.0 {
+                    (-(choice.3 - lowest) / self.temperature).exp()
+                } else if choice.3 == lowest { 1.0 } else { 0.0 })
+                .collect();
+            let mut pick = rng.gen::<f64>() * weights.iter().sum::<f64>();
+            let index = weights.iter().position(|&weight| {
+                pick -= weight;
+                pick < 0.0
+            }).or_else(|| weights.iter().rposition(|&weight| weight > 0.0))?;
+            let (action, successor, step, _) = choices.swap_remove(index);
+            on_path.insert(successor.clone());
+            cost = cost + step;
+            path.push(action, successor);
+        }
+        Some((path, cost))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::collections::HashSet;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Cell};
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::PathSampler;
+
+    #[test]
+    pub fn test_path_sampler() {
+        let grid = random_grid(5, 5, 0.0, &mut StdRng::seed_from_u64(1));
+        let to_goal = |cell: &Cell| manhattan(*cell, (4, 4));
+        let mut rng = StdRng::seed_from_u64(2);
+
+        let greedy = PathSampler::new(&grid, to_goal, 0.0);
+        let mut shapes = HashSet::new();
+        for _ in 0..20 {
+            let (path, cost) = greedy.sample((0, 0), &(4, 4), &mut rng).unwrap();
+            assert_eq!((path.len(), cost), (8, 8));
+            shapes.insert(path.into_actions());
+        }
+        assert!(shapes.len() > 1);
+
+        let warm = PathSampler::new(&grid, to_goal, 2.0).with_max_depth(24);
+        let costs: Vec<_> = (0..50).filter_map(|_| warm.sample((0, 0), &(4, 4), &mut rng)).map(|(path, cost)| {
+            for (from, action, to) in path.steps() {
+                assert_eq!(grid.expand(from).find(|(taken, _)| taken == action).map(|(_, next)| next), Some(*to));
+            }
+            assert_eq!(cost, path.steps().map(|(from, action, to)| grid.step_cost(from, action, to)).sum::<u64>());
+            cost
+        }).collect();
+        assert!(costs.iter().all(|&cost| cost >= 8) && costs.iter().any(|&cost| cost > 8));
+        assert!(costs.iter().filter(|&&cost| cost == 8).count() > 5);
+    }
+}
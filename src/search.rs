@@ -1,21 +1,53 @@
-use std::iter::Iterator;
-use std::collections::HashSet;
-use std::hash::Hash;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::iter::Iterator;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::mem;
+use core::ops::Add;
 
-struct Visited<T> {
-    hash_set: HashSet<T>
-}
+use hashbrown::HashMap;
 
-impl<T> Visited<T> where T: Hash + Clone + Eq {
-    fn new() -> Visited<T> {
-        Visited {
-            hash_set: HashSet::new()
-        }
-    }
+#[cfg(feature = "smallvec")]
+use smallvec::SmallVec;
 
-    fn insert(&mut self, value: &T) -> bool {
-        self.hash_set.insert(value.clone())
-    }
+use adapters::{FilterExpansions, Interleaved, MapActions, MapStates, Product};
+use anytime::{AnytimeAStar, AraStar};
+use astar::{self, AStarBuffers, AStarSession, NodeIndex};
+use bidirectional::{self, InvertibleAction, ReversedSpace};
+use bnb::{self, AnytimeBranchAndBound};
+use components::{self, Components, Reachable, TopologicalOrder};
+use cost::{Scale, ValidCost};
+use error::{self, TrySolution};
+use heuristic::Heuristic;
+use ida;
+use metrics::{self, SpaceMetrics};
+
+use observer::{NodeCost, Observer};
+use path::Path;
+use session::{Cutoff, SearchSession, Step};
+use stats::{LayerProfile, SearchStats};
+use stopwatch::Stopwatch;
+#[cfg(feature = "tracing")]
+use trace;
+use visited::{Visited, VisitedSet};
+use visit::{self, Control, DfsEvent};
+
+/// Buffer of a search path's frames, kept inline while short when the
+/// `smallvec` feature is enabled.
+#[cfg(feature = "smallvec")]
+pub(crate) type Buffer<T> = SmallVec<[T; 16]>;
+#[cfg(not(feature = "smallvec"))]
+pub(crate) type Buffer<T> = Vec<T>;
+
+/// Where in the search a state is being expanded, for spaces whose moves
+/// depend on how the state was reached.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ExpandContext<C = ()> {
+    /// Actions taken from the start.
+    pub depth: usize,
+    /// Cost of the path so far; `()` in unweighted searches.
+    pub cost: C,
 }
 
 pub trait SearchGoal<T> {
@@ -28,6 +60,689 @@ impl<T> SearchGoal<T> for T where T: PartialEq {
     }
 }
 
+fn insert_canonical<S, V>(search_space: &S, visited: &mut V, state: &S::State) -> bool
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State> {
+    match search_space.canonicalize(state) {
+        Some(canonical) => visited.insert(&canonical),
+        None => visited.insert(state),
+    }
+}
+
+fn remove_canonical<S, V>(search_space: &S, visited: &mut V, state: &S::State)
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State> {
+    match search_space.canonicalize(state) {
+        Some(canonical) => visited.remove(&canonical),
+        None => visited.remove(state),
+    }
+}
+
+struct Frame<S: SearchSpace + ?Sized> {
+    action: Option<S::Action>,
+    state: S::State,
+    successors: Option<S::Iterator>,
+}
+
+/// Visited set, stack and path owned by a search, kept between searches so
+/// their allocations can be reused.
+pub struct SearchBuffers<S: SearchSpace + ?Sized, V = Visited<<S as SearchSpace>::State>, O = ()> {
+    visited: V,
+    stack: Buffer<Frame<S>>,
+    actions: Vec<S::Action>,
+    stats: SearchStats,
+    observer: O,
+    warm_start: bool,
+    // Deepest path reached by the current search: the first `deepest_kept`
+    // frames of the stack, followed by the actions of those already popped,
+    // last first.
+    deepest: Vec<S::Action>,
+    deepest_kept: usize,
+    // Bottom frames of the stack with states left unexplored below them, by
+    // a cut or pruning, which warm starts must not keep as visited.
+    unexplored: usize,
+}
+
+impl<S: SearchSpace + ?Sized> SearchBuffers<S> {
+    pub fn new() -> SearchBuffers<S> {
+        SearchBuffers::with_visited(Visited::new())
+    }
+
+    /// Buffers sized up front for searches visiting about `states` states.
+    pub fn with_capacity(states: usize) -> SearchBuffers<S> {
+        SearchBuffers::with_visited(Visited::with_capacity(states))
+    }
+}
+
+impl<S: SearchSpace + ?Sized> Default for SearchBuffers<S> {
+    fn default() -> SearchBuffers<S> {
+        SearchBuffers::new()
+    }
+}
+
+impl<S: SearchSpace + ?Sized, V: VisitedSet<S::State>> SearchBuffers<S, V> {
+    /// Buffers using a custom duplicate detection strategy.
+    pub fn with_visited(visited: V) -> SearchBuffers<S, V> {
+        SearchBuffers {
+            visited,
+            stack: Buffer::new(),
+            actions: Vec::new(),
+            stats: SearchStats::new(),
+            observer: (),
+            warm_start: false,
+            deepest: Vec::new(),
+            deepest_kept: 0,
+            unexplored: 0,
+        }
+    }
+}
+
+impl<S, V, O> SearchBuffers<S, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    /// Reports the events of subsequent searches to `observer`.
+    pub fn with_observer<P>(self, observer: P) -> SearchBuffers<S, V, P>
+    where P: Observer<S::State, S::Action> {
+        SearchBuffers {
+            visited: self.visited,
+            stack: self.stack,
+            actions: self.actions,
+            stats: self.stats,
+            observer,
+            warm_start: self.warm_start,
+            deepest: self.deepest,
+            deepest_kept: self.deepest_kept,
+            unexplored: self.unexplored,
+        }
+    }
+
+    /// Keeps the visited set from one search to the next instead of starting
+    /// each afresh, so states seen by earlier searches, or inserted up front
+    /// to fence off regions known to be useless, are skipped. Only states
+    /// whose successors were all explored without reaching the goal are kept:
+    /// the path to a goal found, and states leading to a cut or pruned
+    /// state, are forgotten, and a breadth-first traversal keeps its states only if
+    /// it runs to the end without pruning. Only sound for repeated queries
+    /// for the same goal on the same unchanging space; `clear` still empties
+    /// the set.
+    pub fn with_warm_start(mut self, warm_start: bool) -> SearchBuffers<S, V, O> {
+        self.warm_start = warm_start;
+        self
+    }
+
+    pub fn visited(&self) -> &V {
+        &self.visited
+    }
+
+    pub fn visited_mut(&mut self) -> &mut V {
+        &mut self.visited
+    }
+
+    pub fn into_visited(self) -> V {
+        self.visited
+    }
+
+    pub fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub fn into_observer(self) -> O {
+        self.observer
+    }
+
+    /// Enables recording of per-depth node counts for subsequent searches.
+    pub fn profiled(mut self) -> SearchBuffers<S, V, O> {
+        self.stats.profile = Some(LayerProfile::new());
+        self
+    }
+
+    /// Statistics of the last search.
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Per-depth counts of the last search, if profiling is enabled.
+    pub fn profile(&self) -> Option<&LayerProfile> {
+        self.stats.profile.as_ref()
+    }
+
+    pub fn clear(&mut self) {
+        self.visited.clear();
+        self.stack.clear();
+        self.actions.clear();
+        self.deepest.clear();
+        self.deepest_kept = 0;
+        self.unexplored = 0;
+    }
+
+    // Clears what a search leaves behind, keeping the visited set when warm
+    // starting, less the states of the search path, which are not explored
+    // in full.
+    fn release(&mut self, search_space: &S) {
+        if self.warm_start {
+            for frame in self.stack.iter() {
+                remove_canonical(search_space, &mut self.visited, &frame.state);
+            }
+        } else {
+            self.visited.clear();
+        }
+        self.stack.clear();
+        self.deepest.clear();
+        self.deepest_kept = 0;
+        self.unexplored = 0;
+    }
+
+    // Forgets `state`, left unexplored, when warm starting, and marks the
+    // search path leading to it as not explored in full.
+    fn leave_unexplored(&mut self, search_space: &S, state: &S::State) {
+        if self.warm_start {
+            remove_canonical(search_space, &mut self.visited, state);
+        }
+        self.unexplored = self.stack.len();
+    }
+
+    /// Reserves room for about `states` more visited states and a search
+    /// path `depth` states deep.
+    pub fn reserve(&mut self, states: usize, depth: usize) {
+        self.visited.reserve(states);
+        self.stack.reserve(depth);
+    }
+
+    fn reset(&mut self, search_space: &S, start: S::State) {
+        self.release(search_space);
+        self.stats.clear();
+        self.stats.set_entry_bytes(self.visited.approx_entry_bytes(), mem::size_of::<Frame<S>>());
+        insert_canonical(search_space, &mut self.visited, &start);
+        self.push(Frame { action: None, state: start, successors: None });
+        self.stats.record_open(1);
+    }
+
+    fn prune(&mut self, search_space: &S) {
+        if let Some(&Frame { successors: None, .. }) = self.stack.last() {
+            let frame = self.pop().expect("frame to prune");
+            self.leave_unexplored(search_space, &frame.state);
+            self.observer.on_prune(&frame.state, self.stack.len());
+        }
+    }
+
+    fn goal_reached(&mut self) {
+        let depth = self.stack.len() - 1;
+        if let Some(frame) = self.stack.last() {
+            self.observer.on_goal(&frame.state, depth, &());
+        }
+    }
+
+    // Extends the search path, taking it as the deepest once it goes past it.
+    fn push(&mut self, frame: Frame<S>) {
+        self.stack.push(frame);
+        if self.stack.len() > self.deepest_kept + self.deepest.len() {
+            self.deepest.clear();
+            self.deepest_kept = self.stack.len();
+        }
+    }
+
+    // Backtracks, keeping the action of a frame on the deepest path.
+    fn pop(&mut self) -> Option<Frame<S>> {
+        let mut frame = self.stack.pop()?;
+        if self.stack.len() < self.deepest_kept {
+            self.deepest_kept = self.stack.len();
+            self.deepest.extend(frame.action.take());
+        }
+        Some(frame)
+    }
+
+    // Actions of the deepest path reached.
+    fn deepest(&self) -> Vec<S::Action> where S::Action: Clone {
+        self.stack[..self.deepest_kept].iter()
+            .filter_map(|frame| frame.action.clone())
+            .chain(self.deepest.iter().rev().cloned())
+            .collect()
+    }
+
+    // Moves the actions of the search path into the path buffer.
+    fn take_actions(&mut self, search_space: &S) {
+        if self.warm_start {
+            for frame in self.stack.iter() {
+                remove_canonical(search_space, &mut self.visited, &frame.state);
+            }
+        }
+        self.actions.clear();
+        self.actions.extend(self.stack.drain(..).filter_map(|frame| frame.action));
+    }
+
+    fn advance(&mut self, search_space: &S) -> Option<&S::State> {
+        loop {
+            let depth = self.stack.len().saturating_sub(1);
+            let next = match self.stack.last_mut() {
+                None => return None,
+                Some(frame) => {
+                    let state = &frame.state;
+                    let stats = &mut self.stats;
+                    let observer = &mut self.observer;
+                    frame.successors
+                         .get_or_insert_with(|| {
+                             stats.record_expanded(depth);
+                             #[cfg(feature = "tracing")]
+                             {
+                                 if trace::sampled(stats) {
+                                     ::tracing::trace!(depth, expanded = stats.expanded, "expand");
+                                 }
+                             }
+                             observer.on_expand(state, depth, &());
+                             search_space.expand_at(state, ExpandContext { depth, cost: () })
+                         })
+                         .next()
+                }
+            };
+            match next {
+                Some((action, state)) => {
+                    self.stats.record_generated(depth + 1);
+                    if !insert_canonical(search_space, &mut self.visited, &state) {
+                        self.stats.record_duplicate(depth + 1);
+                        self.observer.on_duplicate(&state, depth + 1);
+                        continue;
+                    }
+                    if search_space.is_dead_end(&state) {
+                        self.stats.record_dead_end();
+                        self.observer.on_prune(&state, depth + 1);
+                        continue;
+                    }
+                    if self.observer.is_pruned(&state, depth + 1, &()) {
+                        self.stats.record_pruned();
+                        self.leave_unexplored(search_space, &state);
+                        self.observer.on_prune(&state, depth + 1);
+                        continue;
+                    }
+                    if let Some(parent) = self.stack.last() {
+                        self.observer.on_generate(&parent.state, &action, &state, depth + 1, &());
+                    }
+                    self.push(Frame { action: Some(action), state, successors: None });
+                    self.stats.record_open(self.stack.len());
+                    self.stats.record_depth(depth + 1);
+                    return self.stack.last().map(|frame| &frame.state);
+                }
+                None => {
+                    let frame = self.pop().expect("frame to finish");
+                    if self.stack.len() < self.unexplored {
+                        self.unexplored = self.stack.len();
+                        if self.warm_start {
+                            remove_canonical(search_space, &mut self.visited, &frame.state);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Depth-first search for an action leading back onto the search path,
+    // returning the steps of the cycle it closes. States on the stack are
+    // gray, visited ones off it black.
+    fn find_back_edge(&mut self, search_space: &S) -> Option<Vec<(S::Action, S::State)>> {
+        let key = |state: &S::State| search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+        let mut gray: HashMap<S::State, usize> = self.stack.iter().enumerate()
+            .map(|(position, frame)| (key(&frame.state), position))
+            .collect();
+        loop {
+            let depth = self.stack.len().saturating_sub(1);
+            let next = match self.stack.last_mut() {
+                None => return None,
+                Some(frame) => {
+                    let state = &frame.state;
+                    let stats = &mut self.stats;
+                    let observer = &mut self.observer;
+                    frame.successors
+                         .get_or_insert_with(|| {
+                             stats.record_expanded(depth);
+                             observer.on_expand(state, depth, &());
+                             search_space.expand_at(state, ExpandContext { depth, cost: () })
+                         })
+                         .next()
+                }
+            };
+            match next {
+                Some((action, state)) => {
+                    self.stats.record_generated(depth + 1);
+                    if let Some(&position) = gray.get(&key(&state)) {
+                        let mut steps: Vec<_> = self.stack.drain(position + 1..)
+                            .map(|frame| (frame.action.expect("only the start has no action"), frame.state))
+                            .collect();
+                        steps.push((action, state));
+                        return Some(steps);
+                    }
+                    if !insert_canonical(search_space, &mut self.visited, &state) {
+                        self.stats.record_duplicate(depth + 1);
+                        self.observer.on_duplicate(&state, depth + 1);
+                        continue;
+                    }
+                    gray.insert(key(&state), self.stack.len());
+                    self.stack.push(Frame { action: Some(action), state, successors: None });
+                    self.stats.record_open(self.stack.len());
+                    self.stats.record_depth(depth + 1);
+                }
+                None => {
+                    let frame = self.stack.pop().expect("frame to finish");
+                    gray.remove(&key(&frame.state));
+                }
+            }
+        }
+    }
+}
+
+/// Depth-first traversal yielding `(depth, state)` pairs in discovery order.
+///
+/// A state is only expanded once the iterator is advanced past it, so states
+/// the caller stops at or prunes never pay for expansion.
+pub struct DfsIter<'a, S: SearchSpace + ?Sized + 'a, V = Visited<<S as SearchSpace>::State>, O = ()> {
+    search_space: &'a S,
+    buffers: SearchBuffers<S, V, O>,
+    started: bool,
+    clock: Stopwatch,
+}
+
+impl<'a, S, V, O> DfsIter<'a, S, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    fn new(search_space: &'a S, start: S::State, mut buffers: SearchBuffers<S, V, O>) -> DfsIter<'a, S, V, O> {
+        let clock = Stopwatch::start();
+        buffers.reset(search_space, start);
+        DfsIter {
+            search_space,
+            buffers,
+            started: false,
+            clock,
+        }
+    }
+
+    /// Skips the successors of the most recently yielded state.
+    pub fn prune(&mut self) {
+        self.buffers.prune(self.search_space)
+    }
+
+    /// Actions leading from the start to the most recently yielded state.
+    pub fn actions(&self) -> impl Iterator<Item=&S::Action> {
+        self.buffers.stack.iter().filter_map(|frame| frame.action.as_ref())
+    }
+
+    /// Statistics of the traversal so far, timed from its start.
+    pub fn stats(&mut self) -> &SearchStats {
+        self.time();
+        self.buffers.stats()
+    }
+
+    pub fn profile(&self) -> Option<&LayerProfile> {
+        self.buffers.profile()
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        self.buffers.observer_mut()
+    }
+
+    /// Ends the traversal, handing back its buffers for the next search.
+    pub fn into_buffers(mut self) -> SearchBuffers<S, V, O> {
+        self.time();
+        self.buffers.release(self.search_space);
+        self.buffers
+    }
+
+    fn time(&mut self) {
+        self.buffers.stats.elapsed = self.clock.elapsed();
+    }
+
+    fn advance(&mut self) -> Option<&S::State> {
+        if !self.started {
+            self.started = true;
+            return self.buffers.stack.last().map(|frame| &frame.state);
+        }
+        self.buffers.advance(self.search_space)
+    }
+}
+
+impl<'a, S, V, O> Iterator for DfsIter<'a, S, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    type Item = (usize, S::State);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.advance().cloned().map(|state| (self.buffers.stack.len() - 1, state));
+        if next.is_none() {
+            self.time();
+        }
+        next
+    }
+}
+
+/// Breadth-first traversal yielding `(depth, state)` pairs in discovery
+/// order, which is level by level.
+///
+/// A state is only expanded once the states discovered before it have been
+/// and the iterator needs its successors, so states the caller stops at or
+/// prunes never pay for expansion.
+pub struct BfsIter<'a, S: SearchSpace + ?Sized + 'a, V = Visited<<S as SearchSpace>::State>, O = ()> {
+    search_space: &'a S,
+    buffers: SearchBuffers<S, V, O>,
+    queue: VecDeque<(usize, S::State)>,
+    expanding: Option<(usize, S::State, S::Iterator)>,
+    started: bool,
+    prunable: bool,
+    clock: Stopwatch,
+    // States this traversal added to a warm started visited set, forgotten
+    // unless it runs to the end without pruning.
+    fresh: Vec<S::State>,
+    pruned: bool,
+}
+
+impl<'a, S, V, O> BfsIter<'a, S, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    fn new(search_space: &'a S, start: S::State, mut buffers: SearchBuffers<S, V, O>) -> BfsIter<'a, S, V, O> {
+        let clock = Stopwatch::start();
+        buffers.release(search_space);
+        buffers.stats.clear();
+        buffers.stats.set_entry_bytes(buffers.visited.approx_entry_bytes(), mem::size_of::<(usize, S::State)>());
+        let mut fresh = Vec::new();
+        if insert_canonical(search_space, &mut buffers.visited, &start) && buffers.warm_start {
+            fresh.push(start.clone());
+        }
+        buffers.stats.record_open(1);
+        BfsIter {
+            search_space,
+            buffers,
+            queue: Some((0, start)).into_iter().collect(),
+            expanding: None,
+            started: false,
+            prunable: false,
+            clock,
+            fresh,
+            pruned: false,
+        }
+    }
+
+    /// Skips the successors of the most recently yielded state.
+    pub fn prune(&mut self) {
+        if mem::take(&mut self.prunable) {
+            if let Some((depth, state)) = self.queue.pop_back() {
+                self.pruned = true;
+                self.buffers.observer.on_prune(&state, depth);
+            }
+        }
+    }
+
+    /// Statistics of the traversal so far, timed from its start.
+    pub fn stats(&mut self) -> &SearchStats {
+        self.time();
+        self.buffers.stats()
+    }
+
+    pub fn profile(&self) -> Option<&LayerProfile> {
+        self.buffers.profile()
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        self.buffers.observer_mut()
+    }
+
+    /// Ends the traversal, handing back its buffers for the next search.
+    pub fn into_buffers(mut self) -> SearchBuffers<S, V, O> {
+        self.time();
+        if self.pruned || !self.queue.is_empty() || self.expanding.is_some() {
+            for state in &self.fresh {
+                remove_canonical(self.search_space, &mut self.buffers.visited, state);
+            }
+        }
+        self.buffers.release(self.search_space);
+        self.buffers
+    }
+
+    fn time(&mut self) {
+        self.buffers.stats.elapsed = self.clock.elapsed();
+    }
+
+    fn advance(&mut self) -> Option<(usize, S::State)> {
+        if !self.started {
+            self.started = true;
+            self.prunable = true;
+            return self.queue.front().cloned();
+        }
+        loop {
+            let (depth, parent, successors) = match self.expanding {
+                Some(ref mut expanding) => expanding,
+                None => {
+                    let (depth, state) = self.queue.pop_front()?;
+                    self.buffers.stats.record_expanded(depth);
+                    #[cfg(feature = "tracing")]
+                    {
+                        if trace::sampled(&self.buffers.stats) {
+                            ::tracing::trace!(depth, expanded = self.buffers.stats.expanded, "expand");
+                        }
+                    }
+                    self.buffers.observer.on_expand(&state, depth, &());
+                    let successors = self.search_space.expand_at(&state, ExpandContext { depth, cost: () });
+                    self.expanding.insert((depth, state, successors))
+                }
+            };
+            let depth = *depth + 1;
+            match successors.next() {
+                Some((action, state)) => {
+                    self.buffers.stats.record_generated(depth);
+                    if !insert_canonical(self.search_space, &mut self.buffers.visited, &state) {
+                        self.buffers.stats.record_duplicate(depth);
+                        self.buffers.observer.on_duplicate(&state, depth);
+                        continue;
+                    }
+                    if self.buffers.warm_start {
+                        self.fresh.push(state.clone());
+                    }
+                    if self.search_space.is_dead_end(&state) {
+                        self.buffers.stats.record_dead_end();
+                        self.buffers.observer.on_prune(&state, depth);
+                        continue;
+                    }
+                    if self.buffers.observer.is_pruned(&state, depth, &()) {
+                        self.buffers.stats.record_pruned();
+                        self.pruned = true;
+                        self.buffers.observer.on_prune(&state, depth);
+                        continue;
+                    }
+                    self.buffers.observer.on_generate(parent, &action, &state, depth, &());
+                    self.queue.push_back((depth, state.clone()));
+                    self.buffers.stats.record_open(self.queue.len());
+                    self.buffers.stats.record_depth(depth);
+                    self.prunable = true;
+                    return Some((depth, state));
+                }
+                None => self.expanding = None,
+            }
+        }
+    }
+}
+
+impl<'a, S, V, O> Iterator for BfsIter<'a, S, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    type Item = (usize, S::State);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.prunable = false;
+        let next = self.advance();
+        if next.is_none() {
+            self.time();
+        }
+        next
+    }
+}
+
+/// Depth-first search for a goal, advanced one generated state per step.
+pub struct DfsSession<'a, S: SearchSpace + ?Sized + 'a, G, V = Visited<<S as SearchSpace>::State>, O = ()> {
+    iter: DfsIter<'a, S, V, O>,
+    goal: G,
+    max_depth: Option<usize>,
+    cut_off: bool,
+}
+
+impl<'a, S, G, V, O> DfsSession<'a, S, G, V, O>
+where S: SearchSpace + ?Sized, V: VisitedSet<S::State>, O: Observer<S::State, S::Action> {
+    /// Leaves the successors of states `max_depth` actions from the start
+    /// unexplored. As states are still visited once, a state first reached
+    /// at the limit is not explored from a shorter path found later.
+    pub fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Statistics of the search so far, timed from its start.
+    pub fn stats(&mut self) -> &SearchStats {
+        self.iter.stats()
+    }
+
+    pub fn observer_mut(&mut self) -> &mut O {
+        self.iter.observer_mut()
+    }
+
+    pub fn into_buffers(self) -> SearchBuffers<S, V, O> {
+        self.iter.into_buffers()
+    }
+
+    /// Actions leading to the deepest state reached so far, on whichever
+    /// branch it was, for acting on when the search is given up before it
+    /// finishes. `None` once the search has finished.
+    pub fn partial(&self) -> Option<Vec<S::Action>> where S::Action: Clone {
+        if self.iter.buffers.stack.is_empty() {
+            return None;
+        }
+        Some(self.iter.buffers.deepest())
+    }
+}
+
+impl<'a, S, G, V, O> SearchSession for DfsSession<'a, S, G, V, O>
+where S: SearchSpace + ?Sized, G: SearchGoal<S::State>, V: VisitedSet<S::State>,
+      O: Observer<S::State, S::Action> {
+    type Output = Option<Vec<S::Action>>;
+
+    fn step(&mut self) -> Step<Option<Vec<S::Action>>> {
+        let goal = &self.goal;
+        let step = match self.iter.advance().map(|state| goal.is_goal(state)) {
+            None => Step::Finished(None),
+            Some(false) => {
+                if self.max_depth.is_some_and(|max_depth| self.iter.buffers.stack.len() > max_depth) {
+                    self.iter.prune();
+                    self.cut_off = true;
+                }
+                Step::Continue
+            }
+            Some(true) => {
+                self.iter.buffers.goal_reached();
+                self.iter.buffers.take_actions(self.iter.search_space);
+                Step::Finished(Some(mem::take(&mut self.iter.buffers.actions)))
+            }
+        };
+        if let Step::Finished(_) = step {
+            self.iter.time();
+        }
+        step
+    }
+
+    fn cut_off(&self) -> Option<Cutoff> {
+        if self.cut_off { Some(Cutoff::Depth) } else { None }
+    }
+}
+
 pub trait SearchSpace {
     type State: Hash + Clone + Eq;
     type Action;
@@ -35,44 +750,360 @@ pub trait SearchSpace {
 
     fn expand(&self, state: &Self::State) -> Self::Iterator;
 
+    /// Successors of `state` given where the search reached it; depth-first
+    /// searches expand through this. Duplicate detection still goes by state
+    /// alone, so a state first reached with a poorer context keeps it.
+    fn expand_at(&self, state: &Self::State, _context: ExpandContext) -> Self::Iterator {
+        self.expand(state)
+    }
+
+    /// Representative of the symmetry class of `state`, used in its place for
+    /// duplicate detection; `None` keeps `state` itself.
+    fn canonicalize(&self, _state: &Self::State) -> Option<Self::State> {
+        None
+    }
+
+    /// Whether no goal can be reached from `state`, such as a deadlocked
+    /// Sokoban box or an exhausted budget. Searches drop such states as they
+    /// are generated instead of exploring them, so this must never hold for
+    /// a goal.
+    fn is_dead_end(&self, _state: &Self::State) -> bool {
+        false
+    }
+
+    fn dfs_iter(&self, start: Self::State) -> DfsIter<'_, Self> {
+        DfsIter::new(self, start, SearchBuffers::new())
+    }
+
+    fn dfs_iter_with<V, O>(&self, buffers: SearchBuffers<Self, V, O>, start: Self::State) -> DfsIter<'_, Self, V, O>
+    where V: VisitedSet<Self::State>, O: Observer<Self::State, Self::Action> {
+        DfsIter::new(self, start, buffers)
+    }
+
+    fn bfs_iter(&self, start: Self::State) -> BfsIter<'_, Self> {
+        BfsIter::new(self, start, SearchBuffers::new())
+    }
+
+    fn bfs_iter_with<V, O>(&self, buffers: SearchBuffers<Self, V, O>, start: Self::State) -> BfsIter<'_, Self, V, O>
+    where V: VisitedSet<Self::State>, O: Observer<Self::State, Self::Action> {
+        BfsIter::new(self, start, buffers)
+    }
+
+    /// Path with the fewest actions from `start` to `goal`, found by
+    /// searching from both ends; see `bidirectional::meet_in_the_middle`.
+    fn meet_in_the_middle(&self, start: Self::State, goal: Self::State) -> Option<Path<Self::State, Self::Action>>
+    where Self::Action: InvertibleAction {
+        bidirectional::meet_in_the_middle(self, start, goal)
+    }
+
+    /// Branching factor, depth distribution and duplicate counts of the
+    /// first `max_states` states found from `start`; see `metrics::measure`.
+    fn measure(&self, start: Self::State, max_states: u64) -> SpaceMetrics {
+        metrics::measure(self, start, max_states)
+    }
+
+    /// This space with its states converted by `into` and back by `from`.
+    fn map_states<F, G, T>(self, into: F, from: G) -> MapStates<Self, F, G>
+    where Self: Sized, F: Fn(Self::State) -> T, G: Fn(&T) -> Self::State, T: Hash + Clone + Eq {
+        MapStates::new(self, into, from)
+    }
+
+    /// This space with its actions converted by `into` and back by `from`.
+    fn map_actions<F, G, B>(self, into: F, from: G) -> MapActions<Self, F, G>
+    where Self: Sized, F: Fn(Self::Action) -> B, G: Fn(&B) -> Self::Action {
+        MapActions::new(self, into, from)
+    }
+
+    /// This space without the moves `allow` rejects, such as forbidden ones.
+    fn filter_expansions<P>(self, allow: P) -> FilterExpansions<Self, P>
+    where Self: Sized, P: Fn(&Self::State, &Self::Action, &Self::State) -> bool {
+        FilterExpansions::new(self, allow)
+    }
+
+    /// Pairs of states of this space and `other` moving both at once.
+    fn product<T>(self, other: T) -> Product<Self, T>
+    where Self: Sized, T: SearchSpace, Self::Action: Clone, T::Action: Clone {
+        Product::new(self, other)
+    }
+
+    /// Pairs of states of this space and `other` moving one at a time.
+    fn interleave<T>(self, other: T) -> Interleaved<Self, T> where Self: Sized, T: SearchSpace {
+        Interleaved::new(self, other)
+    }
+
+    /// This space searched backwards, expanding states into their
+    /// predecessors.
+    fn reversed(self) -> ReversedSpace<Self> where Self: Sized, Self::Action: InvertibleAction {
+        ReversedSpace::new(self)
+    }
+
+    /// Depth-first traversal steered by `visitor`; see
+    /// `visit::depth_first_visit`.
+    fn dfs_visit<F, B>(&self, start: Self::State, visitor: F) -> Option<B>
+    where F: FnMut(DfsEvent<Self::State, Self::Action>) -> Control<B> {
+        visit::depth_first_visit(self, start, visitor)
+    }
+
+    /// Depth-first search for `goal` that is run by stepping the returned
+    /// session.
+    fn dfs_session<G>(&self, start: Self::State, goal: G) -> DfsSession<'_, Self, G>
+    where G: SearchGoal<Self::State> {
+        self.dfs_session_with(SearchBuffers::new(), start, goal)
+    }
+
+    fn dfs_session_with<G, V, O>(&self, buffers: SearchBuffers<Self, V, O>, start: Self::State, goal: G)
+        -> DfsSession<'_, Self, G, V, O>
+    where G: SearchGoal<Self::State>, V: VisitedSet<Self::State>, O: Observer<Self::State, Self::Action> {
+        DfsSession { iter: DfsIter::new(self, start, buffers), goal, max_depth: None, cut_off: false }
+    }
+
+    /// Cycle reachable from `start`, as the actions and states leading from
+    /// a state on the cycle all the way around to it, so that the last state
+    /// is where the cycle begins. For a dependency graph this names the
+    /// offending loop.
+    fn find_cycle(&self, start: Self::State) -> Option<Vec<(Self::Action, Self::State)>> {
+        let mut buffers = SearchBuffers::new();
+        buffers.reset(self, start);
+        buffers.find_back_edge(self)
+    }
+
+    /// States reachable from `start`, breadth first; see `Reachable` for
+    /// depth and count limits.
+    fn reachable_from(&self, start: Self::State) -> Reachable<'_, Self> {
+        Reachable::new(self, start)
+    }
+
+    /// Strongly connected components of the states reachable from `roots`,
+    /// or `None` if there are more than `max_states` of them.
+    fn strongly_connected_components<I>(&self, roots: I, max_states: usize) -> Option<Components<Self::State>>
+    where I: IntoIterator<Item=Self::State> {
+        components::strongly_connected(self, roots, max_states)
+    }
+
+    /// States reachable from `roots`, each after the states its actions lead
+    /// to, or the first cycle standing in the way.
+    fn topological_sort<I>(&self, roots: I) -> TopologicalOrder<Self>
+    where I: IntoIterator<Item=Self::State> {
+        components::topological_sort(self, roots)
+    }
+
     fn dfs<G>(&self, start: Self::State, goal: G) -> Option<Vec<Self::Action>>
     where G: SearchGoal<Self::State> {
-        if goal.is_goal(&start) {
-            return Some(vec![]);
-        }
+        self.dfs_with(&mut SearchBuffers::new(), start, goal)
+    }
 
-        let mut visited = Visited::new();
-        let mut stack = vec![(self.expand(&start), None)];
+    fn dfs_with<G, V, O>(&self, buffers: &mut SearchBuffers<Self, V, O>, start: Self::State, goal: G)
+        -> Option<Vec<Self::Action>>
+    where G: SearchGoal<Self::State>, V: VisitedSet<Self::State>, O: Observer<Self::State, Self::Action> {
+        self.dfs_in(buffers, start, goal)?;
+        Some(mem::take(&mut buffers.actions))
+    }
 
-        loop {
-            let next = match stack.last_mut() {
-                None => return None,
-                Some(&mut (ref mut iter, _)) => iter.next()
-            };
-            if let Some((action, state)) = next {
-                if !visited.insert(&state) {
-                    continue;
-                }
-                if goal.is_goal(&state) {
-                    return Some(
-                        stack.into_iter()
-                             .filter_map(|(_, a)| a)
-                             .chain(Some(action).into_iter())
-                             .collect()
-                    )
+    /// Like `dfs_with`, but the actions found are left in the path buffer of
+    /// `buffers` and borrowed from there, so that repeated searches with the
+    /// same buffers do not allocate.
+    fn dfs_in<'b, G, V, O>(&self, buffers: &'b mut SearchBuffers<Self, V, O>, start: Self::State, goal: G)
+        -> Option<&'b [Self::Action]>
+    where G: SearchGoal<Self::State>, V: VisitedSet<Self::State>, O: Observer<Self::State, Self::Action> {
+        #[cfg(feature = "tracing")]
+        let _span = trace::search_span("dfs").entered();
+        let started = Stopwatch::start();
+        let is_goal = goal.is_goal(&start);
+        buffers.reset(self, start);
+        let mut found = false;
+        if is_goal {
+            buffers.goal_reached();
+            buffers.actions.clear();
+            found = true;
+        }
+        while !found {
+            match buffers.advance(self) {
+                None => break,
+                Some(state) => if goal.is_goal(state) {
+                    buffers.goal_reached();
+                    buffers.take_actions(self);
+                    found = true;
                 }
-                stack.push((self.expand(&state), Some(action)));
-            } else {
-                stack.pop();
             }
         }
+        buffers.release(self);
+        buffers.stats.elapsed = started.elapsed();
+        #[cfg(feature = "tracing")]
+        trace::finished(found, &buffers.stats);
+        if found { Some(&buffers.actions) } else { None }
+    }
+}
+
+/// Search space whose actions carry a cost.
+pub trait WeightedSearchSpace: SearchSpace {
+    /// Path cost; `Default` gives the cost of the empty path.
+    type Cost: Copy + Ord + Add<Output=Self::Cost> + Default + Debug;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost;
+
+    /// Cost of entering `state`, whichever action leads there, such as a
+    /// toll or a terrain penalty.
+    fn arrival_cost(&self, _state: &Self::State) -> Self::Cost {
+        Self::Cost::default()
+    }
+
+    /// Cost added to g for taking `action` from `state` to `successor`: the
+    /// action cost plus the arrival cost of the successor. Searches go by
+    /// this rather than by `cost` alone.
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.cost(state, action) + self.arrival_cost(successor)
+    }
+
+    /// Whether `a` is at least as good as `b` in every respect that matters
+    /// for reaching a goal, such as the same place with more fuel left. With
+    /// dominance enabled on `AStarBuffers`, a generated state is dropped when
+    /// a state already seen at no greater cost dominates it.
+    ///
+    /// Each generated state is checked against every state seen with the
+    /// same `dominance_key`, so with the default key, which puts all states
+    /// together, a search makes a number of checks quadratic in the states
+    /// it stores.
+    fn dominates(&self, _a: &Self::State, _b: &Self::State) -> bool {
+        false
+    }
+
+    /// Key of the states `state` can dominate or be dominated by, such as its
+    /// place when fuel left is what tells states apart: only states with
+    /// equal keys are checked for dominance, so states one dominates another
+    /// of must share one.
+    fn dominance_key(&self, _state: &Self::State) -> u64 {
+        0
+    }
+
+    /// Successors of `state` given the depth and cost it was reached at;
+    /// cost-ordered searches expand through this.
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        self.expand_at(state, ExpandContext { depth: context.depth, cost: () })
+    }
+
+    /// Finds a cheapest path, returning its actions and total cost.
+    ///
+    /// The result is optimal as long as `heuristic` never overestimates.
+    fn astar<G, H>(&self, start: Self::State, goal: G, heuristic: H) -> Option<(Vec<Self::Action>, Self::Cost)>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        self.astar_with(&mut AStarBuffers::new(), start, goal, heuristic)
+    }
+
+    fn astar_with<G, H, O, N>(&self, buffers: &mut AStarBuffers<Self, O, N>, start: Self::State, goal: G, heuristic: H)
+        -> Option<(Vec<Self::Action>, Self::Cost)>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost>,
+          O: Observer<Self::State, Self::Action, NodeCost<Self::Cost>>, N: NodeIndex<Self::State> {
+        astar::best_first(buffers, self, start, goal, heuristic, None)
+    }
+
+    /// A* search that is run by stepping the returned session.
+    fn astar_session<'a, G, H, O, N>(&'a self, buffers: &'a mut AStarBuffers<Self, O, N>, start: Self::State, goal: G,
+                                  heuristic: H) -> AStarSession<'a, Self, G, H, O, N>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost>,
+          O: Observer<Self::State, Self::Action, NodeCost<Self::Cost>>, N: NodeIndex<Self::State> {
+        AStarSession::new(buffers, self, start, goal, heuristic, None)
+    }
+
+    /// Partial Expansion A*: like `astar`, but an expansion only stores the
+    /// successors whose f-value is within `threshold` of the parent's.
+    fn pea_star<G, H>(&self, start: Self::State, goal: G, heuristic: H, threshold: Self::Cost)
+        -> Option<(Vec<Self::Action>, Self::Cost)>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        self.pea_star_with(&mut AStarBuffers::new(), start, goal, heuristic, threshold)
+    }
+
+    fn pea_star_with<G, H, O, N>(&self, buffers: &mut AStarBuffers<Self, O, N>, start: Self::State, goal: G, heuristic: H,
+                              threshold: Self::Cost) -> Option<(Vec<Self::Action>, Self::Cost)>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost>,
+          O: Observer<Self::State, Self::Action, NodeCost<Self::Cost>>, N: NodeIndex<Self::State> {
+        astar::best_first(buffers, self, start, goal, heuristic, Some(threshold))
+    }
+
+    /// Bounded-suboptimal focal search, returning a path costing at most
+    /// `factor` times the cheapest; see `astar::focal`.
+    fn focal<G, H, F, D>(&self, start: Self::State, goal: G, heuristic: H, focal_heuristic: F, factor: f64)
+        -> Option<(Vec<Self::Action>, Self::Cost)>
+    where Self::Cost: Scale, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost>,
+          F: Heuristic<Self::State, D>, D: Ord {
+        self.focal_with(&mut AStarBuffers::new(), start, goal, heuristic, focal_heuristic, factor)
+    }
+
+    fn focal_with<G, H, F, D, O, N>(&self, buffers: &mut AStarBuffers<Self, O, N>, start: Self::State, goal: G,
+                                 heuristic: H, focal_heuristic: F, factor: f64)
+        -> Option<(Vec<Self::Action>, Self::Cost)>
+    where Self::Cost: Scale, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost>,
+          F: Heuristic<Self::State, D>, D: Ord, O: Observer<Self::State, Self::Action, NodeCost<Self::Cost>>, N: NodeIndex<Self::State> {
+        astar::focal(buffers, self, start, goal, heuristic, focal_heuristic, factor)
+    }
+
+    /// A* that checks each expansion against `heuristic` and gives up as soon
+    /// as it finds the heuristic inconsistent or meets an invalid cost, such
+    /// as a NaN float, instead of searching on with a muddled open list.
+    fn try_astar<G, H>(&self, start: Self::State, goal: G, heuristic: H) -> TrySolution<Self>
+    where Self::Cost: ValidCost, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        error::checked_astar(self, start, goal, heuristic)
+    }
+
+    /// Cost of the cheapest path from `start` to each reachable state, keyed
+    /// by canonical state; see `astar::distance_map`.
+    fn distance_map(&self, start: Self::State) -> HashMap<Self::State, Self::Cost> {
+        astar::distance_map(self, start)
+    }
+
+    /// Anytime weighted A*, yielding ever cheaper solutions; see
+    /// `anytime::AnytimeAStar`.
+    fn anytime<G, H>(&self, start: Self::State, goal: G, heuristic: H, weight: f64) -> AnytimeAStar<'_, Self, G, H>
+    where Self::Action: Clone, Self::Cost: Scale, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        AnytimeAStar::new(self, start, goal, heuristic, weight)
+    }
+
+    /// Anytime repairing A*, lowering its weight from `epsilon` by
+    /// `decrement` between runs; see `anytime::AraStar`.
+    fn ara_star<G, H>(&self, start: Self::State, goal: G, heuristic: H, epsilon: f64, decrement: f64)
+        -> AraStar<'_, Self, G, H>
+    where Self::Action: Clone, Self::Cost: Scale, G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        AraStar::new(self, start, goal, heuristic, epsilon, decrement)
+    }
+
+    /// Depth-first branch and bound, returning a cheapest path that is
+    /// cheaper than `incumbent`; see `bnb::depth_first`.
+    fn branch_and_bound<G, B>(&self, start: Self::State, goal: G, bound: B, incumbent: Option<Self::Cost>)
+        -> Option<(Vec<Self::Action>, Self::Cost)>
+    where Self::Action: Clone, G: SearchGoal<Self::State>, B: Heuristic<Self::State, Self::Cost> {
+        bnb::depth_first(self, start, goal, bound, incumbent)
+    }
+
+    /// Iterative deepening A*, finding a cheapest path while only keeping the
+    /// current one in memory; see `ida::ida_star`.
+    fn ida_star<G, H>(&self, start: Self::State, goal: G, heuristic: H) -> Option<(Vec<Self::Action>, Self::Cost)>
+    where G: SearchGoal<Self::State>, H: Heuristic<Self::State, Self::Cost> {
+        ida::ida_star(self, start, goal, heuristic)
+    }
+
+    /// Depth-first branch and bound yielding each improving solution; see
+    /// `bnb::AnytimeBranchAndBound`.
+    fn anytime_branch_and_bound<G, B>(&self, start: Self::State, goal: G, bound: B, incumbent: Option<Self::Cost>)
+        -> AnytimeBranchAndBound<'_, Self, G, B>
+    where Self::Action: Clone, G: SearchGoal<Self::State>, B: Heuristic<Self::State, Self::Cost> {
+        AnytimeBranchAndBound::new(self, start, goal, bound, incumbent)
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use std::cell::Cell;
+    use std::thread;
+    use std::time::Duration;
     use std::vec::IntoIter;
-    use super::SearchSpace;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use astar::AStarBuffers;
+    use graph::Graph;
+    use prediction::IdaStarPredictor;
+    use session::SearchSession;
+    use stats::LayerCounts;
+    use visited::PackedVisited;
+    use super::{ExpandContext, SearchBuffers, SearchSpace, WeightedSearchSpace};
 
     #[test]
     pub fn test_dfs() {
@@ -107,4 +1138,316 @@ pub mod tests {
         assert!(ts.dfs(2, 0).is_none());
         assert!(ts.dfs(5, 0).is_none());
     }
+
+    #[derive(Debug, PartialEq)]
+    enum Side { Left, Right }
+
+    // The tree of `test_dfs`, shared by the tests of the other searches.
+    struct Forked;
+
+    impl SearchSpace for Forked {
+        type State = i32;
+        type Action = Side;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![(Side::Left, 1), (Side::Right, 2)],
+                1 => vec![(Side::Left, 3), (Side::Right, 4)],
+                2 => vec![(Side::Left, 2)],
+                _ => vec![]
+            }.into_iter()
+        }
+    }
+
+    // Walk along the integers, one step each way, on a budget of three
+    // steps, or of a cost of five where stepping up costs two.
+    struct Budget;
+
+    impl SearchSpace for Budget {
+        type State = i32;
+        type Action = i32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            vec![(1, state + 1), (-1, state - 1)].into_iter()
+        }
+
+        fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+            if context.depth < 3 { self.expand(state) } else { vec![].into_iter() }
+        }
+    }
+
+    impl WeightedSearchSpace for Budget {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, &step: &Self::Action) -> Self::Cost {
+            if step > 0 { 2 } else { 1 }
+        }
+
+        fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+            if context.cost < 5 { self.expand(state) } else { vec![].into_iter() }
+        }
+    }
+
+    #[test]
+    pub fn test_expand_at() {
+        assert_eq!(Budget.dfs(0, 3), Some(vec![1, 1, 1]));
+        assert_eq!(Budget.dfs(0, 4), None);
+        assert_eq!(Budget.dfs_iter(0).map(|(depth, _)| depth).max(), Some(3));
+        assert_eq!(Budget.astar(0, 3, |_: &i32| 0), Some((vec![1, 1, 1], 6)));
+        assert_eq!(Budget.astar(0, 4, |_: &i32| 0), None);
+        assert_eq!(Budget.astar(0, -5, |_: &i32| 0).map(|found| found.1), Some(5));
+        assert_eq!(Budget.branch_and_bound(0, 3, |_: &i32| 0, None).map(|found| found.1), Some(6));
+        assert_eq!(Budget.ida_star(0, 3, |_: &i32| 0), Some((vec![1, 1, 1], 6)));
+        assert_eq!(Budget.ida_star(0, 4, |_: &i32| 0), None);
+
+        let distances = Budget.distance_map(0);
+        assert_eq!(distances.len(), 9);
+        assert!(!distances.contains_key(&4) && !distances.contains_key(&-6));
+        // Paths in the tree branch while they cost less than five, and every
+        // node of a level with the same cost has the same subtree, so the
+        // prediction is exact.
+        let predictor = IdaStarPredictor::new(&Budget, |_: &i32| 0);
+        assert_eq!(predictor.predict(0, 100, 4, &mut StdRng::seed_from_u64(1)), 25.0);
+    }
+
+    // Integers from -3 to 3 stepping either way, where negative numbers are
+    // known to lead nowhere.
+    struct Slope;
+
+    impl SearchSpace for Slope {
+        type State = i32;
+        type Action = i32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            vec![(-1, state - 1), (1, state + 1)].into_iter().filter(|(_, next)| next.abs() <= 3).collect::<Vec<_>>()
+                .into_iter()
+        }
+
+        fn is_dead_end(&self, state: &Self::State) -> bool {
+            *state < 0
+        }
+    }
+
+    impl WeightedSearchSpace for Slope {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    #[test]
+    pub fn test_dead_ends() {
+        let mut buffers = SearchBuffers::new();
+        assert_eq!(Slope.dfs_with(&mut buffers, 0, 3), Some(vec![1, 1, 1]));
+        assert_eq!(buffers.stats().dead_ends, 1);
+        assert_eq!(Slope.dfs(0, -2), None);
+        assert_eq!(Slope.dfs_iter(0).count(), 4);
+
+        let mut buffers = AStarBuffers::new();
+        assert_eq!(Slope.astar_with(&mut buffers, 0, 3, |_: &i32| 0), Some((vec![1, 1, 1], 3)));
+        assert_eq!(buffers.stats().dead_ends, 1);
+        assert_eq!(Slope.branch_and_bound(0, 3, |_: &i32| 0, Some(4)).map(|found| found.1), Some(3));
+        assert_eq!(Slope.anytime(1, -1, |_: &i32| 0, 1.0).count(), 0);
+    }
+
+    #[test]
+    pub fn test_dfs_in() {
+        let mut buffers = SearchBuffers::new();
+        assert_eq!(Slope.dfs_in(&mut buffers, 0, 3), Some(&[1, 1, 1][..]));
+        assert_eq!(Slope.dfs_in(&mut buffers, 0, 0), Some(&[][..]));
+        assert_eq!(Slope.dfs_in(&mut buffers, 0, -2), None);
+        assert_eq!(Slope.dfs_with(&mut buffers, 0, 2), Some(vec![1, 1]));
+    }
+
+    #[test]
+    pub fn test_find_cycle() {
+        assert_eq!(Forked.find_cycle(0), Some(vec![(Side::Left, 2)]));
+        assert_eq!(Forked.find_cycle(1), None);
+
+        // Build dependencies, where c and e depend on each other through d.
+        let mut graph = Graph::new(5);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(1, 2, 1);
+        graph.add_edge(2, 3, 1);
+        graph.add_edge(3, 4, 1);
+        graph.add_edge(4, 2, 1);
+        let cycle = graph.find_cycle(0).unwrap();
+        assert_eq!(cycle.iter().map(|step| step.1).collect::<Vec<_>>(), vec![3, 4, 2]);
+        assert_eq!(graph.walk(2, &cycle.iter().map(|step| step.0).collect::<Vec<_>>()), vec![2, 3, 4, 2]);
+        assert_eq!(graph.find_cycle(3).map(|cycle| cycle.len()), Some(3));
+        graph = Graph::new(3);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(1, 2, 1);
+        assert_eq!(graph.find_cycle(0), None);
+    }
+
+    #[test]
+    pub fn test_dfs_iter_lazy() {
+        struct Counting(Cell<usize>);
+
+        impl SearchSpace for Counting {
+            type State = i32;
+            type Action = Side;
+            type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+            fn expand(&self, state: &Self::State) -> Self::Iterator {
+                self.0.set(self.0.get() + 1);
+                Forked.expand(state)
+            }
+        }
+
+        let cs = Counting(Cell::new(0));
+        assert_eq!(cs.dfs(0, 0).unwrap(), vec![]);
+        assert_eq!(cs.0.get(), 0);
+        assert_eq!(cs.dfs(0, 1).unwrap(), vec![Side::Left]);
+        assert_eq!(cs.0.get(), 1);
+
+        let ts = Forked;
+        let states: Vec<_> = ts.dfs_iter(0).collect();
+        assert_eq!(states, vec![(0, 0), (1, 1), (2, 3), (2, 4), (1, 2)]);
+
+        let mut iter = ts.dfs_iter(0);
+        let mut seen = vec![];
+        while let Some((depth, state)) = iter.next() {
+            if state == 1 {
+                assert_eq!(iter.actions().collect::<Vec<_>>(), vec![&Side::Left]);
+                iter.prune();
+            }
+            seen.push((depth, state));
+        }
+        assert_eq!(seen, vec![(0, 0), (1, 1), (1, 2)]);
+    }
+
+    #[test]
+    pub fn test_bfs_iter() {
+        let ts = Forked;
+        let states: Vec<_> = ts.bfs_iter(0).collect();
+        assert_eq!(states, vec![(0, 0), (1, 1), (1, 2), (2, 3), (2, 4)]);
+
+        let mut iter = ts.bfs_iter(0);
+        let mut seen = vec![];
+        while let Some((depth, state)) = iter.next() {
+            if state == 1 {
+                iter.prune();
+            }
+            seen.push((depth, state));
+        }
+        assert_eq!(seen, vec![(0, 0), (1, 1), (1, 2)]);
+        assert_eq!((iter.stats().expanded, iter.stats().duplicates), (2, 1));
+
+        let mut buffers = SearchBuffers::new();
+        assert_eq!(ts.dfs_with(&mut buffers, 0, 4).unwrap(), vec![Side::Left, Side::Right]);
+        let mut iter = ts.bfs_iter_with(buffers, 0);
+        assert_eq!(iter.by_ref().take_while(|&(depth, _)| depth < 2).count(), 3);
+        assert_eq!(iter.stats().expanded, 2);
+        assert_eq!(Budget.bfs_iter(0).map(|(depth, _)| depth).max(), Some(3));
+    }
+
+    #[test]
+    pub fn test_dfs_with_buffers() {
+        let ts = Forked;
+        let mut buffers = SearchBuffers::new();
+
+        for _ in 0..3 {
+            assert_eq!(ts.dfs_with(&mut buffers, 0, 4).unwrap(), vec![Side::Left, Side::Right]);
+            assert!(ts.dfs_with(&mut buffers, 2, 0).is_none());
+        }
+
+        let iter = ts.dfs_iter_with(buffers, 0);
+        assert_eq!(iter.count(), 5);
+
+        let mut packed = SearchBuffers::with_visited(PackedVisited::new(|state: &i32| *state as u8));
+        assert_eq!(ts.dfs_with(&mut packed, 0, 4).unwrap(), vec![Side::Left, Side::Right]);
+        assert_eq!(ts.dfs_iter_with(packed, 0).count(), 5);
+    }
+
+    #[test]
+    pub fn test_dfs_with_capacity() {
+        let ts = Forked;
+        let mut buffers = SearchBuffers::with_capacity(8);
+        buffers.reserve(16, 4);
+        assert_eq!(ts.dfs_with(&mut buffers, 0, 4).unwrap(), vec![Side::Left, Side::Right]);
+        assert!(ts.dfs_with(&mut buffers, 2, 0).is_none());
+        assert_eq!(ts.dfs_iter_with(buffers, 0).count(), 5);
+    }
+
+    #[test]
+    pub fn test_dfs_canonicalize() {
+        struct Mirrored;
+
+        impl SearchSpace for Mirrored {
+            type State = i32;
+            type Action = i32;
+            type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+            fn expand(&self, state: &Self::State) -> Self::Iterator {
+                if state.abs() < 3 {
+                    vec![(-1, state - 1), (1, state + 1)].into_iter()
+                } else {
+                    vec![].into_iter()
+                }
+            }
+
+            fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+                Some(state.abs())
+            }
+        }
+
+        let states: Vec<_> = Mirrored.dfs_iter(0).map(|(_, state)| state).collect();
+        assert_eq!(states, vec![0, -1, -2, -3]);
+        assert_eq!(Mirrored.dfs(0, 3), None);
+        assert_eq!(Mirrored.dfs(0, -3), Some(vec![-1, -1, -1]));
+    }
+
+    #[test]
+    pub fn test_dfs_profile() {
+        let ts = Forked;
+        let mut buffers = SearchBuffers::new().profiled();
+        assert!(ts.dfs_with(&mut buffers, 0, 5).is_none());
+
+        let counts = |expanded, generated, duplicates| LayerCounts { expanded, generated, duplicates };
+        let profile = buffers.profile().unwrap();
+        assert_eq!(profile.layers(), &[counts(1, 0, 0), counts(2, 2, 0), counts(2, 3, 1)]);
+        assert_eq!(profile.total(), counts(5, 5, 1));
+
+        let mut iter = ts.dfs_iter(0);
+        iter.next();
+        assert!(iter.profile().is_none());
+    }
+
+    #[test]
+    pub fn test_dfs_stats() {
+        let ts = Forked;
+        let mut buffers = SearchBuffers::new();
+        assert!(ts.dfs_with(&mut buffers, 0, 4).is_some());
+        let stats = buffers.stats();
+        assert_eq!((stats.expanded, stats.generated, stats.duplicates), (3, 3, 0));
+        assert_eq!((stats.max_open, stats.max_depth), (3, 2));
+        assert!(stats.profile.is_none());
+        assert_eq!(stats.stored(), 4);
+        assert!(stats.approx_memory_bytes() >= stats.peak_memory_bytes);
+        assert!(stats.peak_memory_bytes >= 4 * stats.closed_entry_bytes + 3 * stats.open_entry_bytes);
+
+        let mut iter = ts.dfs_iter(0);
+        while iter.next().is_some() {}
+        let stats = iter.stats();
+        assert_eq!((stats.expanded, stats.generated, stats.duplicates), (5, 5, 1));
+
+        // Traversals are timed from their start, including the time between
+        // steps.
+        let mut iter = ts.bfs_iter(0);
+        iter.next();
+        thread::sleep(Duration::from_millis(2));
+        assert!(iter.stats().elapsed >= Duration::from_millis(2));
+        let mut session = ts.dfs_session(0, 5);
+        session.step();
+        thread::sleep(Duration::from_millis(2));
+        assert!(session.stats().elapsed >= Duration::from_millis(2));
+    }
 }
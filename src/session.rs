@@ -0,0 +1,390 @@
+use core::future::Future;
+use core::pin::Pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use error::{Limit, SearchError};
+use stopwatch::Stopwatch;
+
+/// Result of advancing a search session by one step.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step<T> {
+    /// The search has more work to do.
+    Continue,
+    Finished(T),
+}
+
+/// How a search ended, telling a proof that there is no path apart from
+/// giving up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchOutcome<T> {
+    Found(T),
+    /// Every reachable state was explored without finding a goal.
+    Exhausted,
+    /// States past the depth limit were left unexplored.
+    DepthLimit,
+    /// Paths costing more than the cost bound were left unexplored.
+    CostBoundExceeded,
+    NodeLimit,
+    Timeout,
+    Cancelled,
+}
+
+impl<T> SearchOutcome<T> {
+    pub fn found(self) -> Option<T> {
+        match self {
+            SearchOutcome::Found(found) => Some(found),
+            _ => None,
+        }
+    }
+
+    /// Whether the search stopped early, so the outcome proves nothing.
+    pub fn gave_up(&self) -> bool {
+        !matches!(self, SearchOutcome::Found(_) | SearchOutcome::Exhausted)
+    }
+}
+
+impl<T> From<Option<T>> for SearchOutcome<T> {
+    fn from(found: Option<T>) -> SearchOutcome<T> {
+        found.map_or(SearchOutcome::Exhausted, SearchOutcome::Found)
+    }
+}
+
+/// Limit that kept a search from exploring every reachable state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Cutoff {
+    Depth,
+    CostBound,
+}
+
+/// Source of the time that time limits are measured against, so that tests
+/// can use a fake one and targets without `std` can supply their own.
+pub trait Clock {
+    /// Time passed since some fixed point, such as the clock's creation.
+    fn now(&self) -> Duration;
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now(&self) -> Duration {
+        (**self).now()
+    }
+}
+
+/// Clock going by `std::time::Instant`, counting from its creation. Without
+/// `std`, and on `wasm32-unknown-unknown`, it stands still.
+#[derive(Clone, Copy, Debug)]
+pub struct SystemClock(Stopwatch);
+
+impl SystemClock {
+    pub fn new() -> SystemClock {
+        SystemClock(Stopwatch::start())
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> SystemClock {
+        SystemClock::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Limits for `SearchSession::run_limited`; none are set by default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchLimits<'a, C = SystemClock> {
+    max_steps: Option<usize>,
+    timeout: Option<Duration>,
+    cancel: Option<&'a AtomicBool>,
+    clock: C,
+}
+
+impl<'a> SearchLimits<'a> {
+    pub fn new() -> SearchLimits<'a> {
+        SearchLimits::default()
+    }
+}
+
+impl<'a, C: Clock> SearchLimits<'a, C> {
+    /// Gives up after `max_steps` steps, roughly one expansion each.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Gives up once `timeout` has passed on the clock. The default clock
+    /// stands still without `std`, so the search then never times out.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Gives up once `cancel` is set, possibly from another thread.
+    pub fn with_cancel(mut self, cancel: &'a AtomicBool) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// Measures the timeout on `clock` instead.
+    pub fn with_clock<D: Clock>(self, clock: D) -> SearchLimits<'a, D> {
+        SearchLimits { max_steps: self.max_steps, timeout: self.timeout, cancel: self.cancel, clock }
+    }
+}
+
+/// A search that can be advanced a little at a time, so callers can
+/// interleave it with other work.
+pub trait SearchSession {
+    type Output;
+
+    /// Advances the search by roughly one expansion. Once `Finished` has been
+    /// returned the session should not be stepped again.
+    fn step(&mut self) -> Step<Self::Output>;
+
+    /// Steps the search to completion.
+    fn run(mut self) -> Self::Output where Self: Sized {
+        loop {
+            if let Step::Finished(output) = self.step() {
+                return output;
+            }
+        }
+    }
+
+    /// Limit that left states unexplored, if any, so that running out of
+    /// states does not prove there is no path.
+    fn cut_off(&self) -> Option<Cutoff> {
+        None
+    }
+
+    /// Steps the search until it ends or a limit is hit. A session that gave
+    /// up can be stepped or run further.
+    fn run_limited<T, C>(&mut self, limits: &SearchLimits<C>) -> SearchOutcome<T>
+    where Self: SearchSession<Output=Option<T>>, C: Clock {
+        let started = limits.clock.now();
+        let mut steps = 0;
+        loop {
+            if limits.cancel.is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+                return SearchOutcome::Cancelled;
+            }
+            if limits.max_steps.is_some_and(|max_steps| steps == max_steps) {
+                return SearchOutcome::NodeLimit;
+            }
+            if limits.timeout.is_some_and(|timeout| limits.clock.now().saturating_sub(started) >= timeout) {
+                return SearchOutcome::Timeout;
+            }
+            steps += 1;
+            match self.step() {
+                Step::Continue => (),
+                Step::Finished(Some(found)) => return SearchOutcome::Found(found),
+                Step::Finished(None) => return match self.cut_off() {
+                    Some(Cutoff::Depth) => SearchOutcome::DepthLimit,
+                    Some(Cutoff::CostBound) => SearchOutcome::CostBoundExceeded,
+                    None => SearchOutcome::Exhausted,
+                },
+            }
+        }
+    }
+
+    /// Like `run_limited`, with limits and cancellation reported as errors.
+    fn try_run<T, C>(&mut self, limits: &SearchLimits<C>) -> Result<Option<T>, SearchError>
+    where Self: SearchSession<Output=Option<T>>, C: Clock {
+        match self.run_limited(limits) {
+            SearchOutcome::Found(found) => Ok(Some(found)),
+            SearchOutcome::Exhausted => Ok(None),
+            SearchOutcome::DepthLimit => Err(SearchError::LimitExceeded(Limit::Depth)),
+            SearchOutcome::CostBoundExceeded => Err(SearchError::LimitExceeded(Limit::Cost)),
+            SearchOutcome::NodeLimit => Err(SearchError::LimitExceeded(Limit::Nodes)),
+            SearchOutcome::Timeout => Err(SearchError::LimitExceeded(Limit::Time)),
+            SearchOutcome::Cancelled => Err(SearchError::Cancelled),
+        }
+    }
+
+    /// Future running the search `yield_every` steps per poll, handing
+    /// control back to the executor in between so that long searches do not
+    /// starve other tasks.
+    fn run_cooperative(self, yield_every: usize) -> Cooperative<Self> where Self: Sized {
+        Cooperative { session: self, yield_every: yield_every.max(1) }
+    }
+}
+
+/// Future returned by `SearchSession::run_cooperative`.
+pub struct Cooperative<S> {
+    session: S,
+    yield_every: usize,
+}
+
+impl<S> Cooperative<S> {
+    pub fn session(&self) -> &S {
+        &self.session
+    }
+
+    pub fn into_session(self) -> S {
+        self.session
+    }
+}
+
+impl<S: SearchSession + Unpin> Future for Cooperative<S> {
+    type Output = S::Output;
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context) -> Poll<S::Output> {
+        for _ in 0..self.yield_every {
+            if let Step::Finished(output) = self.session.step() {
+                return Poll::Ready(output);
+            }
+        }
+        context.waker().wake_by_ref();
+        Poll::Pending
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+    use std::vec::IntoIter;
+    use astar::AStarBuffers;
+    use search::{SearchBuffers, SearchSpace, WeightedSearchSpace};
+    use std::sync::atomic::AtomicBool;
+    use std::time::Duration;
+    use super::{Clock, SearchLimits, SearchOutcome, SearchSession, Step};
+
+    // Line 0 -> 1 -> ... -> 99.
+    struct Line;
+
+    impl SearchSpace for Line {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            if *state < 99 { vec![(*state + 1, *state + 1)] } else { vec![] }.into_iter()
+        }
+    }
+
+    impl WeightedSearchSpace for Line {
+        type Cost = u32;
+
+        fn cost(&self, _state: &Self::State, _action: &Self::Action) -> Self::Cost {
+            1
+        }
+    }
+
+    // Branch 0 -> 1 -> 2 -> 3 explored before the shorter 0 -> 10 -> 11.
+    struct Branches;
+
+    impl SearchSpace for Branches {
+        type State = u32;
+        type Action = u32;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![(1, 1), (10, 10)],
+                1 | 2 | 10 => vec![(*state + 1, *state + 1)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    // Polls to completion, returning the output and the number of polls.
+    fn block_on<F: Future>(future: F) -> (F::Output, usize) {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        let mut polls = 0;
+        loop {
+            polls += 1;
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return (output, polls);
+            }
+        }
+    }
+
+    #[test]
+    pub fn test_dfs_session() {
+        let mut session = Line.dfs_session(0, 2);
+        assert_eq!(session.step(), Step::Continue);
+        assert_eq!(session.step(), Step::Continue);
+        assert_eq!(session.step(), Step::Finished(Some(vec![1, 2])));
+        assert_eq!(session.stats().expanded, 2);
+
+        assert_eq!(Line.dfs_session(0, 200).run(), None);
+        let (found, polls) = block_on(Line.dfs_session_with(SearchBuffers::new(), 0, 50).run_cooperative(10));
+        assert_eq!(found.map(|actions| actions.len()), Some(50));
+        assert_eq!(polls, 6);
+    }
+
+    #[test]
+    pub fn test_astar_session() {
+        let mut buffers = AStarBuffers::new();
+        let (found, polls) = block_on(Line.astar_session(&mut buffers, 0, 99, |_: &u32| 0).run_cooperative(25));
+        assert_eq!(found.map(|found| found.1), Some(99));
+        assert_eq!(polls, 4);
+        assert_eq!(buffers.stats().expanded, 99);
+        assert_eq!(Line.astar(0, 99, |_: &u32| 0), Line.astar_session(&mut buffers, 0, 99, |_: &u32| 0).run());
+    }
+
+    #[test]
+    pub fn test_run_limited() {
+        let limits = SearchLimits::new().with_max_steps(20);
+        let mut session = Line.dfs_session(0, 50);
+        assert_eq!(session.run_limited(&limits), SearchOutcome::NodeLimit);
+        assert_eq!(session.stats().expanded, 19);
+        assert_eq!(session.run_limited(&SearchLimits::new()).found().map(|actions| actions.len()), Some(50));
+
+        assert_eq!(Line.dfs_session(0, 200).run_limited(&limits.with_max_steps(1000)), SearchOutcome::Exhausted);
+        assert_eq!(Line.dfs_session(0, 50).with_max_depth(10).run_limited(&limits), SearchOutcome::DepthLimit);
+        assert_eq!(Line.dfs_session(0, 5).with_max_depth(10).run_limited(&limits).found(), Some(vec![1, 2, 3, 4, 5]));
+        let mut buffers = AStarBuffers::new();
+        let outcome = Line.astar_session(&mut buffers, 0, 99, |_: &u32| 0).run_limited(&SearchLimits::new());
+        assert_eq!(outcome.found().map(|found| found.1), Some(99));
+
+        let cancel = AtomicBool::new(true);
+        let outcome = Line.dfs_session(0, 50).run_limited(&SearchLimits::new().with_cancel(&cancel));
+        assert!(outcome.gave_up() && outcome == SearchOutcome::Cancelled);
+        let outcome = Line.dfs_session(0, 50).run_limited(&SearchLimits::new().with_timeout(Duration::ZERO));
+        assert_eq!(outcome, SearchOutcome::Timeout);
+    }
+
+    #[test]
+    pub fn test_partial() {
+        let limits = SearchLimits::new().with_max_steps(3);
+        let mut session = Line.dfs_session(0, 50);
+        assert_eq!(session.run_limited(&limits), SearchOutcome::NodeLimit);
+        assert_eq!(session.partial(), Some(vec![1, 2]));
+        let mut session = Branches.dfs_session(0, 99);
+        assert_eq!(session.run_limited(&limits.with_max_steps(6)), SearchOutcome::NodeLimit);
+        assert_eq!(session.partial(), Some(vec![1, 2, 3]));
+        let mut buffers = AStarBuffers::new();
+        let mut session = Line.astar_session(&mut buffers, 0, 99, |state: &u32| 99 - state);
+        assert_eq!(session.run_limited(&limits.with_max_steps(5)), SearchOutcome::NodeLimit);
+        assert_eq!(session.partial(), Some((vec![1, 2, 3, 4, 5], 5)));
+        assert_eq!(Line.dfs_session(0, 5).run_limited(&limits.with_max_steps(20)),
+                   SearchOutcome::Found(vec![1, 2, 3, 4, 5]));
+    }
+
+    // Clock moving a second forward every time it is read.
+    struct Ticking(Cell<Duration>);
+
+    impl Clock for Ticking {
+        fn now(&self) -> Duration {
+            self.0.set(self.0.get() + Duration::from_secs(1));
+            self.0.get()
+        }
+    }
+
+    #[test]
+    pub fn test_clock() {
+        let clock = Ticking(Cell::new(Duration::ZERO));
+        let limits = SearchLimits::new().with_timeout(Duration::from_secs(5)).with_clock(&clock);
+        let mut session = Line.dfs_session(0, 50);
+        assert_eq!(session.run_limited(&limits), SearchOutcome::Timeout);
+        assert_eq!(session.stats().expanded, 3);
+        let outcome = session.run_limited(&limits.with_timeout(Duration::from_secs(100)));
+        assert_eq!(outcome.found().map(|actions| actions.len()), Some(50));
+    }
+}
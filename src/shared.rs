@@ -0,0 +1,120 @@
+//! Search space adapter handing out states behind reference counted
+//! pointers, for states large enough that cloning them into visited sets,
+//! node stores and frontiers dominates the search.
+
+use alloc::sync::Arc;
+
+use search::{ExpandContext, SearchSpace, WeightedSearchSpace};
+
+/// Wraps a search space so that its states become `Arc<S::State>`, each
+/// allocated once when generated and shared by every structure holding it
+/// from then on: clones of a state copy the pointer, not the state. `Arc`
+/// hashes and compares by the state it points to, so duplicate detection is
+/// unchanged, and an equal state generated separately, in its own
+/// allocation, is still recognized as a duplicate.
+pub struct SharedSpace<S> {
+    search_space: S,
+}
+
+impl<S> SharedSpace<S> {
+    pub fn new(search_space: S) -> SharedSpace<S> {
+        SharedSpace { search_space }
+    }
+
+    pub fn inner(&self) -> &S {
+        &self.search_space
+    }
+
+    pub fn into_inner(self) -> S {
+        self.search_space
+    }
+}
+
+/// Successors of a `SharedSpace` expansion.
+pub struct Shared<I> {
+    successors: I,
+}
+
+impl<A, T, I> Iterator for Shared<I> where I: Iterator<Item=(A, T)> {
+    type Item = (A, Arc<T>);
+
+    fn next(&mut self) -> Option<(A, Arc<T>)> {
+        self.successors.next().map(|(action, state)| (action, Arc::new(state)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.successors.size_hint()
+    }
+}
+
+impl<S: SearchSpace> SearchSpace for SharedSpace<S> {
+    type State = Arc<S::State>;
+    type Action = S::Action;
+    type Iterator = Shared<S::Iterator>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        Shared { successors: self.search_space.expand(state) }
+    }
+
+    fn expand_at(&self, state: &Self::State, context: ExpandContext) -> Self::Iterator {
+        Shared { successors: self.search_space.expand_at(state, context) }
+    }
+
+    fn canonicalize(&self, state: &Self::State) -> Option<Self::State> {
+        self.search_space.canonicalize(state).map(Arc::new)
+    }
+
+    fn is_dead_end(&self, state: &Self::State) -> bool {
+        self.search_space.is_dead_end(state)
+    }
+}
+
+impl<S: WeightedSearchSpace> WeightedSearchSpace for SharedSpace<S> {
+    type Cost = S::Cost;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        self.search_space.cost(state, action)
+    }
+
+    fn arrival_cost(&self, state: &Self::State) -> Self::Cost {
+        self.search_space.arrival_cost(state)
+    }
+
+    fn step_cost(&self, state: &Self::State, action: &Self::Action, successor: &Self::State) -> Self::Cost {
+        self.search_space.step_cost(state, action, successor)
+    }
+
+    fn dominates(&self, a: &Self::State, b: &Self::State) -> bool {
+        self.search_space.dominates(a, b)
+    }
+
+    fn dominance_key(&self, state: &Self::State) -> u64 {
+        self.search_space.dominance_key(state)
+    }
+
+    fn expand_weighted(&self, state: &Self::State, context: ExpandContext<Self::Cost>) -> Self::Iterator {
+        Shared { successors: self.search_space.expand_weighted(state, context) }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::sync::Arc;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_grid;
+    use grid::{manhattan, Cell};
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::SharedSpace;
+
+    #[test]
+    pub fn test_shared_space() {
+        let grid = random_grid(20, 20, 0.2, &mut StdRng::seed_from_u64(4));
+        let shared = SharedSpace::new(grid.clone());
+        let to_goal = |cell: &Arc<Cell>| manhattan(**cell, (19, 19));
+        assert_eq!(shared.astar(Arc::new((0, 0)), Arc::new((19, 19)), to_goal),
+                   grid.astar((0, 0), (19, 19), |cell: &Cell| manhattan(*cell, (19, 19))));
+        assert_eq!(shared.dfs(Arc::new((0, 0)), Arc::new((19, 19))), grid.dfs((0, 0), (19, 19)));
+        assert_eq!(shared.bfs_iter(Arc::new((0, 0))).count(), grid.bfs_iter((0, 0)).count());
+    }
+}
@@ -0,0 +1,196 @@
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::Add;
+use core::time::Duration;
+
+/// Node counts for a single search layer (depth or f-value band).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayerCounts {
+    pub expanded: u64,
+    pub generated: u64,
+    pub duplicates: u64,
+}
+
+impl Add for LayerCounts {
+    type Output = LayerCounts;
+
+    fn add(self, other: LayerCounts) -> LayerCounts {
+        LayerCounts {
+            expanded: self.expanded + other.expanded,
+            generated: self.generated + other.generated,
+            duplicates: self.duplicates + other.duplicates,
+        }
+    }
+}
+
+/// Per-layer histogram of expansions, generations and duplicate hits.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LayerProfile {
+    layers: Vec<LayerCounts>,
+}
+
+impl LayerProfile {
+    pub fn new() -> LayerProfile {
+        LayerProfile::default()
+    }
+
+    pub fn layers(&self) -> &[LayerCounts] {
+        &self.layers
+    }
+
+    pub fn total(&self) -> LayerCounts {
+        self.layers.iter().fold(LayerCounts::default(), |total, &layer| total + layer)
+    }
+
+    pub fn record_expanded(&mut self, layer: usize) {
+        self.layer_mut(layer).expanded += 1;
+    }
+
+    pub fn record_generated(&mut self, layer: usize) {
+        self.layer_mut(layer).generated += 1;
+    }
+
+    pub fn record_duplicate(&mut self, layer: usize) {
+        self.layer_mut(layer).duplicates += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    fn layer_mut(&mut self, layer: usize) -> &mut LayerCounts {
+        if layer >= self.layers.len() {
+            self.layers.resize(layer + 1, LayerCounts::default());
+        }
+        &mut self.layers[layer]
+    }
+}
+
+/// Counters gathered by a search run.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SearchStats {
+    /// States whose successors were generated.
+    pub expanded: u64,
+    /// Successors produced by expansions, duplicates included.
+    pub generated: u64,
+    /// Generated successors discarded as already seen.
+    pub duplicates: u64,
+    /// Generated successors discarded as dead ends.
+    pub dead_ends: u64,
+    /// Generated successors discarded by the observer's pruning predicate.
+    pub pruned: u64,
+    /// Generated successors discarded as dominated by a state already seen.
+    pub dominated: u64,
+    /// Largest number of states waiting on the frontier at once.
+    pub max_open: usize,
+    /// Deepest state reached, in actions from the start.
+    pub max_depth: usize,
+    pub elapsed: Duration,
+    /// Per-layer counts, present when profiling was requested.
+    pub profile: Option<LayerProfile>,
+    /// Estimated bytes each stored state takes in the visited set or node
+    /// store, filled in by the search.
+    pub closed_entry_bytes: usize,
+    /// Estimated bytes each frontier entry takes, filled in by the search.
+    pub open_entry_bytes: usize,
+    /// Largest estimated memory use at any point of the search.
+    pub peak_memory_bytes: usize,
+}
+
+/// Estimated bytes per entry of a hash set or map holding `T`, allowing for
+/// its control bytes and load factor.
+pub fn hash_entry_bytes<T>() -> usize {
+    (mem::size_of::<T>() + 1) * 8 / 7
+}
+
+impl SearchStats {
+    pub fn new() -> SearchStats {
+        SearchStats::default()
+    }
+
+    /// Stats that also record a per-layer `LayerProfile`.
+    pub fn profiled() -> SearchStats {
+        SearchStats { profile: Some(LayerProfile::new()), ..SearchStats::default() }
+    }
+
+    /// Zeroes all counters, keeping profiling enabled if it was.
+    pub fn clear(&mut self) {
+        let profile = self.profile.take().map(|mut profile| {
+            profile.clear();
+            profile
+        });
+        *self = SearchStats { profile, ..SearchStats::default() };
+    }
+
+    pub fn record_expanded(&mut self, layer: usize) {
+        self.expanded += 1;
+        if let Some(ref mut profile) = self.profile {
+            profile.record_expanded(layer);
+        }
+    }
+
+    pub fn record_generated(&mut self, layer: usize) {
+        self.generated += 1;
+        if let Some(ref mut profile) = self.profile {
+            profile.record_generated(layer);
+        }
+    }
+
+    pub fn record_duplicate(&mut self, layer: usize) {
+        self.duplicates += 1;
+        if let Some(ref mut profile) = self.profile {
+            profile.record_duplicate(layer);
+        }
+    }
+
+    pub fn record_dead_end(&mut self) {
+        self.dead_ends += 1;
+    }
+
+    pub fn record_pruned(&mut self) {
+        self.pruned += 1;
+    }
+
+    pub fn record_dominated(&mut self) {
+        self.dominated += 1;
+    }
+
+    pub fn record_open(&mut self, open: usize) {
+        self.max_open = self.max_open.max(open);
+        let memory = self.memory_bytes(self.stored(), open);
+        self.peak_memory_bytes = self.peak_memory_bytes.max(memory);
+    }
+
+    /// Sets the per-entry sizes used for memory estimates.
+    pub fn set_entry_bytes(&mut self, closed: usize, open: usize) {
+        self.closed_entry_bytes = closed;
+        self.open_entry_bytes = open;
+    }
+
+    /// States kept after duplicate detection and pruning, the start state
+    /// included.
+    pub fn stored(&self) -> u64 {
+        if self.max_open == 0 {
+            return 0;
+        }
+        1 + self.generated - self.duplicates - self.dead_ends - self.pruned - self.dominated
+    }
+
+    /// Estimated memory needed by the search's structures, assuming every
+    /// stored state and the largest frontier are held at once. Allocator
+    /// overhead and memory held by the states themselves are not included.
+    pub fn approx_memory_bytes(&self) -> usize {
+        self.memory_bytes(self.stored(), self.max_open)
+    }
+
+    fn memory_bytes(&self, stored: u64, open: usize) -> usize {
+        stored as usize * self.closed_entry_bytes + open * self.open_entry_bytes
+    }
+
+    pub fn record_depth(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
+    }
+}
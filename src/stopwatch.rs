@@ -0,0 +1,35 @@
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// Times a search where the platform has a clock. Without `std`, and on
+/// `wasm32-unknown-unknown` where `Instant` is unavailable, every
+/// measurement is zero.
+#[derive(Clone, Copy, Debug)]
+pub struct Stopwatch {
+    #[cfg(feature = "std")]
+    started: Option<Instant>,
+}
+
+impl Stopwatch {
+    #[cfg(feature = "std")]
+    pub fn start() -> Stopwatch {
+        let clock = !cfg!(all(target_arch = "wasm32", target_os = "unknown"));
+        Stopwatch { started: if clock { Some(Instant::now()) } else { None } }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn start() -> Stopwatch {
+        Stopwatch {}
+    }
+
+    #[cfg(feature = "std")]
+    pub fn elapsed(&self) -> Duration {
+        self.started.map(|started| started.elapsed()).unwrap_or_default()
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn elapsed(&self) -> Duration {
+        Duration::default()
+    }
+}
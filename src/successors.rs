@@ -0,0 +1,132 @@
+//! Adapters between search spaces and the successor functions used by the
+//! `pathfinding` crate, so either side can drive the other's domain model.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+use core::hash::Hash;
+use core::iter::Map;
+use core::marker::PhantomData;
+use core::ops::Add;
+
+use search::{SearchSpace, WeightedSearchSpace};
+
+fn step_to<N: Clone>(state: N) -> (N, N) {
+    (state.clone(), state)
+}
+
+fn weighted_step_to<N: Clone, C>(successor: (N, C)) -> ((N, C), N) {
+    let state = successor.0.clone();
+    (successor, state)
+}
+
+/// Search space over a successor function returning the neighbours of a
+/// state, as passed to `pathfinding`'s `bfs` and `dfs`. The action taken is
+/// the state moved to, so a found path lists the states after the start.
+pub struct SuccessorSpace<N, F> {
+    successors: F,
+    marker: PhantomData<fn(&N)>,
+}
+
+impl<N, F> SuccessorSpace<N, F> {
+    pub fn new(successors: F) -> SuccessorSpace<N, F> {
+        SuccessorSpace { successors, marker: PhantomData }
+    }
+}
+
+impl<N, I, F> SearchSpace for SuccessorSpace<N, F>
+where N: Hash + Clone + Eq, I: IntoIterator<Item=N>, F: Fn(&N) -> I {
+    type State = N;
+    type Action = N;
+    type Iterator = Map<I::IntoIter, fn(N) -> (N, N)>;
+
+    fn expand(&self, state: &N) -> Self::Iterator {
+        (self.successors)(state).into_iter().map(step_to as fn(N) -> (N, N))
+    }
+}
+
+/// Search space over a successor function returning `(state, cost)` pairs,
+/// as passed to `pathfinding`'s `dijkstra` and `astar`. The action taken is
+/// the pair itself.
+pub struct WeightedSuccessorSpace<N, C, F> {
+    successors: F,
+    marker: PhantomData<fn(&N) -> C>,
+}
+
+impl<N, C, F> WeightedSuccessorSpace<N, C, F> {
+    pub fn new(successors: F) -> WeightedSuccessorSpace<N, C, F> {
+        WeightedSuccessorSpace { successors, marker: PhantomData }
+    }
+}
+
+impl<N, C, I, F> SearchSpace for WeightedSuccessorSpace<N, C, F>
+where N: Hash + Clone + Eq, I: IntoIterator<Item=(N, C)>, F: Fn(&N) -> I {
+    type State = N;
+    type Action = (N, C);
+    type Iterator = Map<I::IntoIter, fn((N, C)) -> ((N, C), N)>;
+
+    fn expand(&self, state: &N) -> Self::Iterator {
+        (self.successors)(state).into_iter().map(weighted_step_to as fn((N, C)) -> ((N, C), N))
+    }
+}
+
+impl<N, C, I, F> WeightedSearchSpace for WeightedSuccessorSpace<N, C, F>
+where N: Hash + Clone + Eq, C: Copy + Ord + Add<Output=C> + Default + Debug, I: IntoIterator<Item=(N, C)>,
+      F: Fn(&N) -> I {
+    type Cost = C;
+
+    fn cost(&self, _state: &N, action: &(N, C)) -> C {
+        action.1
+    }
+}
+
+/// Successor function listing the states reachable from a state in one
+/// action, for `pathfinding`'s `bfs`, `dfs` and friends.
+pub fn successors<S: SearchSpace>(space: &S) -> impl Fn(&S::State) -> Vec<S::State> + '_ {
+    move |state| space.expand(state).map(|(_, successor)| successor).collect()
+}
+
+/// Successor function pairing each successor with the cost of reaching it,
+/// for `pathfinding`'s `dijkstra`, `astar` and friends.
+pub fn weighted_successors<S: WeightedSearchSpace>(space: &S)
+    -> impl Fn(&S::State) -> Vec<(S::State, S::Cost)> + '_ {
+    move |state| {
+        space.expand(state)
+             .map(|(action, successor)| {
+                 let cost = space.step_cost(state, &action, &successor);
+                 (successor, cost)
+             })
+             .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use pathfinding::prelude::{bfs, dijkstra};
+    use graph::Graph;
+    use search::{SearchSpace, WeightedSearchSpace};
+    use super::{successors, weighted_successors, SuccessorSpace, WeightedSuccessorSpace};
+
+    #[test]
+    pub fn test_successor_spaces() {
+        let collatz = SuccessorSpace::new(|&n: &u32| if n > 1 && n % 2 == 0 { vec![n / 2] } else { vec![3 * n + 1] });
+        assert_eq!(collatz.dfs(3, 1), Some(vec![10, 5, 16, 8, 4, 2, 1]));
+
+        let steps = WeightedSuccessorSpace::new(|&n: &u32| vec![(n + 1, 1u32), (n * 2, 3)]);
+        assert_eq!(steps.astar(1, 8, |_: &u32| 0), Some((vec![(2, 1), (3, 1), (4, 1), (8, 3)], 6)));
+    }
+
+    #[test]
+    pub fn test_pathfinding_interop() {
+        let mut graph = Graph::new(4);
+        graph.add_edge(0, 1, 1);
+        graph.add_edge(1, 3, 1);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(2, 3, 5);
+        graph.add_edge(0, 3, 4);
+
+        assert_eq!(bfs(&0, successors(&graph), |&node| node == 3), Some(vec![0, 3]));
+        let (nodes, cost) = dijkstra(&0, weighted_successors(&graph), |&node| node == 3).unwrap();
+        let (actions, expected) = graph.astar(0, 3, |_: &usize| 0).unwrap();
+        assert_eq!((nodes, cost), (graph.walk(0, &actions), expected));
+    }
+}
@@ -0,0 +1,167 @@
+//! Enumerative program synthesis: grow expressions from a grammar, smallest
+//! first, until one passes a test.
+
+use alloc::vec::{IntoIter, Vec};
+use core::fmt::Debug;
+use core::hash::Hash;
+
+use search::SearchSpace;
+
+/// Grammar of expressions, as productions rewriting a symbol into a node
+/// whose children are expressions of the listed symbols.
+pub trait Grammar {
+    type Symbol: Clone + Hash + Eq + Debug;
+    type Node: Clone + Hash + Eq + Debug;
+
+    fn productions(&self, symbol: &Self::Symbol) -> Vec<(Self::Node, Vec<Self::Symbol>)>;
+}
+
+/// Expression tree.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Expr<N> {
+    pub node: N,
+    pub children: Vec<Expr<N>>,
+}
+
+impl<N> Expr<N> {
+    /// Number of nodes.
+    pub fn size(&self) -> usize {
+        1 + self.children.iter().map(Expr::size).sum::<usize>()
+    }
+}
+
+/// Expression with holes: the nodes filled in so far, in preorder with their
+/// number of children, and the symbols of the holes left, the next one to
+/// fill last.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Partial<N, S> {
+    nodes: Vec<(N, usize)>,
+    holes: Vec<S>,
+}
+
+impl<N: Clone, S> Partial<N, S> {
+    /// A single hole of `symbol`.
+    pub fn new(symbol: S) -> Partial<N, S> {
+        Partial { nodes: Vec::new(), holes: vec![symbol] }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.holes.is_empty()
+    }
+
+    /// Nodes filled in so far.
+    pub fn size(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The expression, once complete.
+    pub fn to_expr(&self) -> Option<Expr<N>> {
+        if !self.is_complete() {
+            return None;
+        }
+        let mut built: Vec<Expr<N>> = Vec::new();
+        for (node, arity) in self.nodes.iter().rev() {
+            let children = built.split_off(built.len() - arity).into_iter().rev().collect();
+            built.push(Expr { node: node.clone(), children });
+        }
+        built.pop()
+    }
+}
+
+/// Search space of the partial expressions of a grammar up to `max_size`
+/// nodes. Expanding fills the leftmost hole with each production of its
+/// symbol; an action is the node filled in.
+pub struct Enumeration<'a, G: 'a> {
+    grammar: &'a G,
+    max_size: usize,
+}
+
+impl<'a, G: Grammar> Enumeration<'a, G> {
+    pub fn new(grammar: &'a G, max_size: usize) -> Enumeration<'a, G> {
+        Enumeration { grammar, max_size }
+    }
+
+    /// Complete expressions of exactly `size` nodes derived from `symbol`.
+    pub fn expressions(grammar: &'a G, symbol: G::Symbol, size: usize) -> impl Iterator<Item=Expr<G::Node>> + 'a {
+        let enumeration = Enumeration::new(grammar, size);
+        let partials: Vec<Partial<G::Node, G::Symbol>> = enumeration.dfs_iter(Partial::new(symbol))
+            .map(|(_, partial)| partial)
+            .filter(|partial| partial.is_complete() && partial.size() == size)
+            .collect();
+        partials.into_iter().filter_map(|partial| partial.to_expr())
+    }
+}
+
+impl<'a, G: Grammar> SearchSpace for Enumeration<'a, G> {
+    type State = Partial<G::Node, G::Symbol>;
+    type Action = G::Node;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, partial: &Self::State) -> Self::Iterator {
+        let mut holes = partial.holes.clone();
+        let symbol = match holes.pop() {
+            Some(symbol) => symbol,
+            None => return Vec::new().into_iter(),
+        };
+        self.grammar.productions(&symbol).into_iter()
+            // Every hole takes at least one more node.
+            .filter(|(_, children)| partial.nodes.len() + 1 + holes.len() + children.len() <= self.max_size)
+            .map(|(node, children)| {
+                let mut next = Partial { nodes: partial.nodes.clone(), holes: holes.clone() };
+                next.nodes.push((node.clone(), children.len()));
+                next.holes.extend(children.into_iter().rev());
+                (node, next)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+/// Smallest expression derived from `symbol` that passes `test`, trying
+/// sizes from 1 to `max_size` in turn: iterative deepening on size.
+pub fn synthesize<G, T>(grammar: &G, symbol: G::Symbol, max_size: usize, test: T) -> Option<Expr<G::Node>>
+where G: Grammar, T: Fn(&Expr<G::Node>) -> bool {
+    (1..=max_size).flat_map(|size| Enumeration::expressions(grammar, symbol.clone(), size)).find(|expr| test(expr))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::Vec;
+    use super::{synthesize, Enumeration, Expr, Grammar};
+
+    #[derive(Clone, Debug, PartialEq, Eq, Hash)]
+    enum Node { X, One, Add, Mul }
+
+    struct Arithmetic;
+
+    impl Grammar for Arithmetic {
+        type Symbol = ();
+        type Node = Node;
+
+        fn productions(&self, _symbol: &()) -> Vec<(Node, Vec<()>)> {
+            vec![(Node::X, vec![]), (Node::One, vec![]), (Node::Add, vec![(), ()]), (Node::Mul, vec![(), ()])]
+        }
+    }
+
+    fn eval(expr: &Expr<Node>, x: i64) -> i64 {
+        match expr.node {
+            Node::X => x,
+            Node::One => 1,
+            Node::Add => eval(&expr.children[0], x) + eval(&expr.children[1], x),
+            Node::Mul => eval(&expr.children[0], x) * eval(&expr.children[1], x),
+        }
+    }
+
+    #[test]
+    pub fn test_enumeration() {
+        assert_eq!(Enumeration::expressions(&Arithmetic, (), 1).count(), 2);
+        assert_eq!(Enumeration::expressions(&Arithmetic, (), 2).count(), 0);
+        assert_eq!(Enumeration::expressions(&Arithmetic, (), 3).count(), 8);
+        assert!(Enumeration::expressions(&Arithmetic, (), 5).all(|expr| expr.size() == 5));
+
+        let found = synthesize(&Arithmetic, (), 9, |expr| (-3..4).all(|x| eval(expr, x) == x * x + 2)).unwrap();
+        assert_eq!(found.size(), 7);
+        assert_eq!(synthesize(&Arithmetic, (), 5, |expr| eval(expr, 2) == 100), None);
+    }
+}
@@ -0,0 +1,32 @@
+use tracing::Span;
+
+use stats::SearchStats;
+
+/// Every this many expansions one is reported as a trace event.
+pub const SAMPLE_INTERVAL: u64 = 1024;
+
+pub fn sampled(stats: &SearchStats) -> bool {
+    stats.expanded % SAMPLE_INTERVAL == 1
+}
+
+pub fn search_span(algorithm: &'static str) -> Span {
+    let span = debug_span!("search", algorithm);
+    span.in_scope(|| debug!("search started"));
+    span
+}
+
+pub fn finished(found: bool, stats: &SearchStats) {
+    debug!(
+        found,
+        expanded = stats.expanded,
+        generated = stats.generated,
+        duplicates = stats.duplicates,
+        dead_ends = stats.dead_ends,
+        pruned = stats.pruned,
+        dominated = stats.dominated,
+        max_open = stats.max_open,
+        max_depth = stats.max_depth,
+        elapsed = ?stats.elapsed,
+        "search finished"
+    );
+}
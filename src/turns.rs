@@ -0,0 +1,179 @@
+//! Road routing with turn costs and turn restrictions.
+//!
+//! Turning is priced by searching the edge-expanded graph: a state is a node
+//! together with the node it was entered from, so the cost of moving on can
+//! depend on the turn being made.
+
+use alloc::vec::{IntoIter, Vec};
+
+use hashbrown::HashSet;
+
+use graph::Graph;
+use search::{SearchGoal, SearchSpace, WeightedSearchSpace};
+
+/// Node reached by a `TurnGraph` search, with the node it was entered from,
+/// which is `None` at the start.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Approach {
+    pub node: usize,
+    pub from: Option<usize>,
+}
+
+impl Approach {
+    /// Start of a route at `node`, where any direction may be taken.
+    pub fn start(node: usize) -> Approach {
+        Approach { node, from: None }
+    }
+}
+
+/// Goal matching a node whichever way it is entered.
+pub struct Arrive(pub usize);
+
+impl SearchGoal<Approach> for Arrive {
+    fn is_goal(&self, state: &Approach) -> bool {
+        state.node == self.0
+    }
+}
+
+/// Kind of turn made at a junction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Turn {
+    Straight,
+    Left,
+    Right,
+    UTurn,
+}
+
+impl Turn {
+    /// Turn made driving from `from` through `via` to `to`, with the y axis
+    /// pointing up. Headings within 45 degrees of the incoming one count as
+    /// straight and those within 45 degrees of its reverse as a U-turn.
+    pub fn classify(from: (f64, f64), via: (f64, f64), to: (f64, f64)) -> Turn {
+        let (inx, iny) = (via.0 - from.0, via.1 - from.1);
+        let (outx, outy) = (to.0 - via.0, to.1 - via.1);
+        let dot = inx * outx + iny * outy;
+        let cross = inx * outy - iny * outx;
+        let sideways = if cross < 0.0 { -cross } else { cross };
+        if dot > 0.0 && sideways <= dot {
+            Turn::Straight
+        } else if dot < 0.0 && sideways <= -dot {
+            Turn::UTurn
+        } else if cross > 0.0 {
+            Turn::Left
+        } else {
+            Turn::Right
+        }
+    }
+}
+
+/// `Graph` searched with turn costs. Moving from node `via` to `to` after
+/// entering `via` from `from` costs the edge plus `turn_cost(from, via, to)`;
+/// banned turns are not offered at all. As in `Graph` an action is the index
+/// of the edge taken in the node's edge list.
+pub struct TurnGraph<'a, F> {
+    graph: &'a Graph,
+    turn_cost: F,
+    banned: HashSet<(usize, usize, usize)>,
+    u_turns: bool,
+}
+
+impl<'a, F> TurnGraph<'a, F> where F: Fn(usize, usize, usize) -> u64 {
+    pub fn new(graph: &'a Graph, turn_cost: F) -> TurnGraph<'a, F> {
+        TurnGraph { graph, turn_cost, banned: HashSet::new(), u_turns: true }
+    }
+
+    /// Bans turning from `from` through `via` onto `to`.
+    pub fn ban(&mut self, from: usize, via: usize, to: usize) {
+        self.banned.insert((from, via, to));
+    }
+
+    /// Bans going back along the road just taken.
+    pub fn without_u_turns(mut self) -> TurnGraph<'a, F> {
+        self.u_turns = false;
+        self
+    }
+
+    pub fn is_allowed(&self, from: usize, via: usize, to: usize) -> bool {
+        (self.u_turns || from != to) && !self.banned.contains(&(from, via, to))
+    }
+
+    /// Nodes of a route taken from `start`, start included.
+    pub fn walk(&self, start: usize, actions: &[usize]) -> Vec<usize> {
+        self.graph.walk(start, actions)
+    }
+}
+
+impl<'a, F> SearchSpace for TurnGraph<'a, F> where F: Fn(usize, usize, usize) -> u64 {
+    type State = Approach;
+    type Action = usize;
+    type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+    fn expand(&self, state: &Self::State) -> Self::Iterator {
+        self.graph.edges(state.node).iter().enumerate()
+            .filter(|(_, edge)| state.from.is_none_or(|from| self.is_allowed(from, state.node, edge.to)))
+            .map(|(index, edge)| (index, Approach { node: edge.to, from: Some(state.node) }))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+impl<'a, F> WeightedSearchSpace for TurnGraph<'a, F> where F: Fn(usize, usize, usize) -> u64 {
+    type Cost = u64;
+
+    fn cost(&self, state: &Self::State, action: &Self::Action) -> Self::Cost {
+        let edge = self.graph.edges(state.node)[*action];
+        edge.cost + state.from.map_or(0, |from| (self.turn_cost)(from, state.node, edge.to))
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use graph::NamedGraph;
+    use search::WeightedSearchSpace;
+    use super::{Approach, Arrive, Turn, TurnGraph};
+
+    #[test]
+    pub fn test_turn_classification() {
+        assert_eq!(Turn::classify((0.0, 0.0), (1.0, 0.0), (2.0, 0.5)), Turn::Straight);
+        assert_eq!(Turn::classify((0.0, 0.0), (1.0, 0.0), (1.0, 1.0)), Turn::Left);
+        assert_eq!(Turn::classify((0.0, 0.0), (1.0, 0.0), (1.0, -1.0)), Turn::Right);
+        assert_eq!(Turn::classify((0.0, 0.0), (1.0, 0.0), (0.0, 0.1)), Turn::UTurn);
+    }
+
+    #[test]
+    pub fn test_turn_costs() {
+        // A junction at b where turning left onto f is dear, so going round
+        // the block by three right turns is cheaper.
+        let mut named = NamedGraph::new();
+        for &(name, x, y) in &[("a", 0.0, 0.0), ("b", 1.0, 0.0), ("c", 2.0, 0.0), ("d", 2.0, -1.0),
+                               ("e", 1.0, -1.0), ("f", 1.0, 1.0)] {
+            let node = named.add_node(name);
+            named.set_position(node, Some((x, y)));
+        }
+        for &(from, to) in &[("a", "b"), ("b", "c"), ("c", "d"), ("d", "e"), ("e", "b"), ("b", "f")] {
+            named.add_edge(from, to, 1);
+        }
+        let node = |name| named.node(name).unwrap();
+        let position = |node| named.position(node).unwrap();
+        let turn_cost = |from, via, to| match Turn::classify(position(from), position(via), position(to)) {
+            Turn::Straight => 0,
+            Turn::Right => 1,
+            Turn::Left => 10,
+            Turn::UTurn => 20,
+        };
+
+        let mut roads = TurnGraph::new(named.graph(), turn_cost);
+        let (actions, cost) = roads.astar(Approach::start(node("a")), Arrive(node("f")), |_: &_| 0).unwrap();
+        assert_eq!(cost, 9);
+        assert_eq!(roads.walk(node("a"), &actions).len(), 7);
+
+        roads.ban(node("e"), node("b"), node("f"));
+        let (actions, cost) = roads.astar(Approach::start(node("a")), Arrive(node("f")), |_: &_| 0).unwrap();
+        assert_eq!((actions.len(), cost), (2, 12));
+
+        roads.ban(node("a"), node("b"), node("f"));
+        assert_eq!(roads.astar(Approach::start(node("a")), Arrive(node("f")), |_: &_| 0), None);
+    }
+}
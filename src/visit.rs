@@ -0,0 +1,159 @@
+//! Depth-first traversal reporting its events to a visitor that steers it.
+
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+
+use search::SearchSpace;
+
+/// What a depth-first traversal ran into, passed to the visitor of
+/// `depth_first_visit`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum DfsEvent<'a, S, A> {
+    /// `state` is reached for the first time, `depth` actions from the start.
+    Discover(&'a S, usize),
+    /// `action` leads from a state on the search path to an undiscovered one,
+    /// which is discovered next unless the visitor prunes the edge.
+    TreeEdge(&'a S, &'a A, &'a S),
+    /// `action` leads back to a state on the search path, closing a cycle.
+    BackEdge(&'a S, &'a A, &'a S),
+    /// `action` leads to a state whose successors were all explored already.
+    CrossForwardEdge(&'a S, &'a A, &'a S),
+    /// Every successor of `state` has been explored.
+    Finish(&'a S, usize),
+}
+
+/// How the traversal goes on after an event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Control<B> {
+    Continue,
+    /// On `Discover`, leaves the successors of the state unexplored; on
+    /// `TreeEdge`, leaves its target undiscovered. Same as `Continue`
+    /// elsewhere.
+    Prune,
+    /// Ends the traversal, which returns the value.
+    Break(B),
+}
+
+/// Depth-first traversal from `start` calling `visitor` on each event, in
+/// the order successors are generated. Each state is discovered and
+/// finished at most once; states are told apart by
+/// `SearchSpace::canonicalize`.
+///
+/// Returns the value of the `Break` that ended the traversal, if any.
+pub fn depth_first_visit<S, F, B>(search_space: &S, start: S::State, mut visitor: F) -> Option<B>
+where S: SearchSpace + ?Sized, F: FnMut(DfsEvent<S::State, S::Action>) -> Control<B> {
+    let key = |state: &S::State| search_space.canonicalize(state).unwrap_or_else(|| state.clone());
+    // Whether each discovered state was finished.
+    let mut finished = HashMap::new();
+    let mut stack: Vec<(S::State, Option<S::Iterator>)> = Vec::new();
+    finished.insert(key(&start), false);
+    match visitor(DfsEvent::Discover(&start, 0)) {
+        Control::Break(value) => return Some(value),
+        Control::Prune => stack.push((start, None)),
+        Control::Continue => {
+            let successors = search_space.expand(&start);
+            stack.push((start, Some(successors)));
+        }
+    }
+    while let Some((state, successors)) = stack.last_mut() {
+        let next = successors.as_mut().and_then(|successors| successors.next());
+        let (action, successor) = match next {
+            Some(step) => step,
+            None => {
+                let depth = stack.len() - 1;
+                let (state, _) = stack.pop().expect("state to finish");
+                finished.insert(key(&state), true);
+                if let Control::Break(value) = visitor(DfsEvent::Finish(&state, depth)) {
+                    return Some(value);
+                }
+                continue;
+            }
+        };
+        let successor_key = key(&successor);
+        let event = match finished.get(&successor_key) {
+            None => DfsEvent::TreeEdge(&*state, &action, &successor),
+            Some(false) => DfsEvent::BackEdge(&*state, &action, &successor),
+            Some(true) => DfsEvent::CrossForwardEdge(&*state, &action, &successor),
+        };
+        let tree_edge = matches!(event, DfsEvent::TreeEdge(..));
+        match visitor(event) {
+            Control::Break(value) => return Some(value),
+            Control::Continue if tree_edge => (),
+            _ => continue,
+        }
+        finished.insert(successor_key, false);
+        let depth = stack.len();
+        match visitor(DfsEvent::Discover(&successor, depth)) {
+            Control::Break(value) => return Some(value),
+            Control::Prune => stack.push((successor, None)),
+            Control::Continue => {
+                let successors = search_space.expand(&successor);
+                stack.push((successor, Some(successors)));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::vec::IntoIter;
+    use search::SearchSpace;
+    use super::{Control, DfsEvent};
+
+    // 0 -> 1 -> 2 -> 0 with a shortcut 0 -> 2 and a spur 1 -> 3.
+    struct Loop;
+
+    impl SearchSpace for Loop {
+        type State = u8;
+        type Action = char;
+        type Iterator = IntoIter<(Self::Action, Self::State)>;
+
+        fn expand(&self, state: &Self::State) -> Self::Iterator {
+            match *state {
+                0 => vec![('a', 1), ('s', 2)],
+                1 => vec![('b', 2), ('c', 3)],
+                2 => vec![('d', 0)],
+                _ => vec![],
+            }.into_iter()
+        }
+    }
+
+    fn describe(event: DfsEvent<u8, char>) -> String {
+        match event {
+            DfsEvent::Discover(state, depth) => format!("discover {} {}", state, depth),
+            DfsEvent::TreeEdge(_, action, _) => format!("tree {}", action),
+            DfsEvent::BackEdge(_, action, _) => format!("back {}", action),
+            DfsEvent::CrossForwardEdge(_, action, _) => format!("cross {}", action),
+            DfsEvent::Finish(state, depth) => format!("finish {} {}", state, depth),
+        }
+    }
+
+    #[test]
+    pub fn test_dfs_visit() {
+        let mut events = vec![];
+        assert_eq!(Loop.dfs_visit(0, |event| { events.push(describe(event)); Control::<()>::Continue }), None);
+        assert_eq!(events, vec![
+            "discover 0 0", "tree a", "discover 1 1", "tree b", "discover 2 2", "back d", "finish 2 2",
+            "tree c", "discover 3 2", "finish 3 2", "finish 1 1", "cross s", "finish 0 0",
+        ]);
+
+        let mut events = vec![];
+        Loop.dfs_visit(0, |event| {
+            let control = match event {
+                DfsEvent::Discover(&1, _) | DfsEvent::TreeEdge(_, &'s', _) => Control::<()>::Prune,
+                _ => Control::Continue,
+            };
+            events.push(describe(event));
+            control
+        });
+        assert_eq!(events, vec!["discover 0 0", "tree a", "discover 1 1", "finish 1 1", "tree s", "finish 0 0"]);
+
+        let cycle = Loop.dfs_visit(0, |event| match event {
+            DfsEvent::BackEdge(&from, _, &to) => Control::Break((from, to)),
+            _ => Control::Continue,
+        });
+        assert_eq!(cycle, Some((2, 0)));
+    }
+}
@@ -0,0 +1,431 @@
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::mem;
+
+use hashbrown::HashSet;
+
+use stats::hash_entry_bytes;
+
+/// Duplicate detection used by searches to skip already seen states.
+pub trait VisitedSet<T> {
+    /// Records `value`, returning `false` if it was seen before.
+    fn insert(&mut self, value: &T) -> bool;
+
+    /// Forgets `value`, for warm starts keeping only states explored in full.
+    fn remove(&mut self, value: &T);
+
+    fn clear(&mut self);
+
+    /// Hints that about `additional` more states are going to be inserted.
+    fn reserve(&mut self, _additional: usize) {}
+
+    /// Estimated bytes taken by each inserted state.
+    fn approx_entry_bytes(&self) -> usize {
+        mem::size_of::<T>()
+    }
+}
+
+/// What a best-first search does on reaching a state it has reached before.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RevisitPolicy {
+    /// Keep the first path found to each state. Fastest, but only optimal
+    /// with a consistent heuristic.
+    NeverRevisit,
+    /// Search the state again if the new path is cheaper.
+    #[default]
+    RevisitIfCheaper,
+    /// Search every path to the state, as when resources spent along the
+    /// way are not part of the state. Only ends on spaces without cycles or
+    /// once a goal is found.
+    AlwaysRevisit,
+}
+
+impl RevisitPolicy {
+    /// Whether a state first reached at cost `known` is searched again when
+    /// reached at `cost`.
+    pub fn revisits<C: PartialOrd>(self, known: C, cost: C) -> bool {
+        match self {
+            RevisitPolicy::NeverRevisit => false,
+            RevisitPolicy::RevisitIfCheaper => cost < known,
+            RevisitPolicy::AlwaysRevisit => true,
+        }
+    }
+}
+
+pub struct Visited<T> {
+    hash_set: HashSet<T>
+}
+
+impl<T> Visited<T> where T: Hash + Clone + Eq {
+    pub fn new() -> Visited<T> {
+        Visited {
+            hash_set: HashSet::new()
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Visited<T> {
+        Visited {
+            hash_set: HashSet::with_capacity(capacity)
+        }
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        self.hash_set.contains(value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.hash_set.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hash_set.is_empty()
+    }
+}
+
+impl<T> Default for Visited<T> where T: Hash + Clone + Eq {
+    fn default() -> Visited<T> {
+        Visited::new()
+    }
+}
+
+impl<T> VisitedSet<T> for Visited<T> where T: Hash + Clone + Eq {
+    fn insert(&mut self, value: &T) -> bool {
+        self.hash_set.insert(value.clone())
+    }
+
+    fn remove(&mut self, value: &T) {
+        self.hash_set.remove(value);
+    }
+
+    fn clear(&mut self) {
+        self.hash_set.clear()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.hash_set.reserve(additional)
+    }
+
+    fn approx_entry_bytes(&self) -> usize {
+        hash_entry_bytes::<T>()
+    }
+}
+
+/// Visited set that remembers nothing, for spaces shaped like a tree where
+/// no state is reached twice and hashing every state is pure overhead. On
+/// a space with cycles a search using it may never end.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoVisited;
+
+impl<T> VisitedSet<T> for NoVisited {
+    fn insert(&mut self, _value: &T) -> bool {
+        true
+    }
+
+    fn remove(&mut self, _value: &T) {}
+
+    fn clear(&mut self) {}
+
+    fn approx_entry_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// Packs states into a compact representation for storage in a visited set.
+///
+/// Two states must pack to equal values exactly when they are equal.
+pub trait StateCodec<T> {
+    type Packed: Hash + Eq;
+
+    fn pack(&self, state: &T) -> Self::Packed;
+}
+
+impl<T, P, F> StateCodec<T> for F where F: Fn(&T) -> P, P: Hash + Eq {
+    type Packed = P;
+
+    fn pack(&self, state: &T) -> P {
+        self(state)
+    }
+}
+
+/// Visited set storing states packed by a `StateCodec` instead of clones.
+pub struct PackedVisited<T, C> where C: StateCodec<T> {
+    codec: C,
+    hash_set: HashSet<C::Packed>,
+    state: PhantomData<fn(&T)>,
+}
+
+impl<T, C> PackedVisited<T, C> where C: StateCodec<T> {
+    pub fn new(codec: C) -> PackedVisited<T, C> {
+        PackedVisited {
+            codec,
+            hash_set: HashSet::new(),
+            state: PhantomData,
+        }
+    }
+
+    pub fn with_capacity(codec: C, capacity: usize) -> PackedVisited<T, C> {
+        PackedVisited {
+            codec,
+            hash_set: HashSet::with_capacity(capacity),
+            state: PhantomData,
+        }
+    }
+}
+
+impl<T, C> VisitedSet<T> for PackedVisited<T, C> where C: StateCodec<T> {
+    fn insert(&mut self, value: &T) -> bool {
+        self.hash_set.insert(self.codec.pack(value))
+    }
+
+    fn remove(&mut self, value: &T) {
+        self.hash_set.remove(&self.codec.pack(value));
+    }
+
+    fn clear(&mut self) {
+        self.hash_set.clear()
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        self.hash_set.reserve(additional)
+    }
+
+    fn approx_entry_bytes(&self) -> usize {
+        hash_entry_bytes::<C::Packed>()
+    }
+}
+
+/// States that map one-to-one onto `0..count` for some known `count`, such
+/// as permutations ranked in lexicographic order.
+pub trait RankableState {
+    fn rank(&self) -> usize;
+}
+
+/// Visited set over rankable states backed by a bit vector indexed by rank
+/// instead of a hash set.
+pub struct RankedVisited {
+    bits: Vec<u64>,
+    count: usize,
+}
+
+impl RankedVisited {
+    /// Visited set for states ranked below `count`.
+    pub fn new(count: usize) -> RankedVisited {
+        RankedVisited {
+            bits: vec![0; count.div_ceil(64)],
+            count,
+        }
+    }
+}
+
+impl<T> VisitedSet<T> for RankedVisited where T: RankableState {
+    fn insert(&mut self, value: &T) -> bool {
+        let rank = value.rank();
+        assert!(rank < self.count, "state rank {} out of range 0..{}", rank, self.count);
+        let (word, bit) = (rank / 64, 1 << (rank % 64));
+        let fresh = self.bits[word] & bit == 0;
+        self.bits[word] |= bit;
+        fresh
+    }
+
+    fn remove(&mut self, value: &T) {
+        let rank = value.rank();
+        assert!(rank < self.count, "state rank {} out of range 0..{}", rank, self.count);
+        self.bits[rank / 64] &= !(1 << (rank % 64));
+    }
+
+    fn clear(&mut self) {
+        for word in &mut self.bits {
+            *word = 0;
+        }
+    }
+
+    /// Zero: the bit vector is allocated up front for every rank.
+    fn approx_entry_bytes(&self) -> usize {
+        0
+    }
+}
+
+/// Lexicographic rank of a permutation of `0..items.len()`.
+pub fn permutation_rank(items: &[u8]) -> usize {
+    let mut rank = 0;
+    for (i, &item) in items.iter().enumerate() {
+        let smaller_after = items[i + 1..].iter().filter(|&&later| later < item).count();
+        rank = rank * (items.len() - i) + smaller_after;
+    }
+    rank
+}
+
+/// Writes fixed-width fields into a dense bit string of `N` words, for
+/// building packed states out of small integers such as puzzle tiles. The
+/// words are kept inline, so a packed state costs its `N` words and no
+/// allocation of its own.
+pub struct BitPacker<const N: usize> {
+    words: [u64; N],
+    used: usize,
+}
+
+impl<const N: usize> Default for BitPacker<N> {
+    fn default() -> BitPacker<N> {
+        BitPacker { words: [0; N], used: 0 }
+    }
+}
+
+impl<const N: usize> BitPacker<N> {
+    pub fn new() -> BitPacker<N> {
+        BitPacker::default()
+    }
+
+    /// Appends the low `bits` bits of `value`.
+    pub fn push(&mut self, value: u64, bits: u32) -> &mut BitPacker<N> {
+        assert!(bits <= 64, "field wider than 64 bits");
+        assert!(self.used + bits as usize <= N * 64, "fields wider than {} words", N);
+        if bits == 0 {
+            return self;
+        }
+        let value = if bits == 64 { value } else { value & ((1 << bits) - 1) };
+        let (word, offset) = (self.used / 64, (self.used % 64) as u32);
+        self.words[word] |= value << offset;
+        if offset + bits > 64 {
+            self.words[word + 1] = value >> (64 - offset);
+        }
+        self.used += bits as usize;
+        self
+    }
+
+    /// The packed words, leaving the packer empty for the next state.
+    pub fn finish(&mut self) -> [u64; N] {
+        mem::take(self).words
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use generators::random_tree;
+    use search::{SearchBuffers, SearchSpace};
+    use session::{SearchLimits, SearchOutcome, SearchSession};
+    use stats::hash_entry_bytes;
+    use super::{BitPacker, NoVisited, PackedVisited, RankableState, RankedVisited, RevisitPolicy, Visited,
+                VisitedSet, permutation_rank};
+
+    #[test]
+    pub fn test_revisit_policy() {
+        assert!(!RevisitPolicy::NeverRevisit.revisits(3, 2));
+        assert!(RevisitPolicy::default().revisits(3, 2) && !RevisitPolicy::default().revisits(3, 3));
+        assert!(RevisitPolicy::AlwaysRevisit.revisits(3, 4));
+    }
+
+    #[test]
+    pub fn test_no_visited() {
+        assert!(VisitedSet::<u8>::insert(&mut NoVisited, &1) && VisitedSet::<u8>::insert(&mut NoVisited, &1));
+        let tree = random_tree(200, 1, &mut StdRng::seed_from_u64(2));
+        let buffers = SearchBuffers::with_visited(NoVisited);
+        assert_eq!(tree.dfs_iter_with(buffers, 0).count(), 200);
+        let mut buffers = SearchBuffers::with_visited(NoVisited);
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199), tree.dfs(0, 199));
+    }
+
+    #[test]
+    pub fn test_warm_start() {
+        let tree = random_tree(200, 1, &mut StdRng::seed_from_u64(2));
+        let mut buffers = SearchBuffers::new().with_warm_start(true);
+        let path = tree.dfs(0, 199).unwrap();
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199).as_ref(), Some(&path));
+        let explored = buffers.stats().expanded;
+        let seen = buffers.visited().len();
+        assert!(seen > path.len() + 1 && !buffers.visited().contains(&199) && !buffers.visited().contains(&0));
+        // The goal is found again, along the path alone, as the subtrees off
+        // it hold no goal and stay pruned.
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199).as_ref(), Some(&path));
+        assert_eq!(buffers.stats().expanded, path.len() as u64);
+        assert!(explored > path.len() as u64 && buffers.visited().len() == seen);
+
+        // A search giving up on a goal out of reach keeps the whole tree.
+        let mut buffers = SearchBuffers::new().with_warm_start(true);
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 200), None);
+        assert_eq!(buffers.visited().len(), 200);
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 200), None);
+        assert_eq!(buffers.stats().expanded, 1);
+
+        // States above a depth cut, or left behind by a traversal stopped
+        // early, are forgotten.
+        let buffers = SearchBuffers::new().with_warm_start(true);
+        let mut session = tree.dfs_session_with(buffers, 0, 199).with_max_depth(1);
+        assert_eq!(session.run_limited(&SearchLimits::new()), SearchOutcome::DepthLimit);
+        let mut buffers = session.into_buffers();
+        assert!(!buffers.visited().contains(&0));
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199).as_ref(), Some(&path));
+        buffers.clear();
+        let mut traversal = tree.bfs_iter_with(buffers, 0);
+        assert!(traversal.nth(5).is_some());
+        let buffers = traversal.into_buffers();
+        assert!(buffers.visited().is_empty());
+        let mut traversal = tree.bfs_iter_with(buffers, 0);
+        assert_eq!(traversal.by_ref().count(), 200);
+        assert_eq!(traversal.into_buffers().visited().len(), 200);
+
+        let mut fenced = Visited::new();
+        fenced.insert(&199);
+        let mut buffers = SearchBuffers::with_visited(fenced).with_warm_start(true);
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199), None);
+        assert_eq!(buffers.visited().len(), 200);
+        buffers.clear();
+        assert!(buffers.visited().is_empty());
+        assert_eq!(tree.dfs_with(&mut buffers, 0, 199), tree.dfs(0, 199));
+    }
+
+    #[test]
+    pub fn test_packed_visited() {
+        let tiles = |state: &Vec<u8>| {
+            let mut packer = BitPacker::<1>::new();
+            for &tile in state {
+                packer.push(tile as u64, 4);
+            }
+            packer.finish()
+        };
+        let mut visited = PackedVisited::new(tiles);
+        let a: Vec<u8> = (0..16).collect();
+        let b: Vec<u8> = (0..16).rev().collect();
+
+        assert!(visited.insert(&a));
+        assert!(visited.insert(&b));
+        assert!(!visited.insert(&a));
+        visited.remove(&b);
+        assert!(visited.insert(&b));
+        visited.clear();
+        assert!(visited.insert(&a));
+
+        assert_eq!(BitPacker::<1>::new().push(0xff, 4).push(1, 4).finish(), [0x1f]);
+        assert_eq!(BitPacker::<2>::new().push(1, 60).push(0b111, 3).push(0b11, 2).finish(),
+                   [1 | 0b111 << 60 | 1 << 63, 1]);
+        assert_eq!(BitPacker::<2>::new().push(u64::MAX, 64).push(5, 64).finish(), [u64::MAX, 5]);
+        assert_eq!(visited.approx_entry_bytes(), hash_entry_bytes::<[u64; 1]>());
+    }
+
+    #[test]
+    pub fn test_ranked_visited() {
+        struct Perm([u8; 4]);
+
+        impl RankableState for Perm {
+            fn rank(&self) -> usize {
+                permutation_rank(&self.0)
+            }
+        }
+
+        assert_eq!(permutation_rank(&[0, 1, 2, 3]), 0);
+        assert_eq!(permutation_rank(&[0, 1, 3, 2]), 1);
+        assert_eq!(permutation_rank(&[1, 0, 2, 3]), 6);
+        assert_eq!(permutation_rank(&[3, 2, 1, 0]), 23);
+
+        let mut visited = RankedVisited::new(24);
+        assert!(visited.insert(&Perm([3, 2, 1, 0])));
+        assert!(visited.insert(&Perm([0, 1, 2, 3])));
+        assert!(!visited.insert(&Perm([3, 2, 1, 0])));
+        visited.remove(&Perm([0, 1, 2, 3]));
+        assert!(visited.insert(&Perm([0, 1, 2, 3])) && !visited.insert(&Perm([3, 2, 1, 0])));
+        VisitedSet::<Perm>::clear(&mut visited);
+        assert!(visited.insert(&Perm([3, 2, 1, 0])));
+    }
+}